@@ -17,7 +17,7 @@ pub fn simple_move(c: &mut Criterion) {
     let mut machine = Machine::new(MachineConfig::default());
     machine.set_step_mode(emulator_2a_lib::machine::StepMode::Assembly);
     let parsed = AsmParser::parse(PROGRAM).expect("Program not parseable");
-    let bytecode = Translator::compile(&parsed);
+    let bytecode = Translator::compile(&parsed).unwrap();
     machine.load(bytecode);
     c.bench_function("run simple program", |b| {
         b.iter(|| run_program(black_box(PROGRAM)))
@@ -27,10 +27,10 @@ pub fn simple_move(c: &mut Criterion) {
 pub fn run_program(program: &str) {
     let mut machine = Machine::new(MachineConfig::default());
     let parsed = AsmParser::parse(program).expect("Program not parseable");
-    let bytecode = Translator::compile(&parsed);
+    let bytecode = Translator::compile(&parsed).unwrap();
     machine.load(bytecode);
     while machine.state() == State::Running {
-        machine.trigger_key_clock()
+        machine.trigger_key_clock();
     }
 }
 
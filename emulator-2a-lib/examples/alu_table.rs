@@ -0,0 +1,50 @@
+//! Print the result of all ALU functions for one pair of inputs.
+//!
+//! ```console
+//! $ cargo run --example alu_table -- 0x2A 7 1
+//! ```
+//!
+//! The arguments are input A, input B and the carry in (`0` or `1`).
+//! All of them are optional.
+use emulator_2a_lib::machine::{Alu, AluSelect};
+use enum_primitive::FromPrimitive;
+
+use std::env;
+
+/// Parse a decimal, hexadecimal (`0x`) or binary (`0b`) byte.
+fn parse_byte(arg: &str) -> u8 {
+    let parsed = if let Some(hex) = arg.strip_prefix("0x") {
+        u8::from_str_radix(hex, 16)
+    } else if let Some(bin) = arg.strip_prefix("0b") {
+        u8::from_str_radix(bin, 2)
+    } else {
+        arg.parse()
+    };
+    parsed.expect("Arguments must be bytes")
+}
+
+fn main() {
+    let args: Vec<_> = env::args().skip(1).map(|arg| parse_byte(&arg)).collect();
+    let a = args.first().copied().unwrap_or(0x2A);
+    let b = args.get(1).copied().unwrap_or(0x07);
+    let carry_in = args.get(2).copied().unwrap_or(0) != 0;
+
+    println!(
+        "A = 0x{:02X}, B = 0x{:02X}, carry in = {}",
+        a, b, carry_in as u8
+    );
+    println!();
+    println!("Function  Output    C V Z N");
+    for op in (0..16).filter_map(AluSelect::from_u8) {
+        let out = Alu::evaluate(a, b, carry_in, op);
+        println!(
+            "{:<8}  {:08b}  {} {} {} {}",
+            format!("{:?}", op),
+            out.output(),
+            out.carry_out() as u8,
+            out.overflow_out() as u8,
+            out.zero_out() as u8,
+            out.negative_out() as u8,
+        );
+    }
+}
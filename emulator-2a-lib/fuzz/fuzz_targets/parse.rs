@@ -0,0 +1,12 @@
+//! Feed arbitrary input into the parser.
+//!
+//! The parser must never panic, every input is either accepted or rejected.
+//! Run with `cargo fuzz run parse` from the `emulator-2a-lib` directory.
+#![no_main]
+use emulator_2a_lib::parser::AsmParser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+    let _ = AsmParser::parse(&input);
+});
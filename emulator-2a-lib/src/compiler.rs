@@ -14,22 +14,189 @@
 //! "#.trim();
 //!
 //! let parsed = AsmParser::parse(asm).expect("Parsing went well");
-//! let bytecode = Translator::compile(&parsed);
+//! let bytecode = Translator::compile(&parsed).expect("Compilation went well");
 //! let bytes: Vec<u8> = bytecode.bytes().cloned().collect();
 //!
 //! assert_eq!(bytes, vec![42, 4]);
 //!
 //! ```
 use colored::Colorize;
-use log::error;
+use thiserror::Error;
 
-use std::{collections::HashMap, fmt, ops::Deref, rc::Rc};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    ops::Deref,
+    rc::Rc,
+};
 
 use crate::parser::{
     Asm, Comment, Constant, Destination, Instruction, Label, Line, MemAddress, Programsize,
     Register, RegisterDdi, RegisterDi, Source, Stacksize,
 };
 
+/// Line number of the first [`Line`] of a program in its source file.
+/// The first source line always contains the `#! mrasm` header.
+pub(crate) const FIRST_SOURCE_LINE: usize = 2;
+/// Number of bytes in a row of the [`ByteCode::listing`].
+const LISTING_BYTES_PER_ROW: usize = 4;
+
+/// Possible failures while translating an [`Asm`] into [`ByteCode`].
+///
+/// Programs returned by [`AsmParser::parse`](crate::parser::AsmParser::parse)
+/// never contain undefined labels, but may still fail otherwise.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CompileError {
+    /// A label was referenced, but never defined.
+    #[error("The label `{0}` is referenced, but never defined")]
+    UnresolvedLabel(Label),
+    /// The program does not fit into memory.
+    /// The field contains the first line reaching address `0xFF`, the last
+    /// address of the memory, which is always occupied by the output register.
+    #[error("The program does not fit into memory, `{0}` reaches address 0xFF")]
+    AddressOverflow(String),
+    /// An `.ORG` points below the end of the code before it.
+    /// Blocks are placed behind each other, thus the addresses of all `.ORG`s
+    /// must ascend, even if a lower block would fit into a gap.
+    #[error("`.ORG 0x{addr:02X}` lies before the end of the code before it at 0x{end:02X}. The addresses of all `.ORG`s must ascend, place the block further down in the program instead")]
+    DescendingOrigin {
+        /// The address of the `.ORG`.
+        addr: u8,
+        /// The last address occupied before the `.ORG`.
+        end: u8,
+    },
+    /// The instruction has no encoding with the given operands, i.e. `DEC (0x20)`.
+    /// The field contains the instruction.
+    #[error("`{0}` cannot be encoded, the Minirechner 2a does not support these operands")]
+    UnsupportedOperands(String),
+}
+
+/// How the operands of an instruction are encoded next to its opcode.
+///
+/// See [`OPCODES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandShape {
+    /// No operands, the opcode is the only byte.
+    None,
+    /// A single register, added to the opcode.
+    Register,
+    /// A single register, used as both registers of a [`OperandShape::TwoRegisters`].
+    RegisterTwice,
+    /// Two registers, the source is shifted by two and added to the opcode
+    /// together with the destination.
+    TwoRegisters,
+    /// A destination and a source. The source mode and register are added to
+    /// the opcode, the destination mode and register to the contained byte.
+    /// Constants and addresses follow their mode bytes.
+    DestinationSource(u8),
+    /// Like [`OperandShape::DestinationSource`], with a register as destination
+    /// and a constant or memory address as source.
+    Load(u8),
+    /// Like [`OperandShape::DestinationSource`], with a memory address as
+    /// destination and a register as source.
+    Store(u8),
+    /// A source only, encoded like [`OperandShape::DestinationSource`]
+    /// followed by the contained byte.
+    Source(u8),
+    /// A label, encoded as the offset to the address after the instruction.
+    RelativeJump,
+    /// A label, encoded as its address.
+    Address,
+    /// A label, encoded as its address, followed by the contained byte.
+    AddressThen(u8),
+}
+
+/// The mnemonic, opcode and operand shape of every instruction executed by the CPU.
+///
+/// Assembler directives like `.ORG` are not part of the table. Some opcodes are
+/// shared by different instructions, i.e. `RET` is the same as `POP R3` and
+/// `LSL R0` is the same as `ADD R0, R0`.
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::compiler::{OperandShape, OPCODES};
+/// let stop = OPCODES.iter().find(|(mnemonic, _, _)| *mnemonic == "STOP");
+/// assert_eq!(stop, Some(&("STOP", 0x01, OperandShape::None)));
+/// ```
+pub const OPCODES: &[(&str, u8, OperandShape)] = &[
+    ("STOP", 0b0000_0001, OperandShape::None),
+    ("NOP", 0b0000_0010, OperandShape::None),
+    ("CLR", 0b0000_0100, OperandShape::Register),
+    ("EI", 0b0000_1000, OperandShape::None),
+    ("DI", 0b0000_1100, OperandShape::None),
+    ("PUSH", 0b0001_0000, OperandShape::Register),
+    ("POP", 0b0001_0100, OperandShape::Register),
+    ("RET", 0b0001_0111, OperandShape::None),
+    ("PUSHF", 0b0001_1000, OperandShape::None),
+    ("POPF", 0b0001_1100, OperandShape::None),
+    ("JR", 0b0010_0000, OperandShape::RelativeJump),
+    ("JCS", 0b0010_0001, OperandShape::RelativeJump),
+    ("JZS", 0b0010_0010, OperandShape::RelativeJump),
+    ("JNS", 0b0010_0011, OperandShape::RelativeJump),
+    ("JCC", 0b0010_0101, OperandShape::RelativeJump),
+    ("JZC", 0b0010_0110, OperandShape::RelativeJump),
+    ("JNC", 0b0010_0111, OperandShape::RelativeJump),
+    ("CALL", 0b0010_1000, OperandShape::Address),
+    ("RETI", 0b0010_1100, OperandShape::None),
+    ("COM", 0b0011_0000, OperandShape::Register),
+    ("NEG", 0b0011_0100, OperandShape::Register),
+    ("LSR", 0b0011_1000, OperandShape::Register),
+    ("ASR", 0b0011_1100, OperandShape::Register),
+    ("RRC", 0b0100_0000, OperandShape::Register),
+    ("INC", 0b0100_0100, OperandShape::Register),
+    ("TST", 0b0100_1000, OperandShape::Register),
+    ("DEC", 0b0101_0000, OperandShape::Register),
+    ("ADD", 0b0110_0000, OperandShape::TwoRegisters),
+    ("LSL", 0b0110_0000, OperandShape::RegisterTwice),
+    ("ADC", 0b0111_0000, OperandShape::TwoRegisters),
+    ("RLC", 0b0111_0000, OperandShape::RegisterTwice),
+    ("SUB", 0b1000_0000, OperandShape::TwoRegisters),
+    ("AND", 0b1001_0000, OperandShape::TwoRegisters),
+    ("OR", 0b1010_0000, OperandShape::TwoRegisters),
+    ("MUL", 0b1011_0000, OperandShape::TwoRegisters),
+    ("DIV", 0b1100_0000, OperandShape::TwoRegisters),
+    ("XOR", 0b1101_0000, OperandShape::TwoRegisters),
+    (
+        "MOV",
+        0b1111_0000,
+        OperandShape::DestinationSource(0b0001_0000),
+    ),
+    ("LD", 0b1111_0000, OperandShape::Load(0b0001_0000)),
+    ("ST", 0b1111_0000, OperandShape::Store(0b0001_0000)),
+    (
+        "CMP",
+        0b1111_0000,
+        OperandShape::DestinationSource(0b0010_0000),
+    ),
+    (
+        "BITT",
+        0b1111_0000,
+        OperandShape::DestinationSource(0b0011_0000),
+    ),
+    ("LDSP", 0b1111_0000, OperandShape::Source(0b0100_0000)),
+    ("LDFR", 0b1111_0000, OperandShape::Source(0b0100_0100)),
+    (
+        "BITS",
+        0b1111_0000,
+        OperandShape::DestinationSource(0b0101_0000),
+    ),
+    (
+        "BITC",
+        0b1111_0000,
+        OperandShape::DestinationSource(0b0110_0000),
+    ),
+    ("JMP", 0b1111_1011, OperandShape::AddressThen(0b0001_0011)),
+];
+
+/// Find the opcode and operand shape of the given mnemonic in [`OPCODES`].
+fn opcode_of(mnemonic: &str) -> Option<(u8, OperandShape)> {
+    OPCODES
+        .iter()
+        .find(|(name, _, _)| *name == mnemonic)
+        .map(|&(_, opcode, shape)| (opcode, shape))
+}
+
 /// An either type for [`u8`]/[`Label`].
 ///
 /// This is used for label references.
@@ -48,7 +215,7 @@ pub enum ByteOrLabel {
 
 /// This is the final byte code with additional information from which [`Line`]
 /// the byte code originates.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ByteCode {
     /// Lines with translated byte code.
     pub lines: Vec<(Line, Vec<u8>)>,
@@ -56,12 +223,28 @@ pub struct ByteCode {
     pub stacksize: Stacksize,
     /// Programsize for limiting.
     pub programsize: Programsize,
+    /// Addresses of all labels. Constants defined by `.EQU` are not included.
+    pub labels: BTreeMap<Label, u8>,
+}
+
+/// The source lines of a program together with the memory they occupy.
+///
+/// Create it using [`ByteCode::source_map`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Every line of the program and the address of its first byte.
+    lines: Vec<(String, u8)>,
+    /// Number of bytes placed into memory by every line.
+    lengths: Vec<usize>,
+    /// Address of the first byte of the program.
+    start: usize,
 }
 
 /// Translator for [`Asm`] -> [`ByteCode`]
 #[derive(Debug, Clone)]
 pub struct Translator {
-    next_addr: u8,
+    /// Address of the next byte, this is `0x100` once the memory is full.
+    next_addr: usize,
     known_labels: HashMap<Label, u8>,
     bytes: Vec<(Line, Vec<ByteOrLabel>)>,
     stacksize: Stacksize,
@@ -76,14 +259,189 @@ impl ByteCode {
     pub fn bytes<'a>(&'a self) -> impl Iterator<Item = &u8> + 'a {
         self.lines.iter().flat_map(|(_, c)| c)
     }
+    /// Get all lines placing bytes into memory, together with the address of their first byte.
+    ///
+    /// Unlike [`ByteCode::bytes`], this skips the zeros filling the gaps
+    /// left by `.ORG` and `.ALIGN`, as they are not part of the program.
+    pub fn placed_lines(&self) -> impl Iterator<Item = (u8, &Line, &[u8])> {
+        let mut addr = 0;
+        self.lines.iter().filter_map(move |(line, bytes)| {
+            let start = addr;
+            addr += bytes.len();
+            if bytes.is_empty() || is_padding(line) {
+                None
+            } else {
+                Some((start as u8, line, bytes.as_slice()))
+            }
+        })
+    }
+    /// Get the number of the source line that placed the byte at `addr` into memory.
+    ///
+    /// Line numbers start at one, the first line contains the `#! mrasm` header.
+    pub fn source_line_at(&self, addr: u8) -> Option<usize> {
+        let addr = addr as usize;
+        let mut start = 0;
+        for (idx, (_, bytes)) in self.lines.iter().enumerate() {
+            let end = start + bytes.len();
+            if (start..end).contains(&addr) {
+                return Some(idx + FIRST_SOURCE_LINE);
+            }
+            start = end;
+        }
+        None
+    }
+    /// Create a [`SourceMap`] of this program.
+    pub fn source_map(&self) -> SourceMap {
+        self.source_map_at(0)
+    }
+    /// Create a [`SourceMap`] of this program, loaded at `address`.
+    ///
+    /// See [`Machine::load_at`](crate::machine::Machine::load_at).
+    pub fn source_map_at(&self, address: u8) -> SourceMap {
+        let mut start = address as usize;
+        let mut lines = Vec::with_capacity(self.lines.len());
+        let mut lengths = Vec::with_capacity(self.lines.len());
+        for (line, bytes) in &self.lines {
+            lines.push((line.to_string(), start as u8));
+            lengths.push(bytes.len());
+            start += bytes.len();
+        }
+        SourceMap {
+            lines,
+            lengths,
+            start: address as usize,
+        }
+    }
+    /// Create a listing of this program, like the `.lst` file of an assembler.
+    ///
+    /// Every source line is prefixed with the address of its first byte and
+    /// the bytes it places into memory. Lines with more than four bytes continue
+    /// in the following rows. The gaps left by `.ORG` and `.ALIGN` are given
+    /// as the number of zeros filling them.
+    ///
+    /// # Example
+    /// ```
+    /// # use emulator_2a_lib::{compiler::Translator, parser::AsmParser};
+    /// let asm = AsmParser::parse("#! mrasm\nLOOP:\n    INC R0\n    JR LOOP\n").unwrap();
+    /// let bytecode = Translator::compile(&asm).unwrap();
+    /// let expected = "\
+    /// 00:                LOOP:
+    /// 00: 44                 INC R0
+    /// 01: 20 FD              JR LOOP
+    /// 03:
+    /// ";
+    /// assert_eq!(bytecode.listing(), expected);
+    /// ```
+    pub fn listing(&self) -> String {
+        self.listing_at(0)
+    }
+    /// Create a listing of this program, loaded at `address`.
+    ///
+    /// See [`ByteCode::listing`] and [`Machine::load_at`](crate::machine::Machine::load_at).
+    pub fn listing_at(&self, address: u8) -> String {
+        let mut listing = String::new();
+        let mut addr = address as usize;
+        for (line, bytes) in &self.lines {
+            let source = line.to_string();
+            if bytes.is_empty() || is_padding(line) {
+                let gap = if bytes.is_empty() {
+                    String::new()
+                } else {
+                    format!("{} x 00", bytes.len())
+                };
+                push_listing_row(&mut listing, addr, &gap, &source);
+            } else {
+                for (idx, chunk) in bytes.chunks(LISTING_BYTES_PER_ROW).enumerate() {
+                    let hex: Vec<_> = chunk.iter().map(|byte| format!("{:02X}", byte)).collect();
+                    let source = if idx == 0 { source.as_str() } else { "" };
+                    let row_addr = addr + idx * LISTING_BYTES_PER_ROW;
+                    push_listing_row(&mut listing, row_addr, &hex.join(" "), source);
+                }
+            }
+            addr += bytes.len();
+        }
+        listing
+    }
+    /// Get the address of the given label.
+    ///
+    /// Labels are compared case insensitively.
+    pub fn label_address(&self, label: &str) -> Option<u8> {
+        self.labels
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(label))
+            .map(|(_, addr)| *addr)
+    }
+    /// Get the numbers of all source lines containing instructions that
+    /// are executed by the CPU, skipping assembler directives.
+    pub fn executable_source_lines(&self) -> BTreeSet<usize> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, (line, _))| match line {
+                Line::Instruction(inst, _) => !inst.is_directive(),
+                _ => false,
+            })
+            .map(|(idx, _)| idx + FIRST_SOURCE_LINE)
+            .collect()
+    }
+}
+
+/// Does the line only fill a gap with zeros, i.e. `.ORG` and `.ALIGN`?
+fn is_padding(line: &Line) -> bool {
+    matches!(
+        line,
+        Line::Instruction(Instruction::AsmOrigin(_), _)
+            | Line::Instruction(Instruction::AsmAlign(_), _)
+    )
+}
+
+/// Append a row of the [`ByteCode::listing`] to `listing`.
+fn push_listing_row(listing: &mut String, addr: usize, bytes: &str, source: &str) {
+    let width = LISTING_BYTES_PER_ROW * 3 - 1;
+    let row = format!(
+        "{:02X}: {:<width$}    {}",
+        addr as u8,
+        bytes,
+        source,
+        width = width
+    );
+    listing.push_str(row.trim_end());
+    listing.push('\n');
+}
+
+impl SourceMap {
+    /// Get all lines of the program, formatted, together with the
+    /// address of their first byte in memory.
+    ///
+    /// The line at index `i` is found in line `i + 2` of the source file,
+    /// as the first line always contains the `#! mrasm` header.
+    pub fn lines(&self) -> &[(String, u8)] {
+        &self.lines
+    }
+    /// Get the index into [`SourceMap::lines`] of the line that placed
+    /// the byte at `addr` into memory.
+    ///
+    /// Lines without bytes, like labels and comments, are never returned.
+    pub fn line_index_at(&self, addr: u8) -> Option<usize> {
+        let addr = addr as usize;
+        let mut start = self.start;
+        for (idx, length) in self.lengths.iter().enumerate() {
+            let end = start + length;
+            if (start..end).contains(&addr) {
+                return Some(idx);
+            }
+            start = end;
+        }
+        None
+    }
 }
 
 impl Translator {
     /// Compile the given [`Asm`] into [`ByteCode`].
-    pub fn compile(asm: &Asm) -> ByteCode {
+    pub fn compile(asm: &Asm) -> Result<ByteCode, CompileError> {
         let mut tr = Translator::new();
         for line in &asm.lines {
-            tr.push(line);
+            tr.push(line)?;
         }
         tr.finish()
     }
@@ -100,45 +458,56 @@ impl Translator {
     }
     /// Push a [`Line`] into the translator, adding the translated bytes,
     /// changing address and pushing labels
-    fn push(&mut self, line: &Line) {
+    fn push(&mut self, line: &Line) -> Result<(), CompileError> {
         match line {
             Line::Empty(_) => {
                 self.bytes.push((line.clone(), vec![]));
             }
             Line::Label(label, _) => {
-                self.known_labels.insert(label.to_string(), self.next_addr);
+                self.known_labels
+                    .insert(label.to_string(), self.next_addr as u8);
                 self.bytes.push((line.clone(), vec![]));
             }
-            Line::Instruction(inst, comment) => self.push_instruction(inst, comment),
+            Line::Instruction(inst, comment) => self.push_instruction(inst, comment)?,
         }
+        Ok(())
     }
     /// Push an instruction into the translator.
-    fn push_instruction(&mut self, inst: &Instruction, comment: &Option<Comment>) {
+    fn push_instruction(
+        &mut self,
+        inst: &Instruction,
+        comment: &Option<Comment>,
+    ) -> Result<(), CompileError> {
         use ByteOrLabel::*;
         use Instruction::*;
         let bols = match inst.clone() {
             AsmOrigin(addr) => {
-                // XXX: This can only skip bytes atm, no fancy
-                // XXX: messing with your programs yet!
-                // XXX: Prevent the usage of negativ skips to prevent diverging
-                // XXX: from the real machine.
-                if addr < self.next_addr {
-                    error! {
-                        "Compiler detected a problematic .ORG instruction!\nThe instruction: `{}` will cause the real machine to behave differently, since the .ORG instruction would point to an existing byte in the program. This is probably unintentional, please use an address larger than {}, to not overwrite your own program.\n\n -> Example: .ORG {}\n\n", inst, self.next_addr, self.next_addr + 5
-                    }
-                    panic!("Compilation aborted")
+                // Every `.ORG` starts a new block of code at the given address.
+                // The blocks follow each other in memory, the gaps between them
+                // are filled with zeros. Thus a block cannot go back to a lower
+                // address, even if it would not overlap with the code there.
+                if (addr as usize) < self.next_addr {
+                    return Err(CompileError::DescendingOrigin {
+                        addr,
+                        end: (self.next_addr - 1) as u8,
+                    });
                 }
-                // Insert blanks
+                // Fill the gap between the blocks with zeros
                 let mut skips = vec![];
-                if addr > self.next_addr {
-                    for _skip in 0..(addr - self.next_addr) {
+                if addr as usize > self.next_addr {
+                    for _skip in 0..(addr as usize - self.next_addr) {
                         skips.push(Byte(0x00));
                     }
                 }
                 skips
             }
+            AsmAlign(alignment) => {
+                // The parser guarantees a power of two, no division by zero
+                let alignment = alignment as usize;
+                let padding = (alignment - self.next_addr % alignment) % alignment;
+                vec![Byte(0x00); padding]
+            }
             AsmByte(nr) => {
-                self.next_addr += nr;
                 let mut ret = vec![];
                 for _ in 0..nr {
                     ret.push(Byte(0b0000_0000));
@@ -168,113 +537,135 @@ impl Translator {
                 self.programsize = ps;
                 vec![]
             }
-            Clr(reg) => {
-                let reg: u8 = reg_to_u8(reg);
-                vec![Byte(0b0000_0100 + reg)]
+            inst => self.encode(inst)?,
+        };
+        let line = Line::Instruction(inst.clone(), comment.clone());
+        // The address after the line must be addressable, too, i.e. by labels
+        if !bols.is_empty() && self.next_addr + bols.len() >= 0x100 {
+            return Err(CompileError::AddressOverflow(
+                line.to_string().trim().into(),
+            ));
+        }
+        self.next_addr += bols.len();
+        self.bytes.push((line, bols));
+        Ok(())
+    }
+    /// Encode an instruction executed by the CPU using the [`OPCODES`] table.
+    ///
+    /// Instructions without an entry, i.e. directives, and operands not matching
+    /// the [`OperandShape`] of the entry, i.e. `DEC (0x20)`, are rejected.
+    fn encode(&self, inst: Instruction) -> Result<Vec<ByteOrLabel>, CompileError> {
+        use ByteOrLabel::*;
+        use Instruction::*;
+        use OperandShape as Shape;
+        let unsupported = |inst: &Instruction| CompileError::UnsupportedOperands(inst.to_string());
+        let (opcode, shape) = opcode_of(inst.mnemonic()).ok_or_else(|| unsupported(&inst))?;
+        let bols = match (shape, inst) {
+            (Shape::None, _) => vec![Byte(opcode)],
+            (
+                Shape::Register,
+                Dec(Source::Register(reg))
+                | Clr(reg)
+                | Inc(reg)
+                | Neg(reg)
+                | Com(reg)
+                | Tst(reg)
+                | Lsr(reg)
+                | Asr(reg)
+                | Rrc(reg)
+                | Push(reg)
+                | Pop(reg),
+            ) => from_base_and_reg(opcode, reg),
+            (Shape::RegisterTwice, Lsl(reg) | Rlc(reg)) => from_base_and_two_regs(opcode, reg, reg),
+            (
+                Shape::TwoRegisters,
+                Add(rd, rs)
+                | Adc(rd, rs)
+                | Sub(rd, rs)
+                | Mul(rd, rs)
+                | Div(rd, rs)
+                | And(rd, rs)
+                | Or(rd, rs)
+                | Xor(rd, rs),
+            ) => from_base_and_two_regs(opcode, rd, rs),
+            (
+                Shape::DestinationSource(second),
+                Mov(dst, src) | Bits(dst, src) | Bitc(dst, src) | Cmp(dst, src) | Bitt(dst, src),
+            ) => from_bases_dst_and_src(opcode, second, &dst, &src),
+            (Shape::Load(second), LdConstant(reg, c)) => {
+                from_bases_dst_and_src(opcode, second, &reg.into(), &c.into())
+            }
+            (Shape::Load(second), LdMemAddress(reg, mem)) => {
+                from_bases_dst_and_src(opcode, second, &reg.into(), &mem.into())
+            }
+            (Shape::Store(second), St(mem, reg)) => {
+                from_bases_dst_and_src(opcode, second, &mem.into(), &reg.into())
             }
-            Add(rd, rs) => from_base_and_two_regs(0b0110_0000, rd, rs),
-            Adc(rd, rs) => from_base_and_two_regs(0b0111_0000, rd, rs),
-            Sub(rd, rs) => from_base_and_two_regs(0b1000_0000, rd, rs),
-            Mul(rd, rs) => from_base_and_two_regs(0b1011_0000, rd, rs),
-            Div(rd, rs) => from_base_and_two_regs(0b1100_0000, rd, rs),
-            Inc(reg) => from_base_and_reg(0b0100_0100, reg),
-            Dec(src) => match src {
-                Source::Register(reg) => from_base_and_reg(0b0101_0000, reg),
-                _ => unimplemented!("DEC [something other than R*] does not work yet"),
-            },
-            Neg(reg) => from_base_and_reg(0b0011_0100, reg),
-            And(rd, rs) => from_base_and_two_regs(0b1001_0000, rd, rs),
-            Or(rd, rs) => from_base_and_two_regs(0b1010_0000, rd, rs),
-            Xor(rd, rs) => from_base_and_two_regs(0b1101_0000, rd, rs),
-            Com(reg) => from_base_and_reg(0b0011_0000, reg),
-            Bits(dst, src) => from_bases_dst_and_src(0b1111_0000, 0b0101_0000, &dst, &src),
-            Bitc(dst, src) => from_bases_dst_and_src(0b1111_0000, 0b0110_0000, &dst, &src),
-            Tst(reg) => from_base_and_reg(0b0100_1000, reg),
-            Cmp(dst, src) => from_bases_dst_and_src(0b1111_0000, 0b0010_0000, &dst, &src),
-            Bitt(dst, src) => from_bases_dst_and_src(0b1111_0000, 0b0011_0000, &dst, &src),
-            Lsr(reg) => from_base_and_reg(0b0011_1000, reg),
-            Asr(reg) => from_base_and_reg(0b0011_1100, reg),
-            Lsl(reg) => {
-                let reg = reg_to_u8(reg);
-                vec![Byte(0b0110_0000 + (reg << 2) + reg)]
+            (Shape::Source(second), Ldsp(src) | Ldfr(src)) => {
+                from_bases_and_src(opcode, second, &src)
             }
-            Rrc(reg) => from_base_and_reg(0b0100_0000, reg),
-            Rlc(reg) => {
-                let reg = reg_to_u8(reg);
-                vec![Byte(0b0111_0000 + (reg << 2) + reg)]
+            (
+                Shape::RelativeJump,
+                Jr(label) | Jcs(label) | Jcc(label) | Jzs(label) | Jzc(label) | Jns(label)
+                | Jnc(label),
+            ) => relative_jump(opcode, label, self.next_addr as u8),
+            (Shape::Address, Call(label)) => vec![Byte(opcode), Label(label)],
+            (Shape::AddressThen(last), Jmp(label)) => {
+                vec![Byte(opcode), Label(label), Byte(last)]
             }
-            Mov(dst, src) => compile_instruction_mov(dst, src),
-            LdConstant(reg, c) => compile_instruction_mov(reg.into(), c.into()),
-            LdMemAddress(reg, mem) => compile_instruction_mov(reg.into(), mem.into()),
-            St(mem, reg) => compile_instruction_mov(mem.into(), reg.into()),
-            Push(reg) => from_base_and_reg(0b0001_0000, reg),
-            Pop(reg) => from_base_and_reg(0b0001_0100, reg),
-            PushF => vec![Byte(0b0001_1000)],
-            PopF => vec![Byte(0b0001_1100)],
-            Ldsp(src) => from_bases_and_src(0b1111_0000, 0b0100_0000, &src),
-            Ldfr(src) => from_bases_and_src(0b1111_0000, 0b0100_0100, &src),
-            Jmp(label) => vec![Byte(0b1111_1011), Label(label), Byte(0b0001_0011)],
-            Jcs(label) => relative_jump(0b001, label, self.next_addr),
-            Jcc(label) => relative_jump(0b101, label, self.next_addr),
-            Jzs(label) => relative_jump(0b010, label, self.next_addr),
-            Jzc(label) => relative_jump(0b110, label, self.next_addr),
-            Jns(label) => relative_jump(0b011, label, self.next_addr),
-            Jnc(label) => relative_jump(0b111, label, self.next_addr),
-            Jr(label) => relative_jump(0b000, label, self.next_addr),
-            Call(label) => vec![Byte(0b0010_1000), Label(label)],
-            Ret => vec![Byte(0b0001_0111)],
-            RetI => vec![Byte(0b0010_1100)],
-            Stop => vec![Byte(0b0000_0001)],
-            Nop => vec![Byte(0b0000_0010)],
-            Ei => vec![Byte(0b0000_1000)],
-            Di => vec![Byte(0b0000_1100)],
+            (_, inst) => return Err(unsupported(&inst)),
         };
-        let line = Line::Instruction(inst.clone(), comment.clone());
-        self.next_addr += bols.len() as u8;
-        self.bytes.push((line, bols));
+        Ok(bols)
     }
     /// Finish the translation.
     /// This replaces all references to labels with the address the
     /// Label was defined at.
-    fn finish(mut self) -> ByteCode {
-        let labels = self.known_labels;
+    fn finish(mut self) -> Result<ByteCode, CompileError> {
+        let known_labels = self.known_labels;
+        let labels = self
+            .bytes
+            .iter()
+            .filter_map(|(line, _)| match line {
+                Line::Label(label, _) => Some((label.clone(), known_labels[label])),
+                _ => None,
+            })
+            .collect();
+        let resolve = |label: Label| {
+            known_labels
+                .get(&label)
+                .cloned()
+                .ok_or(CompileError::UnresolvedLabel(label))
+        };
         let lines = self
             .bytes
             .drain(..)
             .map(|(line, mut bols)| {
                 let bytes = bols
                     .drain(..)
-                    .flat_map(|bol| match bol {
-                        ByteOrLabel::Byte(byte) => vec![byte],
-                        ByteOrLabel::Label(label) => vec![*labels
-                            .get(&label)
-                            .expect("infallible. Labels must be defined")],
-                        ByteOrLabel::LabelFn(label, f) => {
-                            let b = *labels
-                                .get(&label)
-                                .expect("infallible. Labels must be defined");
-                            vec![f.deref()(b)]
-                        }
+                    .map(|bol| match bol {
+                        ByteOrLabel::Byte(byte) => Ok(byte),
+                        ByteOrLabel::Label(label) => resolve(label),
+                        ByteOrLabel::LabelFn(label, f) => resolve(label).map(|b| f.deref()(b)),
                     })
-                    .collect();
-                (line, bytes)
+                    .collect::<Result<_, _>>()?;
+                Ok((line, bytes))
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
         let stacksize = self.stacksize;
         let programsize = self.programsize;
-        ByteCode {
+        Ok(ByteCode {
             lines,
             stacksize,
             programsize,
-        }
+            labels,
+        })
     }
 }
 
-/// Create the necessary [`ByteOrLabel`]s for a relative jump with the given condition.
-fn relative_jump(cond: u8, label: Label, curr_addr: u8) -> Vec<ByteOrLabel> {
+/// Create the necessary [`ByteOrLabel`]s for a relative jump with the given opcode.
+fn relative_jump(opcode: u8, label: Label, curr_addr: u8) -> Vec<ByteOrLabel> {
     use ByteOrLabel::*;
-    debug_assert!(cond <= 0b0000_0111);
-    let first = Byte(0b0010_0000 + cond);
+    let first = Byte(opcode);
     // Calculate relative offset of the target address.
     let second = LabelFn(
         label,
@@ -287,47 +678,6 @@ fn relative_jump(cond: u8, label: Label, curr_addr: u8) -> Vec<ByteOrLabel> {
     vec![first, second]
 }
 
-/// Compile a `MOV` instruction.
-fn compile_instruction_mov(dst: Destination, src: Source) -> Vec<ByteOrLabel> {
-    use ByteOrLabel::*;
-    // SOURCE
-    // Calculate first byte from register and mode
-    let source_addr_mode = source_addr_mode(&src) << 2;
-    let source_register = source_register(&src);
-    let first = 0b1111_0000 + source_addr_mode + source_register;
-    let mut ret = vec![Byte(first)];
-    // Add another byte if we need a constant or an address
-    let second = match src {
-        Source::Constant(c) => Some(c.into()),
-        Source::MemAddress(ref mem) => match mem {
-            MemAddress::Constant(c) => Some(c.clone().into()),
-            MemAddress::Register(_) => None,
-        },
-        _ => None,
-    };
-    if let Some(second) = second {
-        ret.push(second)
-    }
-    // DESTINATION
-    // Calculate first byte from register and mode
-    let destination_addr_mode = destination_addr_mode(&dst) << 2;
-    let destination_register = destination_register(&dst);
-    let third = 0b0001_0000 + destination_addr_mode + destination_register;
-    ret.push(Byte(third));
-    // Add another byte if we need an address
-    let fourth = match dst {
-        Destination::MemAddress(ref mem) => match mem {
-            MemAddress::Constant(c) => Some(c.clone().into()),
-            MemAddress::Register(_) => None,
-        },
-        _ => None,
-    };
-    if let Some(fourth) = fourth {
-        ret.push(fourth)
-    }
-    ret
-}
-
 /// Convert a [`Register`] to [`u8`]
 fn reg_to_u8(reg: Register) -> u8 {
     match reg {
@@ -527,3 +877,329 @@ impl fmt::Debug for ByteOrLabel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::AsmParser;
+
+    fn compile(program: &str) -> Result<ByteCode, CompileError> {
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        Translator::compile(&asm)
+    }
+
+    #[test]
+    fn unresolved_labels_are_reported() {
+        // The parser rejects these, only hand-made programs can contain them
+        let asm = Asm {
+            comment_after_shebang: None,
+            lines: vec![Line::Instruction(Instruction::Jmp("NOWHERE".into()), None)],
+        };
+        assert_eq!(
+            Translator::compile(&asm).unwrap_err(),
+            CompileError::UnresolvedLabel("NOWHERE".into())
+        );
+    }
+
+    #[test]
+    fn address_overflows_are_reported() {
+        let full = compile("#! mrasm\n.ORG 0xFE\nSTOP\nEND:\n").expect("Compilation failed");
+        assert_eq!(full.bytes().count(), 0xFF);
+        assert_eq!(full.label_address("END"), Some(0xFF));
+        assert_eq!(
+            compile("#! mrasm\n.ORG 0xFF\nSTOP\n").unwrap_err(),
+            CompileError::AddressOverflow("STOP".into())
+        );
+        assert_eq!(
+            compile("#! mrasm\n.ORG 0xFE\nSTOP\nNOP\n").unwrap_err(),
+            CompileError::AddressOverflow("NOP".into())
+        );
+        assert_eq!(
+            compile("#! mrasm\n.BYTE 200\n.BYTE 100\n").unwrap_err(),
+            CompileError::AddressOverflow(".BYTE 100".into())
+        );
+    }
+
+    #[test]
+    fn label_addresses_are_kept() {
+        let bytecode =
+            compile("#! mrasm\n.EQU LIMIT 5\nSTART:\nINC R0\n.ORG 0x10\nLoop:\nJR Loop\n")
+                .expect("Compilation failed");
+        let labels: Vec<_> = bytecode
+            .labels
+            .iter()
+            .map(|(l, a)| (l.as_str(), *a))
+            .collect();
+        assert_eq!(labels, vec![("Loop", 0x10), ("START", 0x00)]);
+        assert_eq!(bytecode.label_address("loop"), Some(0x10));
+        assert_eq!(bytecode.label_address("LIMIT"), None);
+    }
+
+    #[test]
+    fn alignments_pad_with_zeros() {
+        let bytecode = compile("#! mrasm\n.DB 1, 2, 3\n.ALIGN 8\nTABLE:\n.DB 4\n.ALIGN 1\n")
+            .expect("Compilation failed");
+        let bytes: Vec<u8> = bytecode.bytes().copied().collect();
+        assert_eq!(bytes, vec![1, 2, 3, 0, 0, 0, 0, 0, 4]);
+        // Aligned addresses are not padded
+        let bytecode = compile("#! mrasm\n.ALIGN 128\n.DB 1\n").expect("Compilation failed");
+        assert_eq!(bytecode.bytes().count(), 1);
+        assert_eq!(
+            compile("#! mrasm\n.ORG 0x81\n.ALIGN 128\nSTOP\n").unwrap_err(),
+            CompileError::AddressOverflow(".ALIGN 128".into())
+        );
+    }
+
+    #[test]
+    fn padding_is_not_placed() {
+        let bytecode = compile("#! mrasm\n.ORG 0x04\n.DB 1, 2\n.ALIGN 8\nCLR R0\n.BYTE 2\n")
+            .expect("Compilation failed");
+        let placed: Vec<_> = bytecode
+            .placed_lines()
+            .map(|(addr, line, bytes)| (addr, line.to_string().trim().to_owned(), bytes.to_vec()))
+            .collect();
+        assert_eq!(
+            placed,
+            vec![
+                (0x04, ".DB 1, 2".to_owned(), vec![1, 2]),
+                (0x08, "CLR R0".to_owned(), vec![4]),
+                (0x09, ".BYTE 2".to_owned(), vec![0, 0]),
+            ]
+        );
+    }
+
+    #[test]
+    fn listings_show_addresses_bytes_and_source() {
+        let bytecode = compile(
+            r#"#! mrasm
+; Sum up the table
+    .ORG 0x02
+START:
+    LD R0, (TABLE)  ; first entry
+    JR START
+    .ORG 0x10
+TABLE:
+    .DB 1, 2, 3, 4, 5
+"#,
+        )
+        .expect("Compilation failed");
+        let expected = "\
+00:                ; Sum up the table
+00: 2 x 00             .ORG 2
+02:                START:
+02: FF 10 10           LD R0, (TABLE)            ; first entry
+05: 20 FB              JR START
+07: 9 x 00             .ORG 16
+10:                TABLE:
+10: 01 02 03 04        .DB 1, 2, 3, 4, 5
+14: 05
+15:
+";
+        assert_eq!(bytecode.listing(), expected);
+        let moved = bytecode.listing_at(0x20);
+        assert!(moved.starts_with("20:                ; Sum up the table\n"));
+        assert!(moved.ends_with("34: 05\n35:\n"));
+    }
+
+    #[test]
+    fn jumps_are_encoded_like_the_isa() {
+        let bytecode = compile(
+            r#"#! mrasm
+BACK:
+    NOP
+    JR BACK
+    JCS FWD
+    JCC BACK
+    JZS FWD
+    JZC BACK
+    JNS FWD
+    JNC BACK
+    CALL FWD
+    JMP BACK
+FWD:
+    JR FWD
+"#,
+        )
+        .expect("Compilation failed");
+        let encoded: Vec<_> = bytecode
+            .lines
+            .iter()
+            .filter(|(line, _)| matches!(line, Line::Instruction(..)))
+            .map(|(_, bytes)| bytes.as_slice())
+            .collect();
+        // Relative offsets are counted from the address after the jump
+        let expected: &[&[u8]] = &[
+            &[0x02],
+            &[0x20, 0xFD],       // 0x01: 0x00 - 0x03
+            &[0x21, 0x0F],       // 0x03: 0x14 - 0x05
+            &[0x25, 0xF9],       // 0x05: 0x00 - 0x07
+            &[0x22, 0x0B],       // 0x07: 0x14 - 0x09
+            &[0x26, 0xF5],       // 0x09: 0x00 - 0x0B
+            &[0x23, 0x07],       // 0x0B: 0x14 - 0x0D
+            &[0x27, 0xF1],       // 0x0D: 0x00 - 0x0F
+            &[0x28, 0x14],       // 0x0F: absolute address
+            &[0xFB, 0x00, 0x13], // 0x11: MOV PC, 0x00
+            &[0x20, 0xFE],       // 0x14: jump to itself
+        ];
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn opcode_table_covers_all_instructions() {
+        use Instruction::*;
+        let (r0, r1) = (Register::R0, Register::R1);
+        let label = || Label::from("L");
+        let instructions = vec![
+            Clr(r0),
+            Add(r0, r1),
+            Adc(r0, r1),
+            Sub(r0, r1),
+            Mul(r0, r1),
+            Div(r0, r1),
+            Inc(r0),
+            Dec(Source::Register(r0)),
+            Neg(r0),
+            And(r0, r1),
+            Or(r0, r1),
+            Xor(r0, r1),
+            Com(r0),
+            Bits(Destination::Register(r0), Source::Register(r1)),
+            Bitc(Destination::Register(r0), Source::Register(r1)),
+            Tst(r0),
+            Cmp(Destination::Register(r0), Source::Register(r1)),
+            Bitt(Destination::Register(r0), Source::Register(r1)),
+            Lsr(r0),
+            Asr(r0),
+            Lsl(r0),
+            Rrc(r0),
+            Rlc(r0),
+            Mov(Destination::Register(r0), Source::Register(r1)),
+            LdConstant(r0, Constant::Constant(0x2A)),
+            LdMemAddress(r0, MemAddress::Register(r1)),
+            St(MemAddress::Register(r1), r0),
+            Push(r0),
+            Pop(r0),
+            PushF,
+            PopF,
+            Ldsp(Source::Register(r0)),
+            Ldfr(Source::Register(r0)),
+            Jmp(label()),
+            Jcs(label()),
+            Jcc(label()),
+            Jzs(label()),
+            Jzc(label()),
+            Jns(label()),
+            Jnc(label()),
+            Jr(label()),
+            Call(label()),
+            Ret,
+            RetI,
+            Stop,
+            Nop,
+            Ei,
+            Di,
+        ];
+        let mut lines = vec![Line::Label(label(), None)];
+        lines.extend(instructions.iter().map(|inst| {
+            assert!(!inst.is_directive());
+            assert!(opcode_of(inst.mnemonic()).is_some(), "{} is missing", inst);
+            Line::Instruction(inst.clone(), None)
+        }));
+        let asm = Asm {
+            comment_after_shebang: None,
+            lines,
+        };
+        Translator::compile(&asm).expect("Compilation failed");
+        let mnemonics: BTreeSet<_> = instructions.iter().map(Instruction::mnemonic).collect();
+        assert_eq!(mnemonics.len(), OPCODES.len());
+        for (index, (mnemonic, opcode, shape)) in OPCODES.iter().enumerate() {
+            assert!(mnemonics.contains(mnemonic), "{} is unused", mnemonic);
+            for (other, other_opcode, other_shape) in &OPCODES[index + 1..] {
+                assert_ne!(mnemonic, other);
+                assert!(
+                    (opcode, shape) != (other_opcode, other_shape),
+                    "{} and {} share their opcode",
+                    mnemonic,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn byte_lengths_match_the_translation() {
+        for dir in &["../programs", "../testing/programs"] {
+            for entry in std::fs::read_dir(dir).expect("Failed to read directory") {
+                let path = entry.expect("Failed to read entry").path();
+                let program = std::fs::read_to_string(&path).expect("Failed to read program");
+                let bytecode = match AsmParser::parse(&program) {
+                    Ok(asm) => Translator::compile(&asm).expect("Compilation failed"),
+                    Err(_) => continue,
+                };
+                for (line, bytes) in &bytecode.lines {
+                    match line {
+                        Line::Instruction(Instruction::AsmOrigin(_), _)
+                        | Line::Instruction(Instruction::AsmAlign(_), _) => {}
+                        Line::Instruction(inst, _) => {
+                            assert_eq!(inst.byte_length() as usize, bytes.len(), "{}", inst)
+                        }
+                        _ => assert!(bytes.is_empty()),
+                    }
+                }
+            }
+        }
+        let program = "#! mrasm\n.DB 1, 2\n.DW 0x1234\n.BYTE 3\nLD R0, (0x10)\nLD R1, 7\n\
+                       MOV (0x20), 0x30\nMOV (R0+), ((R1+))\nJR L\nL:\nJMP L\nCALL L\nSTOP\n";
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        let total: usize = asm
+            .lines
+            .iter()
+            .map(|line| match line {
+                Line::Instruction(inst, _) => inst.byte_length() as usize,
+                _ => 0,
+            })
+            .sum();
+        let bytecode = Translator::compile(&asm).expect("Compilation failed");
+        assert_eq!(total, bytecode.bytes().count());
+    }
+
+    #[test]
+    fn unsupported_operands_are_reported() {
+        let dec = Instruction::Dec(Source::MemAddress(MemAddress::Constant(
+            Constant::Constant(0x20),
+        )));
+        let asm = Asm {
+            comment_after_shebang: None,
+            lines: vec![Line::Instruction(dec, None)],
+        };
+        assert_eq!(
+            Translator::compile(&asm).unwrap_err(),
+            CompileError::UnsupportedOperands("DEC (0x20)".into())
+        );
+        // Directives have no opcode
+        let origin = Instruction::AsmOrigin(0x10);
+        assert_eq!(
+            Translator::new().encode(origin.clone()).unwrap_err(),
+            CompileError::UnsupportedOperands(origin.to_string())
+        );
+    }
+
+    #[test]
+    fn descending_origins_are_reported() {
+        let err = compile("#! mrasm\nNOP\nNOP\n.ORG 1\nSTOP\n").unwrap_err();
+        assert_eq!(err, CompileError::DescendingOrigin { addr: 1, end: 1 });
+        assert!(err
+            .to_string()
+            .starts_with("`.ORG 0x01` lies before the end of the code before it"));
+        compile("#! mrasm\nNOP\nNOP\n.ORG 2\nSTOP\n").expect("Compilation failed");
+        // Blocks cannot be placed into the gap before a higher block
+        let err = compile("#! mrasm\n.ORG 0x20\nNOP\n.ORG 0x10\nSTOP\n").unwrap_err();
+        assert_eq!(
+            err,
+            CompileError::DescendingOrigin {
+                addr: 0x10,
+                end: 0x20
+            }
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Decode bytes back into [`Instruction`]s.
+//!
+//! The disassembler is the inverse of the [`Translator`](crate::compiler::Translator)
+//! and uses the same [`OPCODES`] table. As labels are not part of the bytecode,
+//! jump targets are replaced by their absolute address, i.e. `JR 0x04`.
+//!
+//! # Example
+//! ```
+//! # use emulator_2a_lib::{disassembler, parser::{Constant, Instruction, Register}};
+//! // LD R1, 0x2A
+//! let (inst, len) = disassembler::decode(&[0xFB, 0x2A, 0x11], 0x00).unwrap();
+//! assert_eq!(inst, Instruction::LdConstant(Register::R1, Constant::Constant(0x2A)));
+//! assert_eq!(len, 3);
+//! // JR to the same address
+//! let (inst, _) = disassembler::decode(&[0x20, 0xFE], 0x10).unwrap();
+//! assert_eq!(inst.to_string(), "JR 0x10");
+//! ```
+use crate::{
+    compiler::{OperandShape, OPCODES},
+    parser::{
+        Constant, Destination, Instruction, MemAddress, Register, RegisterDdi, RegisterDi, Source,
+    },
+};
+
+/// Opcode of all instructions with a destination and a source.
+/// These are distinguished by the high nibble of the destination byte.
+const DESTINATION_SOURCE_OPCODE: u8 = 0b1111_0000;
+/// Second byte of `LDSP`.
+const LDSP_SECOND: u8 = 0b0100_0000;
+/// Second byte of `LDFR`.
+const LDFR_SECOND: u8 = 0b0100_0100;
+
+/// Decode the instruction at the start of `bytes`, which is located at `addr`.
+///
+/// Returns the decoded instruction together with its length in bytes.
+/// `None` is returned, if the first byte is not a valid opcode or if the
+/// instruction is cut short by the end of `bytes`.
+///
+/// Some opcodes are shared by different instructions. In this case the instruction
+/// listed first in [`OPCODES`] is returned, with the exception of opcodes without
+/// operands, which are preferred. Thus `RET` is returned instead of `POP R3`,
+/// but `ADD R0, R0` instead of `LSL R0`. Moves are returned as `LD`, `ST` or
+/// `JMP`, where possible.
+pub fn decode(bytes: &[u8], addr: u8) -> Option<(Instruction, u8)> {
+    let opcode = *bytes.first()?;
+    if opcode & 0xF0 == DESTINATION_SOURCE_OPCODE {
+        return decode_destination_source(bytes);
+    }
+    if let Some((mnemonic, _, _)) = OPCODES
+        .iter()
+        .find(|(_, base, shape)| *shape == OperandShape::None && *base == opcode)
+    {
+        return without_operands(mnemonic).map(|inst| (inst, 1));
+    }
+    for (mnemonic, base, shape) in OPCODES {
+        let inst = match shape {
+            OperandShape::Register if opcode & !0b11 == *base => {
+                with_register(mnemonic, register(opcode))
+            }
+            OperandShape::TwoRegisters if opcode & 0xF0 == *base => {
+                with_two_registers(mnemonic, register(opcode), register(opcode >> 2))
+            }
+            OperandShape::RelativeJump if opcode == *base => {
+                let offset = *bytes.get(1)?;
+                let target = addr.wrapping_add(2).wrapping_add(offset);
+                return relative_jump(mnemonic, target).map(|inst| (inst, 2));
+            }
+            OperandShape::Address if opcode == *base => {
+                let target = *bytes.get(1)?;
+                return Some((Instruction::Call(address_label(target)), 2));
+            }
+            _ => continue,
+        };
+        return inst.map(|inst| (inst, 1));
+    }
+    None
+}
+
+/// Decode an instruction starting with [`DESTINATION_SOURCE_OPCODE`].
+///
+/// ```text
+/// 0b1111_10_11 [0b10110101] 0b0001_11_00 [0b10111001]
+///   ---- MS RS  addr/const    -OP- MD RD  ---addr---
+/// ```
+fn decode_destination_source(bytes: &[u8]) -> Option<(Instruction, u8)> {
+    let mut len = 1;
+    let mut next = || {
+        let byte = bytes.get(len).copied();
+        len += 1;
+        byte
+    };
+    let first = bytes[0];
+    let src_reg = register(first);
+    let src = match ((first >> 2) & 0b11, src_reg) {
+        (0b00, reg) => Source::Register(reg),
+        (0b01, reg) => MemAddress::Register(reg).into(),
+        (0b10, Register::R3) => Constant::Constant(next()?).into(),
+        (0b10, reg) => RegisterDi(reg).into(),
+        (0b11, Register::R3) => MemAddress::Constant(Constant::Constant(next()?)).into(),
+        (_, reg) => RegisterDdi(reg).into(),
+    };
+    let second = next()?;
+    let op = second & 0xF0;
+    if op == LDSP_SECOND & 0xF0 {
+        let inst = match second {
+            LDSP_SECOND => Instruction::Ldsp(src),
+            LDFR_SECOND => Instruction::Ldfr(src),
+            _ => return None,
+        };
+        return Some((inst, len as u8));
+    }
+    let dst_reg = register(second);
+    let dst = match ((second >> 2) & 0b11, dst_reg) {
+        (0b00, reg) => Destination::Register(reg),
+        (0b01, reg) => MemAddress::Register(reg).into(),
+        (0b10, reg) => RegisterDi(reg).into(),
+        (0b11, Register::R3) => MemAddress::Constant(Constant::Constant(next()?)).into(),
+        (_, reg) => RegisterDdi(reg).into(),
+    };
+    let (mnemonic, _, _) = OPCODES.iter().find(|(_, base, shape)| {
+        *base == DESTINATION_SOURCE_OPCODE
+            && matches!(shape, OperandShape::DestinationSource(second) if *second == op)
+    })?;
+    let inst = match (*mnemonic, dst, src) {
+        ("MOV", Destination::Register(Register::R3), Source::Constant(Constant::Constant(c))) => {
+            Instruction::Jmp(address_label(c))
+        }
+        ("MOV", Destination::Register(reg), Source::Constant(c)) => Instruction::LdConstant(reg, c),
+        ("MOV", Destination::Register(reg), Source::MemAddress(mem)) => {
+            Instruction::LdMemAddress(reg, mem)
+        }
+        ("MOV", Destination::MemAddress(mem), Source::Register(reg)) => Instruction::St(mem, reg),
+        ("MOV", dst, src) => Instruction::Mov(dst, src),
+        ("CMP", dst, src) => Instruction::Cmp(dst, src),
+        ("BITT", dst, src) => Instruction::Bitt(dst, src),
+        ("BITS", dst, src) => Instruction::Bits(dst, src),
+        ("BITC", dst, src) => Instruction::Bitc(dst, src),
+        _ => return None,
+    };
+    Some((inst, len as u8))
+}
+
+/// Get the [`Register`] encoded by the two lowest bits.
+fn register(bits: u8) -> Register {
+    match bits & 0b11 {
+        0 => Register::R0,
+        1 => Register::R1,
+        2 => Register::R2,
+        _ => Register::R3,
+    }
+}
+
+/// Use the address as the name of a label.
+fn address_label(addr: u8) -> String {
+    format!("0x{:02X}", addr)
+}
+
+/// Create the instruction without operands with the given mnemonic.
+fn without_operands(mnemonic: &str) -> Option<Instruction> {
+    use Instruction::*;
+    Some(match mnemonic {
+        "STOP" => Stop,
+        "NOP" => Nop,
+        "EI" => Ei,
+        "DI" => Di,
+        "RET" => Ret,
+        "PUSHF" => PushF,
+        "POPF" => PopF,
+        "RETI" => RetI,
+        _ => return None,
+    })
+}
+
+/// Create the instruction with a single register with the given mnemonic.
+fn with_register(mnemonic: &str, reg: Register) -> Option<Instruction> {
+    use Instruction::*;
+    Some(match mnemonic {
+        "CLR" => Clr(reg),
+        "PUSH" => Push(reg),
+        "POP" => Pop(reg),
+        "COM" => Com(reg),
+        "NEG" => Neg(reg),
+        "LSR" => Lsr(reg),
+        "ASR" => Asr(reg),
+        "RRC" => Rrc(reg),
+        "INC" => Inc(reg),
+        "TST" => Tst(reg),
+        "DEC" => Dec(Source::Register(reg)),
+        _ => return None,
+    })
+}
+
+/// Create the instruction with two registers with the given mnemonic.
+fn with_two_registers(mnemonic: &str, dst: Register, src: Register) -> Option<Instruction> {
+    use Instruction::*;
+    Some(match mnemonic {
+        "ADD" => Add(dst, src),
+        "ADC" => Adc(dst, src),
+        "SUB" => Sub(dst, src),
+        "AND" => And(dst, src),
+        "OR" => Or(dst, src),
+        "MUL" => Mul(dst, src),
+        "DIV" => Div(dst, src),
+        "XOR" => Xor(dst, src),
+        _ => return None,
+    })
+}
+
+/// Create the relative jump with the given mnemonic to `target`.
+fn relative_jump(mnemonic: &str, target: u8) -> Option<Instruction> {
+    use Instruction::*;
+    let label = address_label(target);
+    Some(match mnemonic {
+        "JR" => Jr(label),
+        "JCS" => Jcs(label),
+        "JZS" => Jzs(label),
+        "JNS" => Jns(label),
+        "JCC" => Jcc(label),
+        "JZC" => Jzc(label),
+        "JNC" => Jnc(label),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compiler::Translator,
+        parser::{AsmParser, Line},
+    };
+
+    #[test]
+    fn compiled_instructions_are_decoded() {
+        let program = r#"#! mrasm
+    CLR R1
+    ADD R2, R1
+    DEC R0
+    RET
+    POP R2
+    MOV R0, R1
+    MOV (R1+), ((R2+))
+    LD R2, 0x12
+    LD R0, (0x80)
+    LD R1, (R2)
+    ST (0xFF), R0
+    CMP R0, 0x03
+    BITC (0x81), 0x01
+    LDSP 0xEF
+    LDFR (0x40)
+    RETI
+"#;
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        let bytecode = Translator::compile(&asm).expect("Compilation failed");
+        for (line, bytes) in &bytecode.lines {
+            if let Line::Instruction(inst, _) = line {
+                let decoded = decode(bytes, 0);
+                assert_eq!(decoded, Some((inst.clone(), bytes.len() as u8)), "{}", inst);
+            }
+        }
+    }
+
+    #[test]
+    fn jump_targets_are_decoded_as_addresses() {
+        let program = r#"#! mrasm
+    .ORG 0x10
+LOOP:
+    JZS LOOP
+    CALL LOOP
+    JMP LOOP
+"#;
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        let bytecode = Translator::compile(&asm).expect("Compilation failed");
+        let decoded: Vec<_> = bytecode
+            .lines
+            .iter()
+            .filter(|(line, _)| matches!(line, Line::Instruction(inst, _) if !inst.is_directive()))
+            .scan(0x10, |addr, (_, bytes)| {
+                let decoded = decode(bytes, *addr).expect("Decoding failed");
+                *addr += bytes.len() as u8;
+                Some(decoded.0.to_string())
+            })
+            .collect();
+        assert_eq!(decoded, vec!["JZS 0x10", "CALL 0x10", "JMP 0x10"]);
+    }
+
+    #[test]
+    fn invalid_and_truncated_instructions_are_rejected() {
+        assert_eq!(decode(&[], 0), None);
+        assert_eq!(decode(&[0x00], 0), None);
+        assert_eq!(decode(&[0xE0], 0), None);
+        // JR without offset
+        assert_eq!(decode(&[0x20], 0), None);
+        // LD R0, 0x12 without destination byte
+        assert_eq!(decode(&[0xFB, 0x12], 0), None);
+        // Unknown operation in the destination byte
+        assert_eq!(decode(&[0xF0, 0x70], 0), None);
+        assert_eq!(decode(&[0xF0, 0x48], 0), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! Export memory images as Intel HEX and read them back.
+//!
+//! # Example
+//! ```
+//! # use emulator_2a_lib::export;
+//! let hex = export::to_intel_hex(&[0x2A, 0xFF]);
+//! assert_eq!(hex, ":020000002AFFD5\n:00000001FF\n");
+//! assert_eq!(export::parse_intel_hex(&hex).unwrap(), vec![0x2A, 0xFF]);
+//! ```
+use thiserror::Error;
+
+use std::fmt::Write;
+
+/// Number of data bytes written per record.
+const BYTES_PER_RECORD: usize = 16;
+/// Record type of records containing data.
+const DATA_RECORD: u8 = 0x00;
+/// Record type of the record terminating the file.
+const END_OF_FILE_RECORD: u8 = 0x01;
+
+/// Errors thrown when parsing an Intel HEX file.
+///
+/// Line numbers start at one.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HexError {
+    #[error("Line {0} is not a valid Intel HEX record")]
+    InvalidRecord(usize),
+    #[error("The checksum in line {0} does not match the record")]
+    ChecksumMismatch(usize),
+    #[error("Unsupported record type 0x{0:02X} in line {1}")]
+    UnsupportedRecordType(u8, usize),
+    #[error("The end of file record is missing")]
+    MissingEndOfFile,
+}
+
+/// Format `bytes` as Intel HEX, the first byte is placed at address zero.
+///
+/// Every line holds up to 16 bytes and the end of file record is always appended.
+pub fn to_intel_hex(bytes: &[u8]) -> String {
+    let mut hex = String::new();
+    for (index, chunk) in bytes.chunks(BYTES_PER_RECORD).enumerate() {
+        let address = (index * BYTES_PER_RECORD) as u16;
+        write_record(&mut hex, address, DATA_RECORD, chunk);
+    }
+    write_record(&mut hex, 0, END_OF_FILE_RECORD, &[]);
+    hex
+}
+
+/// Parse an Intel HEX file into a memory image starting at address zero.
+///
+/// Gaps between records are filled with zeros. Only data and end of file
+/// records are supported, everything after the end of file is ignored.
+pub fn parse_intel_hex(input: &str) -> Result<Vec<u8>, HexError> {
+    let mut memory = vec![];
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let record = decode_record(line).ok_or(HexError::InvalidRecord(number))?;
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(HexError::ChecksumMismatch(number));
+        }
+        let address = u16::from_be_bytes([record[1], record[2]]) as usize;
+        let data = &record[4..record.len() - 1];
+        match record[3] {
+            DATA_RECORD => {
+                let end = address + data.len();
+                if memory.len() < end {
+                    memory.resize(end, 0);
+                }
+                memory[address..end].copy_from_slice(data);
+            }
+            END_OF_FILE_RECORD => return Ok(memory),
+            kind => return Err(HexError::UnsupportedRecordType(kind, number)),
+        }
+    }
+    Err(HexError::MissingEndOfFile)
+}
+
+/// Append a single record, including its checksum, to `hex`.
+fn write_record(hex: &mut String, address: u16, kind: u8, data: &[u8]) {
+    let [high, low] = address.to_be_bytes();
+    let header = [data.len() as u8, high, low, kind];
+    let sum = header
+        .iter()
+        .chain(data)
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    hex.push(':');
+    for byte in header.iter().chain(data) {
+        write!(hex, "{:02X}", byte).expect("Writing to a string never fails");
+    }
+    writeln!(hex, "{:02X}", sum.wrapping_neg()).expect("Writing to a string never fails");
+}
+
+/// Decode all bytes of a record line, if it is well-formed.
+///
+/// The byte count is verified against the length of the line.
+fn decode_record(line: &str) -> Option<Vec<u8>> {
+    let digits = line.strip_prefix(':')?;
+    if !digits.is_ascii() || digits.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..digits.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&digits[idx..idx + 2], 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    // Byte count, address, record type and checksum
+    if bytes.len() < 5 || bytes[0] as usize != bytes.len() - 5 {
+        return None;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_survives_a_round_trip() {
+        let memory: Vec<u8> = (0..0xF0).map(|byte| byte as u8 ^ 0x5A).collect();
+        let hex = to_intel_hex(&memory);
+        assert_eq!(hex.lines().count(), 0xF0 / 16 + 1);
+        assert!(hex.starts_with(":10000000"));
+        assert_eq!(parse_intel_hex(&hex), Ok(memory));
+        assert_eq!(parse_intel_hex(&to_intel_hex(&[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn gaps_are_filled_with_zeros() {
+        let hex = ":0100040011EA\n\n:00000001FF\n:garbage after the end\n";
+        assert_eq!(parse_intel_hex(hex), Ok(vec![0, 0, 0, 0, 0x11]));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        use HexError::*;
+        assert_eq!(parse_intel_hex(""), Err(MissingEndOfFile));
+        assert_eq!(parse_intel_hex("00000001FF"), Err(InvalidRecord(1)));
+        assert_eq!(parse_intel_hex(":00000001F"), Err(InvalidRecord(1)));
+        assert_eq!(parse_intel_hex(":00000001XX"), Err(InvalidRecord(1)));
+        assert_eq!(parse_intel_hex(":0200000001FF"), Err(InvalidRecord(1)));
+        assert_eq!(parse_intel_hex(":ÄÄ000001FF"), Err(InvalidRecord(1)));
+        assert_eq!(
+            parse_intel_hex(":020000002AFFD5\n:00000001FE"),
+            Err(ChecksumMismatch(2))
+        );
+        assert_eq!(
+            parse_intel_hex(":00000004FC\n"),
+            Err(UnsupportedRecordType(0x04, 1))
+        );
+    }
+}
@@ -5,6 +5,9 @@
 //mod error;
 //mod helpers;
 pub mod compiler;
+pub mod disassembler;
+pub mod export;
+pub mod lint;
 pub mod machine;
 pub mod parser;
 pub mod runner;
@@ -0,0 +1,455 @@
+//! Lints for suspicious, but valid, assembly programs.
+//!
+//! # Example
+//! ```
+//! # use emulator_2a_lib::{compiler::Translator, lint::{self, Lint}, parser::AsmParser};
+//! let asm = AsmParser::parse("#! mrasm\nUNUSED:\n    STOP\n    CLR R0\n").unwrap();
+//! let bytecode = Translator::compile(&asm).unwrap();
+//!
+//! let findings = lint::lint(&bytecode, &[]);
+//! assert_eq!(findings.len(), 3);
+//! assert_eq!(findings[0].to_string(), "line 2: Label 'UNUSED' is never referenced [unused-label]");
+//!
+//! let findings = lint::lint(&bytecode, &[Lint::UnusedLabel, Lint::UnreadRegister]);
+//! assert_eq!(findings[0].lint, Lint::UnreachableCode);
+//! assert_eq!(findings[0].line, 4);
+//! ```
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::{
+    compiler::{ByteCode, FIRST_SOURCE_LINE},
+    parser::{
+        defined_label, referenced_labels, Asm, Destination, Instruction, Line, MemAddress,
+        Register, RegisterDdi, RegisterDi, Source,
+    },
+};
+
+/// Address the CPU jumps to when accepting an interrupt.
+const INTERRUPT_VECTOR: usize = 0x02;
+
+/// The different lints, each can be suppressed individually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lint {
+    /// Instructions following an unconditional `JMP`, `JR`, `RET`, `RETI` or
+    /// `STOP`, that are neither labeled nor located at the interrupt vector.
+    UnreachableCode,
+    /// Labels and `.EQU` constants that are defined but never referenced.
+    UnusedLabel,
+    /// Registers R0 to R2 that are written but never read anywhere.
+    UnreadRegister,
+}
+
+/// A single problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The lint that produced this finding.
+    pub lint: Lint,
+    /// The source line, the first line contains the `#! mrasm` header.
+    pub line: usize,
+    /// Human readable description of the problem.
+    pub message: String,
+}
+
+impl Lint {
+    /// All available lints.
+    pub const ALL: [Lint; 3] = [
+        Lint::UnreachableCode,
+        Lint::UnusedLabel,
+        Lint::UnreadRegister,
+    ];
+
+    /// Name of the lint, as accepted by [`Lint::from_str`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::UnusedLabel => "unused-label",
+            Lint::UnreadRegister => "unread-register",
+        }
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .iter()
+            .find(|lint| lint.name().eq_ignore_ascii_case(s))
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Lint::ALL.iter().map(Lint::name).collect();
+                format!("Unknown lint '{}', expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} [{}]", self.line, self.message, self.lint)
+    }
+}
+
+/// Run all lints, except for the `allowed` ones, against the program.
+///
+/// Findings are sorted by their line.
+pub fn lint(bytecode: &ByteCode, allowed: &[Lint]) -> Vec<Finding> {
+    let lines: Vec<_> = bytecode.lines.iter().map(|(line, _)| line).collect();
+    let mut findings = vec![];
+    if !allowed.contains(&Lint::UnreachableCode) {
+        findings.extend(unreachable_code(bytecode));
+    }
+    if !allowed.contains(&Lint::UnusedLabel) {
+        findings.extend(unused_labels(&lines));
+    }
+    if !allowed.contains(&Lint::UnreadRegister) {
+        findings.extend(unread_registers(&lines));
+    }
+    findings.sort_by_key(|finding| finding.line);
+    findings
+}
+
+/// Count how often each instruction is used in the program.
+///
+/// This is purely static, every line counts once, no matter how often it is
+/// executed. Assembler directives are ignored and both forms of `LD` are counted
+/// together. The result is sorted by the number of uses, most used first, ties
+/// are sorted by mnemonic.
+pub fn instruction_histogram(asm: &Asm) -> Vec<(&'static str, usize)> {
+    let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+    for inst in asm.instructions().filter(|inst| !inst.is_directive()) {
+        *counts.entry(inst.mnemonic()).or_default() += 1;
+    }
+    let mut histogram: Vec<_> = counts.into_iter().collect();
+    histogram.sort_by(|(_, a), (_, b)| b.cmp(a));
+    histogram
+}
+
+/// Report the first instruction of every block that cannot be reached.
+///
+/// A block starts after an unconditional jump and ends at the next label
+/// or at the interrupt vector.
+fn unreachable_code(bytecode: &ByteCode) -> Vec<Finding> {
+    let mut findings = vec![];
+    // The instruction ending the reachable code, if we are in an unreachable block
+    let mut terminator: Option<&Instruction> = None;
+    let mut addr = 0;
+    for (idx, (line, bytes)) in bytecode.lines.iter().enumerate() {
+        match line {
+            Line::Label(_, _) => terminator = None,
+            Line::Instruction(inst, _) if !inst.is_directive() => {
+                if addr == INTERRUPT_VECTOR {
+                    terminator = None;
+                }
+                if let Some(term) = terminator.take() {
+                    findings.push(Finding {
+                        lint: Lint::UnreachableCode,
+                        line: idx + FIRST_SOURCE_LINE,
+                        message: format!("Unreachable code after '{}'", term),
+                    });
+                } else if is_unconditional_jump(inst) {
+                    terminator = Some(inst);
+                }
+            }
+            _ => {}
+        }
+        addr += bytes.len();
+    }
+    findings
+}
+
+/// Does the CPU never continue with the next instruction after this one?
+fn is_unconditional_jump(inst: &Instruction) -> bool {
+    matches!(
+        inst,
+        Instruction::Jmp(_)
+            | Instruction::Jr(_)
+            | Instruction::Ret
+            | Instruction::RetI
+            | Instruction::Stop
+    )
+}
+
+/// Report every label that is never referenced, ignoring duplicates.
+fn unused_labels(lines: &[&Line]) -> Vec<Finding> {
+    let references: Vec<_> = lines
+        .iter()
+        .flat_map(|line| referenced_labels(line))
+        .map(|label| label.to_lowercase())
+        .collect();
+    let mut seen: Vec<String> = vec![];
+    let mut findings = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(label) = defined_label(line) {
+            let lowercase = label.to_lowercase();
+            if !seen.contains(&lowercase) && !references.contains(&lowercase) {
+                findings.push(Finding {
+                    lint: Lint::UnusedLabel,
+                    line: idx + FIRST_SOURCE_LINE,
+                    message: format!("Label '{}' is never referenced", label),
+                });
+            }
+            seen.push(lowercase);
+        }
+    }
+    findings
+}
+
+/// Report every general purpose register that is written, but never read.
+///
+/// The finding points to the first write. Using a register as an address
+/// counts as reading it. The program counter R3 is ignored.
+fn unread_registers(lines: &[&Line]) -> Vec<Finding> {
+    let mut first_writes = BTreeMap::new();
+    let mut reads = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        if let Line::Instruction(inst, _) = line {
+            let (read, written) = register_usage(inst);
+            reads.extend(read);
+            for reg in written {
+                first_writes.entry(reg).or_insert(idx + FIRST_SOURCE_LINE);
+            }
+        }
+    }
+    first_writes
+        .into_iter()
+        .filter(|(reg, _)| *reg != Register::R3 && !reads.contains(reg))
+        .map(|(reg, line)| Finding {
+            lint: Lint::UnreadRegister,
+            line,
+            message: format!("Register {} is written but never read", reg),
+        })
+        .collect()
+}
+
+/// Get the registers read and written by the instruction.
+fn register_usage(inst: &Instruction) -> (Vec<Register>, Vec<Register>) {
+    use Instruction::*;
+    match inst {
+        Clr(reg) | LdConstant(reg, _) | Pop(reg) => (vec![], vec![*reg]),
+        LdMemAddress(reg, mem) => (mem_reads(mem), vec![*reg]),
+        Add(a, b)
+        | Adc(a, b)
+        | Sub(a, b)
+        | Mul(a, b)
+        | Div(a, b)
+        | And(a, b)
+        | Or(a, b)
+        | Xor(a, b) => (vec![*a, *b], vec![*a]),
+        Inc(reg) | Neg(reg) | Com(reg) | Lsr(reg) | Asr(reg) | Lsl(reg) | Rrc(reg) | Rlc(reg) => {
+            (vec![*reg], vec![*reg])
+        }
+        Tst(reg) | Push(reg) => (vec![*reg], vec![]),
+        St(mem, reg) => {
+            let mut reads = mem_reads(mem);
+            reads.push(*reg);
+            (reads, vec![])
+        }
+        Dec(src) => match src {
+            Source::Register(reg) => (vec![*reg], vec![*reg]),
+            src => (source_reads(src), vec![]),
+        },
+        Bits(dst, src) | Bitc(dst, src) => {
+            let mut reads = source_reads(src);
+            reads.extend(destination_reads(dst));
+            (reads, destination_writes(dst))
+        }
+        Cmp(dst, src) | Bitt(dst, src) => {
+            let mut reads = source_reads(src);
+            reads.extend(destination_reads(dst));
+            (reads, vec![])
+        }
+        Mov(dst, src) => {
+            let mut reads = source_reads(src);
+            if !matches!(dst, Destination::Register(_)) {
+                reads.extend(destination_reads(dst));
+            }
+            (reads, destination_writes(dst))
+        }
+        Ldsp(src) | Ldfr(src) => (source_reads(src), vec![]),
+        _ => (vec![], vec![]),
+    }
+}
+
+/// Registers read when using the source.
+fn source_reads(src: &Source) -> Vec<Register> {
+    match src {
+        Source::Register(reg)
+        | Source::RegisterDi(RegisterDi(reg))
+        | Source::RegisterDdi(RegisterDdi(reg)) => vec![*reg],
+        Source::MemAddress(mem) => mem_reads(mem),
+        Source::Constant(_) => vec![],
+    }
+}
+
+/// Registers read when using the destination, including its old value.
+fn destination_reads(dst: &Destination) -> Vec<Register> {
+    match dst {
+        Destination::Register(reg)
+        | Destination::RegisterDi(RegisterDi(reg))
+        | Destination::RegisterDdi(RegisterDdi(reg)) => vec![*reg],
+        Destination::MemAddress(mem) => mem_reads(mem),
+    }
+}
+
+/// Registers written when storing to the destination.
+fn destination_writes(dst: &Destination) -> Vec<Register> {
+    match dst {
+        Destination::Register(reg) => vec![*reg],
+        _ => vec![],
+    }
+}
+
+/// Registers read when dereferencing the address.
+fn mem_reads(mem: &MemAddress) -> Vec<Register> {
+    match mem {
+        MemAddress::Register(reg) => vec![*reg],
+        MemAddress::Constant(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler::Translator, parser::AsmParser};
+
+    fn lint_source(source: &str, allowed: &[Lint]) -> Vec<Finding> {
+        let asm = AsmParser::parse(source).expect("Failed to parse program");
+        let bytecode = Translator::compile(&asm).expect("Failed to compile program");
+        lint(&bytecode, allowed)
+    }
+
+    #[test]
+    fn code_after_unconditional_jumps_is_unreachable() {
+        let findings = lint_source(
+            r#"#! mrasm
+    .ORG 0
+    JR MAIN
+    JR ISR
+MAIN:
+    JZS DONE
+    JMP MAIN
+    CLR R0
+    INC R0
+DONE:
+    STOP
+    .DB 1, 2
+ISR:
+    RETI
+    NOP
+"#,
+            &[Lint::UnreadRegister],
+        );
+        // The second JR is at the interrupt vector, the data is no code
+        assert_eq!(
+            findings,
+            vec![
+                Finding {
+                    lint: Lint::UnreachableCode,
+                    line: 8,
+                    message: "Unreachable code after 'JMP MAIN'".into(),
+                },
+                Finding {
+                    lint: Lint::UnreachableCode,
+                    line: 15,
+                    message: "Unreachable code after 'RETI'".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unreferenced_labels_are_reported() {
+        let findings = lint_source(
+            r#"#! mrasm
+    .EQU UNUSED_CONSTANT 16
+    .EQU USED_CONSTANT 32
+    ST (USED_CONSTANT), R0
+unused:
+loop:
+    JR loop
+"#,
+            &[],
+        );
+        let lines: Vec<_> = findings.iter().map(|f| (f.lint, f.line)).collect();
+        assert_eq!(lines, vec![(Lint::UnusedLabel, 2), (Lint::UnusedLabel, 5)]);
+        assert_eq!(findings[1].message, "Label 'unused' is never referenced");
+        assert!(lint_source("#! mrasm\nUNUSED:\n", &[Lint::UnusedLabel]).is_empty());
+    }
+
+    #[test]
+    fn registers_without_reads_are_reported() {
+        let findings = lint_source(
+            r#"#! mrasm
+    MOV R0, 1
+    MOV R1, R0
+    CLR R2
+    ADD R2, R1
+    LD R1, (R2)
+    MOV (R0+), 4
+    ST (0xFF), R2
+    POP R1
+"#,
+            &[],
+        );
+        assert!(findings.is_empty());
+        let findings = lint_source(
+            r#"#! mrasm
+    CLR R1
+    MOV R0, 3
+    LD R2, (0x10)
+LOOP:
+    MOV R0, 4
+    JR LOOP
+"#,
+            &[],
+        );
+        let lines: Vec<_> = findings.iter().map(|f| (f.lint, f.line)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Lint::UnreadRegister, 2),
+                (Lint::UnreadRegister, 3),
+                (Lint::UnreadRegister, 4)
+            ]
+        );
+        assert_eq!(findings[1].message, "Register R0 is written but never read");
+        assert!(lint_source("#! mrasm\n    CLR R0\n", &[Lint::UnreadRegister]).is_empty());
+    }
+
+    #[test]
+    fn lints_are_parsed_by_name() {
+        for lint in &Lint::ALL {
+            assert_eq!(lint.name().parse(), Ok(*lint));
+        }
+        assert_eq!("Unused-Label".parse(), Ok(Lint::UnusedLabel));
+        assert!("unused".parse::<Lint>().is_err());
+    }
+
+    #[test]
+    fn instructions_are_counted_by_mnemonic() {
+        let asm = AsmParser::parse(
+            r#"#! mrasm
+    .ORG 0
+    MOV R0, R1
+    LD R1, 2
+    MOV R1, R2
+    LD R2, (0x80)
+LOOP:
+    MOV R2, R0
+    JR LOOP
+    .DB 1, 2
+"#,
+        )
+        .expect("Failed to parse program");
+        assert_eq!(
+            instruction_histogram(&asm),
+            vec![("MOV", 3), ("LD", 2), ("JR", 1)]
+        );
+    }
+}
@@ -45,6 +45,30 @@ enum_from_primitive! {
     }
 }
 
+/// The ALU as a pure function.
+///
+/// This is a shortcut for creating an [`AluInput`] and calculating
+/// its [`AluOutput`], i.e. to print truth tables.
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::machine::{Alu, AluSelect};
+/// let output = Alu::evaluate(0xFF, 0x01, false, AluSelect::ADC);
+/// assert_eq!(output.output(), 0x00);
+/// assert_eq!(output.carry_out(), true);
+/// assert_eq!(output.zero_out(), true);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Alu;
+
+impl Alu {
+    /// Calculate the output of function `op` for inputs `a`, `b` and `carry_in`.
+    pub fn evaluate(a: u8, b: u8, carry_in: bool, op: AluSelect) -> AluOutput {
+        AluOutput::from_input(&AluInput::new(a, b, carry_in), &op)
+    }
+}
+
 /// Input values needed for the ALU.
 ///
 /// # Example
@@ -109,6 +133,7 @@ impl AluInput {
 /// assert_eq!(output.carry_out(), false);
 /// assert_eq!(output.zero_out(), false);
 /// assert_eq!(output.negative_out(), false);
+/// assert_eq!(output.overflow_out(), false);
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(Arbitrary))]
@@ -116,8 +141,10 @@ pub struct AluOutput {
     /// Main output of the last operation.
     output: u8,
     /// Carry out of the operation, usually indicating
-    /// some sort of overflow.
+    /// some sort of unsigned overflow.
     carry_out: bool,
+    /// Signed overflow of the operation, only set by additive functions.
+    overflow_out: bool,
     /// Zero out of the operation, set when the output is zero.
     zero_out: bool,
     /// Negative out of the operation, set when the highest bit of the output is set.
@@ -126,6 +153,17 @@ pub struct AluOutput {
 
 impl AluOutput {
     /// Calculate the output from the input of the alu.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::{AluInput, AluSelect, AluOutput};
+    /// // Subtraction adds the inverted B, the carry out is the inverted borrow
+    /// let input = AluInput::new(10, !3, false);
+    /// let output = AluOutput::from_input(&input, &AluSelect::ADDS);
+    /// assert_eq!(output.output(), 7);
+    /// assert_eq!(output.carry_out(), false);
+    /// ```
     pub fn from_input(input: &AluInput, function: &AluSelect) -> Self {
         let a = input.input_a;
         let b = input.input_b;
@@ -157,8 +195,7 @@ impl AluOutput {
             }
             AluSelect::RR => {
                 let carry_out = (a & 0b0000_0001) != 0;
-                let (o, c) = a.overflowing_shr(1);
-                (o | (c as u8) << 7, carry_out)
+                (a.rotate_right(1), carry_out)
             }
             AluSelect::RRC => {
                 let carry_out = (a & 0b0000_0001) != 0;
@@ -175,9 +212,19 @@ impl AluOutput {
             AluSelect::BH => (b, carry_in),
             AluSelect::INVC => (b, !carry_in),
         };
+        // The sum of two operands with equal signs must keep that sign
+        let overflow_out = match function {
+            AluSelect::ADDH
+            | AluSelect::ADD
+            | AluSelect::ADDS
+            | AluSelect::ADC
+            | AluSelect::ADCS => (a ^ out) & (b ^ out) & 0b1000_0000 != 0,
+            _ => false,
+        };
         AluOutput {
             output: out,
             carry_out,
+            overflow_out,
             zero_out: out == 0,
             negative_out: out & 0b1000_0000 != 0,
         }
@@ -187,6 +234,7 @@ impl AluOutput {
     pub const fn default() -> Self {
         AluOutput {
             carry_out: false,
+            overflow_out: false,
             zero_out: false,
             negative_out: false,
             output: 0,
@@ -201,6 +249,17 @@ impl AluOutput {
     pub const fn carry_out(&self) -> bool {
         self.carry_out
     }
+    /// Get the signed overflow of the last operation.
+    ///
+    /// This is set, if the result of an additive function ([`AluSelect::ADDH`],
+    /// [`AluSelect::ADD`], [`AluSelect::ADDS`], [`AluSelect::ADC`] and
+    /// [`AluSelect::ADCS`]) does not fit into a signed byte, i.e. both operands
+    /// have the same sign, but the output has the other. All other functions
+    /// never set it. The Minirechner 2a has no flag for it, it is not stored
+    /// in the flag register.
+    pub const fn overflow_out(&self) -> bool {
+        self.overflow_out
+    }
     /// Get the zero out flag of the last operation. This is set if the output is zero.
     pub const fn zero_out(&self) -> bool {
         self.zero_out
@@ -212,6 +271,38 @@ impl AluOutput {
     }
 }
 
+impl AluSelect {
+    /// Get the four select bits of this function, ordered `(s3, s2, s1, s0)`.
+    ///
+    /// `s3` is the most significant bit, i.e. the microprogram word bit `MALUS3`.
+    /// This is the inverse of `AluSelect::from((s3, s2, s1, s0))`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::AluSelect;
+    /// assert_eq!(AluSelect::LSR.to_bits(), (true, false, false, false));
+    /// assert_eq!(AluSelect::from((false, true, true, false)), AluSelect::ADC);
+    /// ```
+    pub const fn to_bits(&self) -> (bool, bool, bool, bool) {
+        let select = *self as u8;
+        (
+            select & 0b1000 != 0,
+            select & 0b0100 != 0,
+            select & 0b0010 != 0,
+            select & 0b0001 != 0,
+        )
+    }
+}
+
+impl From<(bool, bool, bool, bool)> for AluSelect {
+    /// Create the function selected by the bits `(s3, s2, s1, s0)`.
+    fn from((s3, s2, s1, s0): (bool, bool, bool, bool)) -> Self {
+        let select = (s3 as u8) << 3 | (s2 as u8) << 2 | (s1 as u8) << 1 | s0 as u8;
+        AluSelect::from_u8(select).expect("infallible")
+    }
+}
+
 impl Default for AluSelect {
     fn default() -> Self {
         AluSelect::from_u8(0).expect("infallible")
@@ -235,5 +326,84 @@ mod tests {
             let output = AluOutput::from_input(&input, &function);
             assert_eq!(output.output() & 0b10000000 == 0b10000000, output.negative_out());
         }
+
+        #[test]
+        fn alu_select_bits_round_trip(function in any::<AluSelect>()) {
+            assert_eq!(AluSelect::from(function.to_bits()), function);
+        }
+
+        #[test]
+        fn alu_overflow_matches_signed_addition(a in any::<u8>(), b in any::<u8>()) {
+            let output = AluOutput::from_input(&AluInput::new(a, b, false), &AluSelect::ADD);
+            assert_eq!((a as i8).checked_add(b as i8).is_none(), output.overflow_out());
+        }
+    }
+
+    #[test]
+    fn carry_and_overflow_are_distinct() {
+        let output = AluOutput::from_input(&AluInput::new(0x7F, 0x01, false), &AluSelect::ADD);
+        assert_eq!(output.output(), 0x80);
+        assert!(output.overflow_out());
+        assert!(!output.carry_out());
+        let output = AluOutput::from_input(&AluInput::new(0xFF, 0x01, false), &AluSelect::ADD);
+        assert_eq!(output.output(), 0x00);
+        assert!(!output.overflow_out());
+        assert!(output.carry_out());
+    }
+
+    #[test]
+    fn additions_work() {
+        let output = Alu::evaluate(200, 100, true, AluSelect::ADD);
+        assert_eq!((output.output(), output.carry_out()), (44, true));
+        let output = Alu::evaluate(20, 100, true, AluSelect::ADC);
+        assert_eq!((output.output(), output.carry_out()), (121, false));
+        let output = Alu::evaluate(255, 0, true, AluSelect::ADC);
+        assert_eq!((output.output(), output.carry_out()), (0, true));
+    }
+
+    #[test]
+    fn subtractions_work() {
+        // A - B is calculated as A + !B + 1, carry out is set on borrow
+        let output = Alu::evaluate(42, !2, false, AluSelect::ADDS);
+        assert_eq!((output.output(), output.carry_out()), (40, false));
+        let output = Alu::evaluate(2, !42, false, AluSelect::ADDS);
+        assert_eq!((output.output(), output.carry_out()), (216, true));
+        // With a borrow from the previous subtraction, one more is subtracted
+        let output = Alu::evaluate(42, !2, true, AluSelect::ADCS);
+        assert_eq!((output.output(), output.carry_out()), (39, false));
+    }
+
+    #[test]
+    fn logic_functions_can_be_built_from_nor() {
+        let nor = |a, b| Alu::evaluate(a, b, false, AluSelect::NOR).output();
+        let (a, b) = (0b1100_1010, 0b1010_0110);
+        assert_eq!(nor(a, b), !(a | b));
+        let or = nor(nor(a, b), nor(a, b));
+        assert_eq!(or, a | b);
+        let and = nor(nor(a, a), nor(b, b));
+        assert_eq!(and, a & b);
+        let xor = nor(nor(or, or), and);
+        assert_eq!(xor, a ^ b);
+    }
+
+    #[test]
+    fn shifts_and_rotates_work() {
+        let run = |a, carry_in, op| {
+            let output = Alu::evaluate(a, 0, carry_in, op);
+            (output.output(), output.carry_out())
+        };
+        assert_eq!(run(0b1000_0011, true, AluSelect::LSR), (0b0100_0001, true));
+        assert_eq!(run(0b1000_0010, true, AluSelect::ASR), (0b1100_0001, false));
+        assert_eq!(run(0b0000_0011, false, AluSelect::RR), (0b1000_0001, true));
+        assert_eq!(run(0b0000_0010, true, AluSelect::RRC), (0b1000_0001, false));
+        assert_eq!(run(0b0000_0011, false, AluSelect::RRC), (0b0000_0001, true));
+    }
+
+    #[test]
+    fn only_additive_functions_overflow() {
+        let input = AluInput::new(0x7F, 0x7F, true);
+        for function in &[AluSelect::A, AluSelect::NOR, AluSelect::LSR, AluSelect::B] {
+            assert!(!AluOutput::from_input(&input, function).overflow_out());
+        }
     }
 }
@@ -11,6 +11,39 @@ use std::u8;
 
 const MAX_FAN_RPM: usize = 4200;
 
+/// Voltage of the temperature sensor and all analog ports of a new [`Board`].
+pub const DEFAULT_VOLTAGE: f32 = 0.0;
+
+/// Lowest voltage accepted by the analog ports of the [`Board`].
+pub const MIN_VOLTAGE: f32 = 0.0;
+/// Highest voltage accepted by the analog ports of the [`Board`].
+pub const MAX_VOLTAGE: f32 = 5.0;
+
+/// Reference voltage of the DACs of a new [`Board`].
+///
+/// This is the voltage produced by a DAC for the value `255`, one step
+/// of the real DACs thus equals 10mV.
+pub const DEFAULT_REFERENCE_VOLTAGE: f32 = 2.55;
+
+/// Voltages closer than this are considered equal, see [`voltages_equal`].
+///
+/// The DACs of the board use steps of 10mV and all voltages are displayed
+/// with two decimals, thus differences below half a step are irrelevant.
+pub const VOLTAGE_EPSILON: f32 = 0.005;
+
+/// Compare two voltages, ignoring differences below [`VOLTAGE_EPSILON`].
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::machine::voltages_equal;
+/// assert!(voltages_equal(0.3 - 0.2, 0.1));
+/// assert!(!voltages_equal(0.1, 0.11));
+/// ```
+pub fn voltages_equal(a: f32, b: f32) -> bool {
+    (a - b).abs() < VOLTAGE_EPSILON
+}
+
 /// The external board of the Minirechner 2a (MR2DA2).
 ///
 /// ```text
@@ -61,6 +94,8 @@ pub struct Board {
     analog_inputs: [f32; 2],
     /// Analog output ports: O1 and O2.
     analog_outputs: [f32; 2],
+    /// Voltage produced by the DACs for the value `255`.
+    reference_voltage: f32,
     /// Fan rpms. This is an oversimplification. The maximum fan rpm equals 4200.
     /// But this fan spins even at 0.1V supply voltage.
     /// freq(volt) = 70Hz / 2.55V * volt
@@ -160,9 +195,10 @@ impl Board {
             daisr: DAISR::empty(),
             daicr: DAICR::empty(),
             digital_input1: 0,
-            temp: 0.0,
-            analog_inputs: [0.0; 2],
-            analog_outputs: [0.0; 2],
+            temp: DEFAULT_VOLTAGE,
+            analog_inputs: [DEFAULT_VOLTAGE; 2],
+            analog_outputs: [DEFAULT_VOLTAGE; 2],
+            reference_voltage: DEFAULT_REFERENCE_VOLTAGE,
             digital_output1: 0,
             digital_output2: 0,
             fan_rpm: 0,
@@ -176,18 +212,14 @@ impl Board {
     }
 
     /// Set the temperature value.
+    ///
+    /// See [`clamp_voltage`] for the handling of invalid voltages.
     pub fn set_temp(&mut self, value: f32) {
         trace!("Setting temperature to {}", value);
-        if (0.0..=5.0).contains(&value) {
+        if let Some(value) = clamp_voltage("Temperature value", value) {
             self.temp = value;
-        } else if value >= 0.0 {
-            warn!("Temperature value > 5.0. Set to 5.0!");
-            self.temp = 5.0;
-        } else {
-            warn!("Temperature value < 0.0. Set to 0.0!");
-            self.temp = 0.0;
+            self.update_comp2();
         }
-        self.update_comp2();
     }
 
     /// Set the jumper J1.
@@ -221,31 +253,23 @@ impl Board {
     }
 
     /// Set analog input port I1.
+    ///
+    /// See [`clamp_voltage`] for the handling of invalid voltages.
     pub fn set_analog_input1(&mut self, value: f32) {
-        if (0.0..=5.0).contains(&value) {
+        if let Some(value) = clamp_voltage("I1", value) {
             self.analog_inputs[0] = value;
-        } else if value >= 0.0 {
-            warn!("I1 > 5V. Setting 5V");
-            self.analog_inputs[0] = 5.0;
-        } else {
-            warn!("I1 < 0V. Setting 0V");
-            self.analog_inputs[0] = 0.0;
+            self.update_comp1();
         }
-        self.update_comp1();
     }
 
     /// Set analog input port I2.
+    ///
+    /// See [`clamp_voltage`] for the handling of invalid voltages.
     pub fn set_analog_input2(&mut self, value: f32) {
-        if (0.0..=5.0).contains(&value) {
+        if let Some(value) = clamp_voltage("I2", value) {
             self.analog_inputs[1] = value;
-        } else if value >= 0.0 {
-            warn!("I2 > 5V. Setting 5V");
-            self.analog_inputs[1] = 5.0;
-        } else {
-            warn!("I2 < 0V. Setting 0V");
-            self.analog_inputs[1] = 0.0;
+            self.update_comp2();
         }
-        self.update_comp2();
     }
 
     /// Set universal input/output port UIO1.
@@ -314,10 +338,42 @@ impl Board {
         self.dasr.set(DASR::UIO_3, value);
     }
 
+    /// Set the reference voltage of both DACs.
+    ///
+    /// The DACs scale linearly, the value `255` produces exactly this voltage.
+    /// The analog outputs and comparators are updated immediately.
+    /// See [`clamp_voltage`] for the handling of invalid voltages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::{Board, DASR};
+    /// let mut board = Board::new();
+    /// board.set_analog_input1(2.0);
+    /// board.set_digital_output1(204);
+    /// // 2.04V at the DAC exceed the input
+    /// assert!(!board.dasr().contains(DASR::COMP_DAC1));
+    /// board.set_reference_voltage(2.4);
+    /// // Now the DAC only produces 1.92V
+    /// assert!(board.dasr().contains(DASR::COMP_DAC1));
+    /// ```
+    pub fn set_reference_voltage(&mut self, value: f32) {
+        match clamp_voltage("Reference voltage", value) {
+            Some(value) => self.reference_voltage = value,
+            None => return,
+        }
+        self.analog_outputs = [
+            self.dac_voltage(self.digital_output1),
+            self.dac_voltage(self.digital_output2),
+        ];
+        self.update_comp1();
+        self.update_comp2();
+    }
+
     /// Set the 8-bit output port ORG1.
     pub fn set_digital_output1(&mut self, value: u8) {
         self.digital_output1 = value;
-        let analog = value as f32 / 100.0;
+        let analog = self.dac_voltage(value);
         self.analog_outputs[0] = analog;
         self.update_comp1();
         self.fan_rpm = (MAX_FAN_RPM as f32 * analog / 2.55) as usize;
@@ -327,8 +383,7 @@ impl Board {
     /// Set the 8-bit output port ORG2.
     pub fn set_digital_output2(&mut self, value: u8) {
         self.digital_output2 = value;
-        let analog = value as f32 / 100.0;
-        self.analog_outputs[1] = analog;
+        self.analog_outputs[1] = self.dac_voltage(value);
         self.update_comp2();
     }
 
@@ -397,9 +452,15 @@ impl Board {
         self.daisr.insert(DAISR::INTERRUPT_FF);
     }
 
+    /// Convert a DAC value into its output voltage.
+    fn dac_voltage(&self, value: u8) -> f32 {
+        // Dividing by 100 first keeps the default steps at exactly 10mV
+        value as f32 / 100.0 * (self.reference_voltage / DEFAULT_REFERENCE_VOLTAGE)
+    }
+
     /// Update comparator COMP1.
     fn update_comp1(&mut self) {
-        let analog = self.digital_output1 as f32 / 100.0;
+        let analog = self.dac_voltage(self.digital_output1);
         let new_value = self.analog_inputs[0] > analog;
         if self.daicr().interrupt_source() == InterruptSource::Comp1 {
             if self.dasr.contains(DASR::COMP_DAC1) && !new_value {
@@ -421,7 +482,7 @@ impl Board {
 
     /// Update comparator COMP2.
     fn update_comp2(&mut self) {
-        let analog = self.digital_output2 as f32 / 100.0;
+        let analog = self.dac_voltage(self.digital_output2);
         // TODO: Verify (J9)
         let comp_in = self.temp.max(self.analog_inputs[1]);
         let new_value = comp_in > analog;
@@ -470,6 +531,21 @@ impl Board {
         &self.temp
     }
 
+    /// Is the temperature sensor still at its [`DEFAULT_VOLTAGE`]?
+    pub fn is_temp_default(&self) -> bool {
+        voltages_equal(self.temp, DEFAULT_VOLTAGE)
+    }
+
+    /// Is the analog input port with the given index (0 or 1) at its [`DEFAULT_VOLTAGE`]?
+    pub fn is_analog_input_default(&self, index: usize) -> bool {
+        voltages_equal(self.analog_inputs[index], DEFAULT_VOLTAGE)
+    }
+
+    /// Is the analog output port with the given index (0 or 1) at its [`DEFAULT_VOLTAGE`]?
+    pub fn is_analog_output_default(&self, index: usize) -> bool {
+        voltages_equal(self.analog_outputs[index], DEFAULT_VOLTAGE)
+    }
+
     pub const fn dasr(&self) -> &DASR {
         &self.dasr
     }
@@ -490,6 +566,10 @@ impl Board {
         &self.analog_outputs
     }
 
+    pub const fn reference_voltage(&self) -> &f32 {
+        &self.reference_voltage
+    }
+
     pub const fn fan_rpm(&self) -> &usize {
         &self.fan_rpm
     }
@@ -499,6 +579,36 @@ impl Board {
     }
 }
 
+/// Clamp a voltage set on the board into the valid range of `0.0` to `5.0` Volts.
+///
+/// Non-finite voltages, like `NaN` or infinity, are rejected and `None` is
+/// returned, the setters then keep their previous value. Both cases
+/// are logged as warnings, naming the affected `port`.
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::machine::clamp_voltage;
+/// assert_eq!(clamp_voltage("I1", 1.5), Some(1.5));
+/// assert_eq!(clamp_voltage("I1", 7.0), Some(5.0));
+/// assert_eq!(clamp_voltage("I1", -1.0), Some(0.0));
+/// assert_eq!(clamp_voltage("I1", f32::NAN), None);
+/// ```
+pub fn clamp_voltage(port: &str, value: f32) -> Option<f32> {
+    if !value.is_finite() {
+        warn!("{} of {} is not a valid voltage. Ignoring it", port, value);
+        None
+    } else if value > MAX_VOLTAGE {
+        warn!("{} > {}V. Setting {}V", port, MAX_VOLTAGE, MAX_VOLTAGE);
+        Some(MAX_VOLTAGE)
+    } else if value < MIN_VOLTAGE {
+        warn!("{} < {}V. Setting {}V", port, MIN_VOLTAGE, MIN_VOLTAGE);
+        Some(MIN_VOLTAGE)
+    } else {
+        Some(value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -516,10 +626,11 @@ mod tests {
                 daicr in any::<DAICR>(),
                 analog_inputs in any::<[f32; 2]>(),
                 analog_outputs in any::<[f32; 2]>(),
+                reference_voltage in 0.0f32..=5.0,
                 fan_rpm in any::<usize>(),
                 uio_dir in any::<[bool; 3]>(),
             ) -> Board {
-                Board { digital_input1, digital_output1, digital_output2, temp, dasr, daisr, daicr, analog_inputs, analog_outputs, fan_rpm, uio_dir
+                Board { digital_input1, digital_output1, digital_output2, temp, dasr, daisr, daicr, analog_inputs, analog_outputs, reference_voltage, fan_rpm, uio_dir
                 }
             }
         }
@@ -568,6 +679,13 @@ mod tests {
             assert!((board.temp - pristine.temp).abs() < f32::EPSILON);
         }
 
+        #[test]
+        fn reference_voltage_is_never_reset(mut board in Board::arbitrary()) {
+            let pristine = board.clone();
+            board.master_reset();
+            assert!((board.reference_voltage - pristine.reference_voltage).abs() < f32::EPSILON);
+        }
+
         #[test]
         fn daicr_is_reset_correctly(mut board in Board::arbitrary()) {
             board.master_reset();
@@ -580,6 +698,25 @@ mod tests {
         assert!(Board::new().temp == 0.0);
     }
 
+    #[test]
+    fn default_voltages_ignore_tiny_differences() {
+        let mut board = Board::new();
+        assert!(board.is_temp_default());
+        assert!(board.is_analog_input_default(0));
+        assert!(board.is_analog_output_default(1));
+        board.set_temp(0.004);
+        assert!(board.is_temp_default());
+        board.set_temp(0.01);
+        assert!(!board.is_temp_default());
+        // Values below zero are clamped back to the default
+        board.set_temp(-1.0);
+        assert!(board.is_temp_default());
+        board.set_analog_input2(0.3 - 0.2 - 0.1);
+        assert!(board.is_analog_input_default(1));
+        board.set_digital_output1(1);
+        assert!(!board.is_analog_output_default(0));
+    }
+
     #[test]
     fn test_dac_1() {
         let mut board = Board::new();
@@ -657,4 +794,60 @@ mod tests {
         board.set_analog_input2(0.01);
         assert_eq!(board.dasr.bits(), 0b0010_0000);
     }
+
+    #[test]
+    fn reference_voltage_shifts_comparator_thresholds() {
+        let mut board = Board::new();
+        assert!((board.reference_voltage - DEFAULT_REFERENCE_VOLTAGE).abs() < f32::EPSILON);
+        board.set_analog_input1(1.5);
+        board.set_analog_input2(1.5);
+        board.set_digital_output1(160);
+        board.set_digital_output2(140);
+        // 1.60V and 1.40V at the DACs
+        assert!(!board.dasr.contains(DASR::COMP_DAC1));
+        assert!(board.dasr.contains(DASR::COMP_DAC2));
+        // Raising the reference by half raises both DAC voltages by half
+        board.set_reference_voltage(3.825);
+        assert!((board.analog_outputs[0] - 2.4).abs() < VOLTAGE_EPSILON);
+        assert!((board.analog_outputs[1] - 2.1).abs() < VOLTAGE_EPSILON);
+        assert!(!board.dasr.contains(DASR::COMP_DAC1));
+        assert!(!board.dasr.contains(DASR::COMP_DAC2));
+        // Halving it lowers both thresholds below the inputs
+        board.set_reference_voltage(1.275);
+        assert!(board.dasr.contains(DASR::COMP_DAC1));
+        assert!(board.dasr.contains(DASR::COMP_DAC2));
+        board.set_digital_output1(255);
+        assert!((board.analog_outputs[0] - 1.275).abs() < VOLTAGE_EPSILON);
+        // Out of range references are clamped
+        board.set_reference_voltage(7.0);
+        assert!((board.reference_voltage - 5.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn invalid_voltages_are_clamped_or_ignored() {
+        let mut board = Board::new();
+        board.set_analog_input1(1.0);
+        board.set_analog_input2(1.0);
+        board.set_temp(1.0);
+        board.set_reference_voltage(2.0);
+        for value in &[f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            board.set_analog_input1(*value);
+            board.set_analog_input2(*value);
+            board.set_temp(*value);
+            board.set_reference_voltage(*value);
+            assert_eq!(board.analog_inputs, [1.0, 1.0]);
+            assert_eq!(board.temp, 1.0);
+            assert_eq!(board.reference_voltage, 2.0);
+        }
+        board.set_analog_input1(-0.5);
+        board.set_temp(-3.0);
+        board.set_reference_voltage(-1.0);
+        assert_eq!(board.analog_inputs[0], MIN_VOLTAGE);
+        assert_eq!(board.temp, MIN_VOLTAGE);
+        assert_eq!(board.reference_voltage, MIN_VOLTAGE);
+        board.set_analog_input2(1e30);
+        board.set_temp(5.01);
+        assert_eq!(board.analog_inputs[1], MAX_VOLTAGE);
+        assert_eq!(board.temp, MAX_VOLTAGE);
+    }
 }
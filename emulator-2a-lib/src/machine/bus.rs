@@ -7,12 +7,26 @@ use proptest::prelude::*;
 use proptest_derive::Arbitrary;
 
 use std::{
+    collections::VecDeque,
     fmt,
     ops::{Deref, DerefMut},
 };
 
 use super::{Board, Interrupt};
 
+/// The number of bytes of the [`Ram`], occupying the addresses `00 - EF`.
+pub const RAM_SIZE: usize = 0xF0;
+
+/// The number of bytes the UART receive FIFO can hold.
+///
+/// Bytes pushed into a full FIFO are dropped.
+pub const UART_FIFO_DEPTH: usize = 16;
+
+/// The number of sent bytes the UART keeps, see [`Bus::uart_sent`].
+///
+/// Once more bytes are sent, the oldest ones are dropped.
+pub const UART_SENT_CAPACITY: usize = 256;
+
 /// The bus used in the Minirechner 2a.
 ///
 /// # Address usage
@@ -30,24 +44,72 @@ use super::{Board, Interrupt};
 /// | `FC - FD` | Interrupt timer                   | `_w` |
 /// | `FE - FF` | Output register                   | `_w` |
 ///
-#[derive(Clone, PartialEq)]
-pub struct Bus {
-    ram: Ram,
+/// The RAM is exchangeable, see [`Memory`]. By default the [`Ram`] of the
+/// real machine is used.
+#[derive(Clone)]
+pub struct Bus<M = Ram> {
+    ram: M,
     input_reg: [u8; 4],
     output_reg: [u8; 2],
+    output_written: [bool; 2],
     micr: MICR,
     misr: MISR,
     ucr: UCR,
     usr: USR,
     uart_send: u8,
-    uart_recv: u8,
+    uart_sent: Vec<u8>,
+    uart_recv: VecDeque<u8>,
     int_timer: InterruptTimer,
     board: Board,
+    output_history: Option<OutputHistory>,
+    level_interrupt_request: bool,
+}
+
+/// Memory connected to the [`Bus`], occupying the addresses `00 - EF`.
+///
+/// Implement this to plug an alternative memory model into the bus, i.e.
+/// one that injects faults or records accesses. The [`Ram`] of the real
+/// machine is used by default.
+pub trait Memory {
+    /// Read the byte at `addr`.
+    ///
+    /// The bus only calls this for addresses below [`Memory::len`].
+    fn read(&self, addr: u8) -> u8;
+    /// Write `byte` to `addr`.
+    ///
+    /// The bus only calls this for addresses below [`Memory::len`].
+    fn write(&mut self, addr: u8, byte: u8);
+    /// The number of bytes in this memory.
+    ///
+    /// Reading from the bus at addresses in `len..0xF0` returns zero, writing
+    /// to them does nothing. Bytes beyond `0xEF` cannot be accessed.
+    fn len(&self) -> usize;
+    /// Does this memory contain no bytes at all?
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Fill the memory with zeros.
+    fn reset(&mut self) {
+        for addr in 0..self.len().min(0xF0) {
+            self.write(addr as u8, 0);
+        }
+    }
 }
 
 /// The ram component of the [`Bus`].
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Ram([u8; 0xF0]);
+pub struct Ram([u8; RAM_SIZE]);
+
+/// Changes of the output registers, recorded by [`Bus::record_output_history`].
+///
+/// Every entry holds the clock cycle of the write and the written value.
+/// Cycles start at one with the first clock after the recording was started.
+#[derive(Debug, Clone, Default)]
+struct OutputHistory {
+    cycle: usize,
+    fe: Vec<(usize, u8)>,
+    ff: Vec<(usize, u8)>,
+}
 
 /// The interrupt timer.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -62,7 +124,7 @@ pub struct InterruptTimer {
 bitflags! {
     /// Master Interrupt Control Register
     #[cfg_attr(test, derive(Arbitrary))]
-    struct MICR: u8 {
+    pub struct MICR: u8 {
         const BUS_EDGE_INTERRUPT_ENABLE   = 0b00100000;
         const BUS_LEVEL_INTERRUPT_ENABLE  = 0b00010000;
         const UART_EDGE_INTERRUPT_ENABLE  = 0b00001000;
@@ -112,6 +174,8 @@ bitflags! {
         const NOT_RTS  = 0b00000100;
         const RX_FULL  = 0b00000010;
         const RX_READY = 0b00000001;
+        /// The transmitter holds no byte and accepts the next one.
+        const TX_IDLE  = Self::TX_READY.bits | Self::TX_EMPTY.bits;
     }
 }
 
@@ -119,29 +183,71 @@ impl Bus {
     /// Create a new Bus.
     /// The ram is empty.
     pub const fn new() -> Self {
-        let ram = Ram::new();
+        Bus::with_memory(Ram::new())
+    }
+
+    /// Get the contents of the main memory.
+    ///
+    /// The main memory ranges from 0x00 - 0xEF. This is RAM only, the
+    /// peripherals mapped to 0xF0 - 0xFF are not part of the array.
+    pub fn memory(&self) -> &[u8; 0xF0] {
+        &self.ram
+    }
+
+    /// Get mutable access to the memory connected to the bus.
+    ///
+    /// # Example
+    /// ```
+    /// # use emulator_2a_lib::machine::Bus;
+    /// let mut bus = Bus::new();
+    ///
+    /// let memory = bus.memory_mut();
+    /// memory[0] = 123;
+    /// memory[42] = 76;
+    /// memory[0xEF] = 0xFF;
+    ///
+    /// assert_eq!(bus.read(0), 123);
+    /// assert_eq!(bus.read(42), 76);
+    /// assert_eq!(bus.read(0xEF), 0xFF);
+    /// ```
+    pub fn memory_mut(&mut self) -> &mut [u8; 0xF0] {
+        &mut self.ram
+    }
+}
+
+impl<M: Memory> Bus<M> {
+    /// Create a new Bus using the given memory.
+    pub const fn with_memory(ram: M) -> Self {
         let input_reg = [0; 4];
         let output_reg = [0; 2];
+        let output_written = [false; 2];
         let micr = MICR::empty();
         let misr = MISR::empty();
         let ucr = UCR::empty();
-        let usr = USR::empty();
+        let usr = USR::TX_IDLE;
         let uart_send = 0;
-        let uart_recv = 0;
+        let uart_sent = Vec::new();
+        let uart_recv = VecDeque::new();
         let int_timer = InterruptTimer::new();
         let board = Board::new();
+        let output_history = None;
+        let level_interrupt_request = false;
         Bus {
             ram,
             input_reg,
             output_reg,
+            output_written,
             micr,
             misr,
             ucr,
             usr,
             uart_send,
+            uart_sent,
             uart_recv,
             int_timer,
             board,
+            output_history,
+            level_interrupt_request,
         }
     }
 
@@ -150,10 +256,12 @@ impl Bus {
     /// This resets:
     ///  - The output registers
     ///  - The MICR
+    ///  - The MISR
     ///  - The UCR
     pub fn cpu_reset(&mut self) {
         self.output_reg = [0; 2];
         self.micr = MICR::empty();
+        self.misr = MISR::empty();
         self.ucr = UCR::empty();
     }
 
@@ -162,10 +270,23 @@ impl Bus {
     /// On top of the [`Bus::cpu_reset`], the following will be reset:
     ///  - The input register
     ///  - The interrupt timer config
+    ///  - Whether the output registers were written
+    ///  - The UART receive FIFO
+    ///  - The UART transmitter, dropping a byte that waits for CTS
+    ///  - The bytes sent by the UART
+    ///  - The output history, if it is recorded
     pub fn master_reset(&mut self) {
         self.cpu_reset();
         self.input_reg = [0; 4];
+        self.output_written = [false; 2];
         self.int_timer.reset();
+        self.uart_recv.clear();
+        self.update_uart_receive_status();
+        self.usr.insert(USR::TX_IDLE);
+        self.uart_sent.clear();
+        if let Some(history) = &mut self.output_history {
+            *history = OutputHistory::default();
+        }
     }
 
     /// Fill the ram with zeros.
@@ -189,13 +310,30 @@ impl Bus {
     pub(crate) fn misr_mut(&mut self) -> &mut MISR {
         &mut self.misr
     }
+    /// Update the MISR after the CPU executed a `RETI`.
+    ///
+    /// The pending and request active bits of the edge triggered sources,
+    /// the key and the timer, are cleared, since their request was consumed
+    /// by the returning interrupt service routine. The bits of the level
+    /// triggered sources, the bus and the UART, are kept. They mirror the
+    /// level of their request lines, which only the source itself can withdraw.
+    pub(crate) fn return_from_interrupt(&mut self) {
+        self.misr.remove(
+            MISR::KEY_INTERRUPT_PENDING
+                | MISR::KEY_INTERRUPT_REQUEST_ACTIVE
+                | MISR::TIMER_INTERRUPT_PENDING
+                | MISR::TIMER_INTERRUPT_REQUEST_ACTIVE,
+        );
+    }
 
     /// Write to the bus
     pub fn write(&mut self, addr: u8, byte: u8) {
         let addr = addr as usize;
         trace!("Update 0x{:>02X} = 0x{:>02X}", addr, byte);
-        if addr <= 0xEF {
-            self.ram[addr] = byte;
+        if addr < 0xF0 {
+            if addr < self.ram.len() {
+                self.ram.write(addr as u8, byte);
+            }
         } else if addr == 0xF0 {
             self.board.set_digital_output1(byte);
         } else if addr == 0xF1 {
@@ -224,9 +362,18 @@ impl Bus {
         } else if addr == 0xF9 {
             self.micr = MICR::from_bits_truncate(byte);
         } else if addr == 0xFA {
+            if !self.usr.contains(USR::TX_READY) {
+                warn!(
+                    "UART transmitter is busy, overwriting 0x{:>02X}",
+                    self.uart_send
+                );
+            }
             self.uart_send = byte;
+            self.usr.remove(USR::TX_IDLE);
+            self.update_uart_transmit_status();
         } else if addr == 0xFB {
             self.ucr = UCR::from_bits_truncate(byte);
+            self.update_uart_transmit_status();
         } else if addr == 0xFC {
             let lower = byte as usize;
             let orig = self.int_timer.div3;
@@ -258,15 +405,27 @@ impl Bus {
             }
         } else if addr == 0xFE {
             self.output_reg[0] = byte;
+            self.output_written[0] = true;
+            if let Some(history) = &mut self.output_history {
+                OutputHistory::record(&mut history.fe, history.cycle, byte);
+            }
         } else if addr == 0xFF {
             self.output_reg[1] = byte;
+            self.output_written[1] = true;
+            if let Some(history) = &mut self.output_history {
+                OutputHistory::record(&mut history.ff, history.cycle, byte);
+            }
         }
     }
     /// Read from the bus.
     pub fn read(&self, addr: u8) -> u8 {
         let addr = addr as usize;
-        if addr <= 0xEF {
-            self.ram[addr]
+        if addr < 0xF0 {
+            if addr < self.ram.len() {
+                self.ram.read(addr as u8)
+            } else {
+                0
+            }
         } else if addr == 0xF0 {
             *self.board.digital_input1()
         } else if addr == 0xF1 {
@@ -294,13 +453,108 @@ impl Bus {
         } else if addr == 0xF9 {
             self.misr.bits()
         } else if addr == 0xFA {
-            self.uart_recv
+            self.uart_recv.front().copied().unwrap_or_default()
         } else if addr == 0xFB {
             self.usr.bits()
         } else {
             self.input_reg[addr - 0xFC]
         }
     }
+    /// Read from the bus, like the CPU does.
+    ///
+    /// Unlike [`Bus::read`], reading `FA` removes the received byte
+    /// from the UART receive FIFO.
+    pub fn read_and_advance(&mut self, addr: u8) -> u8 {
+        let byte = self.read(addr);
+        if addr == 0xFA && self.uart_recv.pop_front().is_some() {
+            self.update_uart_receive_status();
+        }
+        byte
+    }
+    /// Enqueue bytes into the UART receive FIFO.
+    ///
+    /// The program reads them one by one from `FA`, `RX_READY` is set in
+    /// the UART status register `FB` until the FIFO is drained. The FIFO holds
+    /// at most [`UART_FIFO_DEPTH`] bytes, the rest is dropped.
+    /// Returns the number of bytes that were enqueued.
+    ///
+    /// # Example
+    /// ```
+    /// # use emulator_2a_lib::machine::Bus;
+    /// let mut bus = Bus::new();
+    ///
+    /// assert_eq!(bus.uart_push_bytes(b"Hi"), 2);
+    /// assert_eq!(bus.read(0xFB) & 0x01, 0x01);
+    /// assert_eq!(bus.read_and_advance(0xFA), b'H');
+    /// assert_eq!(bus.read_and_advance(0xFA), b'i');
+    /// assert_eq!(bus.read(0xFB) & 0x01, 0x00);
+    /// ```
+    pub fn uart_push_bytes(&mut self, bytes: &[u8]) -> usize {
+        let free = UART_FIFO_DEPTH - self.uart_recv.len();
+        let accepted = bytes.len().min(free);
+        if accepted < bytes.len() {
+            warn!(
+                "UART receive FIFO is full, dropping {} byte(s)",
+                bytes.len() - accepted
+            );
+        }
+        self.uart_recv.extend(&bytes[..accepted]);
+        self.update_uart_receive_status();
+        accepted
+    }
+    /// Get the bytes waiting in the UART receive FIFO.
+    pub fn uart_received(&self) -> &VecDeque<u8> {
+        &self.uart_recv
+    }
+    /// Get the bytes the UART sent so far, oldest first.
+    ///
+    /// Only the last [`UART_SENT_CAPACITY`] bytes are kept.
+    ///
+    /// A byte written to `FA` is sent immediately, unless CTS is deasserted
+    /// and not ignored by the UART control register `FB`. In that case it is
+    /// held back, with `TX_READY` and `TX_EMPTY` cleared, until CTS is asserted.
+    ///
+    /// # Example
+    /// ```
+    /// # use emulator_2a_lib::machine::Bus;
+    /// let mut bus = Bus::new();
+    ///
+    /// bus.set_uart_clear_to_send(false);
+    /// bus.write(0xFA, b'A');
+    /// assert!(bus.uart_sent().is_empty());
+    /// assert_eq!(bus.read(0xFB) & 0x80, 0x00);
+    /// bus.set_uart_clear_to_send(true);
+    /// assert_eq!(bus.uart_sent(), b"A");
+    /// assert_eq!(bus.read(0xFB) & 0x80, 0x80);
+    /// ```
+    pub fn uart_sent(&self) -> &[u8] {
+        &self.uart_sent
+    }
+    /// Assert or deassert the CTS (clear to send) input of the UART.
+    ///
+    /// CTS is asserted by default. Asserting it sends a byte that was held back.
+    pub fn set_uart_clear_to_send(&mut self, clear: bool) {
+        self.usr.set(USR::NOT_CTS, !clear);
+        self.update_uart_transmit_status();
+    }
+    /// Send the byte waiting in the transmitter, if the line accepts it.
+    fn update_uart_transmit_status(&mut self) {
+        let waiting = !self.usr.contains(USR::TX_READY);
+        let clear = !self.usr.contains(USR::NOT_CTS) || self.ucr.contains(UCR::IGNORE_CTS);
+        if waiting && clear {
+            if self.uart_sent.len() == UART_SENT_CAPACITY {
+                self.uart_sent.remove(0);
+            }
+            self.uart_sent.push(self.uart_send);
+            self.usr.insert(USR::TX_IDLE);
+        }
+    }
+    /// Update `RX_READY` and `RX_FULL` according to the receive FIFO.
+    fn update_uart_receive_status(&mut self) {
+        self.usr.set(USR::RX_READY, !self.uart_recv.is_empty());
+        self.usr
+            .set(USR::RX_FULL, self.uart_recv.len() == UART_FIFO_DEPTH);
+    }
     /// Set input register `FC`.
     pub fn input_fc(&mut self, byte: u8) {
         self.input_reg[0] = byte;
@@ -325,19 +579,107 @@ impl Bus {
     pub fn output_ff(&self) -> u8 {
         self.output_reg[1]
     }
+    /// Was output register `FE` ever written?
+    ///
+    /// This is not reset by [`Bus::cpu_reset`], only by [`Bus::master_reset`].
+    pub fn was_output_fe_written(&self) -> bool {
+        self.output_written[0]
+    }
+    /// Was output register `FF` ever written?
+    ///
+    /// This is not reset by [`Bus::cpu_reset`], only by [`Bus::master_reset`].
+    pub fn was_output_ff_written(&self) -> bool {
+        self.output_written[1]
+    }
+    /// Forget that the output registers were written, keeping their values.
+    ///
+    /// Use this after setting up the machine, so that only writes by the
+    /// program count, see [`Bus::was_output_fe_written`].
+    pub fn forget_output_writes(&mut self) {
+        self.output_written = [false; 2];
+    }
+    /// Start or stop recording the values written to the output registers.
+    ///
+    /// Recording is disabled by default to avoid the overhead. Starting
+    /// it discards any earlier history.
+    ///
+    /// # Example
+    /// ```
+    /// # use emulator_2a_lib::machine::Bus;
+    /// let mut bus = Bus::new();
+    /// bus.record_output_history(true);
+    /// bus.count_cycle();
+    /// bus.write(0xFF, 1);
+    /// bus.write(0xFF, 1);
+    /// bus.count_cycle();
+    /// bus.write(0xFF, 2);
+    /// assert_eq!(bus.output_ff_history(), &[(1, 1), (2, 2)]);
+    /// assert!(bus.output_fe_history().is_empty());
+    /// ```
+    pub fn record_output_history(&mut self, enabled: bool) {
+        self.output_history = if enabled {
+            Some(OutputHistory::default())
+        } else {
+            None
+        };
+    }
+    /// Advance the cycle counter of the output history.
+    ///
+    /// The [`RawMachine`](super::RawMachine) calls this on every clock edge.
+    pub fn count_cycle(&mut self) {
+        if let Some(history) = &mut self.output_history {
+            history.cycle += 1;
+        }
+    }
+    /// Get the distinct values written to output register `FE` with their cycle.
+    ///
+    /// Writing the last recorded value again is not recorded.
+    /// This is empty unless [`Bus::record_output_history`] was enabled.
+    pub fn output_fe_history(&self) -> &[(usize, u8)] {
+        self.output_history
+            .as_ref()
+            .map(|history| &history.fe[..])
+            .unwrap_or_default()
+    }
+    /// Get the distinct values written to output register `FF` with their cycle.
+    ///
+    /// Writing the last recorded value again is not recorded.
+    /// This is empty unless [`Bus::record_output_history`] was enabled.
+    pub fn output_ff_history(&self) -> &[(usize, u8)] {
+        self.output_history
+            .as_ref()
+            .map(|history| &history.ff[..])
+            .unwrap_or_default()
+    }
     /// Is anything on the bus triggering a level interrupt?
     ///
-    /// TODO: Implement
+    /// This updates the bus and UART interrupt bits of the MISR to mirror
+    /// the bus level interrupt request line and the UART interrupt conditions
+    /// selected in the UART control register. The interrupt is only reported,
+    /// if level interrupts of the requesting source are enabled in the MICR.
     pub fn get_level_interrupt(&mut self) -> Option<Interrupt> {
-        warn!("Bus Interrupts are not implemented yet");
-        None
-        //if self.micr.contains(MICR::UART_LEVEL_INTERRUPT_ENABLE) {
-        //    None
-        //} else if self.micr.contains(MICR::BUS_LEVEL_INTERRUPT_ENABLE) {
-        //    None
-        //} else {
-        //    None
-        //}
+        let bus_active = self.level_interrupt_request;
+        let bus_pending = bus_active && self.micr.contains(MICR::BUS_LEVEL_INTERRUPT_ENABLE);
+        self.misr
+            .set(MISR::BUS_INTERRUPT_REQUEST_ACTIVE, bus_active);
+        self.misr.set(MISR::BUS_INTERRUPT_PENDING, bus_pending);
+        let uart_active = self.has_uart_interrupt();
+        let uart_pending = uart_active && self.micr.contains(MICR::UART_LEVEL_INTERRUPT_ENABLE);
+        self.misr
+            .set(MISR::UART_INTERRUPT_REQUEST_ACTIVE, uart_active);
+        self.misr.set(MISR::UART_INTERUPT_PENDING, uart_pending);
+        if bus_pending || uart_pending {
+            Some(Interrupt)
+        } else {
+            None
+        }
+    }
+    /// Assert or deassert the bus level interrupt request line.
+    ///
+    /// The request stays active until it is deasserted again, neither a `RETI`
+    /// nor a reset withdraws it.
+    pub fn set_level_interrupt_request(&mut self, active: bool) {
+        self.level_interrupt_request = active;
     }
     /// Did anything on the bus trigger an edge interrupt?
     ///
@@ -366,6 +708,14 @@ impl Bus {
         &mut self.board
     }
 
+    /// Get the Master Interrupt Control Register.
+    pub const fn micr(&self) -> &MICR {
+        &self.micr
+    }
+    /// Get the Master Interrupt Status Register.
+    pub const fn misr(&self) -> &MISR {
+        &self.misr
+    }
     /// Is key edge interrupt enabled?
     pub fn is_key_edge_int_enabled(&self) -> bool {
         self.micr.contains(MICR::KEY_EDGE_INTERRUPT_ENABLE)
@@ -375,47 +725,28 @@ impl Bus {
     pub fn is_timer_edge_int_enabled(&self) -> bool {
         self.micr.contains(MICR::TIMER_EDGE_INTERRUPT_ENABLE)
     }
-    /// Get the contents of the main memory.
-    ///
-    /// The main memory ranges from 0x00 - 0xEF.
-    pub fn memory(&self) -> &[u8; 0xF0] {
+    /// Get the memory connected to the bus.
+    pub fn ram(&self) -> &M {
         &self.ram
     }
 
     /// Get mutable access to the memory connected to the bus.
-    ///
-    /// # Example
-    /// ```
-    /// # use emulator_2a_lib::machine::Bus;
-    /// let mut bus = Bus::new();
-    ///
-    /// let memory = bus.memory_mut();
-    /// memory[0] = 123;
-    /// memory[42] = 76;
-    /// memory[0xEF] = 0xFF;
-    ///
-    /// assert_eq!(bus.read(0), 123);
-    /// assert_eq!(bus.read(42), 76);
-    /// assert_eq!(bus.read(0xEF), 0xFF);
-    /// ```
-    pub fn memory_mut(&mut self) -> &mut [u8; 0xF0] {
+    pub fn ram_mut(&mut self) -> &mut M {
         &mut self.ram
     }
 
     /// Did anything trigger an interrupt in the UART?
-    #[allow(dead_code)]
+    ///
+    /// Every condition enabled in the UART control register is checked.
     fn has_uart_interrupt(&self) -> bool {
-        if self.ucr.contains(UCR::INT_ON_RX_READY) {
-            self.usr.contains(USR::RX_READY)
-        } else if self.ucr.contains(UCR::INT_ON_RX_FULL) {
-            self.usr.contains(USR::RX_FULL)
-        } else if self.ucr.contains(UCR::INT_ON_TX_EMPTY) {
-            self.usr.contains(USR::TX_EMPTY)
-        } else if self.ucr.contains(UCR::INT_ON_TX_READY) {
-            self.usr.contains(USR::TX_READY)
-        } else {
-            false
-        }
+        [
+            (UCR::INT_ON_RX_READY, USR::RX_READY),
+            (UCR::INT_ON_RX_FULL, USR::RX_FULL),
+            (UCR::INT_ON_TX_EMPTY, USR::TX_EMPTY),
+            (UCR::INT_ON_TX_READY, USR::TX_READY),
+        ]
+        .iter()
+        .any(|(enabled, status)| self.ucr.contains(*enabled) && self.usr.contains(*status))
     }
     /// Did anything trigger an interrupt on the MR2DA2?
     ///
@@ -434,10 +765,28 @@ impl Ram {
     /// # Note:
     /// Do not download RAM!
     pub const fn new() -> Self {
-        Ram([0; 0xF0])
+        Ram([0; RAM_SIZE])
+    }
+}
+
+impl Default for Ram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory for Ram {
+    fn read(&self, addr: u8) -> u8 {
+        self.0[addr as usize]
+    }
+    fn write(&mut self, addr: u8, byte: u8) {
+        self.0[addr as usize] = byte
+    }
+    fn len(&self) -> usize {
+        self.0.len()
     }
     /// Reset the ram to all zeros.
-    pub fn reset(&mut self) {
+    fn reset(&mut self) {
         self.0 = [0; 0xF0]
     }
 }
@@ -458,11 +807,56 @@ impl InterruptTimer {
     }
 }
 
-impl fmt::Debug for Bus {
+impl OutputHistory {
+    /// Push `byte` unless it equals the last recorded value.
+    fn record(history: &mut Vec<(usize, u8)>, cycle: usize, byte: u8) {
+        if history.last().map(|&(_, last)| last) != Some(byte) {
+            history.push((cycle, byte));
+        }
+    }
+}
+
+impl<M: fmt::Debug> fmt::Debug for Bus<M> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Bus")
-            .field("ram", &self.ram.to_vec())
-            .finish()
+        f.debug_struct("Bus").field("ram", &self.ram).finish()
+    }
+}
+
+impl<M: PartialEq> PartialEq for Bus<M> {
+    /// The output history only observes the machine, it is not part of its
+    /// state and thus ignored. This keeps stall detection working.
+    fn eq(&self, other: &Self) -> bool {
+        let Bus {
+            ram,
+            input_reg,
+            output_reg,
+            output_written,
+            micr,
+            misr,
+            ucr,
+            usr,
+            uart_send,
+            uart_sent,
+            uart_recv,
+            int_timer,
+            board,
+            output_history: _,
+            level_interrupt_request,
+        } = self;
+        *ram == other.ram
+            && *input_reg == other.input_reg
+            && *output_reg == other.output_reg
+            && *output_written == other.output_written
+            && *micr == other.micr
+            && *misr == other.misr
+            && *ucr == other.ucr
+            && *usr == other.usr
+            && *uart_send == other.uart_send
+            && *uart_sent == other.uart_sent
+            && *uart_recv == other.uart_recv
+            && *int_timer == other.int_timer
+            && *board == other.board
+            && *level_interrupt_request == other.level_interrupt_request
     }
 }
 
@@ -490,22 +884,28 @@ prop_compose! {
 mod tests {
     use super::*;
 
+    use std::cell::Cell;
+
     impl Bus {
         prop_compose! {
             pub fn arbitrary()(
                 ram in arbitrary_ram(),
                 input_reg in any::<[u8; 4]>(),
                 output_reg in any::<[u8; 2]>(),
+                output_written in any::<[bool; 2]>(),
                 micr in any::<MICR>(),
                 misr in any::<MISR>(),
                 ucr in any::<UCR>(),
                 usr in any::<USR>(),
                 uart_send in any::<u8>(),
-                uart_recv in any::<u8>(),
+                uart_sent in proptest::collection::vec(any::<u8>(), 0..8),
+                uart_recv in proptest::collection::vec_deque(any::<u8>(), 0..=UART_FIFO_DEPTH),
                 int_timer in any::<InterruptTimer>(),
                 board in Board::arbitrary(),
             ) -> Bus {
-                Bus { ram, input_reg, output_reg, micr, misr, ucr, usr, uart_send, uart_recv, int_timer, board }
+                let output_history = None;
+                let level_interrupt_request = false;
+                Bus { ram, input_reg, output_reg, output_written, micr, misr, ucr, usr, uart_send, uart_sent, uart_recv, int_timer, board, output_history, level_interrupt_request }
             }
         }
     }
@@ -552,12 +952,39 @@ mod tests {
             assert_eq!(bus.micr, Bus::new().micr);
         }
 
+        #[test]
+        fn misr_is_reset_by_cpu_reset(mut bus in Bus::arbitrary()) {
+            bus.cpu_reset();
+            assert_eq!(bus.misr, Bus::new().misr);
+        }
+
+        #[test]
+        fn reti_clears_edge_interrupts_only(mut bus in Bus::arbitrary()) {
+            let level = MISR::BUS_INTERRUPT_PENDING
+                | MISR::BUS_INTERRUPT_REQUEST_ACTIVE
+                | MISR::UART_INTERUPT_PENDING
+                | MISR::UART_INTERRUPT_REQUEST_ACTIVE;
+            let pristine = bus.misr;
+            bus.return_from_interrupt();
+            assert_eq!(bus.misr, pristine & level);
+        }
+
         #[test]
         fn ucr_is_reset_by_cpu_reset(mut bus in Bus::arbitrary()) {
             bus.cpu_reset();
             assert_eq!(bus.ucr, Bus::new().ucr);
         }
 
+        #[test]
+        fn uart_receive_fifo_is_reset_by_master_reset_only(mut bus in Bus::arbitrary()) {
+            let pristine = bus.clone();
+            bus.cpu_reset();
+            assert_eq!(bus.uart_recv, pristine.uart_recv);
+            bus.master_reset();
+            assert!(bus.uart_recv.is_empty());
+            assert!(!bus.usr.intersects(USR::RX_READY | USR::RX_FULL));
+        }
+
         #[test]
         fn interrupt_timer_settings_are_reset_by_master_reset_only(mut bus in Bus::arbitrary()) {
             let pristine = bus.clone();
@@ -587,6 +1014,64 @@ mod tests {
         assert_eq!(bus.read(0x11), 0x14);
     }
 
+    /// Small memory of 16 bytes, counting all accesses.
+    #[derive(Debug, Default)]
+    struct CountingMemory {
+        bytes: [u8; 16],
+        reads: Cell<usize>,
+        writes: usize,
+    }
+
+    impl Memory for CountingMemory {
+        fn read(&self, addr: u8) -> u8 {
+            self.reads.set(self.reads.get() + 1);
+            self.bytes[addr as usize]
+        }
+        fn write(&mut self, addr: u8, byte: u8) {
+            self.writes += 1;
+            self.bytes[addr as usize] = byte
+        }
+        fn len(&self) -> usize {
+            self.bytes.len()
+        }
+    }
+
+    #[test]
+    fn alternative_memories_can_be_plugged_in() {
+        let mut bus = Bus::with_memory(CountingMemory::default());
+        bus.write(0x00, 0x11);
+        bus.write(0x0F, 0x12);
+        assert_eq!(bus.read(0x00), 0x11);
+        assert_eq!(bus.read(0x0F), 0x12);
+        assert_eq!((bus.ram().reads.get(), bus.ram().writes), (2, 2));
+        // Addresses beyond the memory are not forwarded
+        bus.write(0x10, 0x13);
+        assert_eq!(bus.read(0x10), 0);
+        assert_eq!(bus.read(0xEF), 0);
+        // Neither are peripherals
+        bus.write(0xFF, 0x14);
+        assert_eq!(bus.output_ff(), 0x14);
+        assert_eq!((bus.ram().reads.get(), bus.ram().writes), (2, 2));
+        // The default reset writes every byte
+        bus.reset_ram();
+        assert_eq!(bus.ram().writes, 2 + 16);
+        assert_eq!(bus.read(0x00), 0);
+    }
+
+    #[test]
+    fn only_the_last_sent_uart_bytes_are_kept() {
+        let mut bus = Bus::new();
+        for i in 0..UART_SENT_CAPACITY + 3 {
+            bus.write(0xFA, i as u8);
+        }
+        assert_eq!(bus.uart_sent().len(), UART_SENT_CAPACITY);
+        assert_eq!(bus.uart_sent()[0], 3);
+        assert_eq!(
+            bus.uart_sent().last(),
+            Some(&((UART_SENT_CAPACITY + 2) as u8))
+        );
+    }
+
     #[test]
     fn test_bus_input_reg() {
         let mut bus = Bus::new();
@@ -0,0 +1,183 @@
+//! Structured differences between two machine states.
+use std::fmt;
+
+use super::{Flags, RawMachine, RegisterNumber, State};
+
+/// All registers compared by [`RawMachine::diff`].
+///
+/// The flag register R4 is compared separately.
+const COMPARED_REGISTERS: [RegisterNumber; 7] = [
+    RegisterNumber::R0,
+    RegisterNumber::R1,
+    RegisterNumber::R2,
+    RegisterNumber::R3,
+    RegisterNumber::R5,
+    RegisterNumber::R6,
+    RegisterNumber::R7,
+];
+
+/// Differences between two [`RawMachine`]s.
+///
+/// Every difference holds the value of the first machine followed by
+/// the value of the second one.
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::machine::{RawMachine, RegisterNumber};
+/// let pristine = RawMachine::new();
+/// let mut changed = RawMachine::new();
+/// changed.set_register(RegisterNumber::R1, 0x2A);
+///
+/// let diff = pristine.diff(&changed);
+/// assert_eq!(diff.registers, vec![(RegisterNumber::R1, 0x00, 0x2A)]);
+/// assert_eq!(diff.to_string(), "R1: 0x00 -> 0x2A\n");
+/// assert!(pristine.diff(&pristine).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MachineDiff {
+    /// Differing registers, except for the flag register.
+    pub registers: Vec<(RegisterNumber, u8, u8)>,
+    /// Differing flags.
+    pub flags: Option<(Flags, Flags)>,
+    /// Differing memory cells with their address.
+    pub memory: Vec<(u8, u8, u8)>,
+    /// Differing output register FE.
+    pub output_fe: Option<(u8, u8)>,
+    /// Differing output register FF.
+    pub output_ff: Option<(u8, u8)>,
+    /// Differing machine state.
+    pub state: Option<(State, State)>,
+}
+
+impl RawMachine {
+    /// Compare the observable state of this machine with `other`.
+    ///
+    /// Registers, flags, memory, output registers and the [`State`] are compared.
+    pub fn diff(&self, other: &RawMachine) -> MachineDiff {
+        let registers = COMPARED_REGISTERS
+            .iter()
+            .filter_map(|&number| {
+                let (a, b) = (
+                    *self.registers().get(number),
+                    *other.registers().get(number),
+                );
+                differ(a, b).map(|(a, b)| (number, a, b))
+            })
+            .collect();
+        let memory = (0..)
+            .zip(self.bus().memory().iter().zip(other.bus().memory().iter()))
+            .filter(|(_, (a, b))| a != b)
+            .map(|(addr, (&a, &b))| (addr, a, b))
+            .collect();
+        MachineDiff {
+            registers,
+            flags: differ(self.registers().flags(), other.registers().flags()),
+            memory,
+            output_fe: differ(self.bus().output_fe(), other.bus().output_fe()),
+            output_ff: differ(self.bus().output_ff(), other.bus().output_ff()),
+            state: differ(self.state(), other.state()),
+        }
+    }
+}
+
+impl MachineDiff {
+    /// Are both machines equal?
+    pub fn is_empty(&self) -> bool {
+        *self == MachineDiff::default()
+    }
+}
+
+impl fmt::Display for MachineDiff {
+    /// Print one line per difference.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (number, a, b) in &self.registers {
+            writeln!(f, "{:?}: 0x{:02X} -> 0x{:02X}", number, a, b)?;
+        }
+        if let Some((a, b)) = self.flags {
+            writeln!(f, "Flags: {} -> {}", flag_letters(a), flag_letters(b))?;
+        }
+        for (addr, a, b) in &self.memory {
+            writeln!(f, "(0x{:02X}): 0x{:02X} -> 0x{:02X}", addr, a, b)?;
+        }
+        if let Some((a, b)) = self.output_fe {
+            writeln!(f, "FE: 0x{:02X} -> 0x{:02X}", a, b)?;
+        }
+        if let Some((a, b)) = self.output_ff {
+            writeln!(f, "FF: 0x{:02X} -> 0x{:02X}", a, b)?;
+        }
+        if let Some((a, b)) = self.state {
+            writeln!(f, "State: {:?} -> {:?}", a, b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Return both values, if they differ.
+fn differ<T: PartialEq>(a: T, b: T) -> Option<(T, T)> {
+    if a != b {
+        Some((a, b))
+    } else {
+        None
+    }
+}
+
+/// Format the flags as `INZC`, unset flags are replaced by `-`.
+fn flag_letters(flags: Flags) -> String {
+    [
+        (Flags::INTERRUPT_ENABLE_FLAG, 'I'),
+        (Flags::NEGATIVE_FLAG, 'N'),
+        (Flags::ZERO_FLAG, 'Z'),
+        (Flags::CARRY_FLAG, 'C'),
+    ]
+    .iter()
+    .map(|&(flag, letter)| if flags.contains(flag) { letter } else { '-' })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compiler::Translator,
+        machine::{Machine, MachineConfig},
+        parser::AsmParser,
+    };
+
+    #[test]
+    fn stepped_machines_differ_from_pristine_ones() {
+        let asm = AsmParser::parse(
+            r#"#! mrasm
+                MOV R0, 0x80
+                ST (0x10), R0
+                ST (0xFF), R0
+                STOP
+            "#,
+        )
+        .expect("Failed to parse program");
+        let pristine =
+            Machine::new_with_program(MachineConfig::default(), Translator::compile(&asm).unwrap());
+        let mut stepped = pristine.clone();
+        while stepped.state() == State::Running {
+            stepped.trigger_key_clock();
+        }
+        let diff = pristine.diff(&stepped);
+        assert!(!diff.is_empty());
+        assert!(diff.registers.contains(&(RegisterNumber::R0, 0x00, 0x80)));
+        assert!(diff.memory.contains(&(0x10, 0x00, 0x80)));
+        assert_eq!(diff.output_fe, None);
+        assert_eq!(diff.output_ff, Some((0x00, 0x80)));
+        assert_eq!(diff.state, Some((State::Running, State::Stopped)));
+        let report = diff.to_string();
+        assert!(report.contains("R0: 0x00 -> 0x80\n"));
+        assert!(report.contains("(0x10): 0x00 -> 0x80\n"));
+        assert!(report.contains("FF: 0x00 -> 0x80\n"));
+        assert!(report.ends_with("State: Running -> Stopped\n"));
+        // The reverse diff swaps the values
+        assert_eq!(
+            stepped.diff(&pristine).state,
+            Some((State::Stopped, State::Running))
+        );
+        assert!(stepped.diff(&stepped).is_empty());
+    }
+}
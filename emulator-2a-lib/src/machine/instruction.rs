@@ -2,6 +2,13 @@ use bitflags::bitflags;
 #[cfg(test)]
 use proptest_derive::Arbitrary;
 
+use crate::compiler::{OperandShape, OPCODES};
+
+/// Mnemonic of opcodes that do not start an instruction.
+pub const INVALID_MNEMONIC: &str = "???";
+/// Opcode group of all instructions with a destination and a source.
+const DESTINATION_SOURCE_OPCODE: u8 = 0b1111_0000;
+
 /// The instruction register.
 ///
 /// It stores the currently executed [`Instruction`].
@@ -50,6 +57,43 @@ impl InstructionRegister {
     pub fn reset(&mut self) {
         self.content = Instruction::reset();
     }
+    /// Get the mnemonic of the contained opcode, i.e. `"INC"`.
+    ///
+    /// Opcodes shared by different instructions are resolved like the
+    /// [`disassembler`](crate::disassembler) does, i.e. `0x17` is `RET`, not `POP`.
+    /// All instructions with a destination and a source, like `CMP` or `LDSP`,
+    /// are selected by their second byte, which is not part of this register.
+    /// `"MOV"` is returned for all of them. Opcodes that do not start an instruction,
+    /// like `0x00`, return [`INVALID_MNEMONIC`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::InstructionRegister;
+    /// let mut ir = InstructionRegister::new();
+    /// ir.set_raw(0b0100_0100); // INC R0
+    /// assert_eq!(ir.mnemonic(), "INC");
+    /// ```
+    pub fn mnemonic(&self) -> &'static str {
+        let opcode = self.get_raw();
+        if opcode & 0xF0 == DESTINATION_SOURCE_OPCODE {
+            return "MOV";
+        }
+        OPCODES
+            .iter()
+            .filter(|(_, base, shape)| match shape {
+                OperandShape::None | OperandShape::RelativeJump | OperandShape::Address => {
+                    opcode == *base
+                }
+                OperandShape::Register => opcode & !0b11 == *base,
+                OperandShape::TwoRegisters => opcode & 0xF0 == *base,
+                _ => false,
+            })
+            // Prefer instructions without operands, the first match otherwise
+            .min_by_key(|(_, _, shape)| *shape != OperandShape::None)
+            .map(|(mnemonic, _, _)| *mnemonic)
+            .unwrap_or(INVALID_MNEMONIC)
+    }
 }
 
 bitflags! {
@@ -81,6 +125,27 @@ mod tests {
     use super::*;
     use proptest::prelude::*;
 
+    #[test]
+    fn opcodes_are_mapped_to_mnemonics() {
+        let mnemonic = |opcode| {
+            let mut register = InstructionRegister::new();
+            register.set_raw(opcode);
+            register.mnemonic()
+        };
+        assert_eq!(InstructionRegister::new().mnemonic(), "NOP");
+        assert_eq!(mnemonic(0x01), "STOP");
+        assert_eq!(mnemonic(0b0100_0110), "INC");
+        assert_eq!(mnemonic(0b0001_0101), "POP");
+        assert_eq!(mnemonic(0b0001_0111), "RET");
+        assert_eq!(mnemonic(0b0110_0000), "ADD");
+        assert_eq!(mnemonic(0b1101_1011), "XOR");
+        assert_eq!(mnemonic(0b0010_0110), "JZC");
+        assert_eq!(mnemonic(0b0010_1000), "CALL");
+        assert_eq!(mnemonic(0b1111_1011), "MOV");
+        assert_eq!(mnemonic(0x00), INVALID_MNEMONIC);
+        assert_eq!(mnemonic(0b1110_0000), INVALID_MNEMONIC);
+    }
+
     proptest! {
         #[test]
         fn instruction_register_is_reset_correctly(mut register: InstructionRegister) {
@@ -4,30 +4,47 @@ use log::trace;
 #[cfg(test)]
 use proptest_derive::Arbitrary;
 
-use std::ops::Deref;
+use std::{collections::BTreeSet, ops::Deref, sync::Arc};
 
 mod alu;
 mod board;
 mod bus;
+mod diff;
 mod instruction;
 mod microprogram_ram;
 mod raw;
 mod register;
+mod report;
 #[cfg(test)]
 mod tests;
 
 use crate::{
-    compiler::ByteCode,
-    parser::{Programsize, Stacksize},
+    compiler::{ByteCode, CompileError, SourceMap, Translator},
+    disassembler,
+    parser::{self, Asm, Programsize, Stacksize},
 };
-pub use alu::{AluInput, AluOutput, AluSelect};
-pub use board::{Board, InterruptSource, DAICR, DAISR, DASR};
-pub use bus::{Bus, MISR};
-pub use instruction::{Instruction, InstructionRegister};
+pub use alu::{Alu, AluInput, AluOutput, AluSelect};
+pub use board::{
+    clamp_voltage, voltages_equal, Board, InterruptSource, DAICR, DAISR, DASR,
+    DEFAULT_REFERENCE_VOLTAGE, DEFAULT_VOLTAGE, MAX_VOLTAGE, MIN_VOLTAGE, VOLTAGE_EPSILON,
+};
+pub use bus::{Bus, Memory, Ram, MICR, MISR, RAM_SIZE, UART_FIFO_DEPTH, UART_SENT_CAPACITY};
+pub use diff::MachineDiff;
+pub use instruction::{Instruction, InstructionRegister, INVALID_MNEMONIC};
 pub use microprogram_ram::{MicroprogramRam, Word};
 pub(crate) use raw::Interrupt;
-pub use raw::{RawMachine, Signals, State};
+pub use raw::{ErrorCause, RawMachine, Signals, State, StateChange};
 pub use register::{Flags, Register, RegisterNumber};
+pub use report::StepReport;
+
+/// Nominal clock frequency of the real machine in Hertz.
+pub const CLOCK_FREQUENCY: u64 = 7_372_800;
+/// Maximum number of clock cycles emulated by a single [`Machine::step_over`].
+pub const STEP_OVER_MAX_CYCLES: usize = 1_000_000;
+/// Opcode of the `CALL` instruction.
+pub(crate) const OPCODE_CALL: u8 = 0b0010_1000;
+/// Opcode of the `RET` instruction.
+pub(crate) const OPCODE_RET: u8 = 0b0001_0111;
 
 /// A higher level abstraction over the [`RawMachine`].
 ///
@@ -48,7 +65,7 @@ pub use register::{Flags, Register, RegisterNumber};
 ///     ST (0xFF), R0
 ///     JR LOOP
 /// "#).expect("Parsing failed!");
-/// let bytecode = Translator::compile(&parsed);
+/// let bytecode = Translator::compile(&parsed).unwrap();
 /// machine.load(bytecode);
 /// // Step over one instruction at a time
 /// machine.set_step_mode(StepMode::Assembly);
@@ -57,7 +74,7 @@ pub use register::{Flags, Register, RegisterNumber};
 ///     machine.trigger_key_clock();
 /// }
 ///
-/// assert_eq!(machine.bus().output_ff(), 1);
+/// assert_eq!(machine.output_ff(), 1);
 /// assert_eq!(machine.registers().get(RegisterNumber::R0), &1);
 /// ```
 #[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
@@ -67,6 +84,16 @@ pub struct Machine {
     raw: RawMachine,
     /// Currently active [`StepMode`].
     step_mode: StepMode,
+    /// Source of the loaded program, if known.
+    source_map: Arc<SourceMap>,
+    /// Address of the first byte of the loaded program.
+    program_origin: u8,
+    /// Number of bytes of the loaded program.
+    program_len: usize,
+    /// Addresses of instructions at which execution should pause.
+    breakpoints: BTreeSet<u8>,
+    /// The last program loaded by [`Machine::load_at`], if any.
+    program: Option<Arc<ByteCode>>,
 }
 
 impl Machine {
@@ -74,17 +101,34 @@ impl Machine {
         let mut m = Machine {
             raw: RawMachine::new(),
             step_mode: StepMode::Real,
+            source_map: Arc::default(),
+            program_origin: 0,
+            program_len: 0,
+            breakpoints: BTreeSet::new(),
+            program: None,
         };
         m.apply_configuration(config);
         m
     }
 
     pub fn new_with_program(config: MachineConfig, program: ByteCode) -> Self {
+        Machine::new_with_program_at(config, program, 0)
+    }
+
+    /// Create a new machine with the given program loaded at `address`.
+    ///
+    /// See [`Machine::load_at`].
+    pub fn new_with_program_at(config: MachineConfig, program: ByteCode, address: u8) -> Self {
         let mut m = Machine {
             raw: RawMachine::new(),
             step_mode: StepMode::Real,
+            source_map: Arc::default(),
+            program_origin: 0,
+            program_len: 0,
+            breakpoints: BTreeSet::new(),
+            program: None,
         };
-        m.load(program);
+        m.load_at(program, address);
         m.apply_configuration(config);
         m
     }
@@ -100,6 +144,7 @@ impl Machine {
         self.set_jumper2(config.jumper2);
         self.set_analog_input1(config.analog_input1);
         self.set_analog_input2(config.analog_input2);
+        self.set_reference_voltage(config.reference_voltage);
         self.set_universal_input_output1(config.universal_input_output1);
         self.set_universal_input_output2(config.universal_input_output2);
         self.set_universal_input_output3(config.universal_input_output3);
@@ -120,25 +165,137 @@ impl Machine {
         &mut self.raw
     }
 
+    /// Get the contents of the main memory.
+    ///
+    /// This is a shortcut for [`Bus::memory`]. Only the RAM from
+    /// 0x00 - 0xEF is included, peripherals are not.
+    pub fn memory(&self) -> &[u8; 0xF0] {
+        self.bus().memory()
+    }
+
     /// Emulate a rising CLK edge.
     ///
-    /// TODO: Examples
-    pub fn trigger_key_clock(&mut self) {
+    /// Returns the [`StateChange`], if the machine halted.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig, State, StateChange},
+    /// #   parser::AsmParser,
+    /// #   compiler::Translator,
+    /// # };
+    /// let parsed = AsmParser::parse("#! mrasm\n    STOP\n").expect("Parsing failed!");
+    /// let mut machine = Machine::new_with_program(MachineConfig::default(), Translator::compile(&parsed).unwrap());
+    /// let change = (0..10).find_map(|_| machine.trigger_key_clock());
+    /// let expected = StateChange { from: State::Running, to: State::Stopped, cause: None };
+    /// assert_eq!(change, Some(expected));
+    /// ```
+    pub fn trigger_key_clock(&mut self) -> Option<StateChange> {
         match self.step_mode {
             StepMode::Assembly => {
-                // Start the next instruction
-                while self.is_instruction_done() && self.state() == State::Running {
-                    self.raw_mut().trigger_clock_edge()
-                }
-                // Finish this instruction
-                while !self.is_instruction_done() && self.state() == State::Running {
-                    self.raw_mut().trigger_clock_edge()
+                let from = self.state();
+                self.step_instruction();
+                let to = self.state();
+                if from == to {
+                    return None;
                 }
+                Some(StateChange {
+                    from,
+                    to,
+                    cause: self.raw.error_cause(),
+                })
             }
             StepMode::Real => self.raw_mut().trigger_clock_edge(),
         }
     }
 
+    /// Execute the next assembly instruction, stepping over subroutine calls.
+    ///
+    /// If the next instruction is a `CALL`, the whole subroutine is executed
+    /// and the machine stops at the instruction following the call. The return
+    /// is detected by the stackpointer returning to its level from before the
+    /// call, which a matching `RET` or `RETI` does. Any other instruction is
+    /// executed like a single step in [`StepMode::Assembly`].
+    ///
+    /// Execution ends early if the machine halts, hits a breakpoint inside the
+    /// subroutine or after [`STEP_OVER_MAX_CYCLES`] clock cycles. Returns the
+    /// number of emulated clock cycles.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig, RegisterNumber},
+    /// #   parser::AsmParser,
+    /// #   compiler::Translator,
+    /// # };
+    /// let parsed = AsmParser::parse(r#"#! mrasm
+    ///     LDSP 0xEF
+    ///     CALL SUB
+    ///     STOP
+    /// SUB:
+    ///     INC R0
+    ///     RET
+    /// "#).expect("Parsing failed!");
+    /// let mut machine = Machine::new_with_program(MachineConfig::default(), Translator::compile(&parsed).unwrap());
+    /// machine.step_over(); // Initial fetch
+    /// machine.step_over(); // LDSP 0xEF
+    /// machine.step_over(); // CALL SUB, INC R0, RET
+    /// assert_eq!(machine.registers().get(RegisterNumber::R0), &1);
+    /// assert_eq!(machine.registers().get(RegisterNumber::R5), &0xEF);
+    /// ```
+    pub fn step_over(&mut self) -> usize {
+        // Once an instruction is done, the PC points to the next opcode
+        let pc = *self.registers().get(RegisterNumber::R3);
+        let is_call = self.is_instruction_done() && self.bus().read(pc) == OPCODE_CALL;
+        let sp = |machine: &Self| *machine.registers().get(RegisterNumber::R5);
+        let sp_before = sp(self);
+        let mut cycles = self.step_instruction();
+        if is_call {
+            cycles += self.wait_for_next_opcode();
+            while self.state() == State::Running
+                && sp(self) < sp_before
+                && cycles < STEP_OVER_MAX_CYCLES
+                && !self.is_at_breakpoint()
+            {
+                cycles += self.step_instruction();
+                cycles += self.wait_for_next_opcode();
+            }
+        }
+        cycles
+    }
+
+    /// Wait for the memory to deliver the next opcode after an instruction is done.
+    ///
+    /// Breakpoints are only hit once the memory is ready, see [`Machine::is_at_breakpoint`].
+    /// Returns the number of emulated clock cycles.
+    fn wait_for_next_opcode(&mut self) -> usize {
+        let mut cycles = 0;
+        while self.is_instruction_done()
+            && self.is_waiting_for_memory()
+            && self.state() == State::Running
+        {
+            self.raw_mut().trigger_clock_edge();
+            cycles += 1;
+        }
+        cycles
+    }
+
+    /// Finish the current or execute the next assembly instruction.
+    ///
+    /// Returns the number of emulated clock cycles.
+    fn step_instruction(&mut self) -> usize {
+        let mut cycles = 0;
+        // Start the next instruction
+        while self.is_instruction_done() && self.state() == State::Running {
+            self.raw_mut().trigger_clock_edge();
+            cycles += 1;
+        }
+        // Finish this instruction
+        while !self.is_instruction_done() && self.state() == State::Running {
+            self.raw_mut().trigger_clock_edge();
+            cycles += 1;
+        }
+        cycles
+    }
+
     /// Set the content of the input register FC to `number`.
     ///
     /// TODO: Examples
@@ -167,6 +324,61 @@ impl Machine {
         self.raw_mut().bus_mut().input_ff(number)
     }
 
+    /// Get the content of the input register FC.
+    pub fn input_fc(&self) -> u8 {
+        self.bus().read(0xFC)
+    }
+
+    /// Get the content of the input register FD.
+    pub fn input_fd(&self) -> u8 {
+        self.bus().read(0xFD)
+    }
+
+    /// Get the content of the input register FE.
+    pub fn input_fe(&self) -> u8 {
+        self.bus().read(0xFE)
+    }
+
+    /// Get the content of the input register FF.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::{Machine, MachineConfig};
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// machine.set_input_ff(42);
+    /// assert_eq!(machine.input_ff(), 42);
+    /// // Inputs and outputs are separate registers
+    /// assert_eq!(machine.output_ff(), 0);
+    /// ```
+    pub fn input_ff(&self) -> u8 {
+        self.bus().read(0xFF)
+    }
+
+    /// Get the content of the output register FE.
+    pub fn output_fe(&self) -> u8 {
+        self.bus().output_fe()
+    }
+
+    /// Get the content of the output register FF.
+    pub fn output_ff(&self) -> u8 {
+        self.bus().output_ff()
+    }
+
+    /// Enqueue bytes into the UART receive FIFO.
+    ///
+    /// See [`Bus::uart_push_bytes`] for details.
+    pub fn uart_push_bytes(&mut self, bytes: &[u8]) -> usize {
+        self.raw_mut().bus_mut().uart_push_bytes(bytes)
+    }
+
+    /// Assert or deassert the CTS input of the UART.
+    ///
+    /// See [`Bus::set_uart_clear_to_send`] for details.
+    pub fn set_uart_clear_to_send(&mut self, clear: bool) {
+        self.raw_mut().bus_mut().set_uart_clear_to_send(clear)
+    }
+
     /// Trigger the continue key.
     ///
     /// This will return the State to [`Running`](State::Running) if it was [`Stopped`](State::Stopped).
@@ -181,6 +393,13 @@ impl Machine {
         self.raw_mut().trigger_key_edge_interrupt()
     }
 
+    /// Assert or deassert the bus level interrupt request line.
+    ///
+    /// See [`RawMachine::set_level_interrupt`].
+    pub fn set_level_interrupt(&mut self, active: bool) {
+        self.raw_mut().set_level_interrupt(active)
+    }
+
     /// Set the value of the digital input P-DI1.
     ///
     /// This input port is part of the MR2DA2 extension board.
@@ -228,6 +447,17 @@ impl Machine {
             .set_analog_input1(analog_input1)
     }
 
+    /// Set the reference voltage of the DACs.
+    ///
+    /// The DACs are part of the extension board MR2DA2 and feed the
+    /// comparators CP1 and CP2. See [`Board::set_reference_voltage`].
+    pub fn set_reference_voltage(&mut self, reference_voltage: f32) {
+        self.raw_mut()
+            .bus_mut()
+            .board_mut()
+            .set_reference_voltage(reference_voltage)
+    }
+
     /// Set the voltage at the analog input port P-AI2.
     ///
     /// The P-AI2 is part of the extension board MR2DA2. The voltage
@@ -285,8 +515,13 @@ impl Machine {
     {
         trace!("Loading bytes into memory");
         self.master_reset();
+        self.source_map = Arc::default();
+        self.program_origin = 0;
+        self.program_len = 0;
+        self.program = None;
         bytes.enumerate().for_each(|(address, byte)| {
             self.raw_mut().bus_mut().memory_mut()[address] = *byte;
+            self.program_len += 1;
         });
     }
 
@@ -295,16 +530,76 @@ impl Machine {
     /// This will:
     /// - Reset the machine
     /// - Fill the memory
-    /// - Set the maximum stacksize
-    /// - Set the maximum program counter value (the programsize)
+    /// - Set the maximum stacksize, as given by the program's `*STACKSIZE`.
+    ///   Programs without one use the default of 16 bytes.
+    ///   `*STACKSIZE NOSET` keeps the current stacksize
+    /// - Set the maximum program counter value (the programsize), as given by
+    ///   the program's `*PROGRAMSIZE`. `AUTO`, the default, limits the program
+    ///   counter to the number of program bytes. `*PROGRAMSIZE NOSET` keeps the
+    ///   current programsize
     pub fn load(&mut self, program: ByteCode) {
-        trace!("Loading new program");
+        self.load_at(program, 0)
+    }
+
+    /// Compile the given program and load it into the machine.
+    ///
+    /// This is a shortcut for [`Translator::compile`] followed by [`Machine::load`].
+    /// If the program cannot be compiled, the machine is left untouched.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig, State},
+    /// #   parser::AsmParser,
+    /// # };
+    /// let parsed = AsmParser::parse(r#"#! mrasm
+    ///     LD R0, 0x2A
+    ///     ST (0xFF), R0
+    ///     STOP
+    /// "#).expect("Parsing failed!");
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// machine.load_asm(&parsed).expect("Compilation failed!");
+    /// while machine.state() == State::Running {
+    ///     machine.trigger_key_clock();
+    /// }
+    /// assert_eq!(machine.output_ff(), 0x2A);
+    ///
+    /// // The program does not fit into the memory
+    /// let overflowing = AsmParser::parse("#! mrasm\n.ORG 0xFF\n    STOP\n    NOP\n").unwrap();
+    /// assert!(machine.load_asm(&overflowing).is_err());
+    /// assert_eq!(machine.output_ff(), 0x2A);
+    /// ```
+    pub fn load_asm(&mut self, asm: &Asm) -> Result<(), CompileError> {
+        let program = Translator::compile(asm)?;
+        self.load(program);
+        Ok(())
+    }
+
+    /// Load the given program into the machine, placing its first byte at `address`.
+    ///
+    /// This works like [`Machine::load`], but additionally points the program
+    /// counter to `address`. An automatic programsize includes the bytes before
+    /// `address`. Bytes that would end up beyond the main memory are dropped,
+    /// the automatic programsize then ends with the memory, too.
+    ///
+    /// Only the placement of the bytes is shifted, labels keep the addresses
+    /// calculated by the compiler from the `.ORG` directives. Thus only position
+    /// independent code works as expected, i.e. code using relative jumps, but no
+    /// `JMP`, `CALL` or memory accesses to labels. A CPU reset restarts the
+    /// program at `0x00`, not at `address`.
+    pub fn load_at(&mut self, program: ByteCode, address: u8) {
+        trace!("Loading new program at 0x{:02X}", address);
         self.master_reset();
         self.raw_mut().bus_mut().reset_ram();
         trace!("Loading bytes into memory");
-        program.bytes().enumerate().for_each(|(address, byte)| {
-            self.raw_mut().bus_mut().memory_mut()[address] = *byte;
-        });
+        let memory = self.raw_mut().bus_mut().memory_mut();
+        for (cell, byte) in memory
+            .iter_mut()
+            .skip(address as usize)
+            .zip(program.bytes())
+        {
+            *cell = *byte;
+        }
+        self.raw_mut().set_register(RegisterNumber::R3, address);
         // If the stacksize is NOSET, do not update the stacksize
         if program.stacksize != Stacksize::NotSet {
             self.raw_mut().set_stacksize(program.stacksize);
@@ -314,13 +609,219 @@ impl Machine {
             Programsize::Size(_) => self.raw_mut().set_programsize(program.programsize),
             Programsize::Auto => {
                 // Calculate the program size from the amount of bytes in the program
-                let size = program.bytes().fold(0, |sum, _| sum + 1);
-                debug_assert!(size <= 255, "BUG: The program is too large");
+                let size = (address as usize + program.bytes().count()).min(RAM_SIZE);
                 self.raw_mut()
                     .set_programsize(Programsize::Size(size as u8));
             }
             Programsize::NotSet => {}
         }
+        self.source_map = Arc::new(program.source_map_at(address));
+        self.program_origin = address;
+        self.program_len = program.bytes().count();
+        self.program = Some(Arc::new(program));
+    }
+
+    /// Reset the machine and load the last loaded program again.
+    ///
+    /// The program is placed at the same address as before, see [`Machine::load_at`].
+    /// Unlike a real master reset, the input registers `FC - FF` keep their values,
+    /// so the program can be rerun with the same inputs. Breakpoints are kept, too.
+    /// Returns `false` and leaves the machine untouched, if no program was loaded.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig, State},
+    /// #   parser::AsmParser,
+    /// # };
+    /// let parsed = AsmParser::parse("#! mrasm\n    INC R0\n    ST (0x20), R0\n    STOP\n").unwrap();
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// assert!(!machine.reset_and_reload());
+    /// machine.load_asm(&parsed).expect("Compilation failed!");
+    /// while machine.state() == State::Running {
+    ///     machine.trigger_key_clock();
+    /// }
+    /// assert_eq!(machine.bus().read(0x20), 1);
+    /// assert!(machine.reset_and_reload());
+    /// assert_eq!(machine.bus().read(0x20), 0);
+    /// assert_eq!(machine.state(), State::Running);
+    /// ```
+    pub fn reset_and_reload(&mut self) -> bool {
+        let program = match &self.program {
+            Some(program) => ByteCode::clone(program),
+            None => return false,
+        };
+        let inputs = [0xFC, 0xFD, 0xFE, 0xFF].map(|addr| self.bus().read(addr));
+        self.load_at(program, self.program_origin);
+        let [fc, fd, fe, ff] = inputs;
+        self.set_input_fc(fc);
+        self.set_input_fd(fd);
+        self.set_input_fe(fe);
+        self.set_input_ff(ff);
+        true
+    }
+
+    /// Get the number of bytes the loaded program occupies.
+    ///
+    /// This includes the gaps left by `.ORG` and `.ALIGN` and is zero, if no
+    /// program was loaded. Bytes dropped for not fitting into the main memory
+    /// are counted aswell.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig},
+    /// #   parser::AsmParser,
+    /// # };
+    /// let parsed = AsmParser::parse("#! mrasm\n    LD R0, 0x2A\n    STOP\n").unwrap();
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// assert_eq!(machine.loaded_program_len(), 0);
+    /// machine.load_asm(&parsed).unwrap();
+    /// assert_eq!(machine.loaded_program_len(), 4);
+    /// ```
+    pub const fn loaded_program_len(&self) -> usize {
+        self.program_len
+    }
+
+    /// Get the address of the first byte of the loaded program.
+    ///
+    /// This is the address given to [`Machine::load_at`], zero for [`Machine::load`].
+    pub const fn loaded_program_origin(&self) -> u8 {
+        self.program_origin
+    }
+
+    /// Write the bytes of the given program into memory, without resetting the machine.
+    ///
+    /// Unlike [`Machine::load`], registers, inputs, the stacksize and the programsize
+    /// are kept and the memory not touched by the program stays as it is. Gaps
+    /// left by `.ORG` and `.ALIGN` are not written, which allows placing data
+    /// next to an already loaded program. Bytes beyond the main memory are dropped.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   compiler::Translator,
+    /// #   machine::{Machine, MachineConfig},
+    /// #   parser::AsmParser,
+    /// # };
+    /// let program = AsmParser::parse("#! mrasm\n    LD R0, (0x80)\n    STOP\n").unwrap();
+    /// let data = AsmParser::parse("#! mrasm\n.ORG 0x80\n.DB 42\n").unwrap();
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// machine.load_asm(&program).unwrap();
+    /// machine.load_overlay(&Translator::compile(&data).unwrap());
+    /// assert_eq!(machine.bus().read(0x00), 0xFF);
+    /// assert_eq!(machine.bus().read(0x80), 42);
+    /// ```
+    pub fn load_overlay(&mut self, program: &ByteCode) {
+        trace!("Loading overlay into memory");
+        let memory = self.raw_mut().bus_mut().memory_mut();
+        for (start, _, bytes) in program.placed_lines() {
+            for (cell, byte) in memory.iter_mut().skip(start as usize).zip(bytes) {
+                *cell = *byte;
+            }
+        }
+    }
+
+    /// Decode the instruction at `addr` using the current memory.
+    ///
+    /// Returns the instruction together with its length in bytes, see
+    /// [`disassembler::decode`] for details. `None` is returned, if the bytes
+    /// do not form a valid instruction or if `addr` is not located in the RAM.
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{
+    /// #   machine::{Machine, MachineConfig},
+    /// #   parser::AsmParser,
+    /// # };
+    /// let parsed = AsmParser::parse("#! mrasm\n    CLR R0\n    INC R0\n").unwrap();
+    /// let mut machine = Machine::new(MachineConfig::default());
+    /// machine.load_asm(&parsed).unwrap();
+    /// let (inst, len) = machine.peek_instruction_at(0x01).unwrap();
+    /// assert_eq!(inst.to_string(), "INC R0");
+    /// assert_eq!(len, 1);
+    /// ```
+    pub fn peek_instruction_at(&self, addr: u8) -> Option<(parser::Instruction, u8)> {
+        let bytes = self.memory().get(addr as usize..)?;
+        disassembler::decode(bytes, addr)
+    }
+
+    /// Get the lines of the loaded program together with the address
+    /// of their first byte in memory.
+    ///
+    /// This is empty, if no program or only raw bytes were loaded.
+    /// See [`SourceMap::lines`].
+    pub fn source_lines(&self) -> &[(String, u8)] {
+        self.source_map.lines()
+    }
+
+    /// Get the index into [`Machine::source_lines`] of the line the
+    /// program counter points to.
+    ///
+    /// Once an instruction is done, the program counter points to the
+    /// next instruction, thus the next line is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::{compiler::Translator, parser::AsmParser};
+    /// # use emulator_2a_lib::machine::{Machine, MachineConfig};
+    /// let program = "#! mrasm\nLOOP:\n    JR LOOP";
+    /// let bytecode = Translator::compile(&AsmParser::parse(program).unwrap()).unwrap();
+    /// let mut machine = Machine::new_with_program(MachineConfig::default(), bytecode);
+    ///
+    /// let current = machine.current_source_line().unwrap();
+    /// assert_eq!(machine.source_lines()[current], ("    JR LOOP".into(), 0));
+    /// ```
+    pub fn current_source_line(&self) -> Option<usize> {
+        let pc = *self.registers().get(RegisterNumber::R3);
+        self.source_map.line_index_at(pc)
+    }
+
+    /// Get the addresses of all breakpoints.
+    ///
+    /// Breakpoints are kept when loading a new program.
+    pub fn breakpoints(&self) -> &BTreeSet<u8> {
+        &self.breakpoints
+    }
+
+    /// Add a breakpoint at the instruction starting at `addr`.
+    ///
+    /// Returns `false`, if the breakpoint already existed.
+    pub fn add_breakpoint(&mut self, addr: u8) -> bool {
+        self.breakpoints.insert(addr)
+    }
+
+    /// Remove the breakpoint at `addr`.
+    ///
+    /// Returns `false`, if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, addr: u8) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    /// Add the breakpoint at `addr` or remove it, if it exists.
+    ///
+    /// Returns whether the breakpoint exists afterwards.
+    pub fn toggle_breakpoint(&mut self, addr: u8) -> bool {
+        if self.remove_breakpoint(addr) {
+            false
+        } else {
+            self.add_breakpoint(addr)
+        }
+    }
+
+    /// Remove all breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear()
+    }
+
+    /// Is the next instruction located at a breakpoint?
+    ///
+    /// This is only ever `true` between two instructions, when the
+    /// program counter points to an address with a breakpoint. If the
+    /// memory delays the next instruction, this is `true` for the last
+    /// clock edge before the next instruction only.
+    pub fn is_at_breakpoint(&self) -> bool {
+        let pc = *self.registers().get(RegisterNumber::R3);
+        self.is_instruction_done()
+            && !self.is_waiting_for_memory()
+            && self.breakpoints.contains(&pc)
     }
 
     /// Reset the program execution.
@@ -365,8 +866,24 @@ impl Deref for Machine {
 ///
 /// assert_eq!(config1, config2);
 /// ```
-#[derive(Debug, Clone, Default, PartialEq, Builder)]
+///
+/// # Equality
+///
+/// Voltages are compared in whole millivolts, thus tiny rounding errors do
+/// not make two configurations differ. Use [`MachineConfig::approx_eq`] to
+/// compare voltages using [`voltages_equal`] instead.
+///
+/// # Serialization
+///
+/// With the `serde` feature, the configuration can be (de)serialized.
+/// Missing fields take their default value, unknown fields are rejected.
+#[derive(Debug, Clone, Builder)]
 #[builder(default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct MachineConfig {
     pub digital_input1: u8,
     pub temp: f32,
@@ -374,6 +891,10 @@ pub struct MachineConfig {
     pub jumper2: bool,
     pub analog_input1: f32,
     pub analog_input2: f32,
+    /// Reference voltage of the DACs on the MR2DA2.
+    ///
+    /// Defaults to [`DEFAULT_REFERENCE_VOLTAGE`], like the real board.
+    pub reference_voltage: f32,
     pub universal_input_output1: bool,
     pub universal_input_output2: bool,
     pub universal_input_output3: bool,
@@ -383,6 +904,72 @@ pub struct MachineConfig {
     pub input_ff: u8,
 }
 
+impl Default for MachineConfig {
+    fn default() -> Self {
+        MachineConfig {
+            digital_input1: 0,
+            temp: DEFAULT_VOLTAGE,
+            jumper1: false,
+            jumper2: false,
+            analog_input1: DEFAULT_VOLTAGE,
+            analog_input2: DEFAULT_VOLTAGE,
+            reference_voltage: DEFAULT_REFERENCE_VOLTAGE,
+            universal_input_output1: false,
+            universal_input_output2: false,
+            universal_input_output3: false,
+            input_fc: 0,
+            input_fd: 0,
+            input_fe: 0,
+            input_ff: 0,
+        }
+    }
+}
+
+impl MachineConfig {
+    /// Compare two configurations, using [`voltages_equal`] for all voltages.
+    ///
+    /// Unlike `==`, this ignores every difference below [`VOLTAGE_EPSILON`].
+    /// It is not transitive and thus not used for [`PartialEq`].
+    ///
+    /// ```
+    /// # use emulator_2a_lib::machine::MachineConfig;
+    /// let config = MachineConfig {
+    ///     temp: 0.002,
+    ///     ..Default::default()
+    /// };
+    /// assert_ne!(config, MachineConfig::default());
+    /// assert!(config.approx_eq(&MachineConfig::default()));
+    /// ```
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        self.eq_by(other, voltages_equal)
+    }
+
+    /// Compare all fields, using `voltages_eq` for the voltages.
+    fn eq_by(&self, other: &Self, voltages_eq: impl Fn(f32, f32) -> bool) -> bool {
+        self.digital_input1 == other.digital_input1
+            && voltages_eq(self.temp, other.temp)
+            && self.jumper1 == other.jumper1
+            && self.jumper2 == other.jumper2
+            && voltages_eq(self.analog_input1, other.analog_input1)
+            && voltages_eq(self.analog_input2, other.analog_input2)
+            && voltages_eq(self.reference_voltage, other.reference_voltage)
+            && self.universal_input_output1 == other.universal_input_output1
+            && self.universal_input_output2 == other.universal_input_output2
+            && self.universal_input_output3 == other.universal_input_output3
+            && self.input_fc == other.input_fc
+            && self.input_fd == other.input_fd
+            && self.input_fe == other.input_fe
+            && self.input_ff == other.input_ff
+    }
+}
+
+impl PartialEq for MachineConfig {
+    fn eq(&self, other: &Self) -> bool {
+        let millivolts = |voltage: f32| (voltage * 1000.0).round() as i32;
+        self.eq_by(other, |a, b| millivolts(a) == millivolts(b))
+    }
+}
+
 /// Possible step modes for execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(test, derive(Arbitrary))]
@@ -7,7 +7,7 @@ use proptest_derive::Arbitrary;
 mod signals;
 
 use super::{
-    AluInput, AluOutput, Bus, Instruction, InstructionRegister, MicroprogramRam, Register,
+    AluInput, AluOutput, Bus, Flags, Instruction, InstructionRegister, MicroprogramRam, Register,
     RegisterNumber, Word,
 };
 use crate::{
@@ -15,6 +15,9 @@ use crate::{
     parser::{Programsize, Stacksize},
 };
 pub use signals::Signals;
+use signals::SignalsCache;
+
+use std::fmt;
 
 /// A marker for an Interrupt.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -30,9 +33,15 @@ pub struct MemoryWait;
 #[cfg_attr(test, derive(Arbitrary))]
 pub struct FlagWrite;
 
+/// A marker for registers that were modified from outside the program.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct RegisterCheck;
+
 /// State of the machine.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(test, derive(Arbitrary))]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 pub enum State {
     /// Machine stopped regularly.
     Stopped,
@@ -42,7 +51,56 @@ pub enum State {
     Running,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The reason for a [`State::ErrorStopped`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub enum ErrorCause {
+    /// The stackpointer left the area reserved for the stack.
+    InvalidStackpointer,
+    /// The program counter pointed outside of the program.
+    InvalidProgramCounter,
+    /// The opcode 0x00 was fetched.
+    InvalidOpcode,
+}
+
+/// A change of the [`State`] during a single clock edge.
+///
+/// See [`RawMachine::trigger_clock_edge`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StateChange {
+    /// State before the clock edge.
+    pub from: State,
+    /// State after the clock edge.
+    pub to: State,
+    /// The reason, if the machine stopped with [`State::ErrorStopped`].
+    pub cause: Option<ErrorCause>,
+}
+
+impl fmt::Display for State {
+    /// Format the state like the emulator does, i.e. `Running` or `Error`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            State::Running => "Running",
+            State::Stopped => "Stopped",
+            State::ErrorStopped => "Error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for ErrorCause {
+    /// Describe the error, i.e. `the stackpointer became invalid`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            ErrorCause::InvalidStackpointer => "the stackpointer became invalid",
+            ErrorCause::InvalidProgramCounter => "the program counter became invalid",
+            ErrorCause::InvalidOpcode => "the opcode 0x00 was read",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct RawMachine {
     /// Keeping track of the address and content of the microprogram ram.
     microprogram_ram: MicroprogramRam,
@@ -56,12 +114,16 @@ pub struct RawMachine {
     pending_register_write: Option<RegisterNumber>,
     /// Do we have to update the flag register?
     pending_flag_write: Option<FlagWrite>,
+    /// Do we have to verify registers that were modified externally?
+    pending_register_check: Option<RegisterCheck>,
     /// Do we have a pending edge interrupt?
     pending_edge_interrupt: Option<Interrupt>,
     /// Is the level interrupt alive?
     pending_level_interrupt: Option<Interrupt>,
     /// Current state of the machine.
     state: State,
+    /// Reason for the last [`State::ErrorStopped`].
+    error_cause: Option<ErrorCause>,
     /// Do we have to wait one cycle for the memory?
     pending_wait_for_memory: Option<MemoryWait>,
     /// Latest output of the ALU
@@ -72,6 +134,57 @@ pub struct RawMachine {
     programsize: Programsize,
     /// Bus content from last cycle
     last_bus_read: u8,
+    /// Decoded signals of the current microprogram word.
+    signals_cache: SignalsCache,
+    /// Register write committed during the last clock edge.
+    last_register_write: Option<(RegisterNumber, u8)>,
+    /// Bus write committed during the last clock edge.
+    last_memory_write: Option<(u8, u8)>,
+}
+
+impl PartialEq for RawMachine {
+    /// Compare everything but the signals cache, which is derived from the microprogram ram.
+    fn eq(&self, other: &Self) -> bool {
+        let RawMachine {
+            microprogram_ram,
+            register,
+            instruction_register,
+            bus,
+            pending_register_write,
+            pending_flag_write,
+            pending_register_check,
+            pending_edge_interrupt,
+            pending_level_interrupt,
+            state,
+            error_cause,
+            pending_wait_for_memory,
+            alu_output,
+            stacksize,
+            programsize,
+            last_bus_read,
+            signals_cache: _,
+            last_register_write,
+            last_memory_write,
+        } = self;
+        *microprogram_ram == other.microprogram_ram
+            && *register == other.register
+            && *instruction_register == other.instruction_register
+            && *bus == other.bus
+            && *pending_register_write == other.pending_register_write
+            && *pending_flag_write == other.pending_flag_write
+            && *pending_register_check == other.pending_register_check
+            && *pending_edge_interrupt == other.pending_edge_interrupt
+            && *pending_level_interrupt == other.pending_level_interrupt
+            && *state == other.state
+            && *error_cause == other.error_cause
+            && *pending_wait_for_memory == other.pending_wait_for_memory
+            && *alu_output == other.alu_output
+            && *stacksize == other.stacksize
+            && *programsize == other.programsize
+            && *last_bus_read == other.last_bus_read
+            && *last_register_write == other.last_register_write
+            && *last_memory_write == other.last_memory_write
+    }
 }
 
 #[derive(Debug)]
@@ -106,12 +219,13 @@ impl RawMachine {
     ///
     /// # Examples
     /// TODO: Examples
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         let microprogram_ram = MicroprogramRam::new();
         let register = Register::new();
         let instruction_register = InstructionRegister::new();
         let pending_register_write = None;
         let pending_flag_write = None;
+        let pending_register_check = None;
         let pending_edge_interrupt = None;
         let pending_level_interrupt = None;
         let pending_wait_for_memory = None;
@@ -119,8 +233,12 @@ impl RawMachine {
         let stacksize = Stacksize::default();
         let programsize = Programsize::default();
         let state = State::Running;
+        let error_cause = None;
         let alu_output = AluOutput::default();
         let last_bus_read = 0;
+        let signals_cache = SignalsCache::empty();
+        let last_register_write = None;
+        let last_memory_write = None;
         RawMachine {
             microprogram_ram,
             register,
@@ -128,14 +246,19 @@ impl RawMachine {
             bus,
             pending_register_write,
             pending_flag_write,
+            pending_register_check,
             pending_edge_interrupt,
             pending_level_interrupt,
             state,
+            error_cause,
             pending_wait_for_memory,
             alu_output,
             stacksize,
             programsize,
             last_bus_read,
+            signals_cache,
+            last_register_write,
+            last_memory_write,
         }
     }
 
@@ -154,6 +277,23 @@ impl RawMachine {
         self.state
     }
 
+    /// Why the machine stopped with [`State::ErrorStopped`].
+    ///
+    /// Returns `None`, if the machine is not error stopped.
+    pub const fn error_cause(&self) -> Option<ErrorCause> {
+        match self.state {
+            State::ErrorStopped => self.error_cause,
+            _ => None,
+        }
+    }
+
+    /// Get a reference to the instruction register.
+    ///
+    /// See [`InstructionRegister::mnemonic`] for a readable representation.
+    pub const fn instruction_register(&self) -> &InstructionRegister {
+        &self.instruction_register
+    }
+
     /// Get a reference to the currently executed opcode instruction.
     pub const fn word(&self) -> &Instruction {
         self.instruction_register.get()
@@ -179,23 +319,54 @@ impl RawMachine {
         self.programsize = programsize
     }
 
+    /// Get the register write that was committed during the last clock edge.
+    ///
+    /// Returns the written register and the new value.
+    /// This is cleared at the beginning of every clock edge.
+    pub const fn last_register_write(&self) -> Option<(RegisterNumber, u8)> {
+        self.last_register_write
+    }
+
+    /// Get the bus write that was committed during the last clock edge.
+    ///
+    /// Returns the address and the written value. This includes writes to
+    /// memory mapped I/O, i.e. the output registers.
+    /// This is cleared at the beginning of every clock edge.
+    pub const fn last_memory_write(&self) -> Option<(u8, u8)> {
+        self.last_memory_write
+    }
+
     /// Trigger a key edge interrupt.
+    ///
+    /// This marks the key interrupt request as active in the MISR. If key edge
+    /// interrupts are enabled in the MICR, the interrupt is marked as pending, too.
+    /// Both bits are cleared by the next `RETI` or a CPU reset.
     pub fn trigger_key_edge_interrupt(&mut self) {
         trace!("Key edge interrupt fired, checking control registers..");
         if self.bus.is_key_edge_int_enabled() {
             trace!("Key edge interrupt triggered successfully.");
             self.pending_edge_interrupt = Some(Interrupt);
-            // TODO: I don't actually know when this needs setting. See #34
             self.bus_mut()
                 .misr_mut()
                 .insert(MISR::KEY_INTERRUPT_PENDING);
         }
-        // TODO: I don't actually know when this needs setting. See #34
+        // The request is visible, even if the interrupt is disabled
         self.bus_mut()
             .misr_mut()
             .insert(MISR::KEY_INTERRUPT_REQUEST_ACTIVE);
     }
 
+    /// Assert or deassert the bus level interrupt request line.
+    ///
+    /// While the line is active, the bus interrupt request is marked as active
+    /// in the MISR. If bus level interrupts are enabled in the MICR, the interrupt
+    /// is pending, too, and the interrupt service routine is entered again after
+    /// every `RETI`, until the line is deasserted.
+    pub fn set_level_interrupt(&mut self, active: bool) {
+        trace!("Bus level interrupt request set to {}", active);
+        self.bus_mut().set_level_interrupt_request(active);
+    }
+
     /// Trigger the `CONTINUE` key.
     ///
     /// This will move the state from [`State::Stopped`] -> [`State::Running`].
@@ -206,10 +377,59 @@ impl RawMachine {
     }
 
     /// Get mutable access to the underlying registers.
+    ///
+    /// **Note**: Modifying the registers from outside the program can
+    /// easily desync the machine, i.e. changing the program counter in the
+    /// middle of an instruction. The stackpointer and program counter will be
+    /// verified during the next clock edge, stopping the machine
+    /// with [`State::ErrorStopped`] if they are invalid.
     pub fn registers_mut(&mut self) -> &mut Register {
+        self.pending_register_check = Some(RegisterCheck);
         &mut self.register
     }
 
+    /// Set the register `reg` to `value`.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_register(&mut self, reg: RegisterNumber, value: u8) {
+        self.registers_mut().set(reg, value)
+    }
+
+    /// Replace all flags in the flag register.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_flags(&mut self, flags: Flags) {
+        self.registers_mut().set_flags(flags)
+    }
+
+    /// Set the carry flag in the flag register.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_carry_flag(&mut self, val: bool) {
+        self.registers_mut().set_carry_flag(val)
+    }
+
+    /// Set the zero flag in the flag register.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_zero_flag(&mut self, val: bool) {
+        self.registers_mut().set_zero_flag(val)
+    }
+
+    /// Set the negative flag in the flag register.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_negative_flag(&mut self, val: bool) {
+        self.registers_mut().set_negative_flag(val)
+    }
+
+    /// Set the interrupt enable flag in the flag register.
+    ///
+    /// See [`RawMachine::registers_mut`] for the caveats.
+    pub fn set_interrupt_enable_flag(&mut self, val: bool) {
+        self.registers_mut().set_interrupt_enable_flag(val)
+    }
+
     /// Get mutable access to the underlying bus.
     pub fn bus_mut(&mut self) -> &mut Bus {
         &mut self.bus
@@ -220,6 +440,16 @@ impl RawMachine {
         Signals::from(self)
     }
 
+    /// Get the registers currently selected by the register select inputs A and B.
+    ///
+    /// Like [`Signals::selected_register_a`] and [`Signals::selected_register_b`],
+    /// this reflects the current microprogram word, not the last register write.
+    /// See [`RawMachine::last_register_write`] for that.
+    pub fn selected_registers(&self) -> (RegisterNumber, RegisterNumber) {
+        let signals = self.signals();
+        (signals.selected_register_a(), signals.selected_register_b())
+    }
+
     /// Is the current instruction done executing?
     ///
     /// This will return `true`, iff the [`Word`] that was executed during the last
@@ -228,6 +458,23 @@ impl RawMachine {
         self.microprogram_ram.get_word().contains(Word::MAC3)
     }
 
+    /// Will the next clock edge be skipped, because the memory is not ready?
+    pub const fn is_waiting_for_memory(&self) -> bool {
+        self.pending_wait_for_memory.is_some()
+    }
+
+    /// Is an edge interrupt pending?
+    ///
+    /// The interrupt stays pending until the CPU starts to service it.
+    pub const fn is_edge_interrupt_pending(&self) -> bool {
+        self.pending_edge_interrupt.is_some()
+    }
+
+    /// Is a level interrupt active?
+    pub const fn is_level_interrupt_active(&self) -> bool {
+        self.pending_level_interrupt.is_some()
+    }
+
     /// Reset the program execution.
     ///
     /// This resets:
@@ -236,20 +483,26 @@ impl RawMachine {
     ///  - The instruction register
     ///  - The output register
     ///  - The MICR
+    ///  - The MISR
     ///  - The UCR
     ///  - Edge interrupts
     ///  - The machine state back to Running
     pub fn cpu_reset(&mut self) {
         self.microprogram_ram.reset();
+        self.signals_cache.invalidate();
         self.register.reset();
         self.instruction_register.reset();
         self.pending_register_write = None;
         self.pending_flag_write = None;
+        self.pending_register_check = None;
         self.pending_edge_interrupt = None;
         self.state = State::Running;
+        self.error_cause = None;
         self.pending_wait_for_memory = None;
         self.alu_output = AluOutput::default();
         self.last_bus_read = 0;
+        self.last_register_write = None;
+        self.last_memory_write = None;
         self.bus.cpu_reset();
     }
 
@@ -266,7 +519,27 @@ impl RawMachine {
     }
 
     /// Emulate a rising CLK edge.
-    pub fn trigger_clock_edge(&mut self) {
+    ///
+    /// Returns the [`StateChange`], if the [`State`] changed during this edge,
+    /// i.e. when the machine halts.
+    pub fn trigger_clock_edge(&mut self) -> Option<StateChange> {
+        let from = self.state;
+        self.clock_edge();
+        if from == self.state {
+            return None;
+        }
+        Some(StateChange {
+            from,
+            to: self.state,
+            cause: self.error_cause(),
+        })
+    }
+
+    /// Emulate a rising CLK edge, without reporting state changes.
+    fn clock_edge(&mut self) {
+        self.bus.count_cycle();
+        self.last_register_write = None;
+        self.last_memory_write = None;
         if self.state != State::Running {
             trace!("Ignoring clock. Machine halted.");
             return;
@@ -274,6 +547,12 @@ impl RawMachine {
             trace!("Skipping clock. Waiting for memory.");
             return;
         }
+        if self.is_instruction_done() {
+            // The last cycle fetched the opcode at the (not yet incremented) PC
+            let pc = *self.register.get(RegisterNumber::R3);
+            trace!("");
+            trace!("===== Begin of instruction at PC 0x{:02X} =====", pc);
+        }
         trace!("");
         trace!("----- Begin of clock cycle -----");
         self.apply_pending_register_writes()
@@ -285,6 +564,13 @@ impl RawMachine {
             .write_to_memory();
         trace!("----- End of clock cycle -------");
         trace!("");
+        if self.is_instruction_done() {
+            trace!(
+                "===== End of instruction {:?} (0x{:02X}) =====",
+                self.word(),
+                self.instruction_register.get_raw()
+            );
+        }
     }
 
     /// Check the stackpointer.
@@ -316,6 +602,16 @@ impl RawMachine {
         }
     }
 
+    /// Stop the machine with [`State::ErrorStopped`] because of `cause`.
+    ///
+    /// The first cause is kept, if multiple errors occur during one edge.
+    fn error_stop(&mut self, cause: ErrorCause) {
+        if self.state != State::ErrorStopped {
+            self.state = State::ErrorStopped;
+            self.error_cause = Some(cause);
+        }
+    }
+
     /// Writes values to the register that were created during the
     /// last cycle. This writes to the selected register if necessary
     /// and saves the flags, if requested.
@@ -340,14 +636,18 @@ impl RawMachine {
             let new_value = self.alu_output.output();
             trace!("Updating register {:?} with {:?}", register, new_value);
             self.register.set(register, new_value);
+            self.last_register_write = Some((register, new_value));
+            self.pending_register_check = Some(RegisterCheck);
+        }
+        if let Some(RegisterCheck) = self.pending_register_check.take() {
             // Check stackpointer
             if !self.is_stackpointer_valid() {
                 warn!("Stackpointer became invalid");
-                self.state = State::ErrorStopped;
+                self.error_stop(ErrorCause::InvalidStackpointer);
             }
             if !self.is_program_counter_valid() {
                 warn!("Program counter became invalid");
-                self.state = State::ErrorStopped;
+                self.error_stop(ErrorCause::InvalidProgramCounter);
             }
         }
         MachineAfterRegWrite(self)
@@ -365,22 +665,14 @@ impl<'a> MachineAfterRegWrite<'a> {
             // Selecting next instruction
             if machine.last_bus_read == 0x00 {
                 warn!("Read 0x00 instruction! Error halting");
-                machine.state = State::ErrorStopped;
+                machine.error_stop(ErrorCause::InvalidOpcode);
             } else if machine.last_bus_read == 0x01 {
                 warn!("Read 0x01 instruction. Halting.");
                 machine.state = State::Stopped;
             } else if machine.last_bus_read == 0b0010_1100 {
                 // We need to clear some MISR flags once the program returns from interrupt
                 trace!("RETI detected. Removing MISR flags");
-                // TODO: I don't actually know when this needs setting. See #34
-                machine
-                    .bus_mut()
-                    .misr_mut()
-                    .remove(MISR::KEY_INTERRUPT_PENDING);
-                machine
-                    .bus_mut()
-                    .misr_mut()
-                    .remove(MISR::KEY_INTERRUPT_REQUEST_ACTIVE);
+                machine.bus_mut().return_from_interrupt();
             }
             machine.instruction_register.set_raw(machine.last_bus_read);
             trace!("Next instruction: {:?}", machine.instruction_register);
@@ -398,10 +690,9 @@ impl<'a> MachineAfterInstructionUpdate<'a> {
             .pending_edge_interrupt
             .take()
             .or_else(|| machine.bus.take_edge_interrupt());
-        machine.pending_level_interrupt = machine
-            .pending_level_interrupt
-            .take()
-            .or_else(|| machine.bus.get_level_interrupt());
+        // Level interrupts last only as long as their request is active,
+        // a withdrawn request must not enter the interrupt service routine again
+        machine.pending_level_interrupt = machine.bus.get_level_interrupt();
         MachineAfterInterruptFetching(machine)
     }
 }
@@ -419,6 +710,7 @@ impl<'a> MachineAfterInterruptFetching<'a> {
         // Do not update MP_RAM address before interrupt clear check
         trace!("New MP_RAM address: {}", next_mp_ram_addr);
         machine.microprogram_ram.set_address(next_mp_ram_addr);
+        machine.signals_cache.update(&machine.microprogram_ram);
         MachineAfterWordUpdate(machine)
     }
 }
@@ -430,7 +722,7 @@ impl<'a> MachineAfterWordUpdate<'a> {
         let selected_reg_a = machine.signals().selected_register_a();
         let register_out_a = machine.register.get(selected_reg_a);
         if machine.signals().busen() {
-            machine.last_bus_read = machine.bus.read(*register_out_a);
+            machine.last_bus_read = machine.bus.read_and_advance(*register_out_a);
             trace!(
                 "Reading {:?} from bus address {:?}",
                 machine.last_bus_read,
@@ -495,6 +787,7 @@ impl<'a> MachineAfterAluCalculations<'a> {
             machine
                 .bus
                 .write(*register_out_a, machine.alu_output.output());
+            machine.last_memory_write = Some((*register_out_a, machine.alu_output.output()));
             if *register_out_a <= 0xEF {
                 trace!("Generating artificial wait signal");
                 machine.pending_wait_for_memory = Some(MemoryWait);
@@ -506,8 +799,15 @@ impl<'a> MachineAfterAluCalculations<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        compiler::Translator,
+        machine::{Machine, MachineConfig},
+        parser::AsmParser,
+    };
     use proptest::prelude::*;
 
+    use std::fs::read_to_string;
+
     impl RawMachine {
         prop_compose! {
             pub fn arbitrary()(
@@ -517,9 +817,11 @@ mod tests {
                 bus in Bus::arbitrary(),
                 pending_register_write in any::<Option<RegisterNumber>>(),
                 pending_flag_write in any::<Option<FlagWrite>>(),
+                pending_register_check in any::<Option<RegisterCheck>>(),
                 pending_edge_interrupt in any::<Option<Interrupt>>(),
                 pending_level_interrupt in any::<Option<Interrupt>>(),
                 state in any::<State>(),
+                error_cause in any::<Option<ErrorCause>>(),
                 pending_wait_for_memory in any::<Option<MemoryWait>>(),
                 alu_output in any::<AluOutput>(),
                 stacksize in any::<Stacksize>(),
@@ -533,14 +835,19 @@ mod tests {
                     bus,
                     pending_register_write,
                     pending_flag_write,
+                    pending_register_check,
                     pending_edge_interrupt,
                     pending_level_interrupt,
                     state,
+                    error_cause,
                     pending_wait_for_memory,
                     alu_output,
                     stacksize,
                     programsize,
-                    last_bus_read
+                    last_bus_read,
+                    signals_cache: SignalsCache::empty(),
+                    last_register_write: None,
+                    last_memory_write: None,
                 }
             }
         }
@@ -573,6 +880,15 @@ mod tests {
             );
         }
 
+        #[test]
+        fn pending_register_check_is_cleared_during_reset(mut machine in RawMachine::arbitrary()) {
+            machine.cpu_reset();
+            assert_eq!(
+                machine.pending_register_check,
+                RawMachine::new().pending_register_check
+            );
+        }
+
         #[test]
         fn pending_edge_interrupt_is_cleared_during_reset(mut machine in RawMachine::arbitrary()) {
             machine.cpu_reset();
@@ -627,4 +943,95 @@ mod tests {
             assert_eq!(machine.stacksize, pristine.stacksize);
         }
     }
+
+    #[test]
+    fn cached_signals_do_not_change_execution() {
+        for path in &[
+            "../testing/programs/15-manipulate-call-stack.asm",
+            "../testing/programs/21-simple-counter.asm",
+        ] {
+            let program = read_to_string(path).expect("Failed to read program");
+            let asm = AsmParser::parse(&program).expect("Failed to parse program");
+            let bytecode = Translator::compile(&asm).unwrap();
+            let mut cached = Machine::new_with_program(MachineConfig::default(), bytecode.clone());
+            let mut uncached = Machine::new_with_program(MachineConfig::default(), bytecode);
+            for cycle in 0..2_000 {
+                cached.raw_mut().trigger_clock_edge();
+                // Drop the cache, forcing every signals() call to decode the word
+                uncached.raw_mut().signals_cache.invalidate();
+                uncached.raw_mut().trigger_clock_edge();
+                assert_eq!(cached.registers(), uncached.registers(), "cycle {}", cycle);
+                assert_eq!(
+                    cached.bus().memory(),
+                    uncached.bus().memory(),
+                    "cycle {}",
+                    cycle
+                );
+                assert_eq!(cached.state(), uncached.state(), "cycle {}", cycle);
+            }
+        }
+    }
+
+    fn looping_machine() -> Machine {
+        let asm = AsmParser::parse("#! mrasm\nLOOP:\n    JR LOOP\n").expect("Failed to parse");
+        Machine::new_with_program(MachineConfig::default(), Translator::compile(&asm).unwrap())
+    }
+
+    #[test]
+    fn register_setters_modify_registers() {
+        let mut machine = RawMachine::new();
+        machine.set_register(RegisterNumber::R1, 0x42);
+        assert_eq!(*machine.registers().get(RegisterNumber::R1), 0x42);
+        machine.set_flags(Flags::CARRY_FLAG | Flags::ZERO_FLAG);
+        assert!(machine.registers().carry_flag());
+        assert!(machine.registers().zero_flag());
+        machine.set_carry_flag(false);
+        machine.set_negative_flag(true);
+        machine.set_interrupt_enable_flag(true);
+        assert!(!machine.registers().carry_flag());
+        assert!(machine.registers().zero_flag());
+        assert!(machine.registers().negative_flag());
+        assert!(machine.registers().interrupt_enable_flag());
+    }
+
+    #[test]
+    fn invalid_stackpointer_is_detected_after_setting_it() {
+        let mut machine = looping_machine();
+        machine.raw_mut().set_register(RegisterNumber::R5, 0xF5);
+        // Nothing happens until the next edge
+        assert_eq!(machine.state(), State::Running);
+        machine.raw_mut().trigger_clock_edge();
+        assert_eq!(machine.state(), State::ErrorStopped);
+    }
+
+    #[test]
+    fn valid_register_modifications_keep_the_machine_running() {
+        let mut machine = looping_machine();
+        machine.raw_mut().set_register(RegisterNumber::R5, 0xEF);
+        machine
+            .raw_mut()
+            .registers_mut()
+            .set(RegisterNumber::R0, 0x01);
+        for _ in 0..100 {
+            machine.raw_mut().trigger_clock_edge();
+        }
+        assert_eq!(machine.state(), State::Running);
+        assert_eq!(*machine.registers().get(RegisterNumber::R5), 0xEF);
+    }
+
+    #[test]
+    fn signals_describe_active_signals() {
+        let mut machine = RawMachine::new();
+        machine.microprogram_ram.set_address(3);
+        assert_eq!(
+            machine.signals().describe(),
+            "OP01 | NA2 NA0 MRGAA2 MRGAB2 MALUS1 MRGWE"
+        );
+        machine.pending_edge_interrupt = Some(Interrupt);
+        machine.register.set_carry_flag(true);
+        assert_eq!(
+            machine.signals().describe(),
+            "OP01 | NA2 NA0 MRGAA2 MRGAB2 MALUS1 MRGWE | CF | IFF1"
+        );
+    }
 }
@@ -1,7 +1,9 @@
 use enum_primitive::FromPrimitive;
 
 use super::RawMachine;
-use crate::machine::{AluSelect, Flags, Instruction, Interrupt, RegisterNumber, Word};
+use crate::machine::{
+    AluSelect, Flags, Instruction, Interrupt, MicroprogramRam, RegisterNumber, Word,
+};
 
 /// The collection of all relevant internal signals with
 /// simplified methods that all return [`bool`]s to easily
@@ -16,8 +18,28 @@ pub struct Signals<'a> {
     carry_out: bool,
     zero_out: bool,
     negative_out: bool,
+    decoded: DecodedWord,
 }
 
+/// Signals that only depend on the current microprogram [`Word`].
+///
+/// These are decoded once per word and cached in the [`SignalsCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DecodedWord {
+    /// Address of the decoded word inside the [`MicroprogramRam`].
+    address: usize,
+    alu_select: AluSelect,
+    alu_input_b_constant: u8,
+}
+
+/// Cache for the [`DecodedWord`] of the current microprogram word.
+///
+/// The cached value is only used as long as the address of the
+/// [`MicroprogramRam`] does not change. The cache is ignored when comparing
+/// [`RawMachine`]s, since its content is derived from the microprogram ram.
+#[derive(Debug, Clone)]
+pub(crate) struct SignalsCache(Option<DecodedWord>);
+
 impl<'a> Signals<'a> {
     pub const fn a8(&self) -> bool {
         self.instruction.contains(Instruction::A8)
@@ -156,16 +178,15 @@ impl<'a> Signals<'a> {
         self.level_interrupt.is_some()
     }
     /// Get the function to execute inside the ALU.
-    pub fn alu_select(&self) -> AluSelect {
-        let malus3 = self.malus3() as u8;
-        let malus2 = self.malus2() as u8;
-        let malus1 = self.malus1() as u8;
-        let malus0 = self.malus0() as u8;
-        let select = (malus3 << 3) | (malus2 << 2) | (malus1 << 1) | malus0;
-        AluSelect::from_u8(select).expect("infallible")
+    pub const fn alu_select(&self) -> AluSelect {
+        self.decoded.alu_select
     }
     /// Get the [`RegisterNumber`] of the register that is selected by
     /// the select inputs AA2..AA0.
+    ///
+    /// This reflects the current microprogram word, not the last register write.
+    /// With MRGAA3 set, the selection is taken from the operand bits of the
+    /// current instruction.
     pub fn selected_register_a(&self) -> RegisterNumber {
         let (aa2, aa1, aa0) = if self.mrgaa3() {
             (false, self.op01(), self.op00())
@@ -177,6 +198,10 @@ impl<'a> Signals<'a> {
     }
     /// Get the [`RegisterNumber`] of the register that is selected by
     /// the select inputs AB2..AB0.
+    ///
+    /// This reflects the current microprogram word, not the last register write.
+    /// With MRGAB3 set, the selection is taken from the operand bits of the
+    /// current instruction.
     pub fn selected_register_b(&self) -> RegisterNumber {
         let (ab2, ab1, ab0) = if self.mrgab3() {
             (false, self.op11(), self.op10())
@@ -190,10 +215,7 @@ impl<'a> Signals<'a> {
     /// The lower 3 bit contain MRGAB2..MRGAB0, the upper 5 bit are set
     /// to MRGAB3.
     pub const fn alu_input_b_constant(&self) -> u8 {
-        (0b1111_1000 * self.mrgab3() as u8)
-            | (self.mrgab2() as u8) << 2
-            | (self.mrgab1() as u8) << 1
-            | (self.mrgab0() as u8)
+        self.decoded.alu_input_b_constant
     }
     /// Get the [`RegisterNumber`] that will is selected for writing.
     pub fn selected_register_for_writing(&self) -> RegisterNumber {
@@ -268,6 +290,130 @@ impl<'a> Signals<'a> {
     pub fn interrupt_logic_1(&self) -> bool {
         self.interrupt_flipflop_1() && self.mac1() && self.mac0() && self.na0()
     }
+    /// Describe all active signals by name.
+    ///
+    /// Signals are grouped into the instruction, the microprogram word,
+    /// the flags, the ALU outputs and interrupts. Inactive signals are omitted.
+    ///
+    /// ```text
+    /// OP01 | NA2 NA0 MRGAA2 MRGAB2 MALUS1 MRGWE | CF | IFF1
+    /// ```
+    pub fn describe(&self) -> String {
+        let groups: &[&[(&str, bool)]] = &[
+            &[
+                ("A8", self.a8()),
+                ("A7", self.a7()),
+                ("A6", self.a6()),
+                ("A5", self.a5()),
+                ("OP00", self.op00()),
+                ("OP01", self.op01()),
+                ("OP10", self.op10()),
+                ("OP11", self.op11()),
+            ],
+            &[
+                ("MAC3", self.mac3()),
+                ("MAC2", self.mac2()),
+                ("MAC1", self.mac1()),
+                ("MAC0", self.mac0()),
+                ("NA4", self.na4()),
+                ("NA3", self.na3()),
+                ("NA2", self.na2()),
+                ("NA1", self.na1()),
+                ("NA0", self.na0()),
+                ("BUSEN", self.busen()),
+                ("BUSWR", self.buswr()),
+                ("MRGAA3", self.mrgaa3()),
+                ("MRGAA2", self.mrgaa2()),
+                ("MRGAA1", self.mrgaa1()),
+                ("MRGAA0", self.mrgaa0()),
+                ("MRGAB3", self.mrgab3()),
+                ("MRGAB2", self.mrgab2()),
+                ("MRGAB1", self.mrgab1()),
+                ("MRGAB0", self.mrgab0()),
+                ("MALUIA", self.maluia()),
+                ("MALUIB", self.maluib()),
+                ("MALUS3", self.malus3()),
+                ("MALUS2", self.malus2()),
+                ("MALUS1", self.malus1()),
+                ("MALUS0", self.malus0()),
+                ("MRGWE", self.mrgwe()),
+                ("MRGWS", self.mrgws()),
+                ("MCHFLG", self.mchflg()),
+            ],
+            &[
+                ("IEF", self.interrupt_enable_flag()),
+                ("NF", self.negative_flag()),
+                ("ZF", self.zero_flag()),
+                ("CF", self.carry_flag()),
+            ],
+            &[
+                ("NO", self.negative_out()),
+                ("ZO", self.zero_out()),
+                ("CO", self.carry_out()),
+            ],
+            &[
+                ("IFF1", self.interrupt_flipflop_1()),
+                ("LINT", self.level_interrupt()),
+            ],
+        ];
+        groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .filter(|(_, active)| *active)
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .filter(|group| !group.is_empty())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+impl DecodedWord {
+    /// Decode the currently selected word of the given [`MicroprogramRam`].
+    pub fn decode(ram: &MicroprogramRam) -> Self {
+        let word = ram.get_word();
+        let bit = |flag: Word| word.contains(flag) as u8;
+        let select = (bit(Word::MALUS3) << 3)
+            | (bit(Word::MALUS2) << 2)
+            | (bit(Word::MALUS1) << 1)
+            | bit(Word::MALUS0);
+        let alu_input_b_constant = (0b1111_1000 * bit(Word::MRGAB3))
+            | bit(Word::MRGAB2) << 2
+            | bit(Word::MRGAB1) << 1
+            | bit(Word::MRGAB0);
+        DecodedWord {
+            address: ram.get_address(),
+            alu_select: AluSelect::from_u8(select).expect("infallible"),
+            alu_input_b_constant,
+        }
+    }
+}
+
+impl SignalsCache {
+    /// Create an empty cache.
+    pub const fn empty() -> Self {
+        SignalsCache(None)
+    }
+    /// Get the cached [`DecodedWord`] for the currently selected word, decoding
+    /// it if the cache is empty or refers to another address.
+    pub fn get_or_decode(&self, ram: &MicroprogramRam) -> DecodedWord {
+        match self.0 {
+            Some(decoded) if decoded.address == ram.get_address() => decoded,
+            _ => DecodedWord::decode(ram),
+        }
+    }
+    /// Decode and cache the currently selected word of the [`MicroprogramRam`].
+    pub fn update(&mut self, ram: &MicroprogramRam) {
+        self.0 = Some(self.get_or_decode(ram));
+    }
+    /// Drop the cached value.
+    pub fn invalidate(&mut self) {
+        self.0 = None;
+    }
 }
 
 impl<'a> From<&'a RawMachine> for Signals<'a> {
@@ -281,6 +427,9 @@ impl<'a> From<&'a RawMachine> for Signals<'a> {
             word: machine.microprogram_ram.get_word(),
             interrupt_flipflop_1: machine.pending_edge_interrupt.as_ref(),
             level_interrupt: machine.pending_level_interrupt.as_ref(),
+            decoded: machine
+                .signals_cache
+                .get_or_decode(&machine.microprogram_ram),
         }
     }
 }
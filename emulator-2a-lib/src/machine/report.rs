@@ -0,0 +1,96 @@
+//! A compact summary of the observable machine state.
+#[cfg(feature = "wasm")]
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use super::{Machine, RegisterNumber, State};
+
+/// Observable state of a [`Machine`] after a clock step.
+///
+/// This is cheap to create and intended for frontends that drive the
+/// machine step by step, i.e. a browser frontend using the `wasm` feature.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepReport {
+    /// Register R0.
+    pub r0: u8,
+    /// Register R1.
+    pub r1: u8,
+    /// Register R2.
+    pub r2: u8,
+    /// The program counter (R3).
+    pub pc: u8,
+    /// The flag register (R4).
+    pub flags: u8,
+    /// The stackpointer (R5).
+    pub sp: u8,
+    /// Output register FE.
+    pub output_fe: u8,
+    /// Output register FF.
+    pub output_ff: u8,
+    /// State of the machine.
+    pub state: State,
+}
+
+impl Machine {
+    /// Summarize the observable state of the machine.
+    pub fn report(&self) -> StepReport {
+        let reg = |rn| *self.registers().get(rn);
+        StepReport {
+            r0: reg(RegisterNumber::R0),
+            r1: reg(RegisterNumber::R1),
+            r2: reg(RegisterNumber::R2),
+            pc: reg(RegisterNumber::R3),
+            flags: reg(RegisterNumber::R4),
+            sp: reg(RegisterNumber::R5),
+            output_fe: self.bus().output_fe(),
+            output_ff: self.bus().output_ff(),
+            state: self.state(),
+        }
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Machine {
+    /// Trigger the clock once and return a [`StepReport`] of the new state.
+    pub fn step_and_report(&mut self) -> JsValue {
+        self.trigger_key_clock();
+        JsValue::from(self.report())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compiler::Translator, machine::MachineConfig, parser::AsmParser};
+
+    #[test]
+    fn report_reflects_the_machine_state() {
+        let asm = AsmParser::parse(
+            r#"#! mrasm
+                MOV R1, 0x12
+                ST (0xFE), R1
+                STOP
+            "#,
+        )
+        .expect("Failed to parse program");
+        let mut machine =
+            Machine::new_with_program(MachineConfig::default(), Translator::compile(&asm).unwrap());
+        assert_eq!(machine.report().state, State::Running);
+        for _ in 0..100 {
+            machine.trigger_key_clock();
+        }
+        let report = machine.report();
+        assert_eq!(report.r1, 0x12);
+        assert_eq!(report.output_fe, 0x12);
+        assert_eq!(report.pc, *machine.registers().get(RegisterNumber::R3));
+        assert_eq!(report.flags, machine.registers().flags().bits());
+        assert_eq!(report.state, State::Stopped);
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn step_and_report_compiles() {
+        let _: fn(&mut Machine) -> JsValue = Machine::step_and_report;
+    }
+}
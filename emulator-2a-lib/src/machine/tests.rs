@@ -4,7 +4,7 @@ use std::fs::read_to_string;
 
 use super::*;
 use crate::{
-    compiler::Translator,
+    compiler::{CompileError, Translator},
     parser::{self, Asm, AsmParser, Line, Programsize, Register},
     runner::{RunExpectationsBuilder, RunnerConfigBuilder},
 };
@@ -29,7 +29,7 @@ macro_rules! compile {
     { $program:expr } => {
         {
             let asm = AsmParser::parse($program).expect("Failed to parse program");
-            Translator::compile(&asm)
+            Translator::compile(&asm).expect("Failed to compile program")
         }
     }
 }
@@ -100,7 +100,7 @@ proptest! {
             comment_after_shebang: None,
             lines: vec![ Line::Instruction(parser::Instruction::Tst(reg), None) ],
         };
-        let compiled = Translator::compile(&asm);
+        let compiled = Translator::compile(&asm).unwrap();
         let mut machine = Machine::new(MachineConfig::default());
         machine.load(compiled);
         // Set the registers to some value
@@ -125,7 +125,7 @@ proptest! {
             lines: vec![ Line::Instruction(parser::Instruction::Tst(reg), None) ],
         };
         println!("Program:\n'''\n{}\n'''", asm);
-        let compiled = Translator::compile(&asm);
+        let compiled = Translator::compile(&asm).unwrap();
         let mut machine = Machine::new(MachineConfig::default());
         println!("Code:\n'''\n{}\n'''", compiled);
         machine.load(compiled);
@@ -275,22 +275,41 @@ fn ram_is_reset_on_program_load() {
             NOP
         "#;
     let asm = AsmParser::parse(program_1).unwrap();
-    let bytes = Translator::compile(&asm);
+    let bytes = Translator::compile(&asm).unwrap();
     machine.load(bytes);
     // Second byte is non-zero
     assert_ne!(machine.bus().read(1), 0);
     let asm = AsmParser::parse(program_2).unwrap();
-    let bytes = Translator::compile(&asm);
+    let bytes = Translator::compile(&asm).unwrap();
     machine.load(bytes);
     // Second byte should be zero again
     assert_eq!(machine.bus().read(1), 0);
 }
 
+#[test]
+fn overlays_keep_the_machine_state() {
+    let mut machine = Machine::new(MachineConfig::default());
+    let program = AsmParser::parse("#! mrasm\nLOOP:\n    INC R0\n    JR LOOP\n").unwrap();
+    machine.load(Translator::compile(&program).unwrap());
+    while *machine.registers().get(RegisterNumber::R0) == 0 {
+        machine.trigger_key_clock();
+    }
+    let pc = *machine.registers().get(RegisterNumber::R3);
+    let overlay = AsmParser::parse("#! mrasm\n.ORG 0x10\n.DB 0xAB, 0xCD\n").unwrap();
+    machine.load_overlay(&Translator::compile(&overlay).unwrap());
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 1);
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), pc);
+    // The program is not overwritten by the gap before the `.ORG`
+    assert_ne!(machine.bus().read(0x01), 0);
+    assert_eq!(machine.bus().read(0x10), 0xAB);
+    assert_eq!(machine.bus().read(0x11), 0xCD);
+}
+
 #[test]
 fn programsize_default_works() {
     let program = r#"#! mrasm"#;
     let asm = AsmParser::parse(program).unwrap();
-    let bytes = Translator::compile(&asm);
+    let bytes = Translator::compile(&asm).unwrap();
     assert_eq!(bytes.programsize, Programsize::Auto);
 }
 
@@ -300,10 +319,30 @@ fn setting_programsize_works() {
         *PROGRAMSIZE 1
     "#;
     let asm = AsmParser::parse(program).unwrap();
-    let bytes = Translator::compile(&asm);
+    let bytes = Translator::compile(&asm).unwrap();
     assert_eq!(bytes.programsize, Programsize::Size(1));
 }
 
+#[test]
+fn loading_applies_programsize() {
+    let load = |machine: &mut Machine, program: &str| {
+        let asm = AsmParser::parse(program).unwrap();
+        machine.load(Translator::compile(&asm).unwrap());
+    };
+    let mut machine = Machine::new(MachineConfig::default());
+    load(&mut machine, "#! mrasm\n*PROGRAMSIZE 32\nINC R0\n");
+    assert_eq!(machine.raw_mut().programsize(), Programsize::Size(32));
+    // NOSET keeps the previous limit
+    load(&mut machine, "#! mrasm\n*PROGRAMSIZE NOSET\nINC R0\n");
+    assert_eq!(machine.raw_mut().programsize(), Programsize::Size(32));
+    // AUTO is resolved to the number of program bytes
+    load(
+        &mut machine,
+        "#! mrasm\n*PROGRAMSIZE AUTO\nINC R0\nLD R1, 5\n",
+    );
+    assert_eq!(machine.raw_mut().programsize(), Programsize::Size(4));
+}
+
 #[test]
 fn program_counter_supervision_works_for_default_programsize() {
     run! {
@@ -415,17 +454,234 @@ fn test_program_loading() {
     let mut machine = Machine::new(MachineConfig::default());
     let prog = &["#! mrasm", ".DB 42"].join("\n");
     let parsed = AsmParser::parse(prog).expect("Parsing failed");
-    let compiled = Translator::compile(&parsed);
+    let compiled = Translator::compile(&parsed).unwrap();
     machine.load(compiled);
     assert_eq!(machine.bus().memory()[0], 42);
 }
 
+#[test]
+fn loaded_programs_know_their_size() {
+    let mut machine = Machine::new(MachineConfig::default());
+    let parsed = AsmParser::parse("#! mrasm\n    CLR R0\n.ORG 0x10\n    STOP\n").unwrap();
+    machine.load(Translator::compile(&parsed).unwrap());
+    assert_eq!(machine.loaded_program_len(), 0x11);
+    assert_eq!(machine.loaded_program_origin(), 0x00);
+    machine.load_at(Translator::compile(&parsed).unwrap(), 0x20);
+    assert_eq!(machine.loaded_program_len(), 0x11);
+    assert_eq!(machine.loaded_program_origin(), 0x20);
+    // Overlays are not part of the loaded program
+    let overlay = AsmParser::parse("#! mrasm\n.ORG 0x80\n.DB 1, 2\n").unwrap();
+    machine.load_overlay(&Translator::compile(&overlay).unwrap());
+    assert_eq!(machine.loaded_program_len(), 0x11);
+}
+
+#[test]
+fn programs_loaded_beyond_the_memory_are_truncated() {
+    let mut machine = Machine::new(MachineConfig::default());
+    let parsed =
+        AsmParser::parse("#! mrasm\n*PROGRAMSIZE AUTO\n    CLR R0\n.ORG 0x10\n    STOP\n").unwrap();
+    let bytecode = Translator::compile(&parsed).unwrap();
+    let first_byte = *bytecode.bytes().next().unwrap();
+    machine.load_at(bytecode, 0xEE);
+    assert_eq!(machine.raw.programsize(), Programsize::Size(0xF0));
+    assert_eq!(machine.bus().memory()[0xEE], first_byte);
+    assert_eq!(machine.bus().memory()[0xEF], 0x00);
+    machine.load_at(Translator::compile(&parsed).unwrap(), 0xFE);
+    assert_eq!(machine.raw.programsize(), Programsize::Size(0xF0));
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), 0xFE);
+}
+
+#[test]
+fn all_jumps_are_executed_as_encoded() {
+    // Every successful step increments R0, any wrong jump ends at FAIL
+    let program = r#"#! mrasm
+    JR MAIN
+SUB:
+    INC R0
+    RET
+BACK:
+    INC R0
+    JMP DONE
+MAIN:
+    LDSP 0xEF
+    CLR R0
+    ; Carry and zero set
+    LD R1, 0xFF
+    LD R2, 0x01
+    ADD R1, R2
+    JCC FAIL
+    JZC FAIL
+    JNS FAIL
+    JCS CARRY_SET
+    JR FAIL
+CARRY_SET:
+    JZS ZERO_SET
+    JR FAIL
+ZERO_SET:
+    JNC NEGATIVE_CLEAR
+    JR FAIL
+NEGATIVE_CLEAR:
+    INC R0
+    ; All flags cleared
+    LD R1, 0x01
+    ADD R1, R2
+    JCS FAIL
+    JZS FAIL
+    JNS FAIL
+    JCC CARRY_CLEAR
+    JR FAIL
+CARRY_CLEAR:
+    JZC ZERO_CLEAR
+    JR FAIL
+ZERO_CLEAR:
+    INC R0
+    ; Negative set
+    LD R1, 0x7F
+    ADD R1, R2
+    JNC FAIL
+    JNS NEGATIVE_SET
+    JR FAIL
+NEGATIVE_SET:
+    INC R0
+    ; Jump backwards three times
+    LD R1, 0x03
+LOOP:
+    INC R0
+    DEC R1
+    JZC LOOP
+    CALL SUB
+    JR BACK
+FAIL:
+    LD R0, 0xEE
+DONE:
+    ST (0xFF), R0
+    STOP
+"#;
+    let mut machine = Machine::new(MachineConfig::default());
+    machine
+        .load_asm(&AsmParser::parse(program).unwrap())
+        .unwrap();
+    for _ in 0..2000 {
+        if machine.state() != State::Running {
+            break;
+        }
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert_eq!(machine.output_ff(), 8);
+}
+
+#[test]
+fn memory_writes_are_visible_through_the_slice() {
+    let mut machine = Machine::new(MachineConfig::default());
+    let prog = &[
+        "#! mrasm",
+        "MOV R0, 0x42",
+        "ST (0x80), R0",
+        "ST (0xFE), R0",
+        "STOP",
+    ]
+    .join("\n");
+    let parsed = AsmParser::parse(prog).expect("Parsing failed");
+    machine.load(Translator::compile(&parsed).unwrap());
+    assert_eq!(machine.memory()[0x80], 0);
+    while machine.state() == State::Running {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.memory()[0x80], 0x42);
+    assert_eq!(machine.memory(), machine.bus().memory());
+    // The output register is not part of the RAM
+    assert_eq!(machine.output_fe(), 0x42);
+    assert_eq!(machine.memory().len(), 0xF0);
+}
+
+#[test]
+fn source_lines_follow_the_program_counter() {
+    let prog = &[
+        "#! mrasm",
+        "    JR MAIN",
+        ".ORG 0x10",
+        "MAIN:",
+        "    INC R0",
+        "LOOP:",
+        "    JR LOOP",
+    ]
+    .join("\n");
+    let parsed = AsmParser::parse(prog).expect("Parsing failed");
+    let mut machine = Machine::new(MachineConfig::default());
+    assert!(machine.source_lines().is_empty());
+    assert_eq!(machine.current_source_line(), None);
+    machine.load(Translator::compile(&parsed).unwrap());
+    machine.set_step_mode(StepMode::Assembly);
+    let expected: Vec<(String, u8)> = vec![
+        ("    JR MAIN".into(), 0x00),
+        ("    .ORG 16".into(), 0x02),
+        ("MAIN:".into(), 0x10),
+        ("    INC R0".into(), 0x10),
+        ("LOOP:".into(), 0x11),
+        ("    JR LOOP".into(), 0x11),
+    ];
+    assert_eq!(machine.source_lines(), &expected[..]);
+    let mut lines = vec![];
+    for _ in 0..5 {
+        lines.push(machine.current_source_line());
+        machine.trigger_key_clock();
+    }
+    // The initial fetch does not execute an instruction
+    assert_eq!(lines, vec![Some(0), Some(0), Some(3), Some(5), Some(5)]);
+}
+
+#[test]
+fn breakpoints_are_hit_between_instructions() {
+    let prog = &["#! mrasm", "LOOP:", "    INC R0", "    JR LOOP"].join("\n");
+    let parsed = AsmParser::parse(prog).expect("Parsing failed");
+    let mut machine = Machine::new_with_program(
+        MachineConfig::default(),
+        Translator::compile(&parsed).unwrap(),
+    );
+    assert!(machine.add_breakpoint(0x01));
+    assert!(!machine.add_breakpoint(0x01));
+    assert!(!machine.toggle_breakpoint(0x01));
+    assert!(machine.toggle_breakpoint(0x01));
+    assert_eq!(
+        machine.breakpoints().iter().collect::<Vec<_>>(),
+        vec![&0x01]
+    );
+    let mut hits = 0;
+    for _ in 0..100 {
+        machine.trigger_key_clock();
+        if machine.is_at_breakpoint() {
+            hits += 1;
+            // INC R0 was just executed, JR LOOP is next
+            assert_eq!(*machine.registers().get(RegisterNumber::R0), hits);
+        }
+    }
+    assert!(hits > 1);
+    machine.clear_breakpoints();
+    assert!(machine.breakpoints().is_empty());
+    assert!(!machine.is_at_breakpoint());
+}
+
+#[test]
+fn programs_without_stacksize_use_the_default() {
+    assert_eq!(Stacksize::default(), Stacksize::_16);
+    let asm = AsmParser::parse("#! mrasm\nSTOP").expect("Parsing failed");
+    let bytecode = Translator::compile(&asm).unwrap();
+    assert_eq!(bytecode.stacksize, Stacksize::_16);
+    // A fresh machine already uses the default, loading must restore it
+    let mut machine = Machine::new(MachineConfig::default());
+    assert_eq!(machine.stacksize(), Stacksize::_16);
+    machine.raw_mut().set_stacksize(Stacksize::_64);
+    machine.load(bytecode);
+    assert_eq!(machine.stacksize(), Stacksize::_16);
+}
+
 #[test]
 fn test_stackpointer_when_loading() {
     let mut machine = Machine::new(MachineConfig::default());
     let mut load_verify = |program: &str, ss: Stacksize| {
         let asm = AsmParser::parse(program).expect("Parsing failed");
-        let bytecode = Translator::compile(&asm);
+        let bytecode = Translator::compile(&asm).unwrap();
         machine.load(bytecode);
         assert_eq!(machine.stacksize(), ss)
     };
@@ -456,6 +712,52 @@ fn misr_is_set_correctly_by_key_interrupt() {
     assert_eq!(misr & 0b0000_0001, 0b0000_0001);
 }
 
+#[test]
+fn misr_key_bits_are_cleared_by_reti() {
+    let program = r#"#! mrasm
+            .ORG 0
+            JR MAIN
+            JR ISR
+        MAIN:
+            LDSP 0xEF
+            BITS (0xF9), 1
+            EI
+        LOOP:
+            JR LOOP
+        ISR:
+            LD R0, (0xF9)
+            ST (0xFF), R0
+            RETI
+        "#;
+    let key_bits = MISR::KEY_INTERRUPT_PENDING | MISR::KEY_INTERRUPT_REQUEST_ACTIVE;
+    let mut machine = Machine::new_with_program(MachineConfig::default(), compile!(program));
+    for _ in 0..100 {
+        machine.trigger_key_clock();
+    }
+    assert!(machine.bus().misr().is_empty());
+    machine.trigger_key_interrupt();
+    assert_eq!(*machine.bus().misr(), key_bits);
+    let mut cycles = 0;
+    while machine.output_ff() == 0 {
+        machine.trigger_key_clock();
+        cycles += 1;
+        assert!(cycles < 100, "Interrupt was not serviced");
+    }
+    // The ISR saw both bits
+    assert_eq!(machine.output_ff(), key_bits.bits());
+    assert_eq!(*machine.bus().misr(), key_bits);
+    for _ in 0..100 {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Running);
+    assert!(machine.bus().misr().is_empty());
+    // A reset clears the bits aswell
+    machine.trigger_key_interrupt();
+    assert_eq!(*machine.bus().misr(), key_bits);
+    machine.cpu_reset();
+    assert!(machine.bus().misr().is_empty());
+}
+
 #[test]
 fn define_words_equivalent_to_written_program() {
     let real = read_to_string("../testing/programs/21-simple-counter.asm").unwrap();
@@ -467,3 +769,617 @@ fn define_words_equivalent_to_written_program() {
         fake_compiled.bytes().collect::<Vec<_>>()
     );
 }
+
+#[test]
+fn multiple_origins_place_code_blocks() {
+    let program = read_to_string("../testing/programs/27-isr-at-fixed-address.asm").unwrap();
+    let bytecode = compile!(&program);
+    let bytes: Vec<u8> = bytecode.bytes().cloned().collect();
+    // The ISR starts exactly at 0x20
+    assert_eq!(&bytes[0x20..], &[0xFB, 42, 0x10, 0xF0, 0x1F, 0xFF, 0x01]);
+    // Everything between the main block and the ISR is filled with zeros
+    let main_len = 14;
+    assert!(bytes[main_len..0x20].iter().all(|byte| *byte == 0));
+    // The jump into the ISR is resolved to the new origin
+    assert_eq!(bytes[2..4], [0x20, 0x20 - 4]);
+    run! {
+        path = "../testing/programs/27-isr-at-fixed-address.asm";
+        config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_interrupts([100]);
+        expect = RunExpectationsBuilder::default()
+            .expect_state(State::Stopped)
+            .expect_output_ff(42);
+    }
+}
+
+#[test]
+fn descending_origins_are_rejected() {
+    let program = r#"#! mrasm
+            .ORG 0x10
+            INC R0
+            INC R0
+            .ORG 0x11
+            STOP
+        "#;
+    let asm = AsmParser::parse(program).expect("Failed to parse program");
+    assert_eq!(
+        Translator::compile(&asm).unwrap_err(),
+        CompileError::DescendingOrigin {
+            addr: 0x11,
+            end: 0x11
+        }
+    );
+}
+
+#[test]
+fn reserved_bytes_do_not_shift_labels() {
+    let program = r#"#! mrasm
+            .BYTE 2
+        HERE:
+            JR HERE
+        "#;
+    let bytes: Vec<u8> = compile!(program).bytes().cloned().collect();
+    // The relative jump must point to itself
+    assert_eq!(bytes, vec![0x00, 0x00, 0x20, 0xFE]);
+}
+
+#[test]
+fn last_register_and_memory_writes_are_reported() {
+    let mut machine = load!(
+        r#"#! mrasm
+            INC R0
+            ST (0x42), R0
+            STOP
+        "#
+    );
+    // Run until INC R0 has been committed
+    let mut cycles = 0;
+    while machine.last_register_write() != Some((RegisterNumber::R0, 1)) {
+        machine.trigger_key_clock();
+        cycles += 1;
+        assert!(cycles < 100, "INC R0 never wrote to R0");
+    }
+    assert_eq!(machine.last_memory_write(), None);
+    // The report is cleared with the next edge
+    machine.trigger_key_clock();
+    assert_ne!(machine.last_register_write(), Some((RegisterNumber::R0, 1)));
+    // Run until the store is committed
+    while machine.last_memory_write().is_none() {
+        machine.trigger_key_clock();
+        cycles += 1;
+        assert!(cycles < 100, "ST never wrote to memory");
+    }
+    assert_eq!(machine.last_memory_write(), Some((0x42, 1)));
+}
+
+#[test]
+fn poked_registers_are_used_by_the_program() {
+    let mut machine = load!(
+        r#"#! mrasm
+            ADD R0, R1
+            ST (0xFF), R0
+            STOP
+        "#
+    );
+    machine
+        .raw_mut()
+        .registers_mut()
+        .set(RegisterNumber::R0, 40);
+    machine.raw_mut().registers_mut().set(RegisterNumber::R1, 2);
+    for _ in 0..100 {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert_eq!(machine.output_ff(), 42);
+}
+
+/// Run the given program until it halts in the given [`StepMode`].
+fn run_until_halted(program: &str, step_mode: StepMode) -> Machine {
+    let mut machine = Machine::new_with_program(MachineConfig::default(), compile!(program));
+    machine.set_step_mode(step_mode);
+    for _ in 0..10_000 {
+        if machine.state() != State::Running {
+            break;
+        }
+        machine.trigger_key_clock();
+    }
+    machine
+}
+
+#[test]
+fn division_by_zero_sets_carry_and_saturates() {
+    let program = r#"#! mrasm
+            MOV R0, 42
+            MOV R1, 5
+            DIV R0, R1
+            MOV R2, R0
+            CLR R1
+            DIV R0, R1
+            STOP
+        "#;
+    for &step_mode in &[StepMode::Real, StepMode::Assembly] {
+        let machine = run_until_halted(program, step_mode);
+        assert_eq!(machine.state(), State::Stopped);
+        assert_eq!(*machine.registers().get(RegisterNumber::R2), 8);
+        assert_eq!(*machine.registers().get(RegisterNumber::R0), 0xFF);
+        assert!(machine.registers().flags().contains(Flags::CARRY_FLAG));
+    }
+}
+
+#[test]
+fn key_edge_interrupts_are_pending_until_serviced() {
+    let program = r#"#! mrasm
+            JR MAIN
+            JR ISR
+        MAIN:
+            LDSP 0xEF
+            BITS (0xF9), 1
+            EI
+        LOOP:
+            JR LOOP
+        ISR:
+            ST (0xFF), R0
+            STOP
+        "#;
+    let mut machine = Machine::new_with_program(MachineConfig::default(), compile!(program));
+    for _ in 0..100 {
+        machine.trigger_key_clock();
+    }
+    assert!(!machine.is_edge_interrupt_pending());
+    assert!(!machine.is_level_interrupt_active());
+    machine.trigger_key_interrupt();
+    assert!(machine.is_edge_interrupt_pending());
+    let mut cycles = 0;
+    while machine.is_edge_interrupt_pending() {
+        machine.trigger_key_clock();
+        cycles += 1;
+        assert!(cycles < 20, "Interrupt was not serviced");
+    }
+    assert_eq!(machine.state(), State::Running);
+    while machine.state() == State::Running && cycles < 100 {
+        machine.trigger_key_clock();
+        cycles += 1;
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert!(!machine.is_edge_interrupt_pending());
+}
+
+#[test]
+fn call_and_ret_behave_identically_in_all_step_modes() {
+    let program = r#"#! mrasm
+            LDSP 0xEF
+            CALL SUBROUTINE
+            CALL SUBROUTINE
+            ST (0xFF), R0
+            STOP
+        SUBROUTINE:
+            INC R0
+            RET
+        "#;
+    let real = run_until_halted(program, StepMode::Real);
+    let asm = run_until_halted(program, StepMode::Assembly);
+    assert_eq!(real.state(), State::Stopped);
+    assert_eq!(real.output_ff(), 2);
+    assert_eq!(real.registers(), asm.registers());
+    assert_eq!(real.bus().memory(), asm.bus().memory());
+    assert_eq!(real.state(), asm.state());
+}
+
+#[test]
+fn all_instructions_example_stops_in_all_step_modes() {
+    let program =
+        read_to_string("../programs/06-all-instructions.asm").expect("Failed to read program");
+    let real = run_until_halted(&program, StepMode::Real);
+    let asm = run_until_halted(&program, StepMode::Assembly);
+    assert_eq!(real.state(), State::Stopped);
+    assert_eq!(real.registers(), asm.registers());
+    assert_eq!(real.bus().memory(), asm.bus().memory());
+    assert_eq!(real.state(), asm.state());
+}
+
+#[test]
+fn step_over_executes_nested_calls() {
+    let mut machine = load!(
+        r#"#! mrasm
+            LDSP 0xEF
+            CALL OUTER
+            ST (0xFF), R0
+            STOP
+        OUTER:
+            INC R0
+            CALL INNER
+            INC R0
+            RET
+        INNER:
+            PUSH R0
+            POP R1
+            INC R0
+            RET
+        "#
+    );
+    // Initial fetch and LDSP 0xEF
+    machine.step_over();
+    machine.step_over();
+    let pc_after_call = machine.registers().get(RegisterNumber::R3) + 2;
+    // CALL OUTER, including the nested CALL INNER
+    let cycles = machine.step_over();
+    assert!(cycles > 0);
+    assert_eq!(machine.state(), State::Running);
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 3);
+    assert_eq!(*machine.registers().get(RegisterNumber::R1), 1);
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), pc_after_call);
+    assert_eq!(*machine.registers().get(RegisterNumber::R5), 0xEF);
+    // ST (0xFF), R0
+    machine.step_over();
+    assert_eq!(machine.output_ff(), 3);
+    // Stepping over regular instructions executes exactly one instruction
+    machine.step_over();
+    assert_eq!(machine.state(), State::Stopped);
+}
+
+#[test]
+fn step_over_stops_if_the_machine_halts() {
+    let mut machine = load!(
+        r#"#! mrasm
+            LDSP 0xEF
+            CALL NEVER_RETURNS
+        NEVER_RETURNS:
+            STOP
+        "#
+    );
+    machine.step_over();
+    machine.step_over();
+    machine.step_over();
+    assert_eq!(machine.state(), State::Stopped);
+}
+
+#[test]
+fn step_over_stops_at_breakpoints_inside_the_subroutine() {
+    let mut machine = load!(
+        r#"#! mrasm
+            LDSP 0xEF
+            CALL SUB
+            STOP
+        SUB:
+            INC R0
+            INC R0
+            RET
+        "#
+    );
+    // The second INC R0
+    machine.add_breakpoint(0x07);
+    machine.step_over();
+    machine.step_over();
+    // CALL SUB stops in front of the second INC R0
+    machine.step_over();
+    assert!(machine.is_at_breakpoint());
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), 0x07);
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 1);
+    // Stepping on finishes the subroutine
+    machine.step_over();
+    machine.step_over();
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 2);
+    assert_eq!(*machine.registers().get(RegisterNumber::R5), 0xEF);
+    // Breakpoints at the first instruction of the subroutine are hit, too
+    machine.clear_breakpoints();
+    machine.add_breakpoint(0x06);
+    machine.cpu_reset();
+    machine.step_over();
+    machine.step_over();
+    machine.step_over();
+    assert!(machine.is_at_breakpoint());
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), 0x06);
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 0);
+}
+
+#[test]
+fn uart_bytes_are_received_in_order() {
+    let mut machine = load! {
+        r#"#! mrasm
+            MOV R1, 0x80
+        LOOP:
+            BITT (0xFB), 0x01
+            JZS DONE
+            LD R0, (0xFA)
+            ST (R1), R0
+            ST (0xFF), R0
+            INC R1
+            JR LOOP
+        DONE:
+            STOP
+        "#
+    };
+    assert_eq!(machine.uart_push_bytes(b"Hello!"), 6);
+    while machine.state() == State::Running {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert_eq!(&machine.bus().memory()[0x80..0x87], b"Hello!\0");
+    assert_eq!(machine.output_ff(), b'!');
+    assert!(machine.bus().uart_received().is_empty());
+    // Bytes exceeding the FIFO depth are dropped
+    let bytes = [0x2A; UART_FIFO_DEPTH + 3];
+    assert_eq!(machine.uart_push_bytes(&bytes), UART_FIFO_DEPTH);
+    assert_eq!(machine.bus().read(0xFB) & 0b11, 0b11);
+}
+
+#[test]
+fn polled_uart_transmission_waits_for_cts() {
+    let mut machine = load! {
+        r#"#! mrasm
+            MOV R1, 0x80
+        LOOP:
+            LD R0, (R1)
+            TST R0
+            JZS DONE
+        POLL:
+            BITT (0xFB), 0x80
+            JZS POLL
+            ST (0xFA), R0
+            INC R1
+            JR LOOP
+        DONE:
+            STOP
+        "#
+    };
+    machine.raw_mut().bus_mut().memory_mut()[0x80..0x83].copy_from_slice(b"Hi\0");
+    machine.set_uart_clear_to_send(false);
+    for _ in 0..200 {
+        machine.trigger_key_clock();
+    }
+    // The first byte is held back, the program keeps polling
+    assert_eq!(machine.state(), State::Running);
+    assert!(machine.bus().uart_sent().is_empty());
+    assert_eq!(machine.bus().read(0xFB) & 0xE0, 0x20);
+    machine.set_uart_clear_to_send(true);
+    while machine.state() == State::Running {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert_eq!(machine.bus().uart_sent(), b"Hi");
+    assert_eq!(machine.bus().read(0xFB) & 0xE0, 0xC0);
+}
+
+#[test]
+fn uart_transmission_can_be_interrupt_driven() {
+    let mut machine = load! {
+        r#"#! mrasm
+            JR MAIN
+            JR ISR
+        MAIN:
+            LDSP 0xEF
+            MOV R1, 0x80
+            MOV (0xF9), 0x04
+            MOV (0xFB), 0x18
+            EI
+        LOOP:
+            JR LOOP
+        ISR:
+            LD R0, (R1)
+            TST R0
+            JZS DONE
+            ST (0xFA), R0
+            INC R1
+            RETI
+        DONE:
+            STOP
+        "#
+    };
+    machine.raw_mut().bus_mut().memory_mut()[0x80..0x84].copy_from_slice(b"Hey\0");
+    // CTS is ignored by the program
+    machine.set_uart_clear_to_send(false);
+    let mut cycles = 0;
+    while machine.state() == State::Running && cycles < 1_000 {
+        machine.trigger_key_clock();
+        cycles += 1;
+    }
+    assert_eq!(machine.state(), State::Stopped);
+    assert_eq!(machine.bus().uart_sent(), b"Hey");
+    assert!(machine
+        .bus()
+        .misr()
+        .contains(MISR::UART_INTERUPT_PENDING | MISR::UART_INTERRUPT_REQUEST_ACTIVE));
+}
+
+#[test]
+fn withdrawn_uart_level_interrupts_are_not_serviced_again() {
+    let mut machine = load! {
+        r#"#! mrasm
+            JR MAIN
+            JR ISR
+        MAIN:
+            LDSP 0xEF
+            MOV (0xF9), 0x04
+            MOV (0xFB), 0x10
+            EI
+        LOOP:
+            JR LOOP
+        ISR:
+            INC R0
+            ST (0xFF), R0
+            MOV (0xFB), 0x00
+            RETI
+        "#
+    };
+    for _ in 0..500 {
+        machine.trigger_key_clock();
+    }
+    assert_eq!(machine.state(), State::Running);
+    assert_eq!(machine.output_ff(), 1);
+    assert!(!machine.is_level_interrupt_active());
+    assert!(!machine
+        .bus()
+        .misr()
+        .intersects(MISR::UART_INTERUPT_PENDING | MISR::UART_INTERRUPT_REQUEST_ACTIVE));
+}
+
+#[test]
+fn reset_and_reload_restores_the_program_and_zeroes_registers() {
+    let program = r#"#! mrasm
+            MOV R1, 0x03
+        LOOP:
+            INC R0
+            ST (LOOP), R0
+            LD R2, (0xFC)
+            ST (0xFF), R2
+            DEC R1
+            JZC LOOP
+            STOP
+        "#;
+    let asm = AsmParser::parse(program).expect("Parsing failed");
+    let bytecode = Translator::compile(&asm).expect("Compilation failed");
+    let mut machine = Machine::new(MachineConfig::default());
+    machine.load_at(bytecode, 0x10);
+    machine.add_breakpoint(0x12);
+    machine.set_input_fc(0x2A);
+    let pristine = machine.clone();
+    while machine.state() == State::Running {
+        machine.trigger_key_clock();
+    }
+    assert_ne!(machine.bus().memory(), pristine.bus().memory());
+    assert_eq!(machine.output_ff(), 0x2A);
+    assert!(machine.reset_and_reload());
+    assert_eq!(machine.bus().memory(), pristine.bus().memory());
+    assert_eq!(machine.registers(), pristine.registers());
+    assert_eq!(*machine.registers().get(RegisterNumber::R0), 0);
+    assert_eq!(*machine.registers().get(RegisterNumber::R3), 0x10);
+    assert_eq!(machine.output_ff(), 0);
+    assert_eq!(machine.state(), State::Running);
+    // Inputs and breakpoints are kept
+    assert_eq!(machine.bus().read(0xFC), 0x2A);
+    assert!(machine.breakpoints().contains(&0x12));
+}
+
+#[test]
+fn machine_configs_ignore_voltage_rounding() {
+    let mut config = MachineConfig {
+        temp: 0.3 - 0.2,
+        ..Default::default()
+    };
+    let mut other = MachineConfig {
+        temp: 0.1,
+        ..Default::default()
+    };
+    assert_eq!(config, other);
+    other.temp = 0.101;
+    assert_ne!(config, other);
+    assert!(config.approx_eq(&other));
+    other.temp = 0.11;
+    assert!(!config.approx_eq(&other));
+    // The default temperature equals a sensor that was never set
+    config.temp = 0.001;
+    assert_ne!(config, MachineConfig::default());
+    assert!(config.approx_eq(&MachineConfig::default()));
+    config.input_fc = 1;
+    assert!(!config.approx_eq(&MachineConfig::default()));
+}
+
+#[test]
+fn selected_registers_follow_the_current_word() {
+    let mut machine = load!("#! mrasm\n    ADD R1, R2\n    STOP\n");
+    // Initial fetch, waiting for the memory: the program counter is selected
+    for _ in 0..2 {
+        machine.raw_mut().trigger_clock_edge();
+        assert_eq!(machine.selected_registers().0, RegisterNumber::R3);
+    }
+    // ADD R1, R2: both registers are taken from the operand bits
+    machine.raw_mut().trigger_clock_edge();
+    assert_eq!(
+        machine.selected_registers(),
+        (RegisterNumber::R1, RegisterNumber::R2)
+    );
+    // The next fetch selects the program counter again, while R1 was written last
+    machine.raw_mut().trigger_clock_edge();
+    assert_eq!(machine.selected_registers().0, RegisterNumber::R3);
+    assert_eq!(
+        machine.last_register_write().map(|(reg, _)| reg),
+        Some(RegisterNumber::R1)
+    );
+}
+
+#[test]
+fn instructions_are_peeked_from_memory() {
+    let mut machine = load!("#! mrasm\nLOOP:\n    LD R1, 0x2A\n    JR LOOP\n");
+    assert_eq!(
+        machine.peek_instruction_at(0x00),
+        Some((
+            parser::Instruction::LdConstant(Register::R1, 0x2A.into()),
+            3
+        ))
+    );
+    assert_eq!(
+        machine.peek_instruction_at(0x03),
+        Some((parser::Instruction::Jr("0x00".into()), 2))
+    );
+    // The offset of the jump is no instruction on its own
+    assert_eq!(machine.peek_instruction_at(0x04), None);
+    assert_eq!(machine.peek_instruction_at(0xF0), None);
+    // The memory is read, not the program
+    machine.raw_mut().bus_mut().memory_mut()[0x01] = 0x10;
+    assert_eq!(
+        machine
+            .peek_instruction_at(0x03)
+            .map(|(inst, _)| inst.to_string()),
+        Some("JR 0x00".into())
+    );
+    assert_eq!(
+        machine
+            .peek_instruction_at(0x00)
+            .map(|(inst, _)| inst.to_string()),
+        Some("LD R1, 0x10".into())
+    );
+}
+
+#[test]
+fn halting_is_reported_as_state_change() {
+    let mut machine = load!("#! mrasm\n    INC R0\n    STOP\n");
+    let changes: Vec<_> = (0..20)
+        .filter_map(|_| machine.raw_mut().trigger_clock_edge())
+        .collect();
+    assert_eq!(
+        changes,
+        vec![StateChange {
+            from: State::Running,
+            to: State::Stopped,
+            cause: None,
+        }]
+    );
+    assert_eq!(machine.error_cause(), None);
+}
+
+#[test]
+fn error_stops_report_their_cause() {
+    let mut machine = load!("#! mrasm\n    LDSP 0xF0\n    STOP\n");
+    let change = (0..20).find_map(|_| machine.trigger_key_clock());
+    assert_eq!(
+        change,
+        Some(StateChange {
+            from: State::Running,
+            to: State::ErrorStopped,
+            cause: Some(ErrorCause::InvalidStackpointer),
+        })
+    );
+    assert_eq!(machine.error_cause(), Some(ErrorCause::InvalidStackpointer));
+    machine.cpu_reset();
+    assert_eq!(machine.error_cause(), None);
+}
+
+#[test]
+fn states_and_error_causes_are_displayed() {
+    assert_eq!(State::Running.to_string(), "Running");
+    assert_eq!(State::Stopped.to_string(), "Stopped");
+    assert_eq!(State::ErrorStopped.to_string(), "Error");
+    assert_eq!(
+        ErrorCause::InvalidStackpointer.to_string(),
+        "the stackpointer became invalid"
+    );
+    assert_eq!(
+        ErrorCause::InvalidProgramCounter.to_string(),
+        "the program counter became invalid"
+    );
+    assert_eq!(
+        ErrorCause::InvalidOpcode.to_string(),
+        "the opcode 0x00 was read"
+    );
+}
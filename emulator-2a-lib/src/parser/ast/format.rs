@@ -4,7 +4,8 @@ use std::fmt;
 
 use super::*;
 
-const COMMENT_WIDTH: usize = 30;
+/// Column at which trailing comments start by default.
+pub const DEFAULT_COMMENT_COLUMN: usize = 30;
 const INST_WIDTH: usize = 4;
 
 impl fmt::Display for Constant {
@@ -67,6 +68,7 @@ impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Instruction::AsmOrigin(byte) => write!(f, ".ORG {}", byte),
+            Instruction::AsmAlign(byte) => write!(f, ".ALIGN {}", byte),
             Instruction::AsmByte(byte) => write!(f, ".BYTE {}", byte),
             Instruction::AsmDefineBytes(bytes) => {
                 write!(f, ".DB ")?;
@@ -176,46 +178,105 @@ impl fmt::Display for RegisterDdi {
 
 impl fmt::Display for Line {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Line::Empty(Some(comment)) => {
-                let comment = format!("; {}", comment);
-                write!(f, "{}", comment)
-            }
+        write!(f, "{}", self.with_comment_column(DEFAULT_COMMENT_COLUMN))
+    }
+}
+
+impl fmt::Display for Asm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.with_comment_column(DEFAULT_COMMENT_COLUMN))
+    }
+}
+
+/// Display a [`Line`] or an [`Asm`] with trailing comments
+/// aligned to a specific column.
+///
+/// Comments that make up a whole line are not indented. If the code
+/// reaches the column, the comment is separated by a single space.
+/// Formatting and reparsing a program results in the same [`Asm`].
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::parser::AsmParser;
+/// let program = "#! mrasm\nLOOP: ; forever\n  JR LOOP ; jump\n; the end";
+/// let asm = AsmParser::parse(program).unwrap();
+/// let formatted = asm.with_comment_column(12).to_string();
+/// assert_eq!(
+///     formatted,
+///     "#! mrasm\nLOOP:       ; forever\n    JR LOOP ; jump\n; the end"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentAligned<'a, T> {
+    inner: &'a T,
+    column: usize,
+}
+
+impl Line {
+    /// Display this line with a trailing comment starting at `column`.
+    pub fn with_comment_column(&self, column: usize) -> CommentAligned<'_, Line> {
+        CommentAligned {
+            inner: self,
+            column,
+        }
+    }
+}
+
+impl Asm {
+    /// Display this program with trailing comments starting at `column`.
+    pub fn with_comment_column(&self, column: usize) -> CommentAligned<'_, Asm> {
+        CommentAligned {
+            inner: self,
+            column,
+        }
+    }
+}
+
+/// Write `code` followed by the optional `comment` starting at `column`.
+fn write_with_comment(
+    f: &mut fmt::Formatter,
+    code: &str,
+    comment: &Option<Comment>,
+    column: usize,
+) -> fmt::Result {
+    match comment {
+        Some(comment) => {
+            let code = code.pad_to_width(column.saturating_sub(1));
+            write!(f, "{} ; {}", code, comment)
+        }
+        None => write!(f, "{}", code),
+    }
+}
+
+impl<'a> fmt::Display for CommentAligned<'a, Line> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.inner {
+            Line::Empty(Some(comment)) => write!(f, "; {}", comment),
             Line::Empty(None) => write!(f, ""),
             Line::Label(label, comment) => {
-                let out = format!("{}:", label).pad_to_width(COMMENT_WIDTH);
-                write!(f, "{}", out)?;
-                if let Some(comment) = comment {
-                    let comment = format!("; {}", comment);
-                    write!(f, "{}", comment)?;
-                }
-                Ok(())
+                let code = format!("{}:", label);
+                write_with_comment(f, &code, comment, self.column)
             }
             Line::Instruction(inst, comment) => {
-                let prefix = "".pad_to_width(INST_WIDTH);
-                let inst = format!("{}", inst).pad_to_width(COMMENT_WIDTH - INST_WIDTH);
-                write!(f, "{}{}", prefix, inst)?;
-                if let Some(comment) = comment {
-                    let comment = format!("; {}", comment);
-                    write!(f, "{}", comment)?;
-                }
-                Ok(())
+                let code = format!("{}{}", "".pad_to_width(INST_WIDTH), inst);
+                write_with_comment(f, &code, comment, self.column)
             }
         }
     }
 }
 
-impl fmt::Display for Asm {
+impl<'a> fmt::Display for CommentAligned<'a, Asm> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let header = "#! mrasm".pad_to_width(COMMENT_WIDTH);
-        if let Some(comment) = &self.comment_after_shebang {
-            let line = format!("{}; {}", header, comment);
-            writeln!(f, "{}", line)?;
-        } else {
-            writeln!(f, "{}", header)?;
-        }
-        for line in &self.lines {
-            writeln!(f, "{}", line)?;
+        write_with_comment(
+            f,
+            "#! mrasm",
+            &self.inner.comment_after_shebang,
+            self.column,
+        )?;
+        // Lines are separated, not terminated by newlines, see the grammar
+        for line in &self.inner.lines {
+            write!(f, "\n{}", line.with_comment_column(self.column))?;
         }
         Ok(())
     }
@@ -224,6 +285,7 @@ impl fmt::Display for Asm {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::AsmParser;
 
     macro_rules! s {
         ($val:expr, $expect:expr) => {
@@ -271,6 +333,7 @@ mod tests {
     #[test]
     fn test_display_instruction() {
         s!(Instruction::AsmOrigin(17), ".ORG 17");
+        s!(Instruction::AsmAlign(8), ".ALIGN 8");
         s!(Instruction::AsmByte(0x0A), ".BYTE 10");
         s!(
             Instruction::AsmDefineBytes(vec![0, 255, 33, 1]),
@@ -303,4 +366,63 @@ mod tests {
         );
         s!(Instruction::Jr("ReL".into()), "JR ReL");
     }
+
+    #[test]
+    fn test_display_line() {
+        s!(Line::Empty(None), "");
+        s!(Line::Empty(Some("Hi".into())), "; Hi");
+        s!(Line::Label("LOOP".into(), None), "LOOP:");
+        s!(
+            Line::Instruction(Instruction::Inc(Register::R0), Some("Count".into())),
+            "    INC R0                    ; Count"
+        );
+        s!(
+            Line::Label("LOOP".into(), Some("Label".into())).with_comment_column(4),
+            "LOOP: ; Label"
+        );
+    }
+
+    #[test]
+    fn comments_survive_a_round_trip() {
+        let program = r#"#! mrasm ; Header comment
+            ; Whole line comment
+            .EQU answer 42
+            .ORG 0x00
+        START:    ; Label comment
+            LDSP 0xEF ; Stack
+            MOV R0, answer
+              ST (0xFF), R0     ; Output
+            LD R1, (R0)
+            JR START
+
+            ; Trailing comment"#;
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        for column in &[0, 10, DEFAULT_COMMENT_COLUMN, 60] {
+            let formatted = asm.with_comment_column(*column).to_string();
+            let reparsed = AsmParser::parse(&formatted).expect("Reparsing failed");
+            assert_eq!(asm, reparsed);
+            // Formatting is stable
+            assert_eq!(formatted, reparsed.with_comment_column(*column).to_string());
+        }
+        let formatted = asm.with_comment_column(20).to_string();
+        let lines: Vec<_> = formatted.lines().collect();
+        assert_eq!(lines[0], "#! mrasm            ; Header comment");
+        assert_eq!(lines[1], "; Whole line comment");
+        assert_eq!(lines[4], "START:              ; Label comment");
+        assert_eq!(lines[5], "    LDSP 0xEF       ; Stack");
+        assert_eq!(lines[7], "    ST (0xFF), R0   ; Output");
+        assert_eq!(lines[9], "    JR START");
+    }
+
+    #[test]
+    fn program_counter_is_formatted_as_r3() {
+        let asm = AsmParser::parse("#! mrasm\n    MOV PC, R0\n    INC PC\n    ST (PC), R3\n")
+            .expect("Parsing failed");
+        let formatted = asm.to_string();
+        let lines: Vec<_> = formatted.lines().collect();
+        assert_eq!(lines[1], "    MOV R3, R0");
+        assert_eq!(lines[2], "    INC R3");
+        assert_eq!(lines[3], "    ST (R3), R3");
+        assert_eq!(AsmParser::parse(&formatted).expect("Reparsing failed"), asm);
+    }
 }
@@ -7,6 +7,8 @@ pub type Label = String;
 mod format;
 mod trait_impls;
 
+pub use format::{CommentAligned, DEFAULT_COMMENT_COLUMN};
+
 /// The default Stacksize. To be used if no `*STACKSIZE n` is given.
 /// Specified in mr2_icd-4.1_asm-1.3.7.pdf.
 const DEFAULT_STACKSIZE: Stacksize = Stacksize::_16;
@@ -69,6 +71,10 @@ pub enum Stacksize {
 }
 
 /// The different program restrictions that may be used.
+///
+/// Set by the `*PROGRAMSIZE n|AUTO|NOSET` directive. The last directive in a
+/// program wins. [`Programsize::Auto`] is resolved when the program is loaded
+/// into a machine, where it becomes a fixed size covering all program bytes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(test, derive(Arbitrary))]
 pub enum Programsize {
@@ -81,6 +87,9 @@ pub enum Programsize {
 }
 
 /// Possible register values.
+///
+/// `PC` is an alias for `R3`. The parser canonicalizes both spellings into
+/// [`Register::R3`], which is always formatted as `R3`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(test, derive(Arbitrary))]
 pub enum Register {
@@ -107,7 +116,10 @@ pub enum MemAddress {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
     /// Set program origin.
+    /// The origins of a program must ascend, see [`CompileError::DescendingOrigin`](crate::compiler::CompileError::DescendingOrigin).
     AsmOrigin(u8),
+    /// Pad with zeros up to the next multiple of n.
+    AsmAlign(u8),
     /// Leave space for n bytes.
     AsmByte(u8),
     /// Define multiple bytes.
@@ -131,6 +143,10 @@ pub enum Instruction {
     /// Multiply the first register by the second.
     Mul(Register, Register),
     /// Divide the first register by the second.
+    ///
+    /// Dividing by zero does not halt the machine. Like on the real hardware,
+    /// the first register is set to `0xFF` and the carry flag is set. For every
+    /// other divisor, the carry flag is cleared.
     Div(Register, Register),
     /// Increase the register by 1.
     Inc(Register),
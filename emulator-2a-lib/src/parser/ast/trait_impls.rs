@@ -105,10 +105,204 @@ impl IntoIterator for Asm {
     }
 }
 
-impl Stacksize {
+impl Asm {
+    /// Iterate over all instructions, including assembler directives.
+    ///
+    /// Labels, comments and empty lines are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::parser::{AsmParser, Instruction};
+    /// let asm = AsmParser::parse("#! mrasm\nLOOP: ; Forever\n    JR LOOP\n").unwrap();
+    /// let instructions: Vec<_> = asm.instructions().collect();
+    /// assert_eq!(instructions, vec![&Instruction::Jr("LOOP".into())]);
+    /// ```
+    pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Instruction(inst, _) => Some(inst),
+            _ => None,
+        })
+    }
+    /// Iterate over all labels, together with the index of their line in [`Asm::lines`].
+    ///
+    /// Constants defined by `.EQU` are not included.
+    pub fn labels(&self) -> impl Iterator<Item = (&Label, usize)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match line {
+                Line::Label(label, _) => Some((label, idx)),
+                _ => None,
+            })
+    }
+}
+
+impl Instruction {
+    /// Get the mnemonic of this instruction, i.e. `"MOV"` or `".ORG"`.
+    pub fn mnemonic(&self) -> &'static str {
+        use Instruction::*;
+        match self {
+            AsmOrigin(_) => ".ORG",
+            AsmAlign(_) => ".ALIGN",
+            AsmByte(_) => ".BYTE",
+            AsmDefineBytes(_) => ".DB",
+            AsmDefineWords(_) => ".DW",
+            AsmEquals(_, _) => ".EQU",
+            AsmStacksize(_) => "*STACKSIZE",
+            AsmProgramsize(_) => "*PROGRAMSIZE",
+            Clr(_) => "CLR",
+            Add(_, _) => "ADD",
+            Adc(_, _) => "ADC",
+            Sub(_, _) => "SUB",
+            Mul(_, _) => "MUL",
+            Div(_, _) => "DIV",
+            Inc(_) => "INC",
+            Dec(_) => "DEC",
+            Neg(_) => "NEG",
+            And(_, _) => "AND",
+            Or(_, _) => "OR",
+            Xor(_, _) => "XOR",
+            Com(_) => "COM",
+            Bits(_, _) => "BITS",
+            Bitc(_, _) => "BITC",
+            Tst(_) => "TST",
+            Cmp(_, _) => "CMP",
+            Bitt(_, _) => "BITT",
+            Lsr(_) => "LSR",
+            Asr(_) => "ASR",
+            Lsl(_) => "LSL",
+            Rrc(_) => "RRC",
+            Rlc(_) => "RLC",
+            Mov(_, _) => "MOV",
+            LdConstant(_, _) | LdMemAddress(_, _) => "LD",
+            St(_, _) => "ST",
+            Push(_) => "PUSH",
+            Pop(_) => "POP",
+            PushF => "PUSHF",
+            PopF => "POPF",
+            Ldsp(_) => "LDSP",
+            Ldfr(_) => "LDFR",
+            Jmp(_) => "JMP",
+            Jcs(_) => "JCS",
+            Jcc(_) => "JCC",
+            Jzs(_) => "JZS",
+            Jzc(_) => "JZC",
+            Jns(_) => "JNS",
+            Jnc(_) => "JNC",
+            Jr(_) => "JR",
+            Call(_) => "CALL",
+            Ret => "RET",
+            RetI => "RETI",
+            Stop => "STOP",
+            Nop => "NOP",
+            Ei => "EI",
+            Di => "DI",
+        }
+    }
+
+    /// Get the number of bytes this instruction places into memory.
+    ///
+    /// This is exactly what the [`Translator`](crate::compiler::Translator) emits,
+    /// except for `.ORG` and `.ALIGN`, which return zero. The gaps they fill
+    /// depend on the address of the instruction before them. Lengths that do not fit
+    /// into a byte are capped at `0xFF`, such a program does not fit into memory anyway.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::parser::{Constant, Instruction, MemAddress, Register};
+    /// assert_eq!(Instruction::Stop.byte_length(), 1);
+    /// let load = Instruction::LdMemAddress(Register::R0, MemAddress::Constant(Constant::Constant(0x20)));
+    /// assert_eq!(load.byte_length(), 3);
+    /// assert_eq!(Instruction::AsmDefineWords(vec![1, 2]).byte_length(), 4);
+    /// ```
+    pub fn byte_length(&self) -> u8 {
+        use Instruction::*;
+        // Constants and constant addresses take an additional byte
+        let mem_len = |mem: &MemAddress| match mem {
+            MemAddress::Constant(_) => 1,
+            MemAddress::Register(_) => 0,
+        };
+        let src_len = |src: &Source| match src {
+            Source::Constant(_) => 1,
+            Source::MemAddress(mem) => mem_len(mem),
+            _ => 0,
+        };
+        let dst_len = |dst: &Destination| match dst {
+            Destination::MemAddress(mem) => mem_len(mem),
+            _ => 0,
+        };
+        let length = match self {
+            AsmOrigin(_) | AsmAlign(_) | AsmEquals(_, _) | AsmStacksize(_) | AsmProgramsize(_) => 0,
+            AsmByte(count) => *count as usize,
+            AsmDefineBytes(bytes) => bytes.len(),
+            AsmDefineWords(words) => 2 * words.len(),
+            Clr(_)
+            | Add(_, _)
+            | Adc(_, _)
+            | Sub(_, _)
+            | Mul(_, _)
+            | Div(_, _)
+            | Inc(_)
+            | Dec(_)
+            | Neg(_)
+            | And(_, _)
+            | Or(_, _)
+            | Xor(_, _)
+            | Com(_)
+            | Tst(_)
+            | Lsr(_)
+            | Asr(_)
+            | Lsl(_)
+            | Rrc(_)
+            | Rlc(_)
+            | Push(_)
+            | Pop(_)
+            | PushF
+            | PopF
+            | Ret
+            | RetI
+            | Stop
+            | Nop
+            | Ei
+            | Di => 1,
+            Bits(dst, src) | Bitc(dst, src) | Cmp(dst, src) | Bitt(dst, src) | Mov(dst, src) => {
+                2 + dst_len(dst) + src_len(src)
+            }
+            LdConstant(_, _) => 3,
+            LdMemAddress(_, mem) | St(mem, _) => 2 + mem_len(mem),
+            Ldsp(src) | Ldfr(src) => 2 + src_len(src),
+            Jcs(_) | Jcc(_) | Jzs(_) | Jzc(_) | Jns(_) | Jnc(_) | Jr(_) | Call(_) => 2,
+            Jmp(_) => 3,
+        };
+        length.min(u8::MAX as usize) as u8
+    }
+
+    /// Is this an assembler directive like `.ORG` or `.DB`?
+    ///
+    /// Directives are handled by the assembler and never executed by the CPU.
+    pub fn is_directive(&self) -> bool {
+        matches!(
+            self,
+            Instruction::AsmOrigin(_)
+                | Instruction::AsmAlign(_)
+                | Instruction::AsmByte(_)
+                | Instruction::AsmDefineBytes(_)
+                | Instruction::AsmDefineWords(_)
+                | Instruction::AsmEquals(_, _)
+                | Instruction::AsmStacksize(_)
+                | Instruction::AsmProgramsize(_)
+        )
+    }
+}
+
+impl Default for Stacksize {
     /// Default Stacksize if none is specified in the asm file.
-    // XXX: Replace with Default impl when `const impl` is available.
-    pub const fn default() -> Self {
+    ///
+    /// This is also the stacksize of a fresh machine. Loading a program
+    /// replaces it with the program's stacksize, unless that is `NOSET`.
+    fn default() -> Self {
         DEFAULT_STACKSIZE
     }
 }
@@ -10,11 +10,26 @@ use super::Rule;
 #[derive(Debug, Error)]
 pub enum ParserError {
     /// Some syntax violation occured.
-    InvalidSyntax(#[source] PestError<Rule>),
+    InvalidSyntax(#[source] Box<PestError<Rule>>),
     /// An undefined Label was referenced.
     UndefinedLabels(Vec<String>),
     /// More than 40 Labels have been used.
     TooManyLabels,
+    /// The first line is not the `#! mrasm` header.
+    /// The field contains the first line.
+    MissingShebang(String),
+    /// A register that does not exist was referenced.
+    /// The fields contain the register and the line number.
+    InvalidRegister(String, usize),
+    /// A constant does not fit into a byte.
+    /// The fields contain the constant and the line number.
+    ConstantOutOfRange(String, usize),
+    /// The alignment of an `.ALIGN` is not a power of two up to 128.
+    /// The fields contain the alignment and the line number.
+    InvalidAlignment(u8, usize),
+    /// The grammar produced a rule the parser did not expect.
+    /// The fields contain an explanation and the line number.
+    UnexpectedRule(String, usize),
 }
 
 macro_rules! map {
@@ -86,6 +101,10 @@ impl fmt::Display for Rule {
             constant_bin => "a binary constant",
             constant_hex => "a hex constant",
             constant_dec => "a constant",
+            constant_overflow => "a constant",
+            dec_overflow => "a constant",
+            constant_u8 => "a constant in bin/dec/hex",
+            constant_dec_u8 => "a constant",
             constant_bhd => "a constant in bin/dec/hex",
             constant => "a constant or a label",
             word_bin => "a binary word",
@@ -103,6 +122,7 @@ impl fmt::Display for Rule {
             source => "'Rs|(Rs)|(Rs+)|((Rs+))|(adr)|const'",
             destination => "'Rs|(Rs)|(Rs+)|((Rs+))|(adr)'",
             org => ".ORG",
+            align => ".ALIGN",
             byte => ".BYTE",
             db => ".DB",
             dw => ".DW",
@@ -185,7 +205,7 @@ impl From<PestError<Rule>> for ParserError {
             EOI, eol, semicolon, ws => "Expected comment or end of line. Too many arguments?"
 
         };
-        ParserError::InvalidSyntax(e)
+        ParserError::InvalidSyntax(Box::new(e))
     }
 }
 
@@ -204,6 +224,35 @@ impl fmt::Display for ParserError {
                 f,
                 "More than 40 Labels have been used. 'mcontrol' can't handle this!"
             ),
+            ParserError::MissingShebang(first_line) if first_line.starts_with('#') => write!(
+                f,
+                "Malformed header '{}'. Did you mean '#! mrasm'?",
+                first_line
+            ),
+            ParserError::InvalidRegister(register, line) => write!(
+                f,
+                "Invalid register '{}' in line {}. Only 'R0' - 'R3' and 'PC' exist",
+                register, line
+            ),
+            ParserError::ConstantOutOfRange(constant, line) => write!(
+                f,
+                "The constant '{}' in line {} is out of range",
+                constant, line
+            ),
+            ParserError::InvalidAlignment(alignment, line) => write!(
+                f,
+                "Invalid alignment {} in line {}. Only powers of two up to 128 are allowed",
+                alignment, line
+            ),
+            ParserError::UnexpectedRule(explanation, line) => write!(
+                f,
+                "Internal parser error in line {}: {}",
+                line, explanation
+            ),
+            ParserError::MissingShebang(_) => write!(
+                f,
+                "Missing header! All source files have to begin with '#! mrasm', followed by a newline"
+            ),
         }
     }
 }
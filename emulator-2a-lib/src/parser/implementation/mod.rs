@@ -7,13 +7,17 @@ use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 
+use std::convert::TryFrom;
+
 use super::ast::*;
 
 mod error;
 #[cfg(test)]
 mod tests;
+mod warning;
 
 pub use error::ParserError;
+pub use warning::ParserWarning;
 type ParseResult<T> = Result<T, ParserError>;
 
 /// Parser for valid Minirechner 2a assembly files.
@@ -38,27 +42,37 @@ macro_rules! inner_tuple {
     ($rule:expr;
      $($($expected:pat )|+ => $function:ident $( | $error:literal)?);* $(;)?) => {
         {
+            let outer_rule = $rule.as_rule();
+            let outer_line = line_of(&$rule);
             let mut inner = $rule.into_inner();
             // return tuple
             (
                 $(
                     {
-                        #[allow(unused_variables)]
-                        let e1 = format!("No inner rule. '{:?}' expected '{}'",
-                                         $rule,
-                                         stringify!($($expected),+));
-                        #[allow(unused_variables)]
-                        let e2 = format!("Wrong rule found inside '{:?}'. Expected '{:?}'",
-                                         $rule,
-                                         stringify!($($expected),+));
-                        $(let e1: String = $error.into();)?
-                        $(let e2: String = $error.into();)?
-                        let inner = inner.next().expect(&e1);
+                        let expected = stringify!($($expected),+);
+                        let inner = match inner.next() {
+                            Some(inner) => inner,
+                            None => {
+                                #[allow(unused_variables)]
+                                let message = format!("No inner rule. '{:?}' expected '{}'",
+                                                      outer_rule,
+                                                      expected);
+                                $(let message: String = $error.into();)?
+                                return Err(ParserError::UnexpectedRule(message, outer_line));
+                            }
+                        };
                         use Rule::*;
                         #[allow(unreachable_patterns)]
                         match inner.as_rule() {
-                            $($expected)|+ => $function(inner),
-                            _ => panic!("{}", e2)
+                            $($expected)|+ => $function(inner)?,
+                            _ => {
+                                #[allow(unused_variables)]
+                                let message = format!("Wrong rule found inside '{:?}'. Expected '{}'",
+                                                      outer_rule,
+                                                      expected);
+                                $(let message: String = $error.into();)?
+                                return Err(ParserError::UnexpectedRule(message, line_of(&inner)));
+                            }
                         }
                     }
                 ),*
@@ -67,22 +81,57 @@ macro_rules! inner_tuple {
     }
 }
 
+/// Make sure that the first line of `input` is the `#! mrasm` header.
+///
+/// This produces a more helpful error than the generic syntax error.
+fn check_shebang(input: &str) -> ParseResult<()> {
+    let first_line = input.lines().next().unwrap_or_default();
+    let valid = first_line
+        .strip_prefix("#! mrasm")
+        .map(|rest| rest.trim_start_matches(&[' ', '\t'][..]))
+        .map(|rest| rest.is_empty() || rest.starts_with(';'))
+        .unwrap_or(false);
+    if valid {
+        Ok(())
+    } else {
+        Err(ParserError::MissingShebang(first_line.to_owned()))
+    }
+}
+
 /// Helper function for [`inner_tuple`] macro.
 /// This function is the identity for all input.
-fn id<T>(element: T) -> T {
-    element
+fn id<T>(element: T) -> ParseResult<T> {
+    Ok(element)
 }
 
 /// Helper function for [`inner_tuple`] macro.
 /// This function discards it's input.
-fn ignore<T>(_: T) {}
+fn ignore<T>(_: T) -> ParseResult<()> {
+    Ok(())
+}
+
+/// Create an error for a [`Pair`] that does not fit the surrounding rule.
+///
+/// The grammar should prevent this, but a mismatch between the grammar
+/// and the parser must not crash the program.
+fn unexpected_rule(pair: &Pair<Rule>) -> ParserError {
+    ParserError::UnexpectedRule(format!("Unexpected {:?}", pair.as_rule()), line_of(pair))
+}
+
+/// Get the line number of the given [`Pair`] in the source file.
+///
+/// Line numbers start at one, the first line contains the `#! mrasm` header.
+fn line_of(pair: &Pair<Rule>) -> usize {
+    pair.as_span().start_pos().line_col().0
+}
 
 impl AsmParser {
     /// Parse a valid Minirechner 2a assembly file.
     ///
     /// # Checks
-    /// 1) **Syntax** Is it a valid file?
-    /// 2) **Labels** Are all used labels defined?
+    /// 1) **Header** Does the file begin with `#! mrasm`?
+    /// 2) **Syntax** Is it a valid file?
+    /// 3) **Labels** Are all used labels defined?
     ///
     /// # Arguments
     /// - `input`: The [`str`] to parse.
@@ -92,6 +141,7 @@ impl AsmParser {
     /// - a [`ParserError`]
     pub fn parse(input: &str) -> ParseResult<Asm> {
         let mut lines = vec![];
+        check_shebang(input)?;
         let mut parsed = <Self as Parser<Rule>>::parse(Rule::file, input)?;
         // Get the header of the asm file
         let header = parsed.next().expect("Infallible: Header must exist");
@@ -99,13 +149,13 @@ impl AsmParser {
         let mut comment_after_shebang = None;
         for el in header.into_inner() {
             if el.as_rule() == Rule::comment {
-                comment_after_shebang = Some(parse_comment(el));
+                comment_after_shebang = Some(parse_comment(el)?);
             }
         }
         // iterate over lines, skipping the header
         for line in parsed {
             if line.as_rule() == Rule::line {
-                lines.push(parse_line(line));
+                lines.push(parse_line(line)?);
             }
         }
         // Do some checks
@@ -115,6 +165,24 @@ impl AsmParser {
             lines,
         })
     }
+    /// Parse a valid Minirechner 2a assembly file and collect warnings.
+    ///
+    /// This does the same checks as [`AsmParser::parse`]. On top of that,
+    /// suspicious but valid constructs are returned as [`ParserWarning`]s.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use emulator_2a_lib::parser::{AsmParser, ParserWarning};
+    /// let (asm, warnings) = AsmParser::parse_with_warnings("#! mrasm\nUNUSED:\n    STOP\n").unwrap();
+    /// assert_eq!(asm.lines.len(), 3);
+    /// assert_eq!(warnings, vec![ParserWarning::UnusedLabel("UNUSED".into())]);
+    /// ```
+    pub fn parse_with_warnings(input: &str) -> ParseResult<(Asm, Vec<ParserWarning>)> {
+        let asm = Self::parse(input)?;
+        let warnings = collect_warnings(&asm.lines);
+        Ok((asm, warnings))
+    }
 }
 /// Parse an assembler instruction line into a valid type.
 ///
@@ -125,7 +193,7 @@ impl AsmParser {
 /// - Some tuple of a [`Line`] and a [`Comment`] or
 /// - None, if the line is empty or
 /// - a [`ParserError`]
-fn parse_line(line: Pair<Rule>) -> Line {
+fn parse_line(line: Pair<Rule>) -> ParseResult<Line> {
     let line = line.into_inner();
     let mut ret = Line::Empty(None);
     // Possible elements in a line:
@@ -139,22 +207,22 @@ fn parse_line(line: Pair<Rule>) -> Line {
             Rule::space => ret,
             // The label or instruction rule comes first and they occur
             // exclusive so replacing is just fine.
-            Rule::label => Line::Label(parse_label(element), None),
-            Rule::instruction => Line::Instruction(parse_instruction(element), None),
+            Rule::label => Line::Label(parse_label(element)?, None),
+            Rule::instruction => Line::Instruction(parse_instruction(element)?, None),
             // comment can only occur once.
             // So it has to be THE comment.
             Rule::comment => {
-                let c = Some(parse_comment(element));
+                let c = Some(parse_comment(element)?);
                 match ret {
                     Line::Empty(_) => Line::Empty(c),
                     Line::Instruction(i, _) => Line::Instruction(i, c),
                     Line::Label(l, _) => Line::Label(l, c),
                 }
             }
-            _ => unreachable!(),
+            _ => return Err(unexpected_rule(&element)),
         }
     }
-    ret
+    Ok(ret)
 }
 /// Do some validity checking on the given lines.
 ///
@@ -162,19 +230,63 @@ fn parse_line(line: Pair<Rule>) -> Line {
 /// - Undefined Labels
 /// - Too many Labels
 fn validate_lines(lines: &[Line]) -> Result<(), ParserError> {
-    // Collect labels
-    let mut labels = vec![];
-    for line in lines {
-        match line {
-            Line::Label(label, _) => labels.push(label.to_lowercase()),
-            Line::Instruction(Instruction::AsmEquals(label, _), _) => {
-                labels.push(label.to_lowercase())
+    let labels: Vec<_> = lines
+        .iter()
+        .filter_map(defined_label)
+        .map(|label| label.to_lowercase())
+        .collect();
+    // Check for undefined labels
+    let undefined_labels: Vec<String> = lines
+        .iter()
+        .flat_map(referenced_labels)
+        .filter(|label| !labels.contains(&label.to_lowercase()))
+        .collect();
+    if labels.len() > 40 {
+        Err(ParserError::TooManyLabels)
+    } else if undefined_labels.is_empty() {
+        Ok(())
+    } else {
+        Err(ParserError::UndefinedLabels(undefined_labels))
+    }
+}
+/// Collect warnings for the given lines.
+///
+/// # Checks
+/// - Labels defined more than once
+/// - Labels that are never referenced
+fn collect_warnings(lines: &[Line]) -> Vec<ParserWarning> {
+    let mut warnings = vec![];
+    let references: Vec<_> = lines
+        .iter()
+        .flat_map(referenced_labels)
+        .map(|label| label.to_lowercase())
+        .collect();
+    let mut seen: Vec<String> = vec![];
+    for label in lines.iter().filter_map(defined_label) {
+        let lowercase = label.to_lowercase();
+        if seen.contains(&lowercase) {
+            warnings.push(ParserWarning::DuplicateLabel(label.clone()));
+        } else {
+            if !references.contains(&lowercase) {
+                warnings.push(ParserWarning::UnusedLabel(label.clone()));
             }
-            _ => {}
+            seen.push(lowercase);
         }
     }
-    // Check for undefined labels
-    let mut undefined_labels: Vec<String> = vec![];
+    warnings
+}
+/// Get the label defined by the given line, if any.
+///
+/// Both labels and `.EQU` definitions are considered.
+pub(crate) fn defined_label(line: &Line) -> Option<&Label> {
+    match line {
+        Line::Label(label, _) => Some(label),
+        Line::Instruction(Instruction::AsmEquals(label, _), _) => Some(label),
+        _ => None,
+    }
+}
+/// Get all labels referenced by the given line.
+pub(crate) fn referenced_labels(line: &Line) -> Vec<Label> {
     // Function to map a Constant to a vec of labels
     let const_to_vec = |c: &Constant| match c {
         Constant::Label(label) => vec![label.clone()],
@@ -196,71 +308,58 @@ fn validate_lines(lines: &[Line]) -> Result<(), ParserError> {
         Destination::MemAddress(mem) => mem_to_vec(mem),
         _ => vec![],
     };
-    for line in lines {
-        let mut refs = match line {
-            Line::Instruction(inst, _) => match inst {
-                Instruction::Jmp(label)
-                | Instruction::Jcs(label)
-                | Instruction::Jcc(label)
-                | Instruction::Jzs(label)
-                | Instruction::Jzc(label)
-                | Instruction::Jns(label)
-                | Instruction::Jnc(label)
-                | Instruction::Jr(label)
-                | Instruction::Call(label) => vec![label.clone()],
-                Instruction::LdConstant(_, c) => const_to_vec(c),
-                Instruction::LdMemAddress(_, mem) | Instruction::St(mem, _) => mem_to_vec(mem),
-                Instruction::Dec(src) | Instruction::Ldsp(src) | Instruction::Ldfr(src) => {
-                    src_to_vec(src)
-                }
-                Instruction::Bits(dst, src)
-                | Instruction::Bitc(dst, src)
-                | Instruction::Cmp(dst, src)
-                | Instruction::Bitt(dst, src)
-                | Instruction::Mov(dst, src) => {
-                    let mut labels = dst_to_vec(dst);
-                    labels.append(&mut src_to_vec(src));
-                    labels
-                }
-                _ => vec![],
-            },
+    match line {
+        Line::Instruction(inst, _) => match inst {
+            Instruction::Jmp(label)
+            | Instruction::Jcs(label)
+            | Instruction::Jcc(label)
+            | Instruction::Jzs(label)
+            | Instruction::Jzc(label)
+            | Instruction::Jns(label)
+            | Instruction::Jnc(label)
+            | Instruction::Jr(label)
+            | Instruction::Call(label) => vec![label.clone()],
+            Instruction::LdConstant(_, c) => const_to_vec(c),
+            Instruction::LdMemAddress(_, mem) | Instruction::St(mem, _) => mem_to_vec(mem),
+            Instruction::Dec(src) | Instruction::Ldsp(src) | Instruction::Ldfr(src) => {
+                src_to_vec(src)
+            }
+            Instruction::Bits(dst, src)
+            | Instruction::Bitc(dst, src)
+            | Instruction::Cmp(dst, src)
+            | Instruction::Bitt(dst, src)
+            | Instruction::Mov(dst, src) => {
+                let mut labels = dst_to_vec(dst);
+                labels.append(&mut src_to_vec(src));
+                labels
+            }
             _ => vec![],
-        };
-        // Check if labels exist and add missing ones to the list of undefined labels
-        let mut refs = refs
-            .drain(..)
-            .filter(|label| !labels.contains(&label.to_lowercase()))
-            .collect();
-        undefined_labels.append(&mut refs)
-    }
-    if labels.len() > 40 {
-        Err(ParserError::TooManyLabels)
-    } else if undefined_labels.is_empty() {
-        Ok(())
-    } else {
-        Err(ParserError::UndefinedLabels(undefined_labels))
+        },
+        _ => vec![],
     }
 }
 /// Parse a `label` rule into a [`Label`].
-fn parse_label(label: Pair<Rule>) -> Label {
+fn parse_label(label: Pair<Rule>) -> ParseResult<Label> {
     let (label, _) = inner_tuple! { label;
         raw_label => parse_raw_label;
         colon     => ignore
     };
-    label
+    Ok(label)
 }
 /// Parse a `raw_label` rule into a [`Label`].
-fn parse_raw_label(label: Pair<Rule>) -> Label {
-    label.as_str().into()
+fn parse_raw_label(label: Pair<Rule>) -> ParseResult<Label> {
+    Ok(label.as_str().into())
 }
 /// Parse an `instruction` rule into an [`Instruction`].
-fn parse_instruction(instruction: Pair<Rule>) -> Instruction {
+fn parse_instruction(instruction: Pair<Rule>) -> ParseResult<Instruction> {
+    let line = line_of(&instruction);
     let instruction = instruction
         .into_inner()
         .next()
-        .expect("an instruction rule should have an actual instruction");
+        .ok_or_else(|| ParserError::UnexpectedRule("Instruction without content".into(), line))?;
     match instruction.as_rule() {
         Rule::org => parse_instruction_org(instruction),
+        Rule::align => parse_instruction_align(instruction),
         Rule::byte => parse_instruction_byte(instruction),
         Rule::db => parse_instruction_db(instruction),
         Rule::dw => parse_instruction_dw(instruction),
@@ -315,624 +414,624 @@ fn parse_instruction(instruction: Pair<Rule>) -> Instruction {
         Rule::nop => parse_instruction_nop(),
         Rule::ei => parse_instruction_ei(),
         Rule::di => parse_instruction_di(),
-        _ => unreachable!(),
+        _ => Err(unexpected_rule(&instruction)),
     }
 }
 /// Parse an `org` rule into an [`Instruction`].
-fn parse_instruction_org(org: Pair<Rule>) -> Instruction {
+fn parse_instruction_org(org: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, number) = inner_tuple! { org;
         sep_ip => ignore;
-        constant_bin | constant_hex | constant_dec => id;
+        constant_bin | constant_hex | constant_dec | constant_overflow => parse_number;
     };
-    let number = match number.as_rule() {
-        Rule::constant_bin => u8::from_str_radix(&number.as_str()[2..], 2).unwrap(),
-        Rule::constant_hex => u8::from_str_radix(&number.as_str()[2..], 16).unwrap(),
-        Rule::constant_dec => number.as_str().parse().unwrap(),
-        _ => unreachable!(),
+    Ok(Instruction::AsmOrigin(number))
+}
+/// Parse an `align` rule into an [`Instruction`].
+fn parse_instruction_align(align: Pair<Rule>) -> ParseResult<Instruction> {
+    let line = line_of(&align);
+    let (_, alignment): (_, u8) = inner_tuple! { align;
+        sep_ip => ignore;
+        constant_bin | constant_hex | constant_dec | constant_overflow => parse_number;
     };
-    Instruction::AsmOrigin(number)
+    if !alignment.is_power_of_two() || alignment > 128 {
+        return Err(ParserError::InvalidAlignment(alignment, line));
+    }
+    Ok(Instruction::AsmAlign(alignment))
 }
 /// Parse a `constant_bhd` rule into a [`u8`].
-fn parse_constant_bhd(constant_bhd: Pair<Rule>) -> u8 {
+fn parse_constant_bhd(constant_bhd: Pair<Rule>) -> ParseResult<u8> {
     let inner = inner_tuple! { constant_bhd;
-        constant_bin | constant_hex | constant_dec => id;
+        constant_bin | constant_hex | constant_dec | constant_overflow => parse_number;
     };
-    match inner.as_rule() {
-        Rule::constant_bin => u8::from_str_radix(&inner.as_str()[2..], 2).unwrap(),
-        Rule::constant_hex => u8::from_str_radix(&inner.as_str()[2..], 16).unwrap(),
-        Rule::constant_dec => parse_constant_dec(inner),
-        _ => unreachable!(),
-    }
+    Ok(inner)
 }
 /// Parse a `word_bhd` rule into a [`u16`].
-fn parse_word_bhd(word_bhd: Pair<Rule>) -> u16 {
+fn parse_word_bhd(word_bhd: Pair<Rule>) -> ParseResult<u16> {
     let inner = inner_tuple! { word_bhd;
-        word_bin | word_hex | word_dec => id;
+        word_bin | word_hex | word_dec | constant_overflow => parse_number;
     };
-    match inner.as_rule() {
-        Rule::word_bin => u16::from_str_radix(&inner.as_str()[2..], 2).unwrap(),
-        Rule::word_hex => u16::from_str_radix(&inner.as_str()[2..], 16).unwrap(),
-        Rule::word_dec => parse_word_dec(inner),
-        _ => unreachable!(),
-    }
+    Ok(inner)
 }
 /// Parse a `constant` rule into a [`Constant`].
-fn parse_constant(constant: Pair<Rule>) -> Constant {
+fn parse_constant(constant: Pair<Rule>) -> ParseResult<Constant> {
     let inner = inner_tuple! { constant;
-        constant_bin | constant_hex | constant_dec | raw_label => id;
+        constant_bin | constant_hex | constant_dec | constant_overflow | raw_label => id;
     };
     match inner.as_rule() {
-        Rule::constant_bin => u8::from_str_radix(&inner.as_str()[2..], 2)
-            .map(Constant::Constant)
-            .unwrap(),
-        Rule::constant_hex => u8::from_str_radix(&inner.as_str()[2..], 16)
-            .map(Constant::Constant)
-            .unwrap(),
-        Rule::constant_dec => parse_constant_dec(inner).into(),
-        Rule::raw_label => Constant::Label(parse_raw_label(inner)),
-        _ => unreachable!(),
+        Rule::raw_label => parse_raw_label(inner).map(Constant::Label),
+        _ => parse_number(inner).map(Constant::Constant),
     }
 }
-/// Parse a `constant_dec` rule into a [`u8`].
-fn parse_constant_dec(constant_dec: Pair<Rule>) -> u8 {
-    constant_dec
-        .as_str()
-        .parse()
-        .expect("Could not parse constant_dec")
+/// Parse a binary, hex or decimal constant or word rule into a number.
+///
+/// Numbers that do not fit into `T` are reported as [`ParserError::ConstantOutOfRange`].
+fn parse_number<T: TryFrom<u64>>(number: Pair<Rule>) -> ParseResult<T> {
+    let text = number.as_str();
+    let parsed = match number.as_rule() {
+        Rule::constant_bin | Rule::word_bin => u64::from_str_radix(&text[2..], 2),
+        Rule::constant_hex | Rule::word_hex => u64::from_str_radix(&text[2..], 16),
+        Rule::constant_dec | Rule::word_dec => text.parse(),
+        // The grammar only matches these if they are too large
+        Rule::constant_overflow | Rule::dec_overflow => return Err(out_of_range(&number)),
+        _ => return Err(unexpected_rule(&number)),
+    };
+    parsed
+        .ok()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| out_of_range(&number))
 }
-/// Parse a `word_dec` rule into a [`u16`].
-fn parse_word_dec(word_dec: Pair<Rule>) -> u16 {
-    word_dec.as_str().parse().expect("Could not parse word_dec")
+/// Create an error for a number that does not fit into its type.
+fn out_of_range(number: &Pair<Rule>) -> ParserError {
+    ParserError::ConstantOutOfRange(number.as_str().into(), line_of(number))
 }
 /// Parse a `byte` rule into an [`Instruction`].
-fn parse_instruction_byte(byte: Pair<Rule>) -> Instruction {
+fn parse_instruction_byte(byte: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, number) = inner_tuple! { byte;
         sep_ip => ignore;
-        constant_bin | constant_hex | constant_dec => id;
-    };
-    let number = match number.as_rule() {
-        Rule::constant_bin => u8::from_str_radix(&number.as_str()[2..], 2).unwrap(),
-        Rule::constant_hex => u8::from_str_radix(&number.as_str()[2..], 16).unwrap(),
-        Rule::constant_dec => parse_constant_dec(number),
-        _ => unreachable!(),
+        constant_bin | constant_hex | constant_dec | constant_overflow => parse_number;
     };
-    Instruction::AsmByte(number)
+    Ok(Instruction::AsmByte(number))
 }
 /// Parse a `db` rule into an [`Instruction`].
-fn parse_instruction_db(db: Pair<Rule>) -> Instruction {
+fn parse_instruction_db(db: Pair<Rule>) -> ParseResult<Instruction> {
     let results = db
         .into_inner()
         .filter(|pair| pair.as_rule() == Rule::constant_bhd)
         .map(parse_constant_bhd);
-    Instruction::AsmDefineBytes(results.collect())
+    Ok(Instruction::AsmDefineBytes(
+        results.collect::<ParseResult<_>>()?,
+    ))
 }
 /// Parse a `dw` rule into an [`Instruction`].
-fn parse_instruction_dw(dw: Pair<Rule>) -> Instruction {
+fn parse_instruction_dw(dw: Pair<Rule>) -> ParseResult<Instruction> {
     let results = dw
         .into_inner()
         .filter(|pair| pair.as_rule() == Rule::word_bhd)
         .map(parse_word_bhd);
-    Instruction::AsmDefineWords(results.collect())
+    Ok(Instruction::AsmDefineWords(
+        results.collect::<ParseResult<_>>()?,
+    ))
 }
 /// Parse an `equ` rule into an [`Instruction`].
-fn parse_instruction_equ(equ: Pair<Rule>) -> Instruction {
+fn parse_instruction_equ(equ: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label, _, constant) = inner_tuple! { equ;
         sep_ip       => ignore;
         raw_label    => parse_raw_label;
         sep_ip       => ignore;
-        constant_dec => parse_constant_dec;
+        constant_dec | dec_overflow => parse_number;
     };
-    Instruction::AsmEquals(label, constant)
+    Ok(Instruction::AsmEquals(label, constant))
 }
 /// Parse a `stacksize` rule into an [`Instruction`].
-fn parse_instruction_stacksize(instruction: Pair<Rule>) -> Instruction {
+fn parse_instruction_stacksize(instruction: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, stacksize) = inner_tuple! { instruction;
         sep_ip          => ignore;
         raw_stacksize   => parse_raw_stacksize;
     };
-    Instruction::AsmStacksize(stacksize)
+    Ok(Instruction::AsmStacksize(stacksize))
 }
 /// Parse a `raw_stacksize` rule into a [`Stacksize`].
-fn parse_raw_stacksize(stacksize: Pair<Rule>) -> Stacksize {
-    let stacksize = stacksize.as_str().to_lowercase();
-    match stacksize.as_str() {
-        "0" => Stacksize::_0,
-        "16" => Stacksize::_16,
-        "32" => Stacksize::_32,
-        "48" => Stacksize::_48,
-        "64" => Stacksize::_64,
-        "noset" => Stacksize::NotSet,
-        _ => unreachable!(),
+fn parse_raw_stacksize(stacksize: Pair<Rule>) -> ParseResult<Stacksize> {
+    match stacksize.as_str().to_lowercase().as_str() {
+        "0" => Ok(Stacksize::_0),
+        "16" => Ok(Stacksize::_16),
+        "32" => Ok(Stacksize::_32),
+        "48" => Ok(Stacksize::_48),
+        "64" => Ok(Stacksize::_64),
+        "noset" => Ok(Stacksize::NotSet),
+        _ => Err(unexpected_rule(&stacksize)),
     }
 }
 /// Parse a `programsize` rule into an [`Instruction`].
-fn parse_instruction_programsize(instruction: Pair<Rule>) -> Instruction {
+fn parse_instruction_programsize(instruction: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, programsize) = inner_tuple! { instruction;
         sep_ip          => ignore;
         raw_programsize => parse_raw_programsize;
     };
-    Instruction::AsmProgramsize(programsize)
+    Ok(Instruction::AsmProgramsize(programsize))
 }
 /// Parse a `raw_programsize` rule into a [`Programsize`].
-fn parse_raw_programsize(programsize: Pair<Rule>) -> Programsize {
+fn parse_raw_programsize(programsize: Pair<Rule>) -> ParseResult<Programsize> {
     let size = programsize.as_str().to_lowercase();
     match size.as_str() {
-        "auto" => Programsize::Auto,
-        "noset" => Programsize::NotSet,
+        "auto" => Ok(Programsize::Auto),
+        "noset" => Ok(Programsize::NotSet),
         _ => {
             let inner = inner_tuple! { programsize;
-                constant_dec => parse_constant_dec;
+                constant_dec | dec_overflow => parse_number;
             };
-            Programsize::Size(inner)
+            Ok(Programsize::Size(inner))
         }
     }
 }
 /// Parse a `clr` rule into an [`Instruction`].
-fn parse_instruction_clr(instruction: Pair<Rule>) -> Instruction {
+fn parse_instruction_clr(instruction: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, register) = inner_tuple! { instruction;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Clr(register)
+    Ok(Instruction::Clr(register))
 }
 /// Parse a `register` rule into a [`Register`].
-fn parse_register(register: Pair<Rule>) -> Register {
+///
+/// `PC` is canonicalized into [`Register::R3`], the spelling is not kept.
+fn parse_register(register: Pair<Rule>) -> ParseResult<Register> {
     let reg = register.as_str().to_lowercase();
     match reg.as_str() {
-        "r0" => Register::R0,
-        "r1" => Register::R1,
-        "r2" => Register::R2,
-        "r3" => Register::R3,
-        "pc" => Register::R3,
-        _ => unreachable!(),
+        "r0" => Ok(Register::R0),
+        "r1" => Ok(Register::R1),
+        "r2" => Ok(Register::R2),
+        "r3" => Ok(Register::R3),
+        "pc" => Ok(Register::R3),
+        _ => Err(ParserError::InvalidRegister(
+            register.as_str().into(),
+            line_of(&register),
+        )),
     }
 }
 /// Parse an `add` rule into an [`Instruction`].
-fn parse_instruction_add(add: Pair<Rule>) -> Instruction {
+fn parse_instruction_add(add: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { add;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Add(reg1, reg2)
+    Ok(Instruction::Add(reg1, reg2))
 }
 /// Parse an `adc` rule into an [`Instruction`].
-fn parse_instruction_adc(adc: Pair<Rule>) -> Instruction {
+fn parse_instruction_adc(adc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { adc;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Adc(reg1, reg2)
+    Ok(Instruction::Adc(reg1, reg2))
 }
 /// Parse a `sub` rule into an [`Instruction`].
-fn parse_instruction_sub(sub: Pair<Rule>) -> Instruction {
+fn parse_instruction_sub(sub: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { sub;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Sub(reg1, reg2)
+    Ok(Instruction::Sub(reg1, reg2))
 }
 /// Parse a `mul` rule into an [`Instruction`].
-fn parse_instruction_mul(mul: Pair<Rule>) -> Instruction {
+fn parse_instruction_mul(mul: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { mul;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Mul(reg1, reg2)
+    Ok(Instruction::Mul(reg1, reg2))
 }
 /// Parse a `div` rule into an [`Instruction`].
-fn parse_instruction_div(div: Pair<Rule>) -> Instruction {
+fn parse_instruction_div(div: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { div;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Div(reg1, reg2)
+    Ok(Instruction::Div(reg1, reg2))
 }
 /// Parse an `inc` rule into an [`Instruction`].
-fn parse_instruction_inc(inc: Pair<Rule>) -> Instruction {
+fn parse_instruction_inc(inc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { inc;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Inc(reg)
+    Ok(Instruction::Inc(reg))
 }
 /// Parse a `dec` rule into an [`Instruction`].
-fn parse_instruction_dec(dec: Pair<Rule>) -> Instruction {
+fn parse_instruction_dec(dec: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, source) = inner_tuple! { dec;
         sep_ip     => ignore;
         source     => parse_source;
     };
-    Instruction::Dec(source)
+    Ok(Instruction::Dec(source))
 }
 /// Parse a `source` rule into a [`Source`].
-fn parse_source(source: Pair<Rule>) -> Source {
-    let inner = source
-        .into_inner()
-        .next()
-        .expect("source needs an inner element");
+fn parse_source(source: Pair<Rule>) -> ParseResult<Source> {
+    let inner = inner_tuple! { source;
+        register | registerdi | registerddi | memory | constant => id;
+    };
     match inner.as_rule() {
-        Rule::register => parse_register(inner).into(),
-        Rule::registerdi => parse_register_di(inner).into(),
-        Rule::registerddi => parse_register_ddi(inner).into(),
-        Rule::memory => parse_memory(inner).into(),
-        Rule::constant => parse_constant(inner).into(),
-        _ => unreachable!(),
+        Rule::register => parse_register(inner).map(Source::from),
+        Rule::registerdi => parse_register_di(inner).map(Source::from),
+        Rule::registerddi => parse_register_ddi(inner).map(Source::from),
+        Rule::memory => parse_memory(inner).map(Source::from),
+        Rule::constant => parse_constant(inner).map(Source::from),
+        _ => Err(unexpected_rule(&inner)),
     }
 }
 /// Parse a `registerdi` rule into a [`RegisterDI`].
-fn parse_register_di(registerdi: Pair<Rule>) -> RegisterDi {
+fn parse_register_di(registerdi: Pair<Rule>) -> ParseResult<RegisterDi> {
     let (_, register, _, _) = inner_tuple! { registerdi;
         oparen      => ignore;
         register    => parse_register;
         plus        => ignore;
         cparen      => ignore;
     };
-    register.into()
+    Ok(register.into())
 }
 /// Parse a `registerddi` rule into a [`RegisterDDI`].
-fn parse_register_ddi(registerddi: Pair<Rule>) -> RegisterDdi {
+fn parse_register_ddi(registerddi: Pair<Rule>) -> ParseResult<RegisterDdi> {
     let (_, register, _) = inner_tuple! { registerddi;
         oparen      => ignore;
         registerdi  => parse_register_di;
         cparen      => ignore;
     };
-    register.into()
+    Ok(register.into())
 }
 /// Parse a `memory` rule into a [`MemAddress`].
-fn parse_memory(memory: Pair<Rule>) -> MemAddress {
+fn parse_memory(memory: Pair<Rule>) -> ParseResult<MemAddress> {
     let (_, inner, _) = inner_tuple! { memory;
         oparen                                                  => ignore;
         register | registerdi | registerddi | memory | constant => id;
         cparen                                                  => ignore;
     };
     match inner.as_rule() {
-        Rule::constant => parse_constant(inner).into(),
-        Rule::register => parse_register(inner).into(),
+        Rule::constant => parse_constant(inner).map(MemAddress::from),
+        Rule::register => parse_register(inner).map(MemAddress::from),
         Rule::raw_label => {
-            let constant: Constant = parse_raw_label(inner).into();
-            constant.into()
+            let constant: Constant = parse_raw_label(inner)?.into();
+            Ok(constant.into())
         }
-        _ => unreachable!(),
+        _ => Err(unexpected_rule(&inner)),
     }
 }
 /// Parse a `neg` rule into an [`Instruction`].
-fn parse_instruction_neg(neg: Pair<Rule>) -> Instruction {
+fn parse_instruction_neg(neg: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { neg;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Neg(reg)
+    Ok(Instruction::Neg(reg))
 }
 /// Parse an `and` rule into an [`Instruction`].
-fn parse_instruction_and(and: Pair<Rule>) -> Instruction {
+fn parse_instruction_and(and: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { and;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::And(reg1, reg2)
+    Ok(Instruction::And(reg1, reg2))
 }
 /// Parse an `or` rule into an [`Instruction`].
-fn parse_instruction_or(or: Pair<Rule>) -> Instruction {
+fn parse_instruction_or(or: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { or;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Or(reg1, reg2)
+    Ok(Instruction::Or(reg1, reg2))
 }
 /// Parse an `xor` rule into an [`Instruction`].
-fn parse_instruction_xor(xor: Pair<Rule>) -> Instruction {
+fn parse_instruction_xor(xor: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg1, _, reg2) = inner_tuple! { xor;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::Xor(reg1, reg2)
+    Ok(Instruction::Xor(reg1, reg2))
 }
 /// Parse a `com` rule into an [`Instruction`].
-fn parse_instruction_com(com: Pair<Rule>) -> Instruction {
+fn parse_instruction_com(com: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { com;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Com(reg)
+    Ok(Instruction::Com(reg))
 }
 /// Parse a `bits` rule into an [`Instruction`].
-fn parse_instruction_bits(bits: Pair<Rule>) -> Instruction {
+fn parse_instruction_bits(bits: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, dst, _, src) = inner_tuple! { bits;
         sep_ip      => ignore;
         destination => parse_destination;
         sep_pp      => ignore;
         source      => parse_source;
     };
-    Instruction::Bits(dst, src)
+    Ok(Instruction::Bits(dst, src))
 }
 /// Parse a `destination` rule into a [`Destination`].
-fn parse_destination(destination: Pair<Rule>) -> Destination {
-    let inner = destination
-        .into_inner()
-        .next()
-        .expect("source needs an inner element");
+fn parse_destination(destination: Pair<Rule>) -> ParseResult<Destination> {
+    let inner = inner_tuple! { destination;
+        register | registerdi | registerddi | memory => id;
+    };
     match inner.as_rule() {
-        Rule::register => parse_register(inner).into(),
-        Rule::registerdi => parse_register_di(inner).into(),
-        Rule::registerddi => parse_register_ddi(inner).into(),
-        Rule::memory => parse_memory(inner).into(),
-        _ => unreachable!(),
+        Rule::register => parse_register(inner).map(Destination::from),
+        Rule::registerdi => parse_register_di(inner).map(Destination::from),
+        Rule::registerddi => parse_register_ddi(inner).map(Destination::from),
+        Rule::memory => parse_memory(inner).map(Destination::from),
+        _ => Err(unexpected_rule(&inner)),
     }
 }
 /// Parse a `bitc` rule into an [`Instruction`].
-fn parse_instruction_bitc(bitc: Pair<Rule>) -> Instruction {
+fn parse_instruction_bitc(bitc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, dst, _, src) = inner_tuple! { bitc;
         sep_ip      => ignore;
         destination => parse_destination;
         sep_pp      => ignore;
         source      => parse_source;
     };
-    Instruction::Bitc(dst, src)
+    Ok(Instruction::Bitc(dst, src))
 }
 /// Parse a `tst` rule into an [`Instruction`].
-fn parse_instruction_tst(tst: Pair<Rule>) -> Instruction {
+fn parse_instruction_tst(tst: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { tst;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Tst(reg)
+    Ok(Instruction::Tst(reg))
 }
 /// Parse a `cmp` rule into an [`Instruction`].
-fn parse_instruction_cmp(cmp: Pair<Rule>) -> Instruction {
+fn parse_instruction_cmp(cmp: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, dst, _, src) = inner_tuple! { cmp;
         sep_ip      => ignore;
         destination => parse_destination;
         sep_pp      => ignore;
         source      => parse_source;
     };
-    Instruction::Cmp(dst, src)
+    Ok(Instruction::Cmp(dst, src))
 }
 /// Parse a `bitt` rule into an [`Instruction`].
-fn parse_instruction_bitt(bitt: Pair<Rule>) -> Instruction {
+fn parse_instruction_bitt(bitt: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, dst, _, src) = inner_tuple! { bitt;
         sep_ip      => ignore;
         destination => parse_destination;
         sep_pp      => ignore;
         source      => parse_source;
     };
-    Instruction::Bitt(dst, src)
+    Ok(Instruction::Bitt(dst, src))
 }
 /// Parse a `lsr` rule into an [`Instruction`].
-fn parse_instruction_lsr(lsr: Pair<Rule>) -> Instruction {
+fn parse_instruction_lsr(lsr: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { lsr;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Lsr(reg)
+    Ok(Instruction::Lsr(reg))
 }
 /// Parse an `asr` rule into an [`Instruction`].
-fn parse_instruction_asr(asr: Pair<Rule>) -> Instruction {
+fn parse_instruction_asr(asr: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { asr;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Asr(reg)
+    Ok(Instruction::Asr(reg))
 }
 /// Parse a `lsl` rule into an [`Instruction`].
-fn parse_instruction_lsl(lsl: Pair<Rule>) -> Instruction {
+fn parse_instruction_lsl(lsl: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { lsl;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Lsl(reg)
+    Ok(Instruction::Lsl(reg))
 }
 /// Parse an `rrc` rule into an [`Instruction`].
-fn parse_instruction_rrc(rrc: Pair<Rule>) -> Instruction {
+fn parse_instruction_rrc(rrc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { rrc;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Rrc(reg)
+    Ok(Instruction::Rrc(reg))
 }
 /// Parse an `rlc` rule into an [`Instruction`].
-fn parse_instruction_rlc(rlc: Pair<Rule>) -> Instruction {
+fn parse_instruction_rlc(rlc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { rlc;
         sep_ip      => ignore;
         register    => parse_register;
     };
-    Instruction::Rlc(reg)
+    Ok(Instruction::Rlc(reg))
 }
 /// Parse a `mov` rule into an [`Instruction`].
-fn parse_instruction_mov(mov: Pair<Rule>) -> Instruction {
+fn parse_instruction_mov(mov: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, dst, _, src) = inner_tuple! { mov;
         sep_ip      => ignore;
         destination => parse_destination;
         sep_pp      => ignore;
         source      => parse_source;
     };
-    Instruction::Mov(dst, src)
+    Ok(Instruction::Mov(dst, src))
 }
 /// Parse an `ld_const` rule into an [`Instruction`].
-fn parse_instruction_ld_const(ld_const: Pair<Rule>) -> Instruction {
+fn parse_instruction_ld_const(ld_const: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg, _, constant) = inner_tuple! { ld_const;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         constant    => parse_constant;
     };
-    Instruction::LdConstant(reg, constant)
+    Ok(Instruction::LdConstant(reg, constant))
 }
 /// Parse an `ld_memory` rule into an [`Instruction`].
-fn parse_instruction_ld_memory(ld_memory: Pair<Rule>) -> Instruction {
+fn parse_instruction_ld_memory(ld_memory: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg, _, mem) = inner_tuple! { ld_memory;
         sep_ip      => ignore;
         register    => parse_register;
         sep_pp      => ignore;
         memory      => parse_memory;
     };
-    Instruction::LdMemAddress(reg, mem)
+    Ok(Instruction::LdMemAddress(reg, mem))
 }
 /// Parse an `st` rule into an [`Instruction`].
-fn parse_instruction_st(st: Pair<Rule>) -> Instruction {
+fn parse_instruction_st(st: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, mem, _, reg) = inner_tuple! { st;
         sep_ip      => ignore;
         memory      => parse_memory;
         sep_pp      => ignore;
         register    => parse_register;
     };
-    Instruction::St(mem, reg)
+    Ok(Instruction::St(mem, reg))
 }
 /// Parse a `push` rule into an [`Instruction`].
-fn parse_instruction_push(push: Pair<Rule>) -> Instruction {
+fn parse_instruction_push(push: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { push;
         sep_ip         => ignore;
         register    => parse_register;
     };
-    Instruction::Push(reg)
+    Ok(Instruction::Push(reg))
 }
 /// Parse a `pop` rule into an [`Instruction`].
-fn parse_instruction_pop(pop: Pair<Rule>) -> Instruction {
+fn parse_instruction_pop(pop: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, reg) = inner_tuple! { pop;
         sep_ip         => ignore;
         register    => parse_register;
     };
-    Instruction::Pop(reg)
+    Ok(Instruction::Pop(reg))
 }
 /// Parse a `pushf` rule into an [`Instruction`].
-fn parse_instruction_pushf() -> Instruction {
-    Instruction::PushF
+fn parse_instruction_pushf() -> ParseResult<Instruction> {
+    Ok(Instruction::PushF)
 }
 /// Parse a `popf` rule into an [`Instruction`].
-fn parse_instruction_popf() -> Instruction {
-    Instruction::PopF
+fn parse_instruction_popf() -> ParseResult<Instruction> {
+    Ok(Instruction::PopF)
 }
 /// Parse a `ldsp` rule into an [`Instruction`].
-fn parse_instruction_ldsp(ldsp: Pair<Rule>) -> Instruction {
+fn parse_instruction_ldsp(ldsp: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, src) = inner_tuple! { ldsp;
         sep_ip     => ignore;
         source  => parse_source;
     };
-    Instruction::Ldsp(src)
+    Ok(Instruction::Ldsp(src))
 }
 /// Parse a `ldfr` rule into an [`Instruction`].
-fn parse_instruction_ldfr(ldfr: Pair<Rule>) -> Instruction {
+fn parse_instruction_ldfr(ldfr: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, src) = inner_tuple! { ldfr;
         sep_ip     => ignore;
         source  => parse_source;
     };
-    Instruction::Ldfr(src)
+    Ok(Instruction::Ldfr(src))
 }
 /// Parse a `jmp` rule into an [`Instruction`].
-fn parse_instruction_jmp(jmp: Pair<Rule>) -> Instruction {
+fn parse_instruction_jmp(jmp: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jmp;
         sep_ip       => ignore;
         raw_label => parse_raw_label;
     };
-    Instruction::Jmp(label)
+    Ok(Instruction::Jmp(label))
 }
 /// Parse a `jcs` rule into an [`Instruction`].
-fn parse_instruction_jcs(jcs: Pair<Rule>) -> Instruction {
+fn parse_instruction_jcs(jcs: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jcs;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jcs(label)
+    Ok(Instruction::Jcs(label))
 }
 /// Parse a `jcc` rule into an [`Instruction`].
-fn parse_instruction_jcc(jcc: Pair<Rule>) -> Instruction {
+fn parse_instruction_jcc(jcc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jcc;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jcc(label)
+    Ok(Instruction::Jcc(label))
 }
 /// Parse a `jzs` rule into an [`Instruction`].
-fn parse_instruction_jzs(jzs: Pair<Rule>) -> Instruction {
+fn parse_instruction_jzs(jzs: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jzs;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jzs(label)
+    Ok(Instruction::Jzs(label))
 }
 /// Parse a `jzc` rule into an [`Instruction`].
-fn parse_instruction_jzc(jzc: Pair<Rule>) -> Instruction {
+fn parse_instruction_jzc(jzc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jzc;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jzc(label)
+    Ok(Instruction::Jzc(label))
 }
 /// Parse a `jns` rule into an [`Instruction`].
-fn parse_instruction_jns(jns: Pair<Rule>) -> Instruction {
+fn parse_instruction_jns(jns: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jns;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jns(label)
+    Ok(Instruction::Jns(label))
 }
 /// Parse a `jnc` rule into an [`Instruction`].
-fn parse_instruction_jnc(jnc: Pair<Rule>) -> Instruction {
+fn parse_instruction_jnc(jnc: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jnc;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jnc(label)
+    Ok(Instruction::Jnc(label))
 }
 /// Parse a `jr` rule into an [`Instruction`].
-fn parse_instruction_jr(jr: Pair<Rule>) -> Instruction {
+fn parse_instruction_jr(jr: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { jr;
         sep_ip     => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Jr(label)
+    Ok(Instruction::Jr(label))
 }
 /// Parse a `call` rule into an [`Instruction`].
-fn parse_instruction_call(call: Pair<Rule>) -> Instruction {
+fn parse_instruction_call(call: Pair<Rule>) -> ParseResult<Instruction> {
     let (_, label) = inner_tuple! { call;
         sep_ip         => ignore;
         raw_label   => parse_raw_label;
     };
-    Instruction::Call(label)
+    Ok(Instruction::Call(label))
 }
 /// Parse a `ret` rule into an [`Instruction`].
-fn parse_instruction_ret() -> Instruction {
-    Instruction::Ret
+fn parse_instruction_ret() -> ParseResult<Instruction> {
+    Ok(Instruction::Ret)
 }
 /// Parse a `reti` rule into an [`Instruction`].
-fn parse_instruction_reti() -> Instruction {
-    Instruction::RetI
+fn parse_instruction_reti() -> ParseResult<Instruction> {
+    Ok(Instruction::RetI)
 }
 /// Parse a `stop` rule into an [`Instruction`].
-fn parse_instruction_stop() -> Instruction {
-    Instruction::Stop
+fn parse_instruction_stop() -> ParseResult<Instruction> {
+    Ok(Instruction::Stop)
 }
 /// Parse a `nop` rule into an [`Instruction`].
-fn parse_instruction_nop() -> Instruction {
-    Instruction::Nop
+fn parse_instruction_nop() -> ParseResult<Instruction> {
+    Ok(Instruction::Nop)
 }
 /// Parse an `ei` rule into an [`Instruction`].
-fn parse_instruction_ei() -> Instruction {
-    Instruction::Ei
+fn parse_instruction_ei() -> ParseResult<Instruction> {
+    Ok(Instruction::Ei)
 }
 /// Parse a `di` rule into an [`Instruction`].
-fn parse_instruction_di() -> Instruction {
-    Instruction::Di
+fn parse_instruction_di() -> ParseResult<Instruction> {
+    Ok(Instruction::Di)
 }
 /// Parse a `comment` rule into a [`Comment`].
-fn parse_comment(comment: Pair<Rule>) -> Comment {
+fn parse_comment(comment: Pair<Rule>) -> ParseResult<Comment> {
     let (_, comment) = inner_tuple! { comment;
         semicolon   => ignore;
         rest        => id;
     };
-    comment.as_str().trim_matches(|c| " \t;".contains(c)).into()
+    Ok(comment.as_str().trim_matches(|c| " \t;".contains(c)).into())
 }
@@ -1,5 +1,5 @@
-use super::AsmParser;
 use super::Rule;
+use super::{AsmParser, ParserError, ParserWarning};
 use pest::Parser;
 use proptest::prelude::*;
 
@@ -44,13 +44,86 @@ proptest! {
         let hex_lower = format!("0x{:X}", word);
         for val in &[bin, dec, hex_upper, hex_lower] {
             let inout = format!(".DW {}", val);
-            if word <= 0xFFFF {
-                parse!(Rule::dw, &inout);
-            } else {
-                parse_err!(Rule::dw, &inout);
+            parse!(Rule::dw, &inout);
+            if word > 0xFFFF {
+                let program = format!("#! mrasm\n{}\n", inout);
+                match AsmParser::parse(&program) {
+                    Err(ParserError::ConstantOutOfRange(constant, 2)) => assert_eq!(&constant, val),
+                    other => panic!("Unexpected result for {}: {:?}", inout, other),
+                }
             }
         }
     }
+
+    #[test]
+    fn malformed_programs_do_not_panic(body in "[ -~\t\n]{0,64}") {
+        let _ = AsmParser::parse(&format!("#! mrasm\n{}", body));
+    }
+
+    #[test]
+    fn malformed_instructions_do_not_panic(
+        tokens in prop::collection::vec(prop::sample::select(vec![
+            "MOV", "LD", "ST", "DEC", "BITT", "JR", ".ORG", ".ALIGN", ".DB", ".DW", "*STACKSIZE",
+            "R0", "r3", "PC", "SP", "R4", "(", ")", "+", ",", ", ", " ", "\t", ":", ";",
+            "0x", "0xFF", "0b1", "255", "256", "LABEL", "\n",
+        ]), 0..12)
+    ) {
+        let _ = AsmParser::parse(&format!("#! mrasm\nLABEL:\n{}\n", tokens.concat()));
+    }
+}
+
+#[test]
+fn overflowing_numbers_are_reported() {
+    let mut pairs = <AsmParser as Parser<Rule>>::parse(Rule::word_hex, "0x1FF").unwrap();
+    let pair = pairs.next().unwrap();
+    assert_eq!(super::parse_number::<u16>(pair.clone()).unwrap(), 0x1FF);
+    match super::parse_number::<u8>(pair) {
+        Err(ParserError::ConstantOutOfRange(constant, 1)) => assert_eq!(constant, "0x1FF"),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn out_of_range_constants_are_reported() {
+    for (line, constant) in &[
+        (".ORG 0x100", "0x100"),
+        (".ORG 999", "999"),
+        (".BYTE 300", "300"),
+        ("    LD R0, 0b100000000", "0b100000000"),
+        (".DB 1, 0x1FF ; comment", "0x1FF"),
+        ("    MOV (0x0100), R0", "0x0100"),
+        (".EQU X 300", "300"),
+        ("*PROGRAMSIZE 300", "300"),
+        (".DW 0x10000", "0x10000"),
+        (".DW 1, 65536", "65536"),
+    ] {
+        let program = format!("#! mrasm\nSTART:\n{}\n", line);
+        match AsmParser::parse(&program) {
+            Err(e @ ParserError::ConstantOutOfRange(_, _)) => {
+                assert_eq!(
+                    e.to_string(),
+                    format!("The constant '{}' in line 3 is out of range", constant)
+                );
+            }
+            other => panic!("Unexpected result for {}: {:?}", line, other),
+        }
+    }
+    // The largest constants are still fine
+    let program = "#! mrasm\n.ORG 0xFF\n.BYTE 0b011111111\n    LD R0, 00255\n.EQU X 255\n*PROGRAMSIZE 255\n.DW 0xFFFF, 65535\n";
+    AsmParser::parse(program).expect("Parsing failed");
+}
+
+#[test]
+fn mismatched_register_rules_are_reported() {
+    let mut pairs = <AsmParser as Parser<Rule>>::parse(Rule::constant_dec, "42").unwrap();
+    let pair = pairs.next().unwrap();
+    match super::parse_register(pair) {
+        Err(ParserError::InvalidRegister(register, line)) => {
+            assert_eq!(register, "42");
+            assert_eq!(line, 1);
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
 }
 
 // ======================================================================
@@ -366,6 +439,96 @@ fn test_header() {
     parse_err!(header, "\t#! mrasm");
 }
 
+#[test]
+fn missing_shebangs_are_reported() {
+    for program in &["", "\n", "    INC R0\n", "; A comment\n#! mrasm\n"] {
+        match AsmParser::parse(program) {
+            Err(e @ ParserError::MissingShebang(_)) => {
+                assert!(e.to_string().contains("'#! mrasm'"))
+            }
+            res => panic!("Unexpected result for {:?}: {:?}", program, res),
+        }
+    }
+}
+
+#[test]
+fn misspelled_shebangs_are_reported() {
+    for program in &["#!mrasm\n", "#! masm\n", "#! mrasm2\n", " #! mrasm\n"] {
+        match AsmParser::parse(program) {
+            Err(e @ ParserError::MissingShebang(_)) => {
+                assert!(e.to_string().contains("'#! mrasm'"))
+            }
+            res => panic!("Unexpected result for {:?}: {:?}", program, res),
+        }
+    }
+    // Trailing spaces, comments and CRLF line endings are fine
+    assert!(AsmParser::parse("#! mrasm  ; comment\n").is_ok());
+    assert!(AsmParser::parse("#! mrasm\r\n    INC R0\r\n").is_ok());
+}
+
+#[test]
+fn warnings_are_collected() {
+    let program = r#"#! mrasm
+        .EQU UNUSED_CONSTANT 18
+        .EQU LIMIT 32
+        CLR R0
+    LOOP:
+        INC R0
+        CMP R0, LIMIT
+        JZC loop
+    UNUSED:
+    loop:
+        STOP
+    "#;
+    let (asm, warnings) = AsmParser::parse_with_warnings(program).expect("Parsing failed");
+    assert_eq!(asm, AsmParser::parse(program).unwrap());
+    assert_eq!(
+        warnings,
+        vec![
+            ParserWarning::UnusedLabel("UNUSED_CONSTANT".into()),
+            ParserWarning::UnusedLabel("UNUSED".into()),
+            ParserWarning::DuplicateLabel("loop".into()),
+        ]
+    );
+    assert_eq!(
+        warnings[2].to_string(),
+        "The label 'loop' is defined more than once"
+    );
+    // Errors still take precedence
+    assert!(AsmParser::parse_with_warnings("#! mrasm\n    JR NOWHERE\n").is_err());
+}
+
+#[test]
+fn instructions_and_labels_skip_other_lines() {
+    use crate::parser::{Instruction, Line, Register};
+    let program = r#"#! mrasm
+        .EQU LIMIT 32
+    ; Count to the limit
+    START:
+        CLR R0
+
+    LOOP:   ; Here we go
+        INC R0
+        CMP R0, LIMIT
+        JZC LOOP
+    "#;
+    let asm = AsmParser::parse(program).expect("Parsing failed");
+    let instructions: Vec<_> = asm.instructions().map(Instruction::mnemonic).collect();
+    assert_eq!(instructions, vec![".EQU", "CLR", "INC", "CMP", "JZC"]);
+    assert_eq!(
+        asm.instructions().nth(2),
+        Some(&Instruction::Inc(Register::R0))
+    );
+    let labels: Vec<_> = asm
+        .labels()
+        .map(|(label, idx)| (label.as_str(), idx))
+        .collect();
+    assert_eq!(labels, vec![("START", 2), ("LOOP", 5)]);
+    for (label, idx) in asm.labels() {
+        assert!(matches!(&asm.lines[idx], Line::Label(l, _) if l == label));
+    }
+}
+
 #[test]
 fn test_org() {
     use Rule::org;
@@ -376,6 +539,26 @@ fn test_org() {
     parse_err!(org, ".org20");
 }
 
+#[test]
+fn test_align() {
+    use Rule::align;
+    parse!(align, ".ALIGN 8");
+    parse!(align, ".align 0x80");
+    parse!(align, ".ALIGN\t0b10");
+    parse_err!(align, ".align8");
+    for valid in &["1", "2", "16", "0x80"] {
+        let program = format!("#! mrasm\n.ALIGN {}\n", valid);
+        AsmParser::parse(&program).expect("Parsing failed");
+    }
+    for (invalid, alignment) in &[("0", 0), ("3", 3), ("0xFF", 255), ("96", 96)] {
+        let program = format!("#! mrasm\nSTOP\n.ALIGN {}\n", invalid);
+        match AsmParser::parse(&program) {
+            Err(ParserError::InvalidAlignment(found, 3)) => assert_eq!(found, *alignment),
+            other => panic!("Unexpected result for {}: {:?}", invalid, other),
+        }
+    }
+}
+
 #[test]
 fn test_byte() {
     use Rule::byte;
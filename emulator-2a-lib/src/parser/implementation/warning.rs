@@ -0,0 +1,29 @@
+use std::fmt;
+
+use super::Label;
+
+/// Suspicious, but valid constructs found while parsing assembly.
+///
+/// Unlike a [`ParserError`](super::ParserError), a warning does not
+/// prevent the program from being compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParserWarning {
+    /// A label was defined more than once.
+    /// This is reported for every definition after the first one.
+    DuplicateLabel(Label),
+    /// A label was defined, but never referenced.
+    UnusedLabel(Label),
+}
+
+impl fmt::Display for ParserWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParserWarning::DuplicateLabel(label) => {
+                write!(f, "The label '{}' is defined more than once", label)
+            }
+            ParserWarning::UnusedLabel(label) => {
+                write!(f, "The label '{}' is never used", label)
+            }
+        }
+    }
+}
@@ -27,4 +27,5 @@ mod ast;
 mod implementation;
 
 pub use ast::*;
-pub use implementation::{AsmParser, ParserError};
+pub(crate) use implementation::{defined_label, referenced_labels};
+pub use implementation::{AsmParser, ParserError, ParserWarning};
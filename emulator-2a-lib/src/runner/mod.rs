@@ -1,34 +1,106 @@
 use derive_builder::Builder;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use thiserror::Error;
 
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
     marker::PhantomData,
     time::{Duration, Instant},
 };
 
 use crate::{
-    compiler::Translator,
-    machine::{Machine, MachineConfig, State},
+    compiler::{CompileError, Translator},
+    machine::{
+        Machine, MachineConfig, RegisterNumber, State, CLOCK_FREQUENCY, OPCODE_CALL, OPCODE_RET,
+        RAM_SIZE,
+    },
     parser::{AsmParser, ParserError},
 };
 
+/// Number of cycles between two checks of [`RunnerConfig::max_time`].
+pub const TIME_CHECK_INTERVAL: usize = 4096;
+
 #[derive(Debug, Builder, Clone, PartialEq)]
-#[builder(setter(prefix = "with"))]
+#[builder(setter(prefix = "with"), build_fn(validate = "Self::validate"))]
 pub struct RunnerConfig<'a> {
     /// Maximum number of cycles to emulate.
     pub max_cycles: usize,
+    /// Maximum wall-clock time the emulation may take.
+    ///
+    /// The time is only checked every [`TIME_CHECK_INTERVAL`] cycles,
+    /// the limit may thus be exceeded slightly.
+    #[builder(default, setter(strip_option))]
+    pub max_time: Option<Duration>,
     /// Configuration for the machine.
     /// The machine will be initialized with this configuration.
     #[builder(default)]
     pub machine_config: MachineConfig,
     /// Program to run on the machine.
     pub program: &'a str,
+    /// Bytes to write to the given addresses before the emulation starts.
+    ///
+    /// These are written in order after the compiled program was loaded,
+    /// they thus override any bytes placed by the program, including
+    /// `.ORG`, `.DB` and `.DW` output. Addresses from `0xF0` upwards are
+    /// written to the I/O registers, just like a `ST` instruction would.
+    /// Preloaded output registers do not count as written by the program,
+    /// see [`RunResults::output_fe_written`].
+    #[builder(default, setter(into))]
+    pub preload: Vec<(u8, u8)>,
+    /// Address at which the first byte of the compiled program is placed.
+    ///
+    /// The program counter starts at this address, which must lie within the RAM.
+    /// See [`Machine::load_at`] for the interaction with `.ORG`. The whole
+    /// program must fit into the RAM, otherwise the run fails with
+    /// [`RunnerError::ProgramTooLarge`].
+    #[builder(default)]
+    pub load_address: u8,
     /// A list of cycles at which to trigger a key edge interrupt.
     #[builder(default, setter(into))]
     pub interrupts: Vec<usize>,
+    /// A list of instants, relative to the start of the emulation, at which
+    /// to trigger a key edge interrupt.
+    ///
+    /// These are converted using [`cycle_at`] and add to [`RunnerConfig::interrupts`].
+    #[builder(default, setter(into))]
+    pub interrupt_times: Vec<Duration>,
+    /// Hold the bus level interrupt request line active during the whole emulation.
+    ///
+    /// See [`Machine::set_level_interrupt`].
+    #[builder(default)]
+    pub level_interrupt: bool,
     /// A list of cycles at which to trigger a cpu reset.
     #[builder(default, setter(into))]
     pub resets: Vec<usize>,
+    /// A list of instants, relative to the start of the emulation, at which
+    /// to trigger a cpu reset.
+    ///
+    /// These are converted using [`cycle_at`] and add to [`RunnerConfig::resets`].
+    #[builder(default, setter(into))]
+    pub reset_times: Vec<Duration>,
+    /// Memory faults to inject, given as the cycle, the address and a mask.
+    ///
+    /// At the given cycle, the byte at the address is XORed with the mask,
+    /// flipping the set bits. This is a teaching and testing aid for error
+    /// detection, the real machine does not corrupt its memory. Only the RAM
+    /// is affected, faults at addresses from `0xF0` upwards are ignored.
+    #[builder(default, setter(into))]
+    pub faults: Vec<(usize, u8, u8)>,
+    /// Stop the emulation early, if the machine state did not change
+    /// during one complete instruction.
+    ///
+    /// Such a stall is only detected when no further interrupts or resets
+    /// are scheduled, as these could still change the outcome.
+    #[builder(default)]
+    pub detect_stalls: bool,
+    /// Record the values written to the output registers during the emulation.
+    ///
+    /// See [`Bus::record_output_history`](crate::machine::Bus::record_output_history).
+    /// Writes by [`RunnerConfig::preload`] are not recorded.
+    #[builder(default)]
+    pub record_output_history: bool,
     /// Prevent the manual creation of this struct for the purpose of extension
     #[builder(setter(skip), default)]
     _phantom: PhantomData<u8>,
@@ -47,10 +119,61 @@ pub struct RunResults<'a> {
     /// Reference to the configuration that was used to
     /// generate this result.
     pub config: &'a RunnerConfig<'a>,
+    /// Did the emulation stop early, because the machine stalled?
+    /// This is only ever `true` if [`RunnerConfig::detect_stalls`] was enabled.
+    pub stalled: bool,
+    /// Did the emulation stop early, because [`RunnerConfig::max_time`]
+    /// was exceeded?
+    pub timed_out: bool,
+    /// Did the machine error-stop at any point during the emulation?
+    ///
+    /// This stays `true`, even if a later reset revived the machine.
+    pub error_stopped: bool,
+    /// Did the program write the output register FE at any point?
+    pub output_fe_written: bool,
+    /// Did the program write the output register FF at any point?
+    pub output_ff_written: bool,
+    /// Subroutines that returned with a different stackpointer than before their call.
+    ///
+    /// Only `CALL`/`RET` pairs are tracked, in the order of the returns.
+    pub stack_imbalances: Vec<StackImbalance>,
+    /// Source lines that were executed at least once.
+    covered_lines: BTreeSet<usize>,
+    /// Source lines containing instructions executable by the CPU.
+    executable_lines: BTreeSet<usize>,
     /// Prevent the manual creation of this struct for the purpose of extension
     _phantom: PhantomData<u8>,
 }
 
+/// A subroutine that did not leave the stack as it found it.
+///
+/// See [`RunResults::stack_imbalances`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackImbalance {
+    /// Address of the `CALL` instruction.
+    pub call_site: u8,
+    /// Stackpointer before the `CALL`.
+    pub expected_sp: u8,
+    /// Stackpointer after the matching `RET`.
+    pub found_sp: u8,
+}
+
+/// Possible failures while preparing a run.
+#[derive(Debug, Error)]
+pub enum RunnerError {
+    /// The program could not be parsed.
+    #[error("{0}")]
+    Parsing(#[from] ParserError),
+    /// The parsed program could not be compiled.
+    #[error("{0}")]
+    Compilation(#[from] CompileError),
+    /// The compiled program does not fit into the RAM at the load address.
+    #[error(
+        "The program of {size} bytes does not fit into the memory when loaded at 0x{address:02X}"
+    )]
+    ProgramTooLarge { address: u8, size: usize },
+}
+
 #[derive(Debug, Error)]
 pub enum VerificationError {
     #[error("State == {found:?} != {expected:?}")]
@@ -59,6 +182,26 @@ pub enum VerificationError {
     OutputFeMismatch { expected: u8, found: u8 },
     #[error("Output Register FF == {found} != {expected}")]
     OutputFfMismatch { expected: u8, found: u8 },
+    #[error("Output Register FF sequence {found:?} != {expected:?}")]
+    OutputFfSequenceMismatch { expected: Vec<u8>, found: Vec<u8> },
+    #[error("Machine error-stopped during the run")]
+    ErrorStopped,
+    #[error(
+        "Subroutine called at 0x{:02X} returned with SP == 0x{:02X} != 0x{:02X}",
+        .0.call_site,
+        .0.found_sp,
+        .0.expected_sp
+    )]
+    UnbalancedSubroutine(StackImbalance),
+}
+
+/// Suspicious, but non-fatal findings of [`RunExpectations::warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationWarning {
+    /// FE is verified, but the program never wrote it.
+    OutputFeNeverWritten,
+    /// FF is verified, but the program never wrote it.
+    OutputFfNeverWritten,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default, Builder)]
@@ -70,46 +213,295 @@ pub struct RunExpectations {
     output_fe: Option<u8>,
     /// Expected output register FF
     output_ff: Option<u8>,
+    /// Expected sequence of distinct values written to FF.
+    /// This requires [`RunnerConfig::record_output_history`].
+    output_ff_sequence: Option<Vec<u8>>,
+    /// Expect the machine to never error-stop during the whole run.
+    never_error_stopped: bool,
+    /// Expect every subroutine to restore the stackpointer before returning.
+    /// See [`RunResults::stack_imbalances`].
+    balanced_subroutines: bool,
+}
+
+impl<'a> RunnerConfigBuilder<'a> {
+    /// Reject load addresses outside of the RAM.
+    fn validate(&self) -> Result<(), String> {
+        match self.load_address {
+            Some(address) if address as usize >= RAM_SIZE => Err(format!(
+                "The load address 0x{:02X} lies outside of the RAM",
+                address
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<'a> RunnerConfig<'a> {
     /// Execute the runner.
     ///
     /// This executes the runner and checks all verifications.
-    pub fn run(&self) -> Result<RunResults, ParserError> {
+    pub fn run(&self) -> Result<RunResults, RunnerError> {
+        self.run_with_trace(|_, _| ())
+    }
+
+    /// Execute the runner, calling `on_cycle` after every emulated cycle.
+    ///
+    /// The callback receives the number of cycles emulated so far and the
+    /// machine in the state after the cycle. This can be used to record
+    /// an execution trace.
+    pub fn run_with_trace<F>(&self, mut on_cycle: F) -> Result<RunResults<'_>, RunnerError>
+    where
+        F: FnMut(usize, &Machine),
+    {
         // Prepare the machine
         let parsed = AsmParser::parse(self.program)?;
-        let bytecode = Translator::compile(&parsed);
-        let mut machine = Machine::new_with_program(self.machine_config.clone(), bytecode);
+        let bytecode = Translator::compile(&parsed)?;
+        let size = bytecode.bytes().count();
+        if self.load_address as usize + size > RAM_SIZE {
+            return Err(RunnerError::ProgramTooLarge {
+                address: self.load_address,
+                size,
+            });
+        }
+        let executable_lines = bytecode.executable_source_lines();
+        let mut machine = Machine::new_with_program_at(
+            self.machine_config.clone(),
+            bytecode.clone(),
+            self.load_address,
+        );
+        for &(addr, byte) in &self.preload {
+            machine.raw_mut().bus_mut().write(addr, byte);
+        }
+        machine.raw_mut().bus_mut().forget_output_writes();
+        machine
+            .raw_mut()
+            .bus_mut()
+            .record_output_history(self.record_output_history);
+        machine.set_level_interrupt(self.level_interrupt);
         // Initialize variables
+        let interrupts = with_cycles_at(&self.interrupts, &self.interrupt_times);
+        let resets = with_cycles_at(&self.resets, &self.reset_times);
+        let fault_cycles: Vec<_> = self.faults.iter().map(|&(cycle, _, _)| cycle).collect();
         let before_emulation = Instant::now();
         let mut emulated_cycles = 0;
+        let mut executed_addresses = [false; 0x100];
+        let mut stalled = false;
+        let mut timed_out = false;
+        let mut error_stopped = false;
+        let mut last_instruction_done: Option<Machine> = None;
+        let mut was_instruction_done = false;
+        // Call sites and stackpointers of the active subroutines
+        let mut calls: Vec<(u8, u8)> = vec![];
+        let mut returning = false;
+        let mut stack_imbalances = vec![];
         // RUN!
         while emulated_cycles < self.max_cycles {
             // Prerequisites for the cycle
-            if self.interrupts.contains(&emulated_cycles) {
+            if interrupts.contains(&emulated_cycles) {
                 machine.trigger_key_interrupt();
             }
-            if self.resets.contains(&emulated_cycles) {
+            if resets.contains(&emulated_cycles) {
                 machine.cpu_reset();
+                calls.clear();
+                returning = false;
+            }
+            for &(_, addr, mask) in self.faults.iter().filter(|f| f.0 == emulated_cycles) {
+                if let Some(byte) = machine
+                    .raw_mut()
+                    .bus_mut()
+                    .memory_mut()
+                    .get_mut(addr as usize)
+                {
+                    *byte ^= mask;
+                }
             }
             // Trigger the next cycle
             machine.trigger_key_clock();
             emulated_cycles += 1;
-            // Bail if possible
-            if machine.state() != State::Running {
+            on_cycle(emulated_cycles, &machine);
+            // Once an instruction is done, the PC points to the next instruction
+            let is_instruction_done = machine.is_instruction_done();
+            if is_instruction_done {
+                let pc = *machine.registers().get(RegisterNumber::R3);
+                executed_addresses[pc as usize] = true;
+            }
+            // Memory waits may repeat the fetch, only look at the first cycle of it
+            let starts_instruction = is_instruction_done && !was_instruction_done;
+            if starts_instruction {
+                let pc = *machine.registers().get(RegisterNumber::R3);
+                let sp = *machine.registers().get(RegisterNumber::R5);
+                if returning {
+                    returning = false;
+                    if let Some((call_site, expected_sp)) = calls.pop() {
+                        if sp != expected_sp {
+                            stack_imbalances.push(StackImbalance {
+                                call_site,
+                                expected_sp,
+                                found_sp: sp,
+                            });
+                        }
+                    }
+                }
+                match machine.bus().read(pc) {
+                    OPCODE_CALL => calls.push((pc, sp)),
+                    OPCODE_RET => returning = true,
+                    _ => {}
+                }
+            }
+            if self.detect_stalls
+                && starts_instruction
+                && !has_events_after(&interrupts, emulated_cycles)
+                && !has_events_after(&resets, emulated_cycles)
+                && !has_events_after(&fault_cycles, emulated_cycles)
+            {
+                if last_instruction_done.as_ref() == Some(&machine) {
+                    stalled = true;
+                    break;
+                }
+                last_instruction_done = Some(machine.clone());
+            }
+            was_instruction_done = is_instruction_done;
+            if machine.state() == State::ErrorStopped {
+                error_stopped = true;
+            }
+            // Bail if possible, a scheduled reset revives a halted machine
+            if machine.state() != State::Running && !has_events_after(&resets, emulated_cycles) {
                 break;
             }
+            if let Some(max_time) = self.max_time {
+                if emulated_cycles % TIME_CHECK_INTERVAL == 0
+                    && before_emulation.elapsed() >= max_time
+                {
+                    timed_out = true;
+                    break;
+                }
+            }
         }
+        let time_taken = before_emulation.elapsed();
+        let covered_lines = (0..=u8::MAX)
+            .filter(|&addr| executed_addresses[addr as usize])
+            .filter_map(|addr| addr.checked_sub(self.load_address))
+            .filter_map(|addr| bytecode.source_line_at(addr))
+            .filter(|line| executable_lines.contains(line))
+            .collect();
+        let output_fe_written = machine.bus().was_output_fe_written();
+        let output_ff_written = machine.bus().was_output_ff_written();
         // Assemble results
         Ok(RunResults {
             config: self,
-            time_taken: before_emulation.elapsed(),
+            time_taken,
             emulated_cycles,
             machine,
+            stalled,
+            timed_out,
+            error_stopped,
+            output_fe_written,
+            output_ff_written,
+            stack_imbalances,
+            covered_lines,
+            executable_lines,
             _phantom: PhantomData,
         })
     }
+
+    /// Execute the runner, keeping a copy of the machine after each of the given cycles.
+    ///
+    /// The returned map contains the machine in the state after the given
+    /// number of cycles was emulated. Cycles that were never reached, because
+    /// the emulation stopped early, are missing from the map.
+    ///
+    /// Every checkpoint is a full clone of the [`Machine`], including its
+    /// memory and program. Requesting many checkpoints for long runs thus
+    /// costs a noticeable amount of memory.
+    pub fn run_with_checkpoints(
+        &self,
+        checkpoints: &BTreeSet<usize>,
+    ) -> Result<(RunResults<'_>, BTreeMap<usize, Machine>), RunnerError> {
+        let mut snapshots = BTreeMap::new();
+        let results = self.run_with_trace(|cycle, machine| {
+            if checkpoints.contains(&cycle) {
+                snapshots.insert(cycle, machine.clone());
+            }
+        })?;
+        Ok((results, snapshots))
+    }
+}
+
+/// Get the cycle that starts at the given instant of the emulation,
+/// assuming the nominal [`CLOCK_FREQUENCY`].
+///
+/// Instants between two cycles are rounded up to the next cycle, the
+/// event thus never happens earlier than requested. Cycle `n` starts
+/// `n / CLOCK_FREQUENCY` seconds after the start of the emulation.
+///
+/// # Example
+///
+/// ```
+/// # use emulator_2a_lib::runner::cycle_at;
+/// # use std::time::Duration;
+/// // One millisecond equals 7372.8 cycles
+/// assert_eq!(cycle_at(Duration::from_millis(1)), 7_373);
+/// assert_eq!(cycle_at(Duration::from_secs(1)), 7_372_800);
+/// assert_eq!(cycle_at(Duration::from_secs(0)), 0);
+/// ```
+pub fn cycle_at(time: Duration) -> usize {
+    const NANOS_PER_SECOND: u128 = 1_000_000_000;
+    let cycles = time.as_nanos() * CLOCK_FREQUENCY as u128;
+    cycles.div_ceil(NANOS_PER_SECOND) as usize
+}
+
+/// Merge the `cycles` with the cycles of the given instants.
+fn with_cycles_at(cycles: &[usize], times: &[Duration]) -> Vec<usize> {
+    let mut cycles = cycles.to_vec();
+    cycles.extend(times.iter().copied().map(cycle_at));
+    cycles
+}
+
+/// Are any of the events scheduled after the given cycle?
+fn has_events_after(events: &[usize], cycle: usize) -> bool {
+    events.iter().any(|&event| event >= cycle)
+}
+
+/// Execute all given runners.
+///
+/// Every run is independent of the others, a failing run does not
+/// influence the remaining ones. The results are returned in the same
+/// order as the given configurations.
+///
+/// With the `parallel` feature enabled, the runs are executed
+/// concurrently using [rayon](https://docs.rs/rayon).
+pub fn run_batch<'a>(configs: &'a [RunnerConfig<'a>]) -> Vec<Result<RunResults<'a>, RunnerError>> {
+    #[cfg(feature = "parallel")]
+    {
+        configs.par_iter().map(RunnerConfig::run).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        configs.iter().map(RunnerConfig::run).collect()
+    }
+}
+
+impl<'a> RunResults<'a> {
+    /// Get the source lines that were executed at least once.
+    ///
+    /// Line numbers start at one, the first line contains the `#! mrasm` header.
+    pub fn covered_lines(&self) -> &BTreeSet<usize> {
+        &self.covered_lines
+    }
+    /// Get the source lines containing instructions executable by the CPU.
+    ///
+    /// Assembler directives like `.ORG` are not included.
+    pub fn executable_lines(&self) -> &BTreeSet<usize> {
+        &self.executable_lines
+    }
+    /// Get the executable source lines that were never executed.
+    pub fn uncovered_lines(&self) -> BTreeSet<usize> {
+        self.executable_lines
+            .difference(&self.covered_lines)
+            .cloned()
+            .collect()
+    }
 }
 
 impl RunExpectations {
@@ -119,24 +511,72 @@ impl RunExpectations {
                 expected: self.state.unwrap(),
                 found: result.machine.state(),
             })
-        } else if self.output_fe.is_some()
-            && self.output_fe != Some(result.machine.bus().output_fe())
-        {
+        } else if self.never_error_stopped && result.error_stopped {
+            Err(VerificationError::ErrorStopped)
+        } else if self.output_fe.is_some() && self.output_fe != Some(result.machine.output_fe()) {
             Err(VerificationError::OutputFeMismatch {
                 expected: self.output_fe.unwrap(),
-                found: result.machine.bus().output_fe(),
+                found: result.machine.output_fe(),
             })
-        } else if self.output_ff.is_some()
-            && self.output_ff != Some(result.machine.bus().output_ff())
-        {
+        } else if self.output_ff.is_some() && self.output_ff != Some(result.machine.output_ff()) {
             Err(VerificationError::OutputFfMismatch {
                 expected: self.output_ff.unwrap(),
-                found: result.machine.bus().output_ff(),
+                found: result.machine.output_ff(),
             })
+        } else if let Some(&imbalance) = self
+            .balanced_subroutines
+            .then(|| result.stack_imbalances.first())
+            .flatten()
+        {
+            Err(VerificationError::UnbalancedSubroutine(imbalance))
+        } else if let Some(ref expected) = self.output_ff_sequence {
+            let found: Vec<u8> = result
+                .machine
+                .bus()
+                .output_ff_history()
+                .iter()
+                .map(|&(_, byte)| byte)
+                .collect();
+            if found == *expected {
+                Ok(())
+            } else {
+                Err(VerificationError::OutputFfSequenceMismatch {
+                    expected: expected.clone(),
+                    found,
+                })
+            }
         } else {
             Ok(())
         }
     }
+
+    /// Find expectations that are likely mistakes.
+    ///
+    /// Verifying an output register that the program never wrote
+    /// only ever compares against its reset value.
+    pub fn warnings(&self, result: &RunResults) -> Vec<VerificationWarning> {
+        let mut warnings = vec![];
+        if self.output_fe.is_some() && !result.output_fe_written {
+            warnings.push(VerificationWarning::OutputFeNeverWritten);
+        }
+        if self.output_ff.is_some() && !result.output_ff_written {
+            warnings.push(VerificationWarning::OutputFfNeverWritten);
+        }
+        warnings
+    }
+}
+
+impl fmt::Display for VerificationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationWarning::OutputFeNeverWritten => {
+                write!(f, "FE was never written by the program")
+            }
+            VerificationWarning::OutputFfNeverWritten => {
+                write!(f, "FF was never written by the program")
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -165,6 +605,85 @@ mod tests {
         expectations.verify(&res).expect("Verification failed");
     }
 
+    #[test]
+    fn output_sequences_are_verified() {
+        let program = r#"#! mrasm
+                LD R0, 1
+                ST (0xFF), R0
+                INC R0
+                ST (0xFF), R0
+                ST (0xFF), R0
+                INC R0
+                ST (0xFF), R0
+                STOP
+        "#;
+        let mut builder = RunnerConfigBuilder::default();
+        builder.with_max_cycles(1_000).with_program(program);
+        let unrecorded = builder.build().unwrap();
+        let res = unrecorded.run().expect("Parsing failed");
+        assert!(res.machine.bus().output_ff_history().is_empty());
+        let config = builder.with_record_output_history(true).build().unwrap();
+        let res = config.run().expect("Parsing failed");
+        let history = res.machine.bus().output_ff_history();
+        let values: Vec<u8> = history.iter().map(|&(_, byte)| byte).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+        assert!(history.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(history
+            .iter()
+            .all(|&(cycle, _)| cycle <= res.emulated_cycles));
+        assert!(res.machine.bus().output_fe_history().is_empty());
+        let expect = |sequence: Vec<u8>| {
+            RunExpectationsBuilder::default()
+                .expect_output_ff_sequence(sequence)
+                .build()
+                .unwrap()
+                .verify(&res)
+        };
+        expect(vec![1, 2, 3]).expect("Verification failed");
+        assert!(matches!(
+            expect(vec![1, 3]),
+            Err(VerificationError::OutputFfSequenceMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_and_compile_errors_are_distinguished() {
+        let run = |program| {
+            RunnerConfigBuilder::default()
+                .with_max_cycles(100)
+                .with_program(program)
+                .build()
+                .unwrap()
+                .run()
+                .err()
+        };
+        assert!(matches!(
+            run("#! mrasm\n    FOO R0\n"),
+            Some(RunnerError::Parsing(ParserError::InvalidSyntax(_)))
+        ));
+        assert!(matches!(
+            run("#! mrasm\n    .ORG 0xFF\n    STOP\n    NOP\n"),
+            Some(RunnerError::Compilation(CompileError::AddressOverflow(_)))
+        ));
+    }
+
+    #[test]
+    fn programs_reaching_the_last_address_are_not_run() {
+        let config = |program| {
+            RunnerConfigBuilder::default()
+                .with_max_cycles(100)
+                .with_program(program)
+                .build()
+                .unwrap()
+        };
+        let fitting = config("#! mrasm\n    .ORG 0xEF\n    STOP\n");
+        assert!(fitting.run().is_ok());
+        assert!(matches!(
+            config("#! mrasm\n    .ORG 0xFF\n    STOP\n").run(),
+            Err(RunnerError::Compilation(CompileError::AddressOverflow(line))) if line == "STOP"
+        ));
+    }
+
     #[test]
     fn runner_resets_work_correctly() {
         let program = r#"#! mrasm
@@ -243,4 +762,519 @@ mod tests {
             .unwrap();
         expectations.verify(&res).expect("Verification failed");
     }
+
+    #[test]
+    fn active_level_interrupts_reenter_the_isr() {
+        let program = r#"#! mrasm
+                JR MAIN
+                JR ISR
+            MAIN:
+                LDSP 0xEF
+                BITS (0xF9), 0x10
+                EI
+            LOOP:
+                JR LOOP
+            ISR:
+                INC R0
+                ST (0xFF), R0
+                CMP R0, 3
+                JZS DONE
+                RETI
+            DONE:
+                STOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(10_000)
+            .with_program(program)
+            .with_level_interrupt(true)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        let expectations = RunExpectationsBuilder::default()
+            .expect_state(State::Stopped)
+            .expect_output_ff(3)
+            .build()
+            .unwrap();
+        expectations.verify(&res).expect("Verification failed");
+    }
+
+    #[test]
+    fn interrupts_can_be_given_as_instants() {
+        use crate::machine::MISR;
+        let program = r#"#! mrasm
+                JR MAIN
+                JR ISR
+            MAIN:
+                LDSP 0xEF
+                BITS (0xF9), 1
+                EI
+            LOOP:
+                JR LOOP
+            ISR:
+                STOP
+        "#;
+        // Number of the first cycle after which the key interrupt is pending
+        let first_pending = |builder: &mut RunnerConfigBuilder| {
+            let config = builder
+                .with_max_cycles(10_000)
+                .with_program(program)
+                .build()
+                .unwrap();
+            let mut first = None;
+            config
+                .run_with_trace(|cycle, machine| {
+                    let pending = machine.bus().misr().contains(MISR::KEY_INTERRUPT_PENDING);
+                    if pending && first.is_none() {
+                        first = Some(cycle);
+                    }
+                })
+                .expect("Parsing failed");
+            first
+        };
+        // 1ms equals 7372.8 cycles, which is rounded up
+        let at_time = first_pending(
+            RunnerConfigBuilder::default().with_interrupt_times([Duration::from_millis(1)]),
+        );
+        assert_eq!(at_time, Some(7_374));
+        let at_cycle = first_pending(RunnerConfigBuilder::default().with_interrupts([7_373]));
+        assert_eq!(at_time, at_cycle);
+    }
+
+    #[test]
+    fn covered_lines_are_recorded() {
+        let program = r#"#! mrasm
+                .ORG 0
+                MOV R0, 1
+                DEC R0
+                JZS SKIP
+                INC R0
+            SKIP:
+                ST (0xFF), R0
+                STOP
+                DEC R0
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert_eq!(res.machine.state(), State::Stopped);
+        let executable: BTreeSet<usize> = [3, 4, 5, 6, 8, 9, 10].iter().cloned().collect();
+        assert_eq!(res.executable_lines(), &executable);
+        let covered: BTreeSet<usize> = [3, 4, 5, 8, 9].iter().cloned().collect();
+        assert_eq!(res.covered_lines(), &covered);
+        let uncovered: BTreeSet<usize> = [6, 10].iter().cloned().collect();
+        assert_eq!(res.uncovered_lines(), uncovered);
+    }
+
+    #[test]
+    fn preloaded_tables_are_readable() {
+        let program = r#"#! mrasm
+                MOV R0, 0x82
+                LD R1, (R0)
+                ST (0xFF), R1
+                LD R1, (0x90)
+                ST (0xFE), R1
+                STOP
+                .ORG 0x90
+                .DB 0x11
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .with_preload([(0x80, 1), (0x81, 4), (0x82, 9), (0x83, 16), (0x90, 0x22)])
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert_eq!(res.machine.state(), State::Stopped);
+        assert_eq!(res.machine.output_ff(), 9);
+        // Preloaded bytes override the program
+        assert_eq!(res.machine.output_fe(), 0x22);
+    }
+
+    #[test]
+    fn injected_faults_flip_memory_bits() {
+        let program = r#"#! mrasm
+            START:
+                LD R0, (0x90)
+                ST (0xFF), R0
+            HALT:
+                JR HALT
+                .ORG 0x90
+                .DB 0x11
+        "#;
+        let run = |faults: Vec<(usize, u8, u8)>| {
+            let config = RunnerConfigBuilder::default()
+                .with_max_cycles(200)
+                .with_program(program)
+                .with_detect_stalls(true)
+                .with_faults(faults)
+                .build()
+                .unwrap();
+            let res = config.run().expect("Parsing failed");
+            (res.machine.output_ff(), res.stalled)
+        };
+        assert_eq!(run(vec![]), (0x11, true));
+        // Redirect `JR HALT` to START by flipping its offset 0xFE to 0xF8.
+        // Scheduled faults are not mistaken for a stall
+        assert_eq!(
+            run(vec![(100, 0x07, 0x06), (100, 0x90, 0x81)]),
+            (0x90, false)
+        );
+        // Faults at the same address accumulate, the I/O registers are not touched
+        let faults = vec![
+            (50, 0x90, 0x01),
+            (100, 0x07, 0x06),
+            (90, 0x90, 0x03),
+            (95, 0xFF, 0xFF),
+        ];
+        assert_eq!(run(faults), (0x13, false));
+    }
+
+    #[test]
+    fn transient_error_stops_are_remembered() {
+        let program = r#"#! mrasm
+                LD R0, (0x80)   ; RAM survives the reset
+                INC R0
+                ST (0x80), R0
+                CMP R0, 1
+                JZS FAIL
+            LOOP:
+                JR LOOP
+            FAIL:
+                RET             ; No stackpointer, error-stop
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(200)
+            .with_program(program)
+            .with_resets([100])
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        // The reset revived the machine, which then kept running
+        assert!(res.error_stopped);
+        assert_eq!(res.machine.state(), State::Running);
+        assert_eq!(res.emulated_cycles, 200);
+        let expectations = RunExpectationsBuilder::default()
+            .expect_never_error_stopped(true)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            expectations.verify(&res),
+            Err(VerificationError::ErrorStopped)
+        ));
+        // Without the expectation, the run is fine
+        RunExpectations::default()
+            .verify(&res)
+            .expect("Verification failed");
+    }
+
+    #[test]
+    fn stalled_machines_are_detected() {
+        let program = r#"#! mrasm
+                INC R0
+                ST (0xFF), R0
+            LOOP:
+                JR LOOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(10_000)
+            .with_program(program)
+            .with_detect_stalls(true)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(res.stalled);
+        assert!(res.emulated_cycles < 100);
+        assert_eq!(res.machine.state(), State::Running);
+        assert_eq!(res.machine.output_ff(), 1);
+        // Without detection, the loop runs until the end
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(10_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(!res.stalled);
+        assert_eq!(res.emulated_cycles, 10_000);
+        // Pending interrupts prevent the detection
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(10_000)
+            .with_program(program)
+            .with_detect_stalls(true)
+            .with_interrupts([5_000])
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(res.stalled);
+        assert!(res.emulated_cycles > 5_000);
+    }
+
+    #[test]
+    fn traces_see_every_cycle() {
+        let program = r#"#! mrasm
+                INC R0
+                ST (0xFF), R0
+                STOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let mut cycles = vec![];
+        let mut last_output = 0;
+        let res = config
+            .run_with_trace(|cycle, machine| {
+                cycles.push(cycle);
+                last_output = machine.output_ff();
+            })
+            .expect("Parsing failed");
+        assert_eq!(cycles, (1..=res.emulated_cycles).collect::<Vec<_>>());
+        assert_eq!(last_output, 1);
+        let untraced = config.run().expect("Parsing failed");
+        assert_eq!(res.machine, untraced.machine);
+        assert_eq!(res.emulated_cycles, untraced.emulated_cycles);
+    }
+
+    #[test]
+    fn checkpoints_capture_intermediate_machines() {
+        let program = r#"#! mrasm
+            LOOP:
+                INC R0
+                ST (0xFF), R0
+                JR LOOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(100)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let checkpoints = [20, 80, 1_000].iter().cloned().collect();
+        let (res, snapshots) = config
+            .run_with_checkpoints(&checkpoints)
+            .expect("Parsing failed");
+        // The last checkpoint is never reached
+        assert_eq!(snapshots.keys().cloned().collect::<Vec<_>>(), vec![20, 80]);
+        let early = &snapshots[&20];
+        let late = &snapshots[&80];
+        assert_ne!(early, late);
+        assert!(early.output_ff() < late.output_ff());
+        assert!(!early.diff(late).is_empty());
+        // Checkpoints do not influence the run itself
+        assert_eq!(res.machine, config.run().expect("Parsing failed").machine);
+    }
+
+    #[test]
+    fn unwritten_output_registers_are_warned_about() {
+        let program = r#"#! mrasm
+                MOV R0, 0
+                ST (0xFE), R0
+                STOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(res.output_fe_written);
+        assert!(!res.output_ff_written);
+        let expectations = RunExpectationsBuilder::default()
+            .expect_output_fe(0)
+            .expect_output_ff(0)
+            .build()
+            .unwrap();
+        // Both expectations hold, but FF is only compared with its reset value
+        expectations.verify(&res).expect("Verification failed");
+        assert_eq!(
+            expectations.warnings(&res),
+            vec![VerificationWarning::OutputFfNeverWritten]
+        );
+        assert_eq!(
+            VerificationWarning::OutputFfNeverWritten.to_string(),
+            "FF was never written by the program"
+        );
+        assert!(RunExpectations::default().warnings(&res).is_empty());
+    }
+
+    #[test]
+    fn preloaded_outputs_are_not_written_by_the_program() {
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program("#! mrasm\n    STOP\n")
+            .with_preload([(0xFF, 42)])
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert_eq!(res.machine.output_ff(), 42);
+        assert!(!res.output_ff_written);
+        let expectations = RunExpectationsBuilder::default()
+            .expect_output_ff(42)
+            .build()
+            .unwrap();
+        assert_eq!(
+            expectations.warnings(&res),
+            vec![VerificationWarning::OutputFfNeverWritten]
+        );
+    }
+
+    #[test]
+    fn programs_can_be_loaded_at_an_offset() {
+        let program = r#"#! mrasm
+                MOV R0, 3
+            LOOP:
+                INC R1
+                DEC R0
+                JZC LOOP
+                ST (0xFF), R1
+                STOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .with_load_address(0x40)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Running failed");
+        let memory = res.machine.bus().memory();
+        assert!(memory[..0x40].iter().all(|&byte| byte == 0));
+        assert_ne!(memory[0x40], 0);
+        assert_eq!(res.machine.state(), State::Stopped);
+        assert_eq!(res.machine.output_ff(), 3);
+        assert!(*res.machine.registers().get(RegisterNumber::R3) > 0x40);
+        // Coverage is reported relative to the program
+        assert!(res.uncovered_lines().is_empty());
+    }
+
+    #[test]
+    fn programs_must_fit_behind_the_load_address() {
+        let config = |load_address| {
+            RunnerConfigBuilder::default()
+                .with_max_cycles(100)
+                .with_program("#! mrasm\n    INC R0\n    INC R0\n    STOP\n")
+                .with_load_address(load_address)
+                .build()
+        };
+        assert!(config(0xED).unwrap().run().is_ok());
+        assert!(matches!(
+            config(0xEE).unwrap().run(),
+            Err(RunnerError::ProgramTooLarge {
+                address: 0xEE,
+                size: 3
+            })
+        ));
+        assert!(config(0xFE).is_err());
+    }
+
+    #[test]
+    fn runs_time_out() {
+        let program = r#"#! mrasm
+            LOOP:
+                INC R0
+                JR LOOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(usize::MAX)
+            .with_max_time(Duration::from_millis(10))
+            .with_program(program)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(res.timed_out);
+        assert!(res.emulated_cycles < usize::MAX);
+        assert_eq!(res.emulated_cycles % TIME_CHECK_INTERVAL, 0);
+        assert!(res.time_taken >= Duration::from_millis(10));
+        assert_eq!(res.machine.state(), State::Running);
+    }
+
+    #[test]
+    fn counting_loops_are_not_stalls() {
+        let program = r#"#! mrasm
+            LOOP:
+                INC R0
+                JR LOOP
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(10_000)
+            .with_program(program)
+            .with_detect_stalls(true)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert!(!res.stalled);
+        assert_eq!(res.emulated_cycles, 10_000);
+    }
+
+    #[test]
+    fn unbalanced_subroutines_are_reported() {
+        let program = r#"#! mrasm
+                LDSP 0xEF
+                CALL BALANCED
+                CALL UNBALANCED
+                STOP
+            BALANCED:
+                PUSH R0
+                POP R0
+                RET
+            UNBALANCED:
+                ; Leave R0 below the return address
+                POP R1
+                PUSH R0
+                PUSH R1
+                RET
+        "#;
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let res = config.run().expect("Parsing failed");
+        assert_eq!(res.machine.state(), State::Stopped);
+        let imbalance = StackImbalance {
+            call_site: 0x05,
+            expected_sp: 0xEF,
+            found_sp: 0xEE,
+        };
+        assert_eq!(res.stack_imbalances, vec![imbalance]);
+        let expectations = RunExpectationsBuilder::default()
+            .expect_balanced_subroutines(true)
+            .build()
+            .unwrap();
+        let error = expectations.verify(&res).unwrap_err();
+        assert!(matches!(error, VerificationError::UnbalancedSubroutine(i) if i == imbalance));
+        assert_eq!(
+            error.to_string(),
+            "Subroutine called at 0x05 returned with SP == 0xEE != 0xEF"
+        );
+    }
+
+    #[test]
+    fn batch_runs_are_independent_and_ordered() {
+        let programs = [
+            "#! mrasm\n    INC R0\n    ST (0xFF), R0\n    STOP\n",
+            "#! mrasm\n    THIS IS NOT VALID\n",
+            "#! mrasm\n    MOV R0, 42\n    ST (0xFF), R0\n    STOP\n",
+        ];
+        let configs: Vec<_> = programs
+            .iter()
+            .map(|program| {
+                RunnerConfigBuilder::default()
+                    .with_max_cycles(1_000)
+                    .with_program(program)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        let results = run_batch(&configs);
+        assert_eq!(results.len(), 3);
+        let first = results[0].as_ref().expect("First program failed");
+        assert_eq!(first.machine.output_ff(), 1);
+        assert_eq!(first.config, &configs[0]);
+        assert!(results[1].is_err());
+        let last = results[2].as_ref().expect("Last program failed");
+        assert_eq!(last.machine.output_ff(), 42);
+        assert_eq!(last.machine.state(), State::Stopped);
+    }
 }
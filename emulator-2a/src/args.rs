@@ -1,11 +1,15 @@
+use derive_builder::Builder;
 use emulator_2a_lib::{
-    machine::{MachineConfig, State},
+    lint::Lint,
+    machine::{MachineConfig, State, RAM_SIZE},
     runner::{RunExpectations, RunExpectationsBuilder},
 };
 use log::Level;
 use structopt::StructOpt;
 
-use std::{num::ParseIntError, path::PathBuf};
+use std::{fs::read_to_string, num::ParseIntError, path::PathBuf, time::Duration};
+
+use crate::error::Error;
 
 #[derive(Debug, StructOpt)]
 #[structopt(author = "Malte Tammena <malte.tammena@gmx.de>")]
@@ -19,6 +23,17 @@ pub struct Args {
     /// warn, info, debug, trace. If none is specified, only errors are logged.
     #[structopt(short, parse(from_occurrences = parse_log_level))]
     pub verbosity: Level,
+    /// Do not print summaries, only errors.
+    ///
+    /// The exit code still reports failed runs and verifications.
+    /// Errors are printed to stderr.
+    #[structopt(short, long, global = true)]
+    pub quiet: bool,
+    /// Do not color the output.
+    ///
+    /// Colors are disabled aswell, if the `NO_COLOR` environment variable is set.
+    #[structopt(long, global = true)]
+    pub no_color: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -31,8 +46,31 @@ pub enum SubCommand {
     ///
     /// It is sufficient to specify a program and the number of clock cycles.
     Run(RunArgs),
+    /// Run all programs in a directory.
+    ///
+    /// Every `.asm` file in the given directory is emulated independently
+    /// using the same machine configuration. The results are printed in
+    /// the order of the file names. A failing program does not abort the
+    /// emulation of the remaining programs.
+    RunBatch(RunBatchArgs),
+    /// Compare a program against a reference program.
+    ///
+    /// Both programs are emulated using the same machine configuration for
+    /// the same number of cycles. If the final machines differ, the
+    /// differences are printed and an error code of 1 is returned.
+    Compare(CompareArgs),
     /// Verify the given program's syntax.
     Verify(VerifyArgs),
+    /// Check the given program for suspicious code.
+    ///
+    /// Reports unreachable code, unused labels and registers that are
+    /// written but never read, together with their line numbers.
+    Lint(LintArgs),
+    /// Print a listing of the given program.
+    ///
+    /// Every source line is printed together with its address and the
+    /// bytes it places into memory, like the `.lst` file of an assembler.
+    List(ListArgs),
     /// Run an interactive session.
     #[cfg(feature = "interactive-tui")]
     Interactive(InteractiveArgs),
@@ -45,6 +83,7 @@ pub struct RunArgs {
     /// The path to the program to compile and run.
     ///
     /// The program will be verified before execution.
+    /// Use `-` to read the program from stdin.
     #[structopt(name = "PROGRAM")]
     pub program: PathBuf,
     /// The number of clock cycles to emulate.
@@ -55,6 +94,8 @@ pub struct RunArgs {
     #[structopt(name = "CYCLES")]
     pub cycles: usize,
     /// Executes a cpu reset before this cycle is executed. Can be issued multiple times.
+    ///
+    /// A halted machine is emulated further, as long as a reset is still scheduled.
     #[structopt(
         name = "reset",
         long,
@@ -72,10 +113,112 @@ pub struct RunArgs {
         number_of_values = 1
     )]
     pub interrupts: Vec<usize>,
+    /// Abort the emulation after this amount of wall-clock time, i.e. `2s` or `500ms`.
+    #[structopt(long, value_name = "DURATION", parse(try_from_str = humantime::parse_duration))]
+    pub max_time: Option<Duration>,
+    /// Print which source lines were executed during the emulation.
+    ///
+    /// Prints the number of covered lines and lists all executable
+    /// lines that were never reached.
+    #[structopt(long)]
+    pub coverage: bool,
+    /// Write an execution trace to the given CSV file.
+    ///
+    /// The file contains one row per emulated cycle with the columns
+    /// cycle, PC, R0, R1, R2, SP, FR, FE, FF and state.
+    #[structopt(long, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+    /// Place the compiled program at the given address and start executing there.
+    ///
+    /// Labels keep the addresses given by `.ORG`, thus only code using
+    /// relative jumps works as expected. A reset restarts the program at 0x00.
+    /// The address must lie within the RAM (`00 - EF`) and the whole program
+    /// must fit behind it.
+    #[structopt(long, value_name = "ADDRESS", default_value = "0",
+                parse(try_from_str = parse_load_address))]
+    pub load_at: u8,
+    /// Write bytes into the memory before the emulation, i.e. `10:DEADBEEF`.
+    /// Can be issued multiple times.
+    ///
+    /// The hexadecimal bytes are written starting at the given address,
+    /// after the program was loaded. They must fit into the RAM (`00 - EF`).
+    #[structopt(
+        long,
+        value_name = "ADDR:HEXBYTES",
+        multiple = true,
+        number_of_values = 1,
+        parse(try_from_str = parse_memory_init)
+    )]
+    pub mem_init: Vec<MemoryInit>,
+    /// Print the results as a single line, i.e. `OK cycles=100 state=Running fe=0 ff=52`.
+    ///
+    /// The line starts with `FAIL` if the verification failed, `OK` otherwise.
+    /// Verification warnings are not printed.
+    #[structopt(long)]
+    pub oneline: bool,
+    /// Print a plain text snapshot of the machine after the emulation.
+    ///
+    /// The snapshot contains the summary, the registers and the memory.
+    /// It uses neither colors nor non-ASCII characters, i.e. for CI logs.
+    #[structopt(long, conflicts_with = "oneline")]
+    pub plain: bool,
+    /// Print a listing of the program after the emulation.
+    ///
+    /// Every source line is printed together with its address and
+    /// the bytes it places into memory. See the `list` subcommand.
+    #[structopt(long)]
+    pub list: bool,
+    /// Run the program again, whenever its source file changes.
+    ///
+    /// The results of every run are printed. Press Ctrl-C to stop watching.
+    #[cfg(feature = "watch")]
+    #[structopt(long)]
+    pub watch: bool,
+    #[structopt(subcommand)]
+    pub verify: Option<RunVerifySubcommand>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct RunBatchArgs {
+    #[structopt(flatten)]
+    pub init: InitialMachineConfiguration,
+    /// The directory containing the programs to compile and run.
+    #[structopt(name = "DIR")]
+    pub directory: PathBuf,
+    /// The number of clock cycles to emulate for each program.
+    ///
+    /// See `2a-emulator run --help` for details.
+    #[structopt(name = "CYCLES")]
+    pub cycles: usize,
     #[structopt(subcommand)]
     pub verify: Option<RunVerifySubcommand>,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct CompareArgs {
+    #[structopt(flatten)]
+    pub init: InitialMachineConfiguration,
+    /// The path to the program to compare.
+    #[structopt(name = "PROGRAM")]
+    pub program: PathBuf,
+    /// The path to the reference program.
+    #[structopt(name = "REFERENCE")]
+    pub reference: PathBuf,
+    /// The number of clock cycles to emulate for both programs.
+    ///
+    /// See `2a-emulator run --help` for details.
+    #[structopt(name = "CYCLES")]
+    pub cycles: usize,
+    /// Compare the registers and flags aswell.
+    ///
+    /// By default only the output registers and the machine state are compared.
+    #[structopt(long)]
+    pub registers: bool,
+    /// Compare the memory aswell.
+    #[structopt(long)]
+    pub memory: bool,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub enum RunVerifySubcommand {
     /// Verify the machine state after emulation has finished.
@@ -115,6 +258,18 @@ pub struct RunVerifyArgs {
     #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
     pub ff: Option<u8>,
+    /// Expect the machine to never error-stop during the emulation.
+    ///
+    /// Unlike `--state`, this also catches error stops that were followed
+    /// by a reset.
+    #[structopt(long)]
+    pub never_error: bool,
+    /// Expect every subroutine to leave the stack as it found it.
+    ///
+    /// The stackpointer after a `RET` is compared to the stackpointer before
+    /// the matching `CALL`. The first mismatch is reported with its call site.
+    #[structopt(long)]
+    pub balanced_stack: bool,
 }
 
 #[derive(Debug, StructOpt)]
@@ -124,6 +279,38 @@ pub struct VerifyArgs {
     /// The program will be verified before execution.
     #[structopt(name = "PROGRAM")]
     pub program: PathBuf,
+    /// Fail the verification if the program produces any warnings.
+    ///
+    /// Warnings, like unused labels, are printed in any case.
+    #[structopt(long)]
+    pub deny_warnings: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LintArgs {
+    /// The path to the program to lint.
+    #[structopt(name = "PROGRAM")]
+    pub program: PathBuf,
+    /// Skip the given lint. Can be issued multiple times.
+    ///
+    /// One of `unreachable-code`, `unused-label` or `unread-register`.
+    #[structopt(long, number_of_values = 1, name = "LINT")]
+    pub allow: Vec<Lint>,
+    /// Fail if any lint reports a finding.
+    #[structopt(long)]
+    pub deny_warnings: bool,
+    /// Print how often each instruction is used in the program.
+    ///
+    /// The program is not executed, every line counts once.
+    #[structopt(long)]
+    pub stats: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ListArgs {
+    /// The path to the program to list.
+    #[structopt(name = "PROGRAM")]
+    pub program: PathBuf,
 }
 
 #[derive(Debug, Default, StructOpt)]
@@ -133,26 +320,57 @@ pub struct InteractiveArgs {
     /// The program will be verified before execution.
     #[structopt(name = "PROGRAM")]
     pub program: Option<PathBuf>,
+    /// Number of frames drawn per second.
+    ///
+    /// The emulated clock frequency stays the same, the cycles are
+    /// distributed over the frames. Defaults to 24.
+    #[structopt(long, value_name = "N")]
+    pub fps: Option<u64>,
+    /// Maximum number of cycles to emulate per frame.
+    ///
+    /// By default, the cycles are chosen so that the emulated clock runs
+    /// at the frequency of the real machine.
+    #[structopt(long, value_name = "CYCLES")]
+    pub cycles_per_frame: Option<u64>,
+    /// Enable mouse support.
+    ///
+    /// Clicking a program line toggles a breakpoint, clicking a memory
+    /// cell selects it. Disabled by default, since some terminals misbehave.
+    #[structopt(long)]
+    pub mouse: bool,
     #[structopt(flatten)]
     pub init: InitialMachineConfiguration,
 }
 
-#[derive(Debug, Clone, Default, StructOpt)]
+/// Flags to configure the machine before the emulation starts.
+///
+/// Use [`InitialMachineConfiguration::machine_config`] to create the
+/// [`MachineConfig`]. For ease of use, the [`InitialMachineConfigurationBuilder`]
+/// mirrors the [`MachineConfigBuilder`](emulator_2a_lib::machine::MachineConfigBuilder).
+#[derive(Debug, Clone, Default, StructOpt, Builder)]
+#[builder(default, setter(strip_option))]
 pub struct InitialMachineConfiguration {
+    /// Read the machine configuration from the given TOML file.
+    ///
+    /// The file may contain any field of the machine configuration, i.e.
+    /// `input_fc = 42` or `jumper1 = true`. Missing fields keep their default.
+    /// Flags given on the command line take precedence over the file.
+    #[structopt(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
     /// Set the value of the digital input P-DI1.
     ///
     /// This input port is part of the MR2DA2 extension board.
-    #[structopt(long, value_name = "BYTE", default_value = "0",
+    #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
-    pub di1: u8,
+    pub di1: Option<u8>,
     /// Set the output voltage of the temperature sensor.
     ///
     /// The temperature sensor is part of the MR2DA2 extension board.
     /// It's output voltage is fed into the comparator CP2 and powers
     /// the led D-AI2. This is equivalent to setting the analog input
     /// voltage of port P-AI2 (--ai2).
-    #[structopt(long, value_name = "VOLTAGE", default_value = "0")]
-    pub temp: f32,
+    #[structopt(long, value_name = "VOLTAGE")]
+    pub temp: Option<f32>,
     /// Plug jumper J1 into the extension board MR2DA2.
     ///
     /// This is a universal jumper. It's current state can be read
@@ -169,16 +387,16 @@ pub struct InitialMachineConfiguration {
     ///
     /// The P-AI1 is part of the extension board MR2DA2. The voltage
     /// will be fed into the comparator CP1.
-    #[structopt(long, value_name = "VOLTAGE", default_value = "0")]
-    pub ai1: f32,
+    #[structopt(long, value_name = "VOLTAGE")]
+    pub ai1: Option<f32>,
     /// Set the voltage at the analog input port P-AI2.
     ///
     /// The P-AI2 is part of the extension board MR2DA2. The voltage
     /// will be fed into the comparator CP2 and power the the led D-AI2.
     /// It's effect is the same as setting the voltage of the
     /// temperature sensor (--temp)
-    #[structopt(long, value_name = "VOLTAGE", default_value = "0")]
-    pub ai2: f32,
+    #[structopt(long, value_name = "VOLTAGE")]
+    pub ai2: Option<f32>,
     /// Set the universal I/O port UIO1.
     ///
     /// The UIO1 port is located on the MR2DA2 extension board and
@@ -199,46 +417,57 @@ pub struct InitialMachineConfiguration {
     /// Set the content of the input register FC.
     ///
     /// This is the main way of inputing data into the program.
-    #[structopt(long, value_name = "BYTE", default_value = "0",
+    #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
-    pub fc: u8,
+    pub fc: Option<u8>,
     /// Set the content of the input register FD.
     ///
     /// This is the main way of inputing data into the program.
-    #[structopt(long, value_name = "BYTE", default_value = "0",
+    #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
-    pub fd: u8,
+    pub fd: Option<u8>,
     /// Set the content of the input register FE.
     ///
     /// This is the main way of inputing data into the program.
-    #[structopt(long, value_name = "BYTE", default_value = "0",
+    #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
-    pub fe: u8,
+    pub fe: Option<u8>,
     /// Set the content of the input register FF.
     ///
     /// This is the main way of inputing data into the program.
-    #[structopt(long, value_name = "BYTE", default_value = "0",
+    #[structopt(long, value_name = "BYTE",
                 parse(try_from_str = parse_u8_auto_radix))]
-    pub ff: u8,
+    pub ff: Option<u8>,
 }
 
-impl From<InitialMachineConfiguration> for MachineConfig {
-    fn from(init: InitialMachineConfiguration) -> Self {
-        MachineConfig {
-            analog_input1: init.ai1,
-            analog_input2: init.ai2,
-            digital_input1: init.di1,
-            temp: init.temp,
-            input_fc: init.fc,
-            input_fd: init.fd,
-            input_fe: init.fe,
-            input_ff: init.ff,
-            jumper1: init.j1,
-            jumper2: init.j2,
-            universal_input_output1: init.uio1,
-            universal_input_output2: init.uio2,
-            universal_input_output3: init.uio3,
-        }
+impl InitialMachineConfiguration {
+    /// Create the [`MachineConfig`], reading the `--config` file if given.
+    ///
+    /// Values given as flags override those from the file.
+    pub fn machine_config(&self) -> Result<MachineConfig, Error> {
+        let mut config = match self.config {
+            Some(ref path) => {
+                let contents =
+                    read_to_string(path).map_err(|e| Error::ReadingConfig(path.clone(), e))?;
+                toml::from_str(&contents).map_err(|e| Error::InvalidConfig(path.clone(), e))?
+            }
+            None => MachineConfig::default(),
+        };
+        override_with(&mut config.digital_input1, self.di1);
+        override_with(&mut config.temp, self.temp);
+        override_with(&mut config.analog_input1, self.ai1);
+        override_with(&mut config.analog_input2, self.ai2);
+        override_with(&mut config.input_fc, self.fc);
+        override_with(&mut config.input_fd, self.fd);
+        override_with(&mut config.input_fe, self.fe);
+        override_with(&mut config.input_ff, self.ff);
+        // Switches can only be turned on from the command line
+        config.jumper1 |= self.j1;
+        config.jumper2 |= self.j2;
+        config.universal_input_output1 |= self.uio1;
+        config.universal_input_output2 |= self.uio2;
+        config.universal_input_output3 |= self.uio3;
+        Ok(config)
     }
 }
 
@@ -254,12 +483,21 @@ impl From<RunVerifyArgs> for RunExpectations {
         if let Some(output_ff) = args.ff {
             expectations.expect_output_ff(output_ff);
         }
+        expectations.expect_never_error_stopped(args.never_error);
+        expectations.expect_balanced_subroutines(args.balanced_stack);
         expectations
             .build()
             .expect("BUG: Couldn't create expectations")
     }
 }
 
+/// Replace `value` with the value of `flag`, if the flag was given.
+fn override_with<T>(value: &mut T, flag: Option<T>) {
+    if let Some(flag) = flag {
+        *value = flag;
+    }
+}
+
 fn parse_u8_auto_radix(num: &str) -> Result<u8, ParseIntError> {
     if let Some(num) = num.strip_prefix("0b") {
         u8::from_str_radix(num, 2)
@@ -270,6 +508,70 @@ fn parse_u8_auto_radix(num: &str) -> Result<u8, ParseIntError> {
     }
 }
 
+/// Parse the address given to `--load-at`, which must lie within the RAM.
+fn parse_load_address(address: &str) -> Result<u8, String> {
+    let address = parse_u8_auto_radix(address).map_err(|e| e.to_string())?;
+    if address as usize >= RAM_SIZE {
+        return Err(format!(
+            "0x{:02X} is outside of the RAM (00 - {:02X})",
+            address,
+            RAM_SIZE - 1
+        ));
+    }
+    Ok(address)
+}
+
+/// Bytes to write into the memory before the emulation, see [`RunArgs::mem_init`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryInit {
+    /// Address of the first byte.
+    pub address: u8,
+    /// Bytes to write, in order.
+    pub bytes: Vec<u8>,
+}
+
+impl MemoryInit {
+    /// Get every byte together with the address it is written to.
+    pub fn preload(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        (self.address..).zip(self.bytes.iter().copied())
+    }
+}
+
+/// Parse an `ADDR:HEXBYTES` spec, i.e. `10:DEADBEEF` or `0x10:deadbeef`.
+///
+/// The address is hexadecimal, the `0x` prefix is optional.
+fn parse_memory_init(spec: &str) -> Result<MemoryInit, String> {
+    let (address, hex) = match spec.find(':') {
+        Some(colon) => (&spec[..colon], &spec[colon + 1..]),
+        None => return Err(format!("expected ADDR:HEXBYTES, found {:?}", spec)),
+    };
+    let address = address.strip_prefix("0x").unwrap_or(address);
+    let address = u8::from_str_radix(address, 16)
+        .map_err(|e| format!("invalid address {:?}: {}", address, e))?;
+    if hex.is_empty() || hex.len() % 2 != 0 {
+        return Err(format!(
+            "expected an even, non-zero number of hex digits, found {}",
+            hex.len()
+        ));
+    }
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex bytes {:?}", hex));
+    }
+    let bytes: Vec<_> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("Only hex digits remain"))
+        .collect();
+    if address as usize + bytes.len() > RAM_SIZE {
+        return Err(format!(
+            "{} byte(s) at 0x{:02X} exceed the RAM, which ends at 0x{:02X}",
+            bytes.len(),
+            address,
+            RAM_SIZE - 1
+        ));
+    }
+    Ok(MemoryInit { address, bytes })
+}
+
 fn parse_state(state: &str) -> State {
     match state.to_lowercase().as_str() {
         "stopped" => State::Stopped,
@@ -288,3 +590,153 @@ fn parse_log_level(occurrences: u64) -> Level {
         _ => Level::Trace,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use emulator_2a_lib::machine::MachineConfigBuilder;
+
+    use crate::helpers::testing::{run_args, TempFile};
+
+    #[test]
+    fn machine_configs_survive_a_round_trip() {
+        let config = MachineConfig {
+            digital_input1: 0x12,
+            temp: 1.5,
+            jumper2: true,
+            analog_input1: 2.25,
+            universal_input_output3: true,
+            input_fc: 0xFC,
+            input_ff: 0x42,
+            ..Default::default()
+        };
+        let serialized = toml::to_string(&config).expect("Failed to serialize config");
+        let deserialized: MachineConfig =
+            toml::from_str(&serialized).expect("Failed to deserialize config");
+        assert_eq!(config, deserialized);
+    }
+
+    #[test]
+    fn flags_take_precedence_over_config_files() {
+        let file = TempFile::new(
+            "2a-emulator-precedence-test.toml",
+            "input_fc = 1\ninput_fd = 2\njumper1 = true\ntemp = 0.5\n",
+        );
+        let init = run_args(&[
+            "2a-emulator",
+            "run",
+            "--config",
+            file.arg(),
+            "--fc",
+            "0x42",
+            "--j2",
+            "program.asm",
+            "100",
+        ])
+        .init;
+        let config = init.machine_config();
+        std::fs::write(file.path(), "input_fc = 1\nunknown = 2\n").unwrap();
+        let invalid = init.machine_config();
+        let config = config.expect("Failed to read config");
+        assert_eq!(
+            config,
+            MachineConfig {
+                input_fc: 0x42,
+                input_fd: 2,
+                jumper1: true,
+                jumper2: true,
+                temp: 0.5,
+                ..Default::default()
+            }
+        );
+        assert!(matches!(invalid, Err(Error::InvalidConfig(ref p, _)) if p == file.path()));
+    }
+
+    #[test]
+    fn every_flag_is_converted_into_the_machine_config() {
+        let init = InitialMachineConfigurationBuilder::default()
+            .di1(0x12)
+            .temp(1.5)
+            .j1(true)
+            .j2(true)
+            .ai1(2.25)
+            .ai2(3.75)
+            .uio1(true)
+            .uio2(true)
+            .uio3(true)
+            .fc(0xFC)
+            .fd(0xFD)
+            .fe(0xFE)
+            .ff(0xFF)
+            .build()
+            .expect("This is always infallible");
+        let expected = MachineConfigBuilder::default()
+            .digital_input1(0x12)
+            .temp(1.5)
+            .jumper1(true)
+            .jumper2(true)
+            .analog_input1(2.25)
+            .analog_input2(3.75)
+            .universal_input_output1(true)
+            .universal_input_output2(true)
+            .universal_input_output3(true)
+            .input_fc(0xFC)
+            .input_fd(0xFD)
+            .input_fe(0xFE)
+            .input_ff(0xFF)
+            .build()
+            .expect("This is always infallible");
+        let config = init.machine_config().expect("No config file was given");
+        assert_eq!(config, expected);
+        // Compare voltages exactly, the equality of configs tolerates rounding errors
+        assert_eq!(config.temp, 1.5);
+        assert_eq!(config.analog_input1, 2.25);
+        assert_eq!(config.analog_input2, 3.75);
+    }
+
+    #[test]
+    fn memory_init_specs_are_parsed() {
+        let init = parse_memory_init("10:DEADbeef").unwrap();
+        assert_eq!(init.address, 0x10);
+        assert_eq!(init.bytes, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        let preload: Vec<_> = init.preload().collect();
+        assert_eq!(preload[3], (0x13, 0xEF));
+        assert_eq!(
+            parse_memory_init("0xEF:2A"),
+            Ok(MemoryInit {
+                address: 0xEF,
+                bytes: vec![0x2A]
+            })
+        );
+        let run_args = run_args(&[
+            "2a-emulator",
+            "run",
+            "prog.asm",
+            "100",
+            "--mem-init",
+            "10:01",
+            "--mem-init",
+            "80:0203",
+        ]);
+        assert_eq!(run_args.mem_init.len(), 2);
+    }
+
+    #[test]
+    fn malformed_memory_init_specs_are_rejected() {
+        // Missing colon
+        assert!(parse_memory_init("10DEADBEEF").is_err());
+        // Invalid address
+        assert!(parse_memory_init("G0:00").is_err());
+        assert!(parse_memory_init("100:00").is_err());
+        // Odd or missing hex digits
+        assert!(parse_memory_init("10:ABC").is_err());
+        assert!(parse_memory_init("10:").is_err());
+        // Not hexadecimal
+        assert!(parse_memory_init("10:XY").is_err());
+        assert!(parse_memory_init("10:+1").is_err());
+        // Beyond the RAM
+        assert!(parse_memory_init("EF:0102").is_err());
+        assert!(parse_memory_init("F0:01").is_err());
+    }
+}
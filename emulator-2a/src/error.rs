@@ -2,10 +2,14 @@
 //!
 //! This module defines the error type used through-out the program.
 
-use emulator_2a_lib::{parser::ParserError, runner::VerificationError};
+use emulator_2a_lib::{
+    compiler::CompileError,
+    parser::ParserError,
+    runner::{RunnerError, VerificationError},
+};
 use thiserror::Error;
 
-use std::io::Error as IOError;
+use std::{io::Error as IOError, path::PathBuf};
 
 #[derive(Error, Debug)]
 /// THE error type.
@@ -13,6 +17,15 @@ pub enum Error {
     /// Thrown when the validation of the ASM source file failes.
     #[error("{_0}")]
     Validation(#[from] ParserError),
+    /// Thrown when the validated ASM source could not be compiled.
+    #[error("{_0}")]
+    Compilation(#[from] CompileError),
+    /// Thrown when the compiled program does not fit into the memory.
+    #[error("{_0}")]
+    Loading(RunnerError),
+    /// Thrown when the program at the given path could not be parsed, compiled or loaded.
+    #[error("{} {}:\n{}", describe_runner_error(.1), .0.display(), .1)]
+    InvalidProgram(PathBuf, #[source] RunnerError),
     /// Thrown when, due to IO failure, no ASM source file could be opened.
     #[error("The source file could not be opened!:\n{_0}")]
     OpeningSourceFile(#[from] IOError),
@@ -28,9 +41,57 @@ pub enum Error {
     #[cfg(feature = "interactive-tui")]
     #[error("Crossterm exit failed: {_0}")]
     CrosstermExit(#[source] crossterm::ErrorKind),
+    /// Warnings were found, but denied. The field is the number of warnings.
+    #[error("{_0} warning(s) found, but warnings are denied")]
+    DeniedWarnings(usize),
+    /// Writing the execution trace failed.
+    #[error("The trace could not be written: {_0}")]
+    WritingTrace(#[source] IOError),
+    /// Printing the results failed.
+    #[error("The results could not be printed: {_0}")]
+    WritingOutput(#[source] IOError),
     /// Verification of a run failed. The first field is an explanation.
     #[error("Verification failed: {_0}")]
     RunVerification(#[from] VerificationError),
+    /// Some programs of a batch run failed. The field is the number of failed programs.
+    #[error("{_0} program(s) of the batch failed")]
+    BatchFailed(usize),
+    /// Watching the program for changes failed.
+    #[cfg(feature = "watch")]
+    #[error("The program could not be watched for changes: {_0}")]
+    Watching(#[source] notify::Error),
+    /// Installing the Ctrl-C handler failed.
+    #[cfg(feature = "watch")]
+    #[error("The Ctrl-C handler could not be installed: {_0}")]
+    InterruptHandler(#[source] ctrlc::Error),
+    /// The machine configuration file could not be read.
+    #[error("The machine configuration {} could not be read: {}", .0.display(), .1)]
+    ReadingConfig(PathBuf, #[source] IOError),
+    /// The machine configuration file is not valid TOML or contains unknown fields.
+    #[error("Invalid machine configuration {}: {}", .0.display(), .1)]
+    InvalidConfig(PathBuf, #[source] toml::de::Error),
+    /// The compared programs ended in different machine states.
+    #[error("The program differs from the reference")]
+    ProgramsDiffer,
+}
+
+impl From<RunnerError> for Error {
+    fn from(err: RunnerError) -> Self {
+        match err {
+            RunnerError::Parsing(err) => Error::Validation(err),
+            RunnerError::Compilation(err) => Error::Compilation(err),
+            err @ RunnerError::ProgramTooLarge { .. } => Error::Loading(err),
+        }
+    }
+}
+
+/// Describe the class of the [`RunnerError`], to prefix the path of the program.
+fn describe_runner_error(err: &RunnerError) -> &'static str {
+    match err {
+        RunnerError::Parsing(_) => "Invalid syntax in program",
+        RunnerError::Compilation(_) => "Failed to compile program",
+        RunnerError::ProgramTooLarge { .. } => "Failed to load program",
+    }
 }
 
 impl Error {
@@ -0,0 +1,90 @@
+use std::{collections::VecDeque, fmt, time::Duration};
+
+use super::format_number;
+
+/// Measures the frequency of a clock over the last few samples.
+///
+/// Every sample consists of the number of cycles executed and the time
+/// needed to do so. The frequency is averaged over all samples currently
+/// held by the meter, the oldest samples are dropped once the meter is full.
+///
+/// ```text
+/// let mut meter = FrequencyMeter::new(2);
+/// meter.record(100, Duration::from_millis(100));
+/// meter.record(300, Duration::from_millis(100));
+/// assert_eq!(meter.hz(), 2000.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyMeter {
+    /// The latest samples, oldest first.
+    samples: VecDeque<(u64, Duration)>,
+    /// Maximum number of samples to keep.
+    capacity: usize,
+}
+
+impl FrequencyMeter {
+    /// Create a new meter averaging over the last `capacity` samples.
+    ///
+    /// A `capacity` of zero is treated as one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        FrequencyMeter {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+    /// Record that `cycles` cycles were executed in `elapsed` time.
+    pub fn record(&mut self, cycles: u64, elapsed: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((cycles, elapsed));
+    }
+    /// Get the averaged frequency in Hertz.
+    ///
+    /// This is zero, if no time has been recorded yet.
+    pub fn hz(&self) -> f32 {
+        let cycles: u64 = self.samples.iter().map(|(cycles, _)| cycles).sum();
+        let elapsed: Duration = self.samples.iter().map(|(_, elapsed)| *elapsed).sum();
+        if elapsed.as_nanos() == 0 {
+            0.0
+        } else {
+            (cycles as f64 / elapsed.as_secs_f64()) as f32
+        }
+    }
+}
+
+impl fmt::Display for FrequencyMeter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", format_number(self.hz()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_is_averaged_over_the_latest_samples() {
+        let mut meter = FrequencyMeter::new(3);
+        assert_eq!(meter.hz(), 0.0);
+        meter.record(100, Duration::from_millis(100));
+        assert_eq!(meter.hz(), 1_000.0);
+        meter.record(300, Duration::from_millis(100));
+        assert_eq!(meter.hz(), 2_000.0);
+        meter.record(0, Duration::from_millis(200));
+        assert_eq!(meter.hz(), 1_000.0);
+        // The first sample is dropped
+        meter.record(600, Duration::from_millis(100));
+        assert_eq!(meter.hz(), 2_250.0);
+        assert_eq!(meter.to_string(), "2.25kHz");
+    }
+
+    #[test]
+    fn meter_keeps_at_least_one_sample() {
+        let mut meter = FrequencyMeter::new(0);
+        meter.record(10, Duration::from_secs(1));
+        meter.record(20, Duration::from_secs(1));
+        assert_eq!(meter.hz(), 20.0);
+    }
+}
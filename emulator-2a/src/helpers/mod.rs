@@ -1,34 +1,146 @@
 //! Types and Functions to aid the program.
 
 use colored::Colorize;
-use emulator_2a_lib::parser::{Asm, AsmParser};
+use emulator_2a_lib::{
+    compiler::Translator,
+    lint::{self, Lint},
+    parser::{Asm, AsmParser},
+};
 
 #[cfg(feature = "interactive-tui")]
 use std::time::Duration;
-use std::{fs::read_to_string, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    fs::read_to_string,
+    io::{self, Write},
+    path::PathBuf,
+};
 
 use crate::error::Error;
 
 #[cfg(feature = "interactive-tui")]
 mod constants;
+#[cfg(feature = "interactive-tui")]
+mod frequency_meter;
+#[cfg(test)]
+pub mod testing;
 
 #[cfg(feature = "interactive-tui")]
 pub use constants::*;
+#[cfg(feature = "interactive-tui")]
+pub use frequency_meter::FrequencyMeter;
 
 /// Loads and verifies the source file found at `path`.
 /// This fails with an [`Error`] if the source code is not worthy.
-/// See [`AsmParser::parse`].
-pub fn load_and_verify_source_file<P>(path: P) -> Result<(), Error>
+/// See [`AsmParser::parse_with_warnings`].
+///
+/// Warnings are printed to `out`. If `deny_warnings` is set, any warning fails the verification.
+pub fn load_and_verify_source_file<P>(
+    path: P,
+    deny_warnings: bool,
+    out: &mut dyn Write,
+) -> Result<(), Error>
 where
     P: Into<PathBuf>,
 {
     let path: PathBuf = path.into();
-    read_asm_file(&path)?;
-    println!(
+    let content = read_to_string(&path)?;
+    let (asm, warnings) = AsmParser::parse_with_warnings(&content)?;
+    Translator::compile(&asm)?;
+    for warning in &warnings {
+        writeln!(out, "{}: {}", "Warning".yellow().bold(), warning)
+            .map_err(Error::WritingOutput)?;
+    }
+    if deny_warnings && !warnings.is_empty() {
+        return Err(Error::DeniedWarnings(warnings.len()));
+    }
+    writeln!(
+        out,
         "Source file {} is valid.",
         path.to_string_lossy().bright_green()
-    );
-    Ok(())
+    )
+    .map_err(Error::WritingOutput)
+}
+
+/// Lints the source file found at `path`, skipping all `allowed` lints.
+/// This fails with an [`Error`] if the source code cannot be compiled.
+///
+/// Findings are printed to `out`. If `deny_warnings` is set, any finding fails the lint.
+/// If `stats` is set, a histogram of the used instructions is printed as well.
+pub fn lint_source_file<P>(
+    path: P,
+    allowed: &[Lint],
+    deny_warnings: bool,
+    stats: bool,
+    out: &mut dyn Write,
+) -> Result<(), Error>
+where
+    P: Into<PathBuf>,
+{
+    let path: PathBuf = path.into();
+    let content = read_to_string(&path)?;
+    let asm = AsmParser::parse(&content)?;
+    let bytecode = Translator::compile(&asm)?;
+    let findings = lint::lint(&bytecode, allowed);
+    for finding in &findings {
+        writeln!(out, "{}: {}", "Warning".yellow().bold(), finding)
+            .map_err(Error::WritingOutput)?;
+    }
+    if stats {
+        writeln!(out, "Instruction usage:").map_err(Error::WritingOutput)?;
+        for (mnemonic, count) in lint::instruction_histogram(&asm) {
+            writeln!(out, "{:>5}  {}", count, mnemonic).map_err(Error::WritingOutput)?;
+        }
+    }
+    if deny_warnings && !findings.is_empty() {
+        return Err(Error::DeniedWarnings(findings.len()));
+    }
+    writeln!(
+        out,
+        "Source file {} linted, {} finding(s).",
+        path.to_string_lossy().bright_green(),
+        findings.len()
+    )
+    .map_err(Error::WritingOutput)
+}
+
+/// Prints a listing of the source file found at `path` to `out`.
+/// This fails with an [`Error`] if the source code cannot be compiled.
+///
+/// See [`ByteCode::listing`](emulator_2a_lib::compiler::ByteCode::listing).
+pub fn list_source_file<P>(path: P, out: &mut dyn Write) -> Result<(), Error>
+where
+    P: Into<PathBuf>,
+{
+    let path: PathBuf = path.into();
+    let content = read_to_string(&path)?;
+    let asm = AsmParser::parse(&content)?;
+    let bytecode = Translator::compile(&asm)?;
+    write!(out, "{}", bytecode.listing()).map_err(Error::WritingOutput)
+}
+
+/// Get the writer for regular output.
+///
+/// If `quiet` is set, everything is discarded instead of written to `stdout`.
+pub fn regular_output<'a>(quiet: bool, stdout: &'a mut dyn Write) -> Box<dyn Write + 'a> {
+    if quiet {
+        Box::new(io::sink())
+    } else {
+        Box::new(stdout)
+    }
+}
+
+/// Should colors be disabled?
+///
+/// This is the case if `--no-color` was given or the `NO_COLOR` environment
+/// variable is set to a non-empty value, see <https://no-color.org>.
+pub fn colors_disabled(no_color: bool, no_color_env: Option<&OsStr>) -> bool {
+    no_color || matches!(no_color_env, Some(value) if !value.is_empty())
+}
+
+/// Disable all colors of the regular and error output.
+pub fn disable_colors() {
+    colored::control::set_override(false)
 }
 
 /// Copy of [`Duration::checked_sub`]
@@ -66,3 +178,55 @@ pub fn format_number(mut nr: f32) -> String {
     }
     format!("{:.2}{}Hz", nr, suffix)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lint_findings_are_printed_with_line_numbers() {
+        let path = std::env::temp_dir().join("2a-emulator-lint-test.asm");
+        std::fs::write(&path, "#! mrasm\nUNUSED:\n    STOP\n    CLR R0\n").unwrap();
+        let mut out = vec![];
+        lint_source_file(&path, &[], false, false, &mut out).expect("Linting failed");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("line 2: Label 'UNUSED' is never referenced [unused-label]"));
+        assert!(out.contains("line 4: Unreachable code after 'STOP' [unreachable-code]"));
+        assert!(out.contains("line 4: Register R0 is written but never read [unread-register]"));
+
+        let mut out = vec![];
+        let allowed = [
+            Lint::UnusedLabel,
+            Lint::UnreachableCode,
+            Lint::UnreadRegister,
+        ];
+        lint_source_file(&path, &allowed, true, false, &mut out).expect("Linting failed");
+        let result = lint_source_file(&path, &[Lint::UnusedLabel], true, false, &mut vec![]);
+        assert!(matches!(result, Err(Error::DeniedWarnings(2))));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn colors_are_disabled_by_flag_and_environment() {
+        assert!(!colors_disabled(false, None));
+        assert!(!colors_disabled(false, Some(OsStr::new(""))));
+        assert!(colors_disabled(false, Some(OsStr::new("1"))));
+        assert!(colors_disabled(true, None));
+    }
+
+    #[test]
+    fn lint_stats_print_an_instruction_histogram() {
+        let path = std::env::temp_dir().join("2a-emulator-lint-stats-test.asm");
+        std::fs::write(
+            &path,
+            "#! mrasm\nLOOP:\n    INC R0\n    INC R0\n    ST (0xFF), R0\n    JR LOOP\n",
+        )
+        .unwrap();
+        let mut out = vec![];
+        let result = lint_source_file(&path, &Lint::ALL, false, true, &mut out);
+        std::fs::remove_file(&path).unwrap();
+        result.expect("Linting failed");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Instruction usage:\n    2  INC\n    1  JR\n    1  ST\n"));
+    }
+}
@@ -0,0 +1,88 @@
+//! Helpers shared by the tests of the binary.
+use structopt::StructOpt;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::args::{Args, RunArgs, RunBatchArgs, SubCommand};
+
+/// Parse `argv` and return the arguments of the `run` subcommand.
+///
+/// Panics if the arguments are invalid or name another subcommand.
+pub fn run_args(argv: &[&str]) -> RunArgs {
+    let args = Args::from_iter_safe(argv).expect("Invalid arguments");
+    match args.subcommand {
+        Some(SubCommand::Run(run_args)) => run_args,
+        _ => panic!("Not a run subcommand: {:?}", argv),
+    }
+}
+
+/// Parse `argv` and return the arguments of the `run-batch` subcommand.
+///
+/// Panics if the arguments are invalid or name another subcommand.
+pub fn run_batch_args(argv: &[&str]) -> RunBatchArgs {
+    let args = Args::from_iter_safe(argv).expect("Invalid arguments");
+    match args.subcommand {
+        Some(SubCommand::RunBatch(batch_args)) => batch_args,
+        _ => panic!("Not a run-batch subcommand: {:?}", argv),
+    }
+}
+
+/// A file in the temporary directory, removed again when dropped.
+pub struct TempFile(PathBuf);
+
+impl TempFile {
+    /// Write `content` to the file `name` in the temporary directory.
+    pub fn new(name: &str, content: &str) -> Self {
+        let path = std::env::temp_dir().join(name);
+        fs::write(&path, content).expect("Failed to write temporary file");
+        TempFile(path)
+    }
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+    /// The path of the file, for use as a command line argument.
+    pub fn arg(&self) -> &str {
+        self.0
+            .to_str()
+            .expect("Temporary directory is not valid UTF-8")
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// A directory in the temporary directory, removed with its content when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create the empty directory `name` in the temporary directory.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(name);
+        fs::remove_dir_all(&path).ok();
+        fs::create_dir(&path).expect("Failed to create temporary directory");
+        TempDir(path)
+    }
+    /// Write `content` to the file `name` in the directory.
+    pub fn file(&self, name: &str, content: &str) {
+        fs::write(self.0.join(name), content).expect("Failed to write temporary file");
+    }
+    /// The path of the directory, for use as a command line argument.
+    pub fn arg(&self) -> &str {
+        self.0
+            .to_str()
+            .expect("Temporary directory is not valid UTF-8")
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
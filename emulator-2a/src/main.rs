@@ -65,11 +65,35 @@
 //! and loaded into main memory. Additionally the input register FF contains 42.
 //! For a full list of options, see `2a-emulator interactive --help`.
 //!
+//! To reuse a machine setup, store it in a TOML file and pass it with
+//! `--config setup.toml` to `interactive`, `run`, `run-batch` or `compare`:
+//! ```toml
+//! input_fc = 42
+//! jumper1 = true
+//! temp = 0.5
+//! ```
+//! The fields are named like those of the library's `MachineConfig`, missing
+//! fields keep their default. Flags given on the command line take precedence.
+//!
 //! You may also be interested in the `run` mode which emulates the execution of a
 //! given program for a number of clock cycles before printing the state of the machine.
 //! Have a look at `2a-emulator run --help` for more information.
 //! To simply verify the syntax of an assembler file run
 //! `2a-emulator verify my_faulty_program.asm`.
+//! Suspicious constructs, like unused labels, are printed as warnings. Add
+//! `--deny-warnings` to fail the verification in that case.
+//! For a closer look run `2a-emulator lint my_program.asm`, which reports
+//! unreachable code, unused labels and registers that are written but never read,
+//! together with their line numbers. Each lint can be skipped using
+//! `--allow unreachable-code`, `--allow unused-label` or `--allow unread-register`.
+//! Add `--stats` to print how often each instruction is used in the program.
+//!
+//! `2a-emulator list my_program.asm` prints a listing of the program, like the
+//! `.lst` file of an assembler. Every source line is preceded by its address and
+//! the bytes it places into memory, i.e. `02: 20 FB              JR LOOP`. Gaps
+//! left by `.ORG` and `.ALIGN` are shown as the number of zeros filling them.
+//! The blocks of a program follow each other in memory, thus every `.ORG` must
+//! use a higher address than the code before it.
 //!
 //! ### `interactive` mode
 //!
@@ -94,7 +118,16 @@
 //!
 //! - `load` *`PATH`* will load the program given by `PATH`. The file is opened and
 //!   the syntax verified. It will then be compiled to byte code and loaded into the
-//!   main memory.
+//!   main memory. A bare `load` opens a file browser in the current directory
+//!   instead. Use the arrow keys to select an entry, `Enter` to open a directory
+//!   or load a file, and `Escape` to cancel.
+//! - `load-overlay` *`PATH`* compiles the program given by `PATH` and writes its
+//!   bytes into the main memory, without resetting the machine. Registers, inputs
+//!   and the rest of the memory are kept, which allows adding data to a loaded
+//!   program. The gaps left by `.ORG` are not written. A warning is shown, if the
+//!   overlay overwrites the instruction the program counter points to.
+//! - `dump` *`PATH`* writes the main memory to the file at `PATH`. Files ending in
+//!   `.hex` are written as Intel HEX, all other files contain the raw bytes.
 //! - `set` *`VAL`* will update an internal variable. Boolean variables can be changed
 //!   with `set BOOL_VAR`. Non-boolean variables need a more explicit syntax of
 //!   `set NON_BOOL_VAR = VALUE`.
@@ -114,28 +147,69 @@
 //!     it is not prefixed with either `0x` or `0b` which will result in a hexadecimal
 //!     or binary interpretation.
 //!   - `IRG =`*`byte`* refers to updating the value of the 8-bit input register
-//!     that is located on the extension board MR2DA2.
+//!     that is located on the extension board MR2DA2. `DI1` is accepted as an alias,
+//!     named after the digital input P-DI1 that feeds this register.
 //!   - `TEMP =`*`voltage`* changes the voltage of the temperature sensor. The voltage
 //!     is clamped between `0.0` and `5.0` Volts and emulates the possible voltage levels
 //!     that would be delivered by the temperature sensor on the real
 //!     MR2DA2 extension board.
 //!   - `I1`/`I2 =`*`voltage`* updates the voltage at the analog input pin on the
 //!     MR2DA2 extension board. `voltage` is clamped between `0.0` and `5.0` Volts.
+//!     `AI1`/`AI2` are accepted as aliases.
+//!   - `STEP =`*`mode`* changes the step mode of the clock. `asm` steps a whole
+//!     assembly instruction at a time, `real` steps a single clock cycle.
 //!
 //! - `unset` *`VAL`* will set a boolean variable to `false`. See `set` for a list of
 //!   boolean variables.
+//! - `reg` *`REG`*` =`*`byte`* sets a CPU register to the given 8-bit value.
+//!   `REG` is one of `R0`, `R1`, `R2`, `R4`/`FR`, `R5`/`SP`, `R6` and `R7`.
+//!   This bypasses the normal program execution, changing the flag register or
+//!   the stackpointer might leave the machine in a state that no program could
+//!   have produced. The stackpointer is still verified on the next register write.
+//!   The program counter (`R3`/`PC`) can only be set with the explicit form
+//!   `reg! PC =`*`byte`*, as this will most likely break the current instruction.
 //! - `show` *`PART`* selects a part to be displayed beneath the main I/O registers.
 //!   Selectable parts are:
 //!
 //!   - `register`, the default part which is the register block containing the eight
 //!     registers.
 //!   - `memory`, the main memory which has a size of `0xF0` bytes and can be accessed
-//!     from addresses `0x00` to `0xEF`.
+//!     from addresses `0x00` to `0xEF`. If the terminal is too small to display the
+//!     memory, the registers are shown instead.
+//!   - `interrupts`, the interrupt sources decoded from the MICR and MISR. For every
+//!     source it shows whether it is enabled and whether an interrupt is pending or
+//!     requested. The panel is dimmed while no interrupt source is enabled.
 //!
 //! - `next` *`<N>`* emulates the next `N` cycles. If `N` is omitted, `1` is used. Thus `next`, `next 1` and hitting enter once are equivalent.
+//! - `next-asm` *`<N>`* executes the next `N` assembly instructions, even if the step mode is set to cycles. If `N` is omitted, `1` is used.
+//!
+//! - `break` *`ADDR|LABEL`* toggles a breakpoint at the given address or at the
+//!   address of the given label of the loaded program, i.e. `break 0x12` or
+//!   `break LOOP`. Breakpoints are marked with `●` in the program panel and stop
+//!   the auto run mode. When a program is reloaded, breakpoints set by label move
+//!   along with their label, breakpoints set by address stay where they are.
+//!
+//! - `clear` *`breakpoints|all`* removes all breakpoints. `clear all` removes
+//!   everything that can be cleared, which are only the breakpoints for now.
+//!   `Ctrl+K` is a shortcut for `clear all`.
+//!
+//! - `rerun` resets the machine and loads the current program again, without
+//!   reading the file from disk. The input registers and breakpoints are kept.
+//!   `Ctrl+N` is a shortcut for `rerun`.
+//!
+//! - `fps` *`N`* draws `N` frames per second. The emulated clock frequency is not
+//!   affected, the cycles are distributed over the frames. Lowering the frame rate
+//!   reduces the cost of redrawing on slow terminals. The `--fps` flag sets the
+//!   initial frame rate, `--cycles-per-frame` changes the number of cycles emulated
+//!   per frame.
 //!
 //! - `quit` which lives up to it's name.
 //!
+//! With `--mouse`, the interactive session captures the mouse. Clicking a program
+//! line toggles a breakpoint at its address, marked with `●`. Auto run mode stops
+//! when the machine reaches a breakpoint. Clicking a memory cell highlights it.
+//! Mouse capture is disabled by default, since some terminals misbehave with it.
+//!
 //! ### `run`ning programs
 //!
 //! Using `2a-emulator run` it is possible to run assembler programs without
@@ -175,8 +249,63 @@
 //! and the state of the machine after executing these cycles. In our example the machine
 //! is still `running`, alternatives are `stopped` and `error`.
 //!
+//! To emulate generated code, pass `-` as the program to read it from stdin,
+//! i.e. `generate-program | 2a-emulator run - 100`. The program is then shown as
+//! `<stdin>` in the results.
+//!
+//! Adding `--coverage` additionally prints how many of the program's instructions
+//! were executed at least once and lists the source lines that were never reached.
+//!
+//! Adding `--list` prints a listing of the program after the results, like the
+//! `list` subcommand does. The addresses respect `--load-at`.
+//!
+//! To guard against programs that never halt, `--max-time 2s` aborts the emulation
+//! after the given wall-clock time. The state is then shown as `timed out`.
+//!
+//! With `--trace out.csv` the state after every cycle is written to `out.csv`, one
+//! row per cycle with the columns `cycle,PC,R0,R1,R2,SP,FR,FE,FF,state`. Register
+//! values are hexadecimal. This makes it easy to diff a run against an expected trace.
+//!
+//! For scripts, `--oneline` prints the results as a single line, i.e.
+//! `OK cycles=100 state=Running fe=0 ff=52`. The line starts with `FAIL` instead
+//! of `OK` if a verification failed.
+//!
+//! For CI logs, `--plain` prints a snapshot of the machine after the emulation,
+//! containing the summary, all registers and the memory. The snapshot uses neither
+//! colors nor box-drawing characters and omits the time taken, so identical runs
+//! produce identical output.
+//!
+//! `--load-at 0x40` places the compiled program at address `0x40` instead of `0x00`
+//! and starts executing there. This does not change the addresses calculated
+//! for labels, which are still based on `.ORG`. Thus only position independent
+//! code, using relative jumps but no `JMP`, `CALL` or label addresses, works as
+//! expected. A `--reset` restarts the program at `0x00`. The whole program must
+//! fit into the RAM (`00 - EF`) behind the load address.
+//!
+//! `--mem-init 10:DEADBEEF` writes the bytes `DE AD BE EF` to the memory starting
+//! at address `0x10`, after the program was loaded and before the emulation starts.
+//! The address and the bytes are hexadecimal. The option can be given multiple
+//! times, the bytes must fit into the RAM (`00 - EF`).
+//!
+//! When compiled with the `watch` feature, `--watch` runs the program again
+//! whenever its source file is saved, printing fresh results every time.
+//! Press Ctrl-C to stop watching.
+//!
 //! **See `2a-emulator run --help` for a full list of options.**
 //!
+//! To run many programs at once, i.e. all solutions of a course, use
+//! `2a-emulator run-batch DIR CYCLES`. Every `.asm` file in `DIR` is emulated with
+//! the same configuration and the results are printed sorted by file name.
+//! A program that fails does not prevent the others from running, its error is
+//! printed to stderr. Compile with the `parallel` feature to emulate the programs
+//! concurrently.
+//!
+//! To check whether a program behaves like a reference solution, use
+//! `2a-emulator compare PROGRAM REFERENCE CYCLES`. Both programs are emulated with
+//! the same configuration and the final output registers and machine states are
+//! compared. Add `--registers` and `--memory` to compare those aswell. All
+//! differences are printed and the exit code is 1 if any were found.
+//!
 //! ### `verify`ing programs
 //!
 //! Basic functionality exists to verify programs. As of yet only three
@@ -220,7 +349,23 @@
 //! ```
 //!
 //! Additionally the exit code of the program is non-zero, which marks that something
-//! failed. This can be used to build more complex verifications using shell scripts.
+//! failed. This can be used to build more complex verifications using shell scripts. Pass
+//! `--quiet` (or `-q`) to skip the summary, only errors are printed to stderr then.
+//!
+//! Colors are disabled with `--no-color` or by setting the `NO_COLOR` environment
+//! variable, which keeps the output of scripts free of escape sequences.
+//!
+//! The final state only tells how the run ended. Adding `--never-error` fails the
+//! verification if the machine error-stopped at any point during the run, even if
+//! a later `--reset` revived it.
+//!
+//! `--balanced-stack` fails the verification if a subroutine returns with a
+//! different stackpointer than it was called with, i.e. because it pushed more
+//! than it popped. The address of the offending `CALL` is reported.
+//!
+//! If the program never writes an output register that `--fe` or `--ff` verifies,
+//! a warning like `FF was never written by the program` is printed, as the
+//! register is only compared against its reset value.
 //!
 //! **See `2a-emulator run verify --help` for a full list of options.**
 //!
@@ -231,6 +376,8 @@
 //!
 //! - `interactive-tui` (*opt-out*) enables the interactive session.
 //!   Without it, no interactive session is possible.
+//! - `parallel` (*opt-in*) emulates the programs given to `run-batch` concurrently.
+//! - `watch` (*opt-in*) enables `run --watch`, which re-runs the program on changes.
 //! - `utf8` (*opt-in*) enables the use of character codes which are supported
 //!   by fewer terminals. Note, that at the moment the difference is marginal.
 //!
@@ -252,14 +399,16 @@ mod runner;
 #[cfg(feature = "interactive-tui")]
 mod tui;
 
-use args::{Args, RunArgs, SubCommand, VerifyArgs};
+use args::{Args, CompareArgs, LintArgs, ListArgs, RunArgs, RunBatchArgs, SubCommand, VerifyArgs};
 use error::Error;
 
 use colored::Colorize;
 use log::error;
 
 use std::{
+    env,
     fs::{self, File},
+    io::{self, Write},
     panic,
     path::Path,
     process,
@@ -270,12 +419,21 @@ fn main(args: Args) {
     let temp_path = std::env::temp_dir().join("2a-emulator.log");
     initialize_logger(&args, &temp_path).expect("Failed to initialize logger");
     register_panic_logger();
+    if helpers::colors_disabled(args.no_color, env::var_os("NO_COLOR").as_deref()) {
+        helpers::disable_colors();
+    }
 
     // Match against the given subcommand and execute the part
     // of the program that is requested.
+    let mut stdout = io::stdout();
+    let mut out = helpers::regular_output(args.quiet, &mut stdout);
     let result: Result<(), Error> = match args.subcommand {
-        Some(SubCommand::Run(args)) => run_runner(&args),
-        Some(SubCommand::Verify(args)) => run_verification(&args),
+        Some(SubCommand::Run(args)) => run_runner(&args, &mut out),
+        Some(SubCommand::RunBatch(args)) => run_batch_runner(&args, &mut out, &mut io::stderr()),
+        Some(SubCommand::Compare(args)) => run_comparison(&args, &mut out),
+        Some(SubCommand::Verify(args)) => run_verification(&args, &mut out),
+        Some(SubCommand::Lint(args)) => run_lint(&args, &mut out),
+        Some(SubCommand::List(args)) => run_listing(&args, &mut out),
         #[cfg(feature = "interactive-tui")]
         Some(SubCommand::Interactive(args)) => run_interactive_session(&args, &temp_path),
         #[cfg(feature = "interactive-tui")]
@@ -327,7 +485,12 @@ fn initialize_logger(args: &Args, path: &Path) -> Result<(), fern::InitError> {
         .level(args.verbosity.to_level_filter())
         .chain(File::create(path)?);
     match args.subcommand {
-        Some(SubCommand::Run(_)) | Some(SubCommand::Verify(_)) => {
+        Some(SubCommand::Run(_))
+        | Some(SubCommand::RunBatch(_))
+        | Some(SubCommand::Compare(_))
+        | Some(SubCommand::Verify(_))
+        | Some(SubCommand::Lint(_))
+        | Some(SubCommand::List(_)) => {
             // If we're not in interactive mode, output to stdout aswell
             dispatch = dispatch.chain(std::io::stderr())
         }
@@ -343,12 +506,42 @@ fn initialize_logger(args: &Args, path: &Path) -> Result<(), fern::InitError> {
     Ok(())
 }
 
-fn run_runner(args: &RunArgs) -> Result<(), Error> {
-    runner::execute_runner_with_args_and_print_results(args)
+fn run_runner(args: &RunArgs, out: &mut dyn Write) -> Result<(), Error> {
+    #[cfg(feature = "watch")]
+    if args.watch {
+        return runner::watch_runner_with_args_and_print_results(args, out);
+    }
+    runner::execute_runner_with_args_and_print_results(args, out)
+}
+
+fn run_batch_runner(
+    args: &RunBatchArgs,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<(), Error> {
+    runner::execute_batch_with_args_and_print_results(args, out, err)
+}
+
+fn run_comparison(args: &CompareArgs, out: &mut dyn Write) -> Result<(), Error> {
+    runner::execute_comparison_with_args_and_print_results(args, out)
+}
+
+fn run_verification(args: &VerifyArgs, out: &mut dyn Write) -> Result<(), Error> {
+    helpers::load_and_verify_source_file(&args.program, args.deny_warnings, out)
+}
+
+fn run_lint(args: &LintArgs, out: &mut dyn Write) -> Result<(), Error> {
+    helpers::lint_source_file(
+        &args.program,
+        &args.allow,
+        args.deny_warnings,
+        args.stats,
+        out,
+    )
 }
 
-fn run_verification(args: &VerifyArgs) -> Result<(), Error> {
-    helpers::load_and_verify_source_file(&args.program)
+fn run_listing(args: &ListArgs, out: &mut dyn Write) -> Result<(), Error> {
+    helpers::list_source_file(&args.program, out)
 }
 
 #[cfg(feature = "interactive-tui")]
@@ -1,69 +1,436 @@
 use colored::Colorize;
 use emulator_2a_lib::{
-    machine::State,
-    runner::{RunExpectations, RunResults, RunnerConfigBuilder, VerificationError},
+    compiler::Translator,
+    machine::{Machine, MachineDiff, RegisterNumber, State},
+    parser::AsmParser,
+    runner::{
+        self, RunExpectations, RunResults, RunnerConfig, RunnerConfigBuilder, VerificationError,
+    },
 };
 use humantime::format_duration;
 use log::trace;
 
-use std::{fmt, fs::read_to_string};
+use std::{
+    ffi::OsStr,
+    fmt,
+    fs::{read_dir, read_to_string, File},
+    io::{self, BufWriter, Error as IOError, Read, Write},
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    args::{RunArgs, RunVerifySubcommand},
+    args::{CompareArgs, MemoryInit, RunArgs, RunBatchArgs, RunVerifySubcommand},
     error::Error,
 };
 
-pub fn execute_runner_with_args_and_print_results(args: &RunArgs) -> Result<(), Error> {
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "watch")]
+pub use watch::watch_runner_with_args_and_print_results;
+
+/// Path given as program to read it from stdin.
+const STDIN_PATH: &str = "-";
+/// Name printed instead of the path of programs read from stdin.
+const STDIN_NAME: &str = "<stdin>";
+/// First line of the execution trace written by `run --trace`.
+const TRACE_HEADER: &str = "cycle,PC,R0,R1,R2,SP,FR,FE,FF,state";
+
+/// Run the program given by `args`, printing the results to `out`.
+///
+/// If the program is `-`, it is read from stdin.
+pub fn execute_runner_with_args_and_print_results(
+    args: &RunArgs,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    execute_runner_with_stdin(args, &mut io::stdin(), out)
+}
+
+/// Like [`execute_runner_with_args_and_print_results`], reading `-` from `stdin`.
+fn execute_runner_with_stdin(
+    args: &RunArgs,
+    stdin: &mut dyn Read,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
     trace!("Constructing Runner..");
-    let program = read_to_string(&args.program)?;
-    let config = RunnerConfigBuilder::default()
-        .with_machine_config(args.init.clone().into())
+    let (program, path) = if args.program == Path::new(STDIN_PATH) {
+        let mut program = String::new();
+        stdin.read_to_string(&mut program)?;
+        (program, PathBuf::from(STDIN_NAME))
+    } else {
+        (read_to_string(&args.program)?, args.program.clone())
+    };
+    let mut builder = RunnerConfigBuilder::default();
+    builder
+        .with_machine_config(args.init.machine_config()?)
         .with_max_cycles(args.cycles)
         .with_resets(args.resets.clone())
         .with_interrupts(args.interrupts.clone())
-        .with_program(&program)
-        .build()
-        .expect("Failed to create RunnerConfig");
+        .with_load_address(args.load_at)
+        .with_preload(
+            args.mem_init
+                .iter()
+                .flat_map(MemoryInit::preload)
+                .collect::<Vec<_>>(),
+        )
+        .with_program(&program);
+    if let Some(max_time) = args.max_time {
+        builder.with_max_time(max_time);
+    }
+    let config = builder.build().expect("Failed to create RunnerConfig");
     trace!("Running Runner..");
-    let results = config.run()?;
+    let results = match args.trace {
+        Some(ref trace_path) => {
+            let file = File::create(trace_path).map_err(Error::WritingTrace)?;
+            run_with_csv_trace(&config, &path, BufWriter::new(file))?
+        }
+        None => config
+            .run()
+            .map_err(|e| Error::InvalidProgram(path.clone(), e))?,
+    };
     let status: Result<(), VerificationError> =
         if let Some(RunVerifySubcommand::Verify(verify_args)) = args.verify.clone() {
             trace!("Constructing expectations..");
             let expectations: RunExpectations = verify_args.into();
+            if !args.oneline {
+                print_verification_warnings(out, &expectations, &results, args.plain)
+                    .map_err(Error::WritingOutput)?;
+            }
             expectations.verify(&results)
         } else {
             Ok(())
         };
-    print_run_results(args, &results);
+    if args.oneline {
+        writeln!(out, "{}", oneline_summary(&results, status.is_ok()))
+            .map_err(Error::WritingOutput)?;
+    } else if args.plain {
+        print_plain_snapshot(out, &path, &results).map_err(Error::WritingOutput)?;
+    } else {
+        print_run_results(out, &path, &results).map_err(Error::WritingOutput)?;
+    }
+    if args.list {
+        let bytecode = Translator::compile(&AsmParser::parse(&program)?)?;
+        write!(out, "{}", bytecode.listing_at(args.load_at)).map_err(Error::WritingOutput)?;
+    }
+    if args.coverage {
+        print_coverage(out, &program, &results).map_err(Error::WritingOutput)?;
+    }
     Ok(status?)
 }
 
-fn print_run_results(args: &RunArgs, res: &RunResults) {
+/// Run all programs given by `args`, printing the results to `out`.
+///
+/// Programs that fail are reported to `err`, together with their path.
+pub fn execute_batch_with_args_and_print_results(
+    args: &RunBatchArgs,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> Result<(), Error> {
+    trace!("Collecting programs..");
+    let mut paths: Vec<PathBuf> = read_dir(&args.directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().and_then(OsStr::to_str) == Some("asm"))
+        .collect();
+    paths.sort();
+    let programs: Vec<_> = paths.iter().map(read_to_string).collect();
+    trace!("Constructing Runners..");
+    let machine_config = args.init.machine_config()?;
+    let configs: Vec<_> = programs
+        .iter()
+        .filter_map(|program| program.as_ref().ok())
+        .map(|program| {
+            RunnerConfigBuilder::default()
+                .with_machine_config(machine_config.clone())
+                .with_max_cycles(args.cycles)
+                .with_program(program)
+                .build()
+                .expect("Failed to create RunnerConfig")
+        })
+        .collect();
+    let expectations: Option<RunExpectations> = args
+        .verify
+        .clone()
+        .map(|RunVerifySubcommand::Verify(verify_args)| verify_args.into());
+    trace!("Running {} Runners..", configs.len());
+    let mut results = runner::run_batch(&configs).into_iter();
+    let mut failed = 0;
+    for (path, program) in paths.iter().zip(&programs) {
+        writeln!(out, "Program: {}", path.to_string_lossy()).map_err(Error::WritingOutput)?;
+        let status = match program {
+            Ok(_) => match results.next().expect("BUG: Missing result of batch run") {
+                Ok(results) => {
+                    print_run_summary(out, &results).map_err(Error::WritingOutput)?;
+                    match expectations {
+                        Some(ref expectations) => {
+                            print_verification_warnings(out, expectations, &results, false)
+                                .map_err(Error::WritingOutput)?;
+                            expectations.verify(&results).map_err(Error::from)
+                        }
+                        None => Ok(()),
+                    }
+                }
+                Err(e) => Err(Error::from(e)),
+            },
+            Err(e) => Err(Error::OpeningSourceFile(IOError::new(
+                e.kind(),
+                e.to_string(),
+            ))),
+        };
+        if let Err(e) = status {
+            failed += 1;
+            writeln!(
+                err,
+                "{} in {}: {}",
+                "Error".red().bold(),
+                path.to_string_lossy(),
+                e
+            )
+            .map_err(Error::WritingOutput)?;
+        }
+        writeln!(out).map_err(Error::WritingOutput)?;
+    }
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(Error::BatchFailed(failed))
+    }
+}
+
+/// Run the program and the reference given by `args`, printing their differences to `out`.
+pub fn execute_comparison_with_args_and_print_results(
+    args: &CompareArgs,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    trace!("Constructing Runners..");
+    let program = read_to_string(&args.program)?;
+    let reference = read_to_string(&args.reference)?;
+    let machine_config = args.init.machine_config()?;
+    let config_for = |program| {
+        RunnerConfigBuilder::default()
+            .with_machine_config(machine_config.clone())
+            .with_max_cycles(args.cycles)
+            .with_program(program)
+            .build()
+            .expect("Failed to create RunnerConfig")
+    };
+    let (program_config, reference_config) = (config_for(&program), config_for(&reference));
+    trace!("Running Runners..");
+    let program_results = program_config
+        .run()
+        .map_err(|e| Error::InvalidProgram(args.program.clone(), e))?;
+    let reference_results = reference_config
+        .run()
+        .map_err(|e| Error::InvalidProgram(args.reference.clone(), e))?;
+    print_run_results(out, &args.program, &program_results).map_err(Error::WritingOutput)?;
+    print_run_results(out, &args.reference, &reference_results).map_err(Error::WritingOutput)?;
+    let mut diff = program_results.machine.diff(&reference_results.machine);
+    if !args.registers {
+        diff.registers.clear();
+        diff.flags = None;
+    }
+    if !args.memory {
+        diff.memory.clear();
+    }
+    print_diff(out, &diff).map_err(Error::WritingOutput)?;
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::ProgramsDiffer)
+    }
+}
+
+/// Print the differences between the program and the reference.
+fn print_diff(out: &mut dyn Write, diff: &MachineDiff) -> io::Result<()> {
+    if diff.is_empty() {
+        writeln!(out, "No differences found")
+    } else {
+        writeln!(out, "Differences (program -> reference):")?;
+        write!(out, "{}", diff)
+    }
+}
+
+/// Print all warnings about the given expectations.
+///
+/// If `plain` is set, the warnings are not colored.
+fn print_verification_warnings(
+    out: &mut dyn Write,
+    expectations: &RunExpectations,
+    results: &RunResults,
+    plain: bool,
+) -> io::Result<()> {
+    let label = if plain {
+        "Warning".to_owned()
+    } else {
+        "Warning".yellow().bold().to_string()
+    };
+    for warning in expectations.warnings(results) {
+        writeln!(out, "{}: {}", label, warning)?;
+    }
+    Ok(())
+}
+
+/// Run the given config, writing one CSV row per cycle to `writer`.
+///
+/// The `path` of the program is only used to report invalid programs.
+fn run_with_csv_trace<'a, W: Write>(
+    config: &'a RunnerConfig<'a>,
+    path: &Path,
+    mut writer: W,
+) -> Result<RunResults<'a>, Error> {
+    writeln!(writer, "{}", TRACE_HEADER).map_err(Error::WritingTrace)?;
+    // Remember the first failure, the runner cannot be aborted from the callback
+    let mut status = Ok(());
+    let results = config
+        .run_with_trace(|cycle, machine| {
+            if status.is_ok() {
+                status = write_trace_row(&mut writer, cycle, machine);
+            }
+        })
+        .map_err(|e| Error::InvalidProgram(path.to_owned(), e))?;
+    status
+        .and_then(|_| writer.flush())
+        .map_err(Error::WritingTrace)?;
+    Ok(results)
+}
+
+/// Write the state of `machine` after the given cycle as a CSV row.
+fn write_trace_row<W: Write>(writer: &mut W, cycle: usize, machine: &Machine) -> io::Result<()> {
+    let reg = |number| *machine.registers().get(number);
+    writeln!(
+        writer,
+        "{},0x{:02X},0x{:02X},0x{:02X},0x{:02X},0x{:02X},0x{:02X},0x{:02X},0x{:02X},{:?}",
+        cycle,
+        reg(RegisterNumber::R3),
+        reg(RegisterNumber::R0),
+        reg(RegisterNumber::R1),
+        reg(RegisterNumber::R2),
+        reg(RegisterNumber::R5),
+        reg(RegisterNumber::R4),
+        machine.output_fe(),
+        machine.output_ff(),
+        machine.state(),
+    )
+}
+
+fn print_run_results(out: &mut dyn Write, program: &Path, res: &RunResults) -> io::Result<()> {
     trace!("Printing Runner results..");
-    println!("Program: {}", args.program.to_string_lossy());
-    println!("Time:    {}", format_duration(res.time_taken));
-    println!(
+    writeln!(out, "Program: {}", program.to_string_lossy())?;
+    print_run_summary(out, res)?;
+    writeln!(out)
+}
+
+fn print_run_summary(out: &mut dyn Write, res: &RunResults) -> io::Result<()> {
+    writeln!(out, "Time:    {}", format_duration(res.time_taken))?;
+    writeln!(
+        out,
         "Cycles:  {}/{}",
         hl_if_not(&res.emulated_cycles, &res.config.max_cycles),
         res.config.max_cycles
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "State:   {}",
         match res.machine.state() {
-            State::Running => "Running".to_owned(),
-            State::Stopped => format!("{}", "Stopped".bright_yellow()),
-            State::ErrorStopped => format!("{}", "Error".bright_red()),
+            State::Running if res.timed_out => format!("{}", "Timed out".bright_red()),
+            state @ State::Running => state.to_string(),
+            state @ State::Stopped => format!("{}", state.to_string().bright_yellow()),
+            state @ State::ErrorStopped => format!("{}", state.to_string().bright_red()),
         }
-    );
-    println!(
+    )?;
+    writeln!(
+        out,
         "Output:  FE: {}",
-        hl_if_not(&res.machine.bus().output_fe(), &0)
-    );
-    println!(
+        hl_if_not(&res.machine.output_fe(), &0)
+    )?;
+    writeln!(
+        out,
         "         FF: {}",
-        hl_if_not(&res.machine.bus().output_ff(), &0)
-    );
-    println!()
+        hl_if_not(&res.machine.output_ff(), &0)
+    )
+}
+
+/// Print the results as plain text, as printed by `run --plain`.
+///
+/// Unlike [`print_run_results`] this includes the registers and the memory,
+/// laid out like in the interactive session. The time taken is omitted, to keep
+/// the output of identical runs identical.
+fn print_plain_snapshot(out: &mut dyn Write, program: &Path, res: &RunResults) -> io::Result<()> {
+    let machine = &res.machine;
+    writeln!(out, "Program: {}", program.to_string_lossy())?;
+    writeln!(
+        out,
+        "Cycles:  {}/{}",
+        res.emulated_cycles, res.config.max_cycles
+    )?;
+    let state = match machine.state() {
+        State::Running if res.timed_out => "Timed out".to_owned(),
+        state => state.to_string(),
+    };
+    writeln!(out, "State:   {}", state)?;
+    writeln!(out, "Output:  FE: {}", machine.output_fe())?;
+    writeln!(out, "         FF: {}", machine.output_ff())?;
+    writeln!(out, "Registers:")?;
+    for (index, content) in machine.registers().content().iter().enumerate() {
+        let name = match index {
+            3 => "PC".to_owned(),
+            4 => "FR".to_owned(),
+            5 => "SP".to_owned(),
+            i => format!("R{}", i),
+        };
+        writeln!(out, "{} 0x{:02X}", name, content)?;
+    }
+    writeln!(out, "Memory:")?;
+    write!(out, "  ")?;
+    for column in 0..0x10 {
+        write!(out, " _{:X}", column)?;
+    }
+    writeln!(out)?;
+    for (row, bytes) in machine.memory().chunks(0x10).enumerate() {
+        write!(out, "{:X}_", row)?;
+        for byte in bytes {
+            write!(out, " {:02X}", byte)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Summarize the results in a single line, as printed by `run --oneline`.
+///
+/// `passed` selects the `OK` or `FAIL` prefix.
+fn oneline_summary(res: &RunResults, passed: bool) -> String {
+    format!(
+        "{} cycles={} state={:?} fe={} ff={}",
+        if passed { "OK" } else { "FAIL" },
+        res.emulated_cycles,
+        res.machine.state(),
+        res.machine.output_fe(),
+        res.machine.output_ff(),
+    )
+}
+
+fn print_coverage(out: &mut dyn Write, program: &str, res: &RunResults) -> io::Result<()> {
+    let covered = res.covered_lines().len();
+    let total = res.executable_lines().len();
+    writeln!(
+        out,
+        "Coverage: {}/{} lines",
+        hl_if_not(&covered, &total),
+        total
+    )?;
+    let uncovered = res.uncovered_lines();
+    if !uncovered.is_empty() {
+        writeln!(out, "Not executed:")?;
+        let source: Vec<_> = program.lines().collect();
+        for line in uncovered {
+            let text = source
+                .get(line - 1)
+                .map(|text| text.trim())
+                .unwrap_or_default();
+            writeln!(out, "  {:>4}: {}", line, text)?;
+        }
+    }
+    writeln!(out)
 }
 
 fn hl_if_not<T>(val: &T, cmp: &T) -> String
@@ -79,29 +446,386 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::args::{InitialMachineConfiguration, RunVerifyArgs};
+    use emulator_2a_lib::runner::RunnerError;
+    use structopt::StructOpt;
+
+    use crate::{
+        args::{
+            Args, InitialMachineConfiguration, InitialMachineConfigurationBuilder, RunVerifyArgs,
+        },
+        helpers::{
+            self,
+            testing::{run_args, run_batch_args, TempDir, TempFile},
+        },
+    };
 
     use super::*;
 
     #[test]
     fn flags_are_not_ignored_if_program_is_given() {
         let run_args = RunArgs {
-            init: InitialMachineConfiguration {
-                fc: 1,
-                fd: 2,
-                fe: 3,
-                ff: 4,
-                ..Default::default()
-            },
+            init: InitialMachineConfigurationBuilder::default()
+                .fc(1)
+                .fd(2)
+                .fe(3)
+                .ff(4)
+                .build()
+                .unwrap(),
             program: "../testing/programs/26-specific-input.asm".into(),
             cycles: 1000,
             resets: vec![],
             interrupts: vec![],
+            max_time: None,
+            coverage: false,
+            trace: None,
+            load_at: 0,
+            mem_init: vec![],
+            oneline: false,
+            plain: false,
+            list: false,
+            #[cfg(feature = "watch")]
+            watch: false,
             verify: Some(RunVerifySubcommand::Verify(RunVerifyArgs {
                 state: Some(State::Running),
                 ..Default::default()
             })),
         };
-        execute_runner_with_args_and_print_results(&run_args).unwrap();
+        execute_runner_with_args_and_print_results(&run_args, &mut io::sink()).unwrap();
+    }
+
+    #[test]
+    fn programs_are_read_from_stdin() {
+        let run_args = run_args(&["2a-emulator", "run", "-", "100"]);
+        let mut stdin = "#! mrasm\n    MOV R0, 0x2A\n    ST (0xFF), R0\n    STOP\n".as_bytes();
+        let mut stdout = vec![];
+        execute_runner_with_stdin(&run_args, &mut stdin, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.starts_with("Program: <stdin>\n"));
+        assert!(stdout.contains("42"));
+        // Errors name stdin, too
+        let mut stdin = "#! mrasm\n    FOO R0\n".as_bytes();
+        let result = execute_runner_with_stdin(&run_args, &mut stdin, &mut io::sink());
+        let message = result
+            .expect_err("Invalid program was accepted")
+            .to_string();
+        assert!(message.starts_with("Invalid syntax in program <stdin>"));
+    }
+
+    #[test]
+    fn listings_are_printed_at_the_load_address() {
+        let run_args = run_args(&[
+            "2a-emulator",
+            "run",
+            "--list",
+            "--load-at",
+            "0x10",
+            "-",
+            "100",
+        ]);
+        let mut stdin = "#! mrasm\nLOOP:\n    INC R0\n    JR LOOP\n".as_bytes();
+        let mut stdout = vec![];
+        execute_runner_with_stdin(&run_args, &mut stdin, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("\n10:                LOOP:\n10: 44                 INC R0\n"));
+        assert!(stdout.contains("\n11: 20 FD              JR LOOP\n"));
+    }
+
+    #[test]
+    fn memory_is_initialized_before_the_emulation() {
+        let run_args = run_args(&["2a-emulator", "run", "--mem-init", "80:2A", "-", "100"]);
+        let mut stdin = "#! mrasm
+    LD R0, (0x80)
+    ST (0xFF), R0
+    STOP
+"
+        .as_bytes();
+        let mut stdout = vec![];
+        execute_runner_with_stdin(&run_args, &mut stdin, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("FF: 42"), "{}", stdout);
+    }
+
+    #[test]
+    fn disabled_colors_leave_no_escape_sequences() {
+        helpers::disable_colors();
+        let run_args = run_args(&[
+            "2a-emulator",
+            "--no-color",
+            "run",
+            "-",
+            "100",
+            "verify",
+            "--ff",
+            "0x00",
+        ]);
+        let mut stdin = "#! mrasm\n    MOV R0, 0x2A\n    ST (0xFF), R0\n    STOP\n".as_bytes();
+        let mut stdout = vec![];
+        let result = execute_runner_with_stdin(&run_args, &mut stdin, &mut stdout);
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("42"));
+        assert!(!stdout.contains('\x1b'));
+        let message = result.expect_err("Verification succeeded").to_string();
+        assert!(!message.contains('\x1b'));
+    }
+
+    #[test]
+    fn invalid_programs_are_reported_with_their_path() {
+        let program = TempFile::new(
+            "2a-emulator-invalid-program.asm",
+            "#! mrasm\n    CLR R0\n    FOO R0\n",
+        );
+        let run_args = run_args(&["2a-emulator", "run", program.arg(), "100"]);
+        let result = execute_runner_with_args_and_print_results(&run_args, &mut io::sink());
+        let error = result.expect_err("Invalid program was accepted");
+        assert!(matches!(error, Error::InvalidProgram(ref p, _) if p == program.path()));
+        let message = error.to_string();
+        assert!(message.starts_with("Invalid syntax in program"));
+        assert!(message.contains("2a-emulator-invalid-program.asm"));
+        // The line and column of the error are kept
+        assert!(message.contains("--> 3:"));
+    }
+
+    #[test]
+    fn programs_overflowing_the_load_address_are_rejected() {
+        let argv = |address| ["2a-emulator", "run", "--load-at", address, "-", "10"];
+        assert!(Args::from_iter_safe(&argv("0xFE")).is_err());
+        assert!(Args::from_iter_safe(&argv("0xF0")).is_err());
+        let run_args = run_args(&argv("0xEE"));
+        let mut stdin = "#! mrasm\n    INC R0\n    INC R0\n    STOP\n".as_bytes();
+        let result = execute_runner_with_stdin(&run_args, &mut stdin, &mut io::sink());
+        let error = result.expect_err("The program does not fit");
+        assert!(matches!(
+            error,
+            Error::InvalidProgram(_, RunnerError::ProgramTooLarge { .. })
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("Failed to load program <stdin>"));
+    }
+
+    #[test]
+    fn uncompilable_programs_are_reported_as_such() {
+        let program = TempFile::new(
+            "2a-emulator-uncompilable-program.asm",
+            "#! mrasm\n    .ORG 0xFF\n    STOP\n",
+        );
+        let run_args = run_args(&["2a-emulator", "run", program.arg(), "100"]);
+        let result = execute_runner_with_args_and_print_results(&run_args, &mut io::sink());
+        let message = result
+            .expect_err("Invalid program was accepted")
+            .to_string();
+        assert!(message.starts_with("Failed to compile program"));
+        assert!(message.contains("2a-emulator-uncompilable-program.asm"));
+        assert!(message.contains("does not fit into memory"));
+    }
+
+    #[test]
+    fn batch_failures_are_reported_separately() {
+        let dir = TempDir::new("2a-emulator-batch");
+        dir.file(
+            "a-passing.asm",
+            "#! mrasm\n    MOV (0xFF), 0x2A\n    STOP\n",
+        );
+        dir.file("b-failing.asm", "#! mrasm\n    FOO R0\n");
+        let argv = ["2a-emulator", "run-batch", dir.arg(), "100"];
+        let batch_args = run_batch_args(&argv);
+        let (mut stdout, mut stderr) = (vec![], vec![]);
+        let result =
+            execute_batch_with_args_and_print_results(&batch_args, &mut stdout, &mut stderr);
+        assert!(matches!(result, Err(Error::BatchFailed(1))));
+        let stdout = String::from_utf8(stdout).unwrap();
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert!(stdout.contains("a-passing.asm"));
+        assert!(stdout.contains("b-failing.asm"));
+        assert!(!stdout.contains("Error"));
+        assert!(stderr.contains("Error"));
+        assert!(!stderr.contains("a-passing.asm"));
+        assert!(stderr.contains("b-failing.asm:"));
+        assert!(stderr.contains("--> 2:"));
+    }
+
+    #[test]
+    fn quiet_runs_print_nothing() {
+        let run = |quiet: &[&str]| {
+            let mut cmd = vec!["2a-emulator"];
+            cmd.extend(quiet);
+            cmd.extend(&["run", "../testing/programs/26-specific-input.asm", "100"]);
+            cmd.extend(&["verify", "--state", "stopped"]);
+            let quiet = Args::from_iter_safe(&cmd).expect("Invalid arguments").quiet;
+            let run_args = run_args(&cmd);
+            let mut stdout = vec![];
+            let result = execute_runner_with_args_and_print_results(
+                &run_args,
+                &mut helpers::regular_output(quiet, &mut stdout),
+            );
+            (result, stdout)
+        };
+        let (result, stdout) = run(&[]);
+        assert!(result.is_err());
+        assert!(!stdout.is_empty());
+        for quiet in &["--quiet", "-q"] {
+            // The failed verification is still reported
+            let (result, stdout) = run(&[quiet]);
+            assert!(matches!(result, Err(Error::RunVerification(_))));
+            assert!(stdout.is_empty());
+        }
+    }
+
+    #[test]
+    fn oneline_summaries_report_verification() {
+        let run = |expected_ff: &str| {
+            let run_args = run_args(&[
+                "2a-emulator",
+                "run",
+                "--oneline",
+                "../testing/programs/23-just-halt.asm",
+                "100",
+                "verify",
+                "--ff",
+                expected_ff,
+            ]);
+            let mut stdout = vec![];
+            let result = execute_runner_with_args_and_print_results(&run_args, &mut stdout);
+            (result, String::from_utf8(stdout).unwrap())
+        };
+        let (result, stdout) = run("0");
+        assert!(result.is_ok());
+        assert_eq!(stdout, "OK cycles=3 state=Stopped fe=0 ff=0\n");
+        let (result, stdout) = run("52");
+        assert!(matches!(result, Err(Error::RunVerification(_))));
+        assert_eq!(stdout, "FAIL cycles=3 state=Stopped fe=0 ff=0\n");
+    }
+
+    #[test]
+    fn plain_snapshots_contain_registers_and_memory() {
+        let run_args = run_args(&[
+            "2a-emulator",
+            "run",
+            "--plain",
+            "../testing/programs/23-just-halt.asm",
+            "100",
+        ]);
+        let mut stdout = vec![];
+        execute_runner_with_args_and_print_results(&run_args, &mut stdout).unwrap();
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.is_ascii());
+        let empty_row = |row| format!("{:X}_{}", row, " 00".repeat(0x10));
+        let mut expected = vec![
+            "Program: ../testing/programs/23-just-halt.asm".to_owned(),
+            "Cycles:  3/100".to_owned(),
+            "State:   Stopped".to_owned(),
+            "Output:  FE: 0".to_owned(),
+            "         FF: 0".to_owned(),
+            "Registers:".to_owned(),
+            "R0 0x00".to_owned(),
+            "R1 0x00".to_owned(),
+            "R2 0x00".to_owned(),
+            "PC 0x01".to_owned(),
+            "FR 0x00".to_owned(),
+            "SP 0x00".to_owned(),
+            "R6 0x00".to_owned(),
+            "R7 0x00".to_owned(),
+            "Memory:".to_owned(),
+            "   _0 _1 _2 _3 _4 _5 _6 _7 _8 _9 _A _B _C _D _E _F".to_owned(),
+            format!("0_ 01{}", " 00".repeat(0xF)),
+        ];
+        expected.extend((1..0xF).map(empty_row));
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), expected);
+        // Plain snapshots cannot be combined with single line summaries
+        assert!(Args::from_iter_safe(&[
+            "2a-emulator",
+            "run",
+            "--plain",
+            "--oneline",
+            "../testing/programs/23-just-halt.asm",
+            "100",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn programs_are_compared_to_references() {
+        let compare = |program: &str, reference: &str| {
+            let args = CompareArgs {
+                init: InitialMachineConfiguration::default(),
+                program: program.into(),
+                reference: reference.into(),
+                cycles: 100,
+                registers: true,
+                memory: true,
+            };
+            let mut stdout = vec![];
+            let result = execute_comparison_with_args_and_print_results(&args, &mut stdout);
+            (result, String::from_utf8(stdout).unwrap())
+        };
+        let (result, stdout) = compare(
+            "../testing/programs/21-simple-counter.asm",
+            "../testing/programs/22-simple-counter-manually.asm",
+        );
+        assert!(result.is_ok());
+        assert!(stdout.ends_with("No differences found\n"));
+        let (result, stdout) = compare(
+            "../testing/programs/21-simple-counter.asm",
+            "../testing/programs/23-just-halt.asm",
+        );
+        assert!(matches!(result, Err(Error::ProgramsDiffer)));
+        assert!(stdout.contains("State: Running -> Stopped\n"));
+    }
+
+    #[test]
+    fn traced_programs_that_fail_are_reported_by_their_own_name() {
+        let program = TempFile::new(
+            "2a-emulator-trace-bad.asm",
+            "#! mrasm\n    NOT AN INSTRUCTION\n",
+        );
+        // Created by the runner, removed once the test is done
+        let trace = TempFile::new("2a-emulator-trace-bad.csv", "");
+        let run_args = run_args(&[
+            "2a-emulator",
+            "run",
+            "--trace",
+            trace.arg(),
+            program.arg(),
+            "100",
+        ]);
+        let result = execute_runner_with_args_and_print_results(&run_args, &mut io::sink());
+        let err = result.expect_err("The program is invalid");
+        assert!(
+            err.to_string().contains("2a-emulator-trace-bad.asm"),
+            "{}",
+            err
+        );
+        assert!(matches!(err, Error::InvalidProgram(path, _) if path == program.path()));
+    }
+
+    #[test]
+    fn traces_contain_one_row_per_cycle() {
+        let program = "#! mrasm\n    INC R0\n    ST (0xFF), R0\n    STOP\n";
+        let config = RunnerConfigBuilder::default()
+            .with_max_cycles(1_000)
+            .with_program(program)
+            .build()
+            .unwrap();
+        let mut trace = vec![];
+        let results = run_with_csv_trace(&config, Path::new("trace.asm"), &mut trace).unwrap();
+        let trace = String::from_utf8(trace).unwrap();
+        let rows: Vec<_> = trace.lines().collect();
+        assert_eq!(rows.len(), results.emulated_cycles + 1);
+        assert_eq!(
+            rows[..5],
+            [
+                TRACE_HEADER,
+                "1,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,Running",
+                "2,0x00,0x00,0x00,0x00,0x00,0x00,0x00,0x00,Running",
+                "3,0x01,0x00,0x00,0x00,0x00,0x00,0x00,0x00,Running",
+                "4,0x01,0x01,0x00,0x00,0x00,0x00,0x00,0x00,Running",
+            ]
+        );
+        let last = rows.last().unwrap();
+        assert!(
+            last.ends_with(",0x01,Stopped"),
+            "Unexpected last row: {}",
+            last
+        );
     }
 }
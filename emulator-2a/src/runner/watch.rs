@@ -0,0 +1,125 @@
+//! Re-run a program whenever its source file changes.
+use colored::Colorize;
+use log::trace;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+
+use std::{
+    io::Write,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, RecvTimeoutError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use super::execute_runner_with_args_and_print_results;
+use crate::{args::RunArgs, error::Error};
+
+/// Time to wait for further changes, before the program is re-run.
+/// Editors often touch the file multiple times while saving.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(250);
+/// Interval in which a pressed Ctrl-C is noticed while waiting for changes.
+const INTERRUPT_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run the program given by `args` and run it again whenever it changes.
+///
+/// Results are printed to `out` after every run. A program that fails to
+/// compile or verify does not stop the watching, the error is printed instead.
+/// Returns once Ctrl-C is pressed.
+pub fn watch_runner_with_args_and_print_results(
+    args: &RunArgs,
+    out: &mut dyn Write,
+) -> Result<(), Error> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = Arc::clone(&interrupted);
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))
+        .map_err(Error::InterruptHandler)?;
+    let (tx, rx) = channel();
+    let mut watcher = notify::watcher(tx, DEBOUNCE_DELAY).map_err(Error::Watching)?;
+    // Watch the directory, since editors often replace the file while saving
+    let directory = match args.program.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    watcher
+        .watch(directory, RecursiveMode::NonRecursive)
+        .map_err(Error::Watching)?;
+    run_and_print_errors(args, out)?;
+    while !interrupted.load(Ordering::SeqCst) {
+        match rx.recv_timeout(INTERRUPT_CHECK_INTERVAL) {
+            Ok(event) if changes_program(&event, &args.program) => {
+                trace!("Program changed: {:?}", event);
+                writeln!(
+                    out,
+                    "\n{} {}",
+                    "Change detected, re-running".bold(),
+                    args.program.display()
+                )
+                .map_err(Error::WritingOutput)?;
+                run_and_print_errors(args, out)?;
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Ok(())
+}
+
+/// Run the program once, printing all errors but those writing the output.
+fn run_and_print_errors(args: &RunArgs, out: &mut dyn Write) -> Result<(), Error> {
+    match execute_runner_with_args_and_print_results(args, out) {
+        Err(Error::WritingOutput(err)) => Err(Error::WritingOutput(err)),
+        Err(err) => {
+            eprintln!("{}: {}", "Error".red().bold(), err);
+            Ok(())
+        }
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Does the event change the contents of the file at `program`?
+///
+/// Only the file name is compared, as only the directory of the program is watched.
+fn changes_program(event: &DebouncedEvent, program: &Path) -> bool {
+    match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Rename(_, path) => path.file_name() == program.file_name(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    #[test]
+    fn only_changes_of_the_program_are_noticed() {
+        let program = Path::new("solutions/counter.asm");
+        let path = |name: &str| PathBuf::from("/home/student/solutions").join(name);
+        assert!(changes_program(
+            &DebouncedEvent::Write(path("counter.asm")),
+            program
+        ));
+        assert!(changes_program(
+            &DebouncedEvent::Rename(path(".counter.asm.swp"), path("counter.asm")),
+            program
+        ));
+        assert!(!changes_program(
+            &DebouncedEvent::Write(path("other.asm")),
+            program
+        ));
+        assert!(!changes_program(
+            &DebouncedEvent::NoticeWrite(path("counter.asm")),
+            program
+        ));
+        assert!(!changes_program(
+            &DebouncedEvent::Rename(path("counter.asm"), path("counter.asm.bak")),
+            program
+        ));
+    }
+}
@@ -143,23 +143,23 @@ pub fn render_digital_io(area: Rect, buf: &mut Buffer, board: &Board) {
 /// 0.02V  AO2
 /// ```
 pub fn render_analog_io(area: Rect, buf: &mut Buffer, board: &Board) {
-    if *board.temp() != 0.0 {
+    if !board.is_temp_default() {
         let s = format!("{:.2}V TEMP", board.temp());
         buf.set_string(area.right() - 10, area.y + TEMP_OFFSET, s, Style::default());
     }
-    if board.analog_inputs()[0] != 0.0 {
+    if !board.is_analog_input_default(0) {
         let s = format!("{:.2}V  AI1", board.analog_inputs()[0]);
         buf.set_string(area.right() - 10, area.y + AI1_OFFSET, s, Style::default());
     }
-    if board.analog_inputs()[1] != 0.0 {
+    if !board.is_analog_input_default(1) {
         let s = format!("{:.2}V  AI2", board.analog_inputs()[1]);
         buf.set_string(area.right() - 10, area.y + AI2_OFFSET, s, Style::default());
     }
-    if board.analog_outputs()[0] != 0.0 {
+    if !board.is_analog_output_default(0) {
         let s = format!("{:.2}V  AO1", board.analog_outputs()[0]);
         buf.set_string(area.right() - 10, area.y + AO1_OFFSET, s, Style::default());
     }
-    if board.analog_outputs()[1] != 0.0 {
+    if !board.is_analog_output_default(1) {
         let s = format!("{:.2}V  AO2", board.analog_outputs()[1]);
         buf.set_string(area.right() - 10, area.y + AO2_OFFSET, s, Style::default());
     }
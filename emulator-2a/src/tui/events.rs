@@ -1,5 +1,5 @@
 //! TUI I/O events
-use crossterm::event::{self, Event, KeyEvent};
+use crossterm::event::{self, Event};
 
 use std::time::Duration;
 
@@ -12,17 +12,13 @@ impl Events {
         Events
     }
     /// Get the next [`Event`].
+    ///
+    /// This includes key events and terminal resizes. Mouse events
+    /// are only received while mouse capture is enabled.
     pub fn next(&mut self) -> Option<Event> {
         match event::poll(Duration::from_secs(0)) {
             Ok(true) => event::read().ok(),
             _ => None,
         }
     }
-    /// Get the next [`KeyEvent`].
-    pub fn next_key(&mut self) -> Option<KeyEvent> {
-        self.next().and_then(|ie| match ie {
-            Event::Key(ke) => Some(ke),
-            _ => None,
-        })
-    }
 }
@@ -0,0 +1,231 @@
+//! Everything related to the [`FilePickerWidget`], opened by a bare `load`.
+use rustyline::completion::FilenameCompleter;
+use tui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+
+use std::{
+    fs, io,
+    path::{Path, MAIN_SEPARATOR},
+};
+
+use crate::helpers;
+
+/// Hint drawn in the bottom right corner of the picker.
+const KEY_HINT: &str = "<Enter> Select  <Esc> Cancel";
+
+/// A widget for browsing the file system and picking a program to load.
+///
+/// # Example
+///
+/// ```text
+/// Load program from ../testing/programs/
+/// ../
+/// > 01-stacksize-16.asm
+/// 02-stacksize-32.asm
+/// 03-stacksize-48.asm
+///
+///                   <Enter> Select  <Esc> Cancel
+/// ```
+pub struct FilePickerWidget;
+
+/// State needed to draw the [`FilePickerWidget`].
+pub struct FilePickerState {
+    /// The listed directory, relative to the current working directory.
+    /// This is either empty or ends with a separator.
+    directory: String,
+    /// Names of the listed entries. Directories end with a separator.
+    entries: Vec<String>,
+    /// Index of the selected entry.
+    selected: usize,
+}
+
+/// The entry chosen in a [`FilePickerState`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+    /// A directory that should be opened next.
+    Directory(String),
+    /// A file that should be loaded.
+    File(String),
+}
+
+impl FilePickerState {
+    /// List the entries of `directory`, an empty string refers to the
+    /// current working directory.
+    ///
+    /// The first entry always leads to the parent directory.
+    pub fn open(directory: &str) -> io::Result<Self> {
+        let mut directory = directory.to_owned();
+        if !directory.is_empty() && !directory.ends_with(MAIN_SEPARATOR) {
+            directory.push(MAIN_SEPARATOR);
+        }
+        // The completer silently skips unreadable directories, so report
+        // errors like missing permissions first
+        let readable = if directory.is_empty() {
+            "."
+        } else {
+            &directory
+        };
+        fs::read_dir(Path::new(readable))?;
+        // The leading quote prevents the completer from splitting the
+        // directory at spaces
+        let line = format!("\"{}", directory);
+        let (_, pairs) = FilenameCompleter::new()
+            .complete_path(&line, line.len())
+            .map_err(io::Error::other)?;
+        let parent = format!("..{}", MAIN_SEPARATOR);
+        let entries = Some(parent)
+            .into_iter()
+            .chain(pairs.into_iter().map(|pair| {
+                if pair.replacement.ends_with(MAIN_SEPARATOR) {
+                    format!("{}{}", pair.display, MAIN_SEPARATOR)
+                } else {
+                    pair.display
+                }
+            }))
+            .collect();
+        Ok(FilePickerState {
+            directory,
+            entries,
+            selected: 0,
+        })
+    }
+    /// Select the previous entry.
+    pub fn previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    /// Select the next entry.
+    pub fn next(&mut self) {
+        if self.selected + 1 < self.entries.len() {
+            self.selected += 1;
+        }
+    }
+    /// Get the path of the selected entry.
+    pub fn selected(&self) -> Selection {
+        let entry = &self.entries[self.selected];
+        if self.selected == 0 {
+            Selection::Directory(self.parent())
+        } else if entry.ends_with(MAIN_SEPARATOR) {
+            Selection::Directory(format!("{}{}", self.directory, entry))
+        } else {
+            Selection::File(format!("{}{}", self.directory, entry))
+        }
+    }
+    /// The parent of the listed directory.
+    ///
+    /// The last component is dropped, unless it is `..` itself.
+    fn parent(&self) -> String {
+        let parent = format!("..{}", MAIN_SEPARATOR);
+        if self.directory.is_empty() || self.directory.ends_with(&parent) {
+            return format!("{}{}", self.directory, parent);
+        }
+        let trimmed = &self.directory[..self.directory.len() - 1];
+        match trimmed.rfind(MAIN_SEPARATOR) {
+            Some(idx) => trimmed[..=idx].to_owned(),
+            None => String::new(),
+        }
+    }
+}
+
+impl StatefulWidget for FilePickerWidget {
+    type State = FilePickerState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        if area.width == 0 || area.height < 3 {
+            return;
+        }
+        let header = format!(
+            "Load program from {}",
+            if state.directory.is_empty() {
+                "./"
+            } else {
+                &state.directory
+            }
+        );
+        buf.set_stringn(
+            area.x,
+            area.y,
+            header,
+            area.width as usize,
+            *helpers::DIMMED,
+        );
+        // Keep the selected entry visible
+        let visible = area.height as usize - 2;
+        let offset = (state.selected + 1).saturating_sub(visible);
+        let rows = state.entries.iter().enumerate().skip(offset).take(visible);
+        for (row, (index, entry)) in rows.enumerate() {
+            let (text, style) = if index == state.selected {
+                (format!("> {}", entry), *helpers::YELLOW_BOLD)
+            } else {
+                (entry.clone(), Default::default())
+            };
+            buf.set_stringn(
+                area.x,
+                area.y + 1 + row as u16,
+                text,
+                area.width as usize,
+                style,
+            );
+        }
+        if area.width as usize > KEY_HINT.len() {
+            buf.set_string(
+                area.right() - KEY_HINT.len() as u16,
+                area.bottom() - 1,
+                KEY_HINT,
+                *helpers::YELLOW_BOLD,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directories_can_be_browsed() {
+        let mut picker =
+            FilePickerState::open("../testing/programs").expect("Failed to open directory");
+        assert_eq!(picker.directory, "../testing/programs/");
+        assert_eq!(picker.entries[0], "../");
+        assert_eq!(
+            picker.selected(),
+            Selection::Directory("../testing/".into())
+        );
+        picker.previous();
+        assert_eq!(picker.selected, 0);
+        picker.next();
+        assert_eq!(
+            picker.selected(),
+            Selection::File("../testing/programs/01-stacksize-16.asm".into())
+        );
+        for _ in 0..picker.entries.len() {
+            picker.next();
+        }
+        assert_eq!(picker.selected, picker.entries.len() - 1);
+
+        let parent = FilePickerState::open("../testing/").expect("Failed to open directory");
+        assert!(parent.entries.contains(&"programs/".to_string()));
+        assert_eq!(parent.parent(), "../");
+        let cwd = FilePickerState::open("").expect("Failed to open directory");
+        assert_eq!(cwd.parent(), "../");
+        assert!(cwd.entries.contains(&"src/".to_string()));
+    }
+
+    #[test]
+    fn unreadable_directories_are_reported() {
+        assert!(FilePickerState::open("../testing/does-not-exist").is_err());
+    }
+
+    #[test]
+    fn file_picker_renders_at_any_size() {
+        let mut picker =
+            FilePickerState::open("../testing/programs").expect("Failed to open directory");
+        picker.next();
+        for width in 0..50 {
+            for height in 0..20 {
+                let area = Rect::new(0, 0, width, height);
+                let mut buf = Buffer::empty(area);
+                FilePickerWidget.render(area, &mut buf, &mut picker);
+            }
+        }
+    }
+}
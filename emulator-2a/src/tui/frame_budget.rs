@@ -0,0 +1,125 @@
+//! Everything related to the timing of the main loop.
+use emulator_2a_lib::machine::CLOCK_FREQUENCY;
+
+use std::time::Duration;
+
+/// Number of frames drawn per second by default.
+pub const DEFAULT_FRAMES_PER_SECOND: u64 = 24;
+/// Upper limit for the number of frames per second.
+pub const MAX_FRAMES_PER_SECOND: u64 = 1_000;
+/// Clock frequency of the real machine.
+pub const CYCLES_PER_SECOND: u64 = CLOCK_FREQUENCY;
+/// Share of the cycle budget in percent, that has to be emulated in a
+/// frame's time to not count as falling behind.
+const KEEP_UP_THRESHOLD_PERCENT: u64 = 95;
+
+/// How often to draw the interface and how many cycles to emulate
+/// between two frames while in auto run mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameBudget {
+    /// Number of frames drawn per second.
+    frames_per_second: u64,
+    /// Maximum number of cycles emulated between two frames.
+    cycles_per_frame: u64,
+}
+
+impl FrameBudget {
+    /// Create a budget for the given frame rate, targeting the clock
+    /// frequency of the real machine.
+    ///
+    /// The frame rate is clamped to `1..=MAX_FRAMES_PER_SECOND`.
+    pub fn new(frames_per_second: u64) -> Self {
+        let frames_per_second = frames_per_second.clamp(1, MAX_FRAMES_PER_SECOND);
+        FrameBudget {
+            frames_per_second,
+            cycles_per_frame: CYCLES_PER_SECOND / frames_per_second,
+        }
+    }
+    /// Change the number of cycles emulated between two frames.
+    ///
+    /// At least one cycle is emulated per frame.
+    pub fn with_cycles_per_frame(mut self, cycles_per_frame: u64) -> Self {
+        self.cycles_per_frame = cycles_per_frame.max(1);
+        self
+    }
+    /// Number of frames drawn per second.
+    pub const fn frames_per_second(&self) -> u64 {
+        self.frames_per_second
+    }
+    /// Maximum number of cycles emulated between two frames.
+    pub const fn cycles_per_frame(&self) -> u64 {
+        self.cycles_per_frame
+    }
+    /// Time between the start of two frames.
+    pub fn duration_between_frames(&self) -> Duration {
+        Duration::from_micros(1_000_000 / self.frames_per_second)
+    }
+    /// The frequency the machine runs at, if every frame's budget is used.
+    pub fn target_frequency(&self) -> u64 {
+        self.cycles_per_frame.saturating_mul(self.frames_per_second)
+    }
+    /// Did the host fail to emulate the frame's cycles in time?
+    ///
+    /// This is the case, if the time between two frames has `elapsed`, before
+    /// `executed_cycles` reached the budget. Small shortfalls are tolerated.
+    pub fn is_falling_behind(&self, executed_cycles: u64, elapsed: Duration) -> bool {
+        elapsed >= self.duration_between_frames()
+            && executed_cycles.saturating_mul(100)
+                < self
+                    .cycles_per_frame
+                    .saturating_mul(KEEP_UP_THRESHOLD_PERCENT)
+    }
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        FrameBudget::new(DEFAULT_FRAMES_PER_SECOND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_budget_matches_the_real_machine() {
+        let budget = FrameBudget::default();
+        assert_eq!(budget.frames_per_second(), DEFAULT_FRAMES_PER_SECOND);
+        assert_eq!(budget.cycles_per_frame(), 307_200);
+        assert_eq!(budget.target_frequency(), CYCLES_PER_SECOND);
+        assert_eq!(
+            budget.duration_between_frames(),
+            Duration::from_micros(41_666)
+        );
+    }
+
+    #[test]
+    fn falling_behind_is_detected_once_the_frame_time_is_used_up() {
+        let budget = FrameBudget::new(10).with_cycles_per_frame(1_000);
+        let frame = budget.duration_between_frames();
+        // The budget was used before the time ran out
+        assert!(!budget.is_falling_behind(1_000, frame / 2));
+        // Stopped early, i.e. at a breakpoint
+        assert!(!budget.is_falling_behind(100, frame / 2));
+        // Small shortfalls are tolerated
+        assert!(!budget.is_falling_behind(950, frame));
+        assert!(budget.is_falling_behind(949, frame));
+        assert!(budget.is_falling_behind(0, frame * 2));
+    }
+
+    #[test]
+    fn budget_is_clamped_at_extreme_values() {
+        let budget = FrameBudget::new(0);
+        assert_eq!(budget.frames_per_second(), 1);
+        assert_eq!(budget.cycles_per_frame(), CYCLES_PER_SECOND);
+        assert_eq!(budget.duration_between_frames(), Duration::from_secs(1));
+        let budget = FrameBudget::new(u64::MAX);
+        assert_eq!(budget.frames_per_second(), MAX_FRAMES_PER_SECOND);
+        assert_eq!(budget.duration_between_frames(), Duration::from_millis(1));
+        assert!(budget.cycles_per_frame() > 0);
+        let budget = FrameBudget::default().with_cycles_per_frame(0);
+        assert_eq!(budget.cycles_per_frame(), 1);
+        let budget = FrameBudget::new(MAX_FRAMES_PER_SECOND).with_cycles_per_frame(u64::MAX);
+        assert_eq!(budget.target_frequency(), u64::MAX);
+    }
+}
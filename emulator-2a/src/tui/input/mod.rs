@@ -1,5 +1,6 @@
 //! Everything related to the input field of the TUI.
 use crossterm::event::{KeyCode, KeyEvent};
+use emulator_2a_lib::machine::{RegisterNumber, StepMode};
 use rustyline::completion::FilenameCompleter;
 
 use log::warn;
@@ -40,14 +41,48 @@ pub enum InputRegister {
     Ff,
 }
 
+/// Boolean settings that can be unset
+#[derive(Debug, Clone, PartialEq, Hash, Copy, Eq)]
+pub enum BoolSetting {
+    J1,
+    J2,
+    Uio1,
+    Uio2,
+    Uio3,
+}
+
+/// The location of a breakpoint, as entered by the user.
+#[derive(Debug, Clone, PartialEq, Hash, Copy, Eq)]
+pub enum BreakTarget<'a> {
+    /// The address .0.
+    Address(u8),
+    /// The address of the label .0 in the loaded program.
+    Label(&'a str),
+}
+
+/// What to remove with a `clear` command.
+#[derive(Debug, Clone, PartialEq, Hash, Copy, Eq)]
+pub enum ClearTarget {
+    /// All breakpoints.
+    Breakpoints,
+    /// Everything that can be cleared.
+    All,
+}
+
 /// Possible commands to enter in the input
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command<'a> {
     /// Load a program from the path .0.
     LoadProgram(&'a str),
+    /// Write the program from the path .0 into memory, without resetting the machine.
+    LoadOverlay(&'a str),
+    /// Pick a program to load using the file picker.
+    PickProgram,
+    /// Write the main memory to the file at path .0.
+    DumpMemory(&'a str),
     /// Set the input register .0 to the value .1.
     SetInputReg(InputRegister, u8),
-    /// Set the IRG to value .0.
+    /// Set the IRG (digital input P-DI1) to value .0.
     SetIrg(u8),
     /// Set the TEMP value to value .0.
     SetTemp(f32),
@@ -65,10 +100,26 @@ pub enum Command<'a> {
     SetUio2(bool),
     /// Set the UIO3 to value .0.
     SetUio3(bool),
+    /// Set the boolean setting .0 to `false`.
+    Unset(BoolSetting),
+    /// Set the step mode to .0.
+    SetStepMode(StepMode),
+    /// Set the CPU register .0 to value .1.
+    SetRegister(RegisterNumber, u8),
     /// Show the machine part .0.
     Show(Part),
+    /// Add the breakpoint at .0 or remove it, if it exists.
+    ToggleBreakpoint(BreakTarget<'a>),
+    /// Remove everything selected by .0.
+    Clear(ClearTarget),
+    /// Reset the machine and load the current program again.
+    Rerun,
+    /// Draw .0 frames per second.
+    Fps(usize),
     /// Execute the next N cycles.
     Next(usize),
+    /// Execute the next N assembly instructions, regardless of the step mode.
+    NextInstructions(usize),
     /// Quit the program.
     Quit,
 }
@@ -102,8 +153,8 @@ impl InputState {
                 self.previous_completion();
             }
             (_, Char(c)) => {
-                self.input.insert(self.input_index, c);
-                self.input_index += 1;
+                // A typed character is nothing but a very short paste
+                self.paste(c.encode_utf8(&mut [0; 4]));
             }
             (_, Backspace) => {
                 if self.input_index > 0 {
@@ -164,6 +215,22 @@ impl InputState {
             self.curr_completions = None;
         }
     }
+    /// Insert the whole string at the cursor position.
+    ///
+    /// Control characters, like line breaks, are dropped, a paste thus
+    /// never executes a command on its own.
+    ///
+    /// TODO: Feed bracketed paste events into this, once crossterm is
+    /// updated. Version 0.17 does not support them, pasted text thus
+    /// still arrives one key event at a time.
+    pub fn paste(&mut self, text: &str) {
+        let pasted: Vec<char> = text.chars().filter(|c| !c.is_control()).collect();
+        let count = pasted.len();
+        self.input
+            .splice(self.input_index..self.input_index, pasted);
+        self.input_index += count;
+        self.curr_completions = None;
+    }
     /// Check if the input is empty.
     pub fn is_empty(&self) -> bool {
         self.input.len() == 0
@@ -209,33 +276,20 @@ impl InputState {
     /// Try to complete the current input.
     fn complete(&mut self) {
         let s: String = self.input.iter().collect();
-        if let Some(s) = s.strip_prefix("load ") {
-            let file_comp = FilenameCompleter::new();
-            let pos = if self.input_index > 5 {
-                self.input_index - 5
-            } else {
-                0
-            };
-            let comps = file_comp.complete_path(s, pos);
-            match comps {
-                Ok((_, comps)) => {
-                    let start: String = "load ".into();
-                    let comps: Vec<Vec<char>> = comps
-                        .iter()
-                        .map(|p| &p.replacement)
-                        .map(|s| start.clone() + s)
-                        .map(|s| s.chars().collect())
-                        .collect();
-                    self.curr_completions = Some((comps, 0));
-                }
-                Err(e) => {
-                    warn!("Error during completion: {}", e);
-                }
-            }
-        } else if s.starts_with('l') {
-            self.curr_completions = Some((vec!["load ".chars().collect()], 0));
-        } else if s.starts_with('s') {
-            self.curr_completions = Some((vec!["set ".chars().collect()], 0));
+        if let Some(path) = s.strip_prefix("load ") {
+            self.complete_path("load ", path);
+        } else if let Some(path) = s.strip_prefix("load-overlay ") {
+            self.complete_path("load-overlay ", path);
+        } else if let Some(name) = s.strip_prefix("set ") {
+            self.complete_argument("set ", name, SET_COMPLETIONS);
+        } else if let Some(name) = s.strip_prefix("unset ") {
+            self.complete_argument("unset ", name, UNSET_COMPLETIONS);
+        } else if let Some(name) = s.strip_prefix("show ") {
+            self.complete_argument("show ", name, SHOW_COMPLETIONS);
+        } else if let Some(name) = s.strip_prefix("clear ") {
+            self.complete_argument("clear ", name, CLEAR_COMPLETIONS);
+        } else if let Some(name) = s.strip_prefix("reg ") {
+            self.complete_argument("reg ", name, REG_COMPLETIONS);
         } else if s.starts_with('F') && self.input_index > 1 && self.input_index <= 4 {
             let comp = match &s[1..2] {
                 "C" => "FC = ",
@@ -245,6 +299,8 @@ impl InputState {
                 _ => return,
             };
             self.curr_completions = Some((vec![comp.chars().collect()], 0));
+        } else if !s.is_empty() && !s.contains(' ') {
+            self.complete_argument("", &s, COMMAND_COMPLETIONS);
         }
         if let Some((ref mut comps, idx)) = self.curr_completions {
             // Add current input to completions
@@ -254,8 +310,79 @@ impl InputState {
             self.input_index = self.input.len();
         }
     }
+    /// Offer all files and directories starting with `path`.
+    ///
+    /// Every completion is prefixed with `command`.
+    fn complete_path(&mut self, command: &str, path: &str) {
+        let file_comp = FilenameCompleter::new();
+        let pos = self.input_index.saturating_sub(command.len());
+        match file_comp.complete_path(path, pos) {
+            Ok((_, comps)) => {
+                let comps: Vec<Vec<char>> = comps
+                    .iter()
+                    .map(|p| format!("{}{}", command, p.replacement))
+                    .map(|s| s.chars().collect())
+                    .collect();
+                self.curr_completions = Some((comps, 0));
+            }
+            Err(e) => {
+                warn!("Error during completion: {}", e);
+            }
+        }
+    }
+    /// Offer all `candidates` starting with `name`, ignoring the case.
+    ///
+    /// Every completion is prefixed with `command`.
+    fn complete_argument(&mut self, command: &str, name: &str, candidates: &[&str]) {
+        let name = name.to_ascii_uppercase();
+        let comps: Vec<Vec<char>> = candidates
+            .iter()
+            .filter(|comp| comp.to_ascii_uppercase().starts_with(&name))
+            .map(|comp| format!("{}{}", command, comp).chars().collect())
+            .collect();
+        if !comps.is_empty() {
+            self.curr_completions = Some((comps, 0));
+        }
+    }
 }
 
+/// All commands.
+const COMMAND_COMPLETIONS: &[&str] = &[
+    "load ",
+    "load-overlay ",
+    "dump ",
+    "set ",
+    "unset ",
+    "reg ",
+    "show ",
+    "clear ",
+    "next ",
+    "next-asm ",
+    "fps ",
+    "rerun",
+    "quit",
+];
+
+/// Everything that can follow a `set `.
+const SET_COMPLETIONS: &[&str] = &[
+    "FC = ", "FD = ", "FE = ", "FF = ", "IRG = ", "DI1 = ", "TEMP = ", "I1 = ", "I2 = ", "J1",
+    "J2", "UIO1", "UIO2", "UIO3", "STEP = ",
+];
+
+/// Everything that can follow an `unset `.
+const UNSET_COMPLETIONS: &[&str] = &["J1", "J2", "UIO1", "UIO2", "UIO3"];
+
+/// Everything that can follow a `show `.
+const SHOW_COMPLETIONS: &[&str] = &["memory", "register", "interrupts"];
+
+/// Everything that can follow a `clear `.
+const CLEAR_COMPLETIONS: &[&str] = &["breakpoints", "all"];
+
+/// Everything that can follow a `reg `.
+const REG_COMPLETIONS: &[&str] = &[
+    "R0 = ", "R1 = ", "R2 = ", "FR = ", "SP = ", "R6 = ", "R7 = ",
+];
+
 impl<'a> Command<'a> {
     /// Try to parse a string into a Command.
     pub fn parse(input: &'a str) -> Result<Self, NomErr<(&str, NomErrorKind)>> {
@@ -368,4 +495,68 @@ mod tests {
         i.handle(key!(Char('d')));
         assert_eq!(i.input, vec!['a', 'd', 'c', 'd']);
     }
+
+    /// Press Tab `count` times and return the input afterwards.
+    fn tab(i: &mut InputState, count: usize) -> String {
+        for _ in 0..count {
+            i.handle(key!(KeyCode::Tab));
+        }
+        i.current().iter().collect()
+    }
+
+    #[test]
+    fn commands_and_arguments_are_completed() {
+        let mut i = InputState::new();
+        i.paste("s");
+        assert_eq!(tab(&mut i, 1), "set ");
+        assert_eq!(tab(&mut i, 1), "show ");
+        // The original input is the last completion
+        assert_eq!(tab(&mut i, 1), "s");
+
+        let mut i = InputState::new();
+        i.paste("show ");
+        assert_eq!(tab(&mut i, 1), "show memory");
+        assert_eq!(tab(&mut i, 1), "show register");
+        assert_eq!(tab(&mut i, 1), "show interrupts");
+
+        let mut i = InputState::new();
+        i.paste("show I");
+        assert_eq!(tab(&mut i, 1), "show interrupts");
+
+        let mut i = InputState::new();
+        i.paste("reg ");
+        assert_eq!(tab(&mut i, 1), "reg R0 = ");
+        assert_eq!(tab(&mut i, 4), "reg SP = ");
+
+        let mut i = InputState::new();
+        i.paste("set u");
+        assert_eq!(tab(&mut i, 1), "set UIO1");
+        assert_eq!(tab(&mut i, 2), "set UIO3");
+
+        let mut i = InputState::new();
+        i.paste("next");
+        assert_eq!(tab(&mut i, 1), "next ");
+        assert_eq!(tab(&mut i, 1), "next-asm ");
+    }
+
+    #[test]
+    fn pastes_are_inserted_at_once() {
+        use KeyCode::*;
+        let mut i = InputState::new();
+        i.handle(key!(Char('l')));
+        i.handle(key!(Tab));
+        assert!(i.curr_completions.is_some());
+        i.paste("/some/very/long\n/path.asm");
+        assert_eq!(
+            i.input.iter().collect::<String>(),
+            "load /some/very/long/path.asm"
+        );
+        assert_eq!(i.input_index, i.input.len());
+        assert!(i.curr_completions.is_none());
+
+        i.handle(key!(Home));
+        i.paste("  ");
+        assert_eq!(i.input_index, 2);
+        assert!(i.current().starts_with(&[' ', ' ', 'l']));
+    }
 }
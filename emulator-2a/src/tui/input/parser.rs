@@ -1,15 +1,16 @@
 //! [nom](https://crates.io/crates/nom)-based parser for [`Command`]s.
+use emulator_2a_lib::machine::{RegisterNumber, StepMode};
 use nom::{
     branch::alt,
-    bytes::complete::{is_a, tag, tag_no_case},
+    bytes::complete::{is_a, tag, tag_no_case, take_while, take_while1},
     character::complete::{digit1, hex_digit1},
-    combinator::{complete, map, map_res, opt, rest, value},
+    combinator::{all_consuming, complete, map, map_res, opt, recognize, rest, value, verify},
     number::complete::float,
     sequence::{delimited, preceded, terminated, tuple},
     IResult,
 };
 
-use super::{Command, InputRegister};
+use super::{BoolSetting, BreakTarget, ClearTarget, Command, InputRegister};
 use crate::tui::Part;
 
 fn ws(input: &str) -> IResult<&str, &str> {
@@ -60,16 +61,41 @@ fn eq_ws(input: &str) -> IResult<&str, &str> {
 fn parse_part(input: &str) -> IResult<&str, Part> {
     let register = value(Part::RegisterBlock, tag_no_case("register"));
     let memory = value(Part::Memory, tag_no_case("memory"));
-    alt((register, memory))(input)
+    let interrupts = value(Part::Interrupts, tag_no_case("interrupts"));
+    alt((register, memory, interrupts))(input)
 }
 
 /// `load path/to/program`
 fn cmd_load_prgm(input: &str) -> IResult<&str, Command> {
-    map(tuple((tag_no_case("load"), ws, rest)), |(_, _, path)| {
+    let path = verify(rest, |path: &str| !path.trim().is_empty());
+    map(tuple((tag_no_case("load"), ws, path)), |(_, _, path)| {
         Command::LoadProgram(path)
     })(input)
 }
 
+/// `load-overlay path/to/program`
+fn cmd_load_overlay(input: &str) -> IResult<&str, Command> {
+    let path = verify(rest, |path: &str| !path.trim().is_empty());
+    map(
+        tuple((tag_no_case("load-overlay"), ws, path)),
+        |(_, _, path)| Command::LoadOverlay(path),
+    )(input)
+}
+
+/// `load`, without a path
+fn cmd_pick_prgm(input: &str) -> IResult<&str, Command> {
+    let load = terminated(tag_no_case("load"), ws_opt);
+    value(Command::PickProgram, all_consuming(load))(input)
+}
+
+/// `dump PATH`
+fn cmd_dump_memory(input: &str) -> IResult<&str, Command> {
+    let path = verify(rest, |path: &str| !path.trim().is_empty());
+    map(tuple((tag_no_case("dump"), ws, path)), |(_, _, path)| {
+        Command::DumpMemory(path)
+    })(input)
+}
+
 /// `set FC = 99`
 fn cmd_set_input_reg(input: &str) -> IResult<&str, Command> {
     let fc = value(InputRegister::Fc, tag_no_case("fc"));
@@ -83,9 +109,9 @@ fn cmd_set_input_reg(input: &str) -> IResult<&str, Command> {
     )(input)
 }
 
-/// `set IRG = 0xAB`
+/// `set IRG = 0xAB` and `set DI1 = 0xAB`
 fn cmd_set_irg(input: &str) -> IResult<&str, Command> {
-    let irg = tag_no_case("IRG");
+    let irg = alt((tag_no_case("IRG"), tag_no_case("DI1")));
     map(tuple((set_ws, irg, eq_ws, value_u8)), |(_, _, _, val)| {
         Command::SetIrg(val)
     })(input)
@@ -99,58 +125,94 @@ fn cmd_set_temp(input: &str) -> IResult<&str, Command> {
     })(input)
 }
 
-/// `set I1 = 1.1` and `set I2 = 2.2`
+/// `set I1 = 1.1` and `set I2 = 2.2`, `AI1` and `AI2` work aswell
 fn cmd_set_ix(input: &str) -> IResult<&str, Command> {
-    let i1 = map(tuple((tag_no_case("I1"), eq_ws, float)), |(_, _, f)| {
+    let name_i1 = alt((tag_no_case("I1"), tag_no_case("AI1")));
+    let name_i2 = alt((tag_no_case("I2"), tag_no_case("AI2")));
+    let i1 = map(tuple((name_i1, eq_ws, float)), |(_, _, f)| {
         Command::SetI1(f)
     });
-    let i2 = map(tuple((tag_no_case("I2"), eq_ws, float)), |(_, _, f)| {
+    let i2 = map(tuple((name_i2, eq_ws, float)), |(_, _, f)| {
         Command::SetI2(f)
     });
     preceded(set_ws, alt((i1, i2)))(input)
 }
 
-/// `set J1` and `unset J2`
+/// `set J1` and `set J2`
 fn cmd_set_jx<'a>(input: &'a str) -> IResult<&str, Command<'a>> {
-    let set_j1 = value(Command::SetJ1(true), preceded(set_ws, tag_no_case("J1")));
-    let set_j2 = value(Command::SetJ2(true), preceded(set_ws, tag_no_case("J2")));
-    let unset_j1 = value(Command::SetJ1(false), preceded(unset_ws, tag_no_case("J1")));
-    let unset_j2 = value(Command::SetJ2(false), preceded(unset_ws, tag_no_case("J2")));
+    let set_j1 = value(Command::SetJ1(true), tag_no_case("J1"));
+    let set_j2 = value(Command::SetJ2(true), tag_no_case("J2"));
 
-    alt((set_j1, set_j2, unset_j1, unset_j2))(input)
+    preceded(set_ws, alt((set_j1, set_j2)))(input)
 }
 
-/// `set UIO1`, `unset UIO2` and same for `UIO3`
+/// `set UIO1`, `set UIO2` and `set UIO3`
 fn cmd_set_uiox(input: &str) -> IResult<&str, Command> {
-    let set_uio1 = value(
-        Command::SetUio1(true),
-        preceded(set_ws, tag_no_case("UIO1")),
-    );
-    let set_uio2 = value(
-        Command::SetUio2(true),
-        preceded(set_ws, tag_no_case("UIO2")),
-    );
-    let set_uio3 = value(
-        Command::SetUio3(true),
-        preceded(set_ws, tag_no_case("UIO3")),
-    );
-    let unset_uio1 = value(
-        Command::SetUio1(false),
-        preceded(unset_ws, tag_no_case("UIO1")),
-    );
-    let unset_uio2 = value(
-        Command::SetUio2(false),
-        preceded(unset_ws, tag_no_case("UIO2")),
-    );
-    let unset_uio3 = value(
-        Command::SetUio3(false),
-        preceded(unset_ws, tag_no_case("UIO3")),
-    );
+    let set_uio1 = value(Command::SetUio1(true), tag_no_case("UIO1"));
+    let set_uio2 = value(Command::SetUio2(true), tag_no_case("UIO2"));
+    let set_uio3 = value(Command::SetUio3(true), tag_no_case("UIO3"));
+
+    preceded(set_ws, alt((set_uio1, set_uio2, set_uio3)))(input)
+}
+
+/// `unset J1`, `unset UIO2`, etc.
+fn cmd_unset(input: &str) -> IResult<&str, Command> {
+    map(preceded(unset_ws, bool_setting), Command::Unset)(input)
+}
+
+fn bool_setting(input: &str) -> IResult<&str, BoolSetting> {
     alt((
-        set_uio1, set_uio2, set_uio3, unset_uio1, unset_uio2, unset_uio3,
+        value(BoolSetting::J1, tag_no_case("J1")),
+        value(BoolSetting::J2, tag_no_case("J2")),
+        value(BoolSetting::Uio1, tag_no_case("UIO1")),
+        value(BoolSetting::Uio2, tag_no_case("UIO2")),
+        value(BoolSetting::Uio3, tag_no_case("UIO3")),
     ))(input)
 }
 
+/// `set STEP = asm` and `set STEP = real`
+fn cmd_set_step_mode(input: &str) -> IResult<&str, Command> {
+    let asm = value(StepMode::Assembly, tag_no_case("asm"));
+    let real = value(StepMode::Real, tag_no_case("real"));
+    map(
+        tuple((set_ws, tag_no_case("STEP"), eq_ws, alt((asm, real)))),
+        |(_, _, _, mode)| Command::SetStepMode(mode),
+    )(input)
+}
+
+/// `reg R0 = 5`, `reg SP = 0xEF`, ...
+///
+/// The program counter (R3/PC) can only be set using the explicit
+/// `reg! PC = 0x10` form, since changing it will most likely break
+/// the currently executed instruction.
+fn cmd_set_register(input: &str) -> IResult<&str, Command> {
+    let register = alt((
+        value(RegisterNumber::R0, tag_no_case("R0")),
+        value(RegisterNumber::R1, tag_no_case("R1")),
+        value(RegisterNumber::R2, tag_no_case("R2")),
+        value(
+            RegisterNumber::R4,
+            alt((tag_no_case("R4"), tag_no_case("FR"))),
+        ),
+        value(
+            RegisterNumber::R5,
+            alt((tag_no_case("R5"), tag_no_case("SP"))),
+        ),
+        value(RegisterNumber::R6, tag_no_case("R6")),
+        value(RegisterNumber::R7, tag_no_case("R7")),
+    ));
+    let pc = value(
+        RegisterNumber::R3,
+        alt((tag_no_case("R3"), tag_no_case("PC"))),
+    );
+    let reg = preceded(terminated(tag_no_case("reg"), ws), register);
+    let forced = preceded(terminated(tag_no_case("reg!"), ws), pc);
+    map(
+        tuple((alt((forced, reg)), eq_ws, value_u8)),
+        |(register, _, val)| Command::SetRegister(register, val),
+    )(input)
+}
+
 /// `show blub`
 fn cmd_show(input: &str) -> IResult<&str, Command> {
     map(
@@ -159,7 +221,49 @@ fn cmd_show(input: &str) -> IResult<&str, Command> {
     )(input)
 }
 
-/// `quit`
+/// A label, like `LOOP` or `_start2`.
+fn label(input: &str) -> IResult<&str, &str> {
+    let is_label_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let first = take_while1(|c: char| c.is_ascii_alphabetic() || c == '_');
+    recognize(tuple((first, take_while(is_label_char))))(input)
+}
+
+/// `break 0x12` and `break LOOP`
+fn cmd_break(input: &str) -> IResult<&str, Command> {
+    let address = map(value_u8, BreakTarget::Address);
+    let label = map(label, BreakTarget::Label);
+    map(
+        preceded(terminated(tag_no_case("break"), ws), alt((address, label))),
+        Command::ToggleBreakpoint,
+    )(input)
+}
+
+/// `clear breakpoints` and `clear all`
+fn cmd_clear(input: &str) -> IResult<&str, Command> {
+    let target = alt((
+        value(ClearTarget::Breakpoints, tag_no_case("breakpoints")),
+        value(ClearTarget::All, tag_no_case("all")),
+    ));
+    map(
+        preceded(terminated(tag_no_case("clear"), ws), target),
+        Command::Clear,
+    )(input)
+}
+
+/// `rerun`
+fn cmd_rerun(input: &str) -> IResult<&str, Command> {
+    value(Command::Rerun, tag_no_case("rerun"))(input)
+}
+
+/// `fps N`
+fn cmd_fps(input: &str) -> IResult<&str, Command> {
+    map(
+        preceded(terminated(tag_no_case("fps"), ws), nr_dec_usize),
+        Command::Fps,
+    )(input)
+}
+
+/// `quit` and `exit`
 fn cmd_quit(input: &str) -> IResult<&str, Command> {
     let quit = tag_no_case("quit");
     let exit = tag_no_case("exit");
@@ -174,16 +278,35 @@ fn cmd_next(input: &str) -> IResult<&str, Command> {
     })(input)
 }
 
+/// `next-asm N`
+fn cmd_next_asm(input: &str) -> IResult<&str, Command> {
+    let next_asm = tag_no_case("next-asm");
+    map(preceded(next_asm, opt(preceded(ws, nr_dec_usize))), |nr| {
+        Command::NextInstructions(nr.unwrap_or(1))
+    })(input)
+}
+
 pub fn parse_cmd(input: &str) -> IResult<&str, Command> {
     let cmd = alt((
+        cmd_load_overlay,
         cmd_load_prgm,
+        cmd_pick_prgm,
+        cmd_dump_memory,
         cmd_set_input_reg,
         cmd_set_irg,
         cmd_set_temp,
         cmd_set_ix,
         cmd_set_jx,
         cmd_set_uiox,
+        cmd_unset,
+        cmd_set_step_mode,
+        cmd_set_register,
         cmd_show,
+        cmd_break,
+        cmd_clear,
+        cmd_rerun,
+        cmd_fps,
+        cmd_next_asm,
         cmd_next,
         cmd_quit,
     ));
@@ -201,6 +324,7 @@ mod tests {
 
         assert!(parse("loadx/a b c/z").is_err());
         assert!(parse("load").is_err());
+        assert!(parse("load \t ").is_err());
         assert_eq!(parse("load x/y/z"), Ok(("", LoadProgram("x/y/z"))));
         assert_eq!(parse("load x/a b c/z"), Ok(("", LoadProgram("x/a b c/z"))));
         assert_eq!(
@@ -214,6 +338,39 @@ mod tests {
         assert_eq!(parse("load\tx/a b c/z"), Ok(("", LoadProgram("x/a b c/z"))));
     }
 
+    #[test]
+    fn cmd_load_overlay_test() {
+        let parse = cmd_load_overlay;
+        use Command::*;
+
+        assert!(parse("load-overlay").is_err());
+        assert!(parse("load-overlay \t ").is_err());
+        assert!(parse("load data.asm").is_err());
+        assert_eq!(
+            parse("load-overlay data.asm"),
+            Ok(("", LoadOverlay("data.asm")))
+        );
+        assert_eq!(
+            parse("LOAD-OVERLAY \tx/a b/data.asm"),
+            Ok(("", LoadOverlay("x/a b/data.asm")))
+        );
+    }
+
+    #[test]
+    fn cmd_dump_memory_test() {
+        let parse = cmd_dump_memory;
+        use Command::*;
+
+        assert!(parse("dump").is_err());
+        assert!(parse("dump \t ").is_err());
+        assert!(parse("dumpx.hex").is_err());
+        assert_eq!(parse("dump ram.hex"), Ok(("", DumpMemory("ram.hex"))));
+        assert_eq!(
+            parse("DUMP \tx/a b/ram.bin"),
+            Ok(("", DumpMemory("x/a b/ram.bin")))
+        );
+    }
+
     #[test]
     fn cmd_set_input_reg_test() {
         let parse = cmd_set_input_reg;
@@ -235,6 +392,8 @@ mod tests {
         assert_eq!(parse("set IRG = 0xA3"), Ok(("", SetIrg(0xA3))));
         assert_eq!(parse("set IRG=0x00"), Ok(("", SetIrg(0x00))));
         assert!(parse("IRG=0x00").is_err());
+        assert_eq!(parse("set di1 = 0x42"), Ok(("", SetIrg(0x42))));
+        assert_eq!(parse("set DI1=0b11"), Ok(("", SetIrg(0b11))));
     }
 
     #[test]
@@ -246,6 +405,25 @@ mod tests {
         assert_eq!(parse("set i2 = 2.2"), Ok(("", SetI2(2.2))));
         assert!(parse("i2 = 2.2").is_err());
         assert!(parse("I=0x00").is_err());
+        assert_eq!(parse("set AI1 = 3.3"), Ok(("", SetI1(3.3))));
+        assert_eq!(parse("set ai2 = 0.5"), Ok(("", SetI2(0.5))));
+    }
+
+    #[test]
+    fn cmd_set_step_mode_test() {
+        let parse = cmd_set_step_mode;
+        use Command::*;
+
+        assert_eq!(
+            parse("set step = asm"),
+            Ok(("", SetStepMode(StepMode::Assembly)))
+        );
+        assert_eq!(
+            parse("set STEP=REAL"),
+            Ok(("", SetStepMode(StepMode::Real)))
+        );
+        assert!(parse("set STEP = fast").is_err());
+        assert!(parse("STEP = asm").is_err());
     }
 
     #[test]
@@ -255,9 +433,8 @@ mod tests {
 
         assert_eq!(parse("set J1"), Ok(("", SetJ1(true))));
         assert_eq!(parse("set j2"), Ok(("", SetJ2(true))));
-        assert_eq!(parse("unset j1"), Ok(("", SetJ1(false))));
-        assert_eq!(parse("unset J2"), Ok(("", SetJ2(false))));
         assert_eq!(parse("set j2 = true"), Ok((" = true", SetJ2(true))));
+        assert!(parse("unset J1").is_err());
         assert!(parse("I2").is_err());
     }
 
@@ -273,6 +450,30 @@ mod tests {
         assert_eq!(parse("next  42x"), Ok(("x", Next(42))));
     }
 
+    #[test]
+    fn cmd_next_asm_works() {
+        let parse = cmd_next_asm;
+        use Command::*;
+
+        assert_eq!(parse("next-asm"), Ok(("", NextInstructions(1))));
+        assert_eq!(parse("next-asm 5"), Ok(("", NextInstructions(5))));
+        assert_eq!(parse("NEXT-ASM\t12"), Ok(("", NextInstructions(12))));
+        assert!(parse("next 5").is_err());
+        assert_eq!(parse_cmd("next-asm 5"), Ok(("", NextInstructions(5))));
+        assert_eq!(parse_cmd("next 5"), Ok(("", Next(5))));
+    }
+
+    #[test]
+    fn cmd_fps_works() {
+        let parse = cmd_fps;
+        use Command::*;
+
+        assert_eq!(parse("fps 30"), Ok(("", Fps(30))));
+        assert_eq!(parse("FPS\t1000"), Ok(("", Fps(1000))));
+        assert!(parse("fps").is_err());
+        assert!(parse("fps x").is_err());
+    }
+
     #[test]
     fn cmd_set_uiox_test() {
         let parse = cmd_set_uiox;
@@ -281,12 +482,45 @@ mod tests {
         assert_eq!(parse("set uIO1"), Ok(("", SetUio1(true))));
         assert_eq!(parse("set UiO2"), Ok(("", SetUio2(true))));
         assert_eq!(parse("set UIo3"), Ok(("", SetUio3(true))));
-        assert_eq!(parse("UNset uIO1"), Ok(("", SetUio1(false))));
-        assert_eq!(parse("UNset UiO2"), Ok(("", SetUio2(false))));
-        assert_eq!(parse("UNset UIo3"), Ok(("", SetUio3(false))));
+        assert!(parse("UNset uIO1").is_err());
         assert!(parse("UIO1").is_err());
     }
 
+    #[test]
+    fn cmd_unset_test() {
+        let parse = cmd_unset;
+        use BoolSetting::*;
+        use Command::*;
+
+        assert_eq!(parse("unset j1"), Ok(("", Unset(J1))));
+        assert_eq!(parse("unset J2"), Ok(("", Unset(J2))));
+        assert_eq!(parse("UNset uIO1"), Ok(("", Unset(Uio1))));
+        assert_eq!(parse("UNset UiO2"), Ok(("", Unset(Uio2))));
+        assert_eq!(parse("UNset UIo3"), Ok(("", Unset(Uio3))));
+        assert!(parse("unset FC").is_err());
+        assert!(parse("unset").is_err());
+        assert!(parse("set J1").is_err());
+    }
+
+    #[test]
+    fn cmd_set_register_test() {
+        let parse = cmd_set_register;
+        use Command::*;
+        use RegisterNumber::*;
+
+        assert_eq!(parse("reg R0 = 5"), Ok(("", SetRegister(R0, 5))));
+        assert_eq!(parse("reg r2=0xFF"), Ok(("", SetRegister(R2, 0xFF))));
+        assert_eq!(parse("REG sp = 0xEF"), Ok(("", SetRegister(R5, 0xEF))));
+        assert_eq!(parse("reg FR = 0b1"), Ok(("", SetRegister(R4, 1))));
+        assert_eq!(parse("reg! PC = 0x10"), Ok(("", SetRegister(R3, 0x10))));
+        assert_eq!(parse("reg! r3 = 1"), Ok(("", SetRegister(R3, 1))));
+        assert!(parse("reg R3 = 1").is_err());
+        assert!(parse("reg PC = 1").is_err());
+        assert!(parse("reg! R0 = 1").is_err());
+        assert!(parse("reg R8 = 1").is_err());
+        assert!(parse("reg R0 = 256").is_err());
+    }
+
     #[test]
     fn cmd_show_test() {
         let parse = cmd_show;
@@ -294,9 +528,63 @@ mod tests {
 
         assert_eq!(parse("show memory"), Ok(("", Show(Part::Memory))));
         assert_eq!(parse("show register"), Ok(("", Show(Part::RegisterBlock))));
+        assert_eq!(parse("show interrupts"), Ok(("", Show(Part::Interrupts))));
         assert!(parse("show foo").is_err());
     }
 
+    #[test]
+    fn cmd_break_test() {
+        let parse = cmd_break;
+        use BreakTarget::*;
+        use Command::*;
+
+        assert_eq!(
+            parse("break 0x12"),
+            Ok(("", ToggleBreakpoint(Address(0x12))))
+        );
+        assert_eq!(parse("BREAK\t18"), Ok(("", ToggleBreakpoint(Address(18)))));
+        assert_eq!(
+            parse("break LOOP"),
+            Ok(("", ToggleBreakpoint(Label("LOOP"))))
+        );
+        assert_eq!(
+            parse("break _isr_2 "),
+            Ok((" ", ToggleBreakpoint(Label("_isr_2"))))
+        );
+        assert!(parse("break").is_err());
+        assert!(parse("break 256").is_err());
+        assert!(parse("breakLOOP").is_err());
+        assert!(parse("break ;").is_err());
+        assert_eq!(
+            parse_cmd(" break loop "),
+            Ok(("", ToggleBreakpoint(Label("loop"))))
+        );
+    }
+
+    #[test]
+    fn cmd_clear_test() {
+        let parse = cmd_clear;
+        use ClearTarget::*;
+        use Command::*;
+
+        assert_eq!(parse("clear breakpoints"), Ok(("", Clear(Breakpoints))));
+        assert_eq!(parse("CLEAR	All"), Ok(("", Clear(All))));
+        assert!(parse("clear").is_err());
+        assert!(parse("clearall").is_err());
+        assert!(parse("clear watchpoints").is_err());
+        assert_eq!(parse_cmd(" clear all "), Ok(("", Clear(All))));
+    }
+
+    #[test]
+    fn cmd_rerun_test() {
+        let parse = cmd_rerun;
+
+        assert_eq!(parse("rerun"), Ok(("", Command::Rerun)));
+        assert_eq!(parse("ReRun"), Ok(("", Command::Rerun)));
+        assert!(parse("rerum").is_err());
+        assert_eq!(parse_cmd(" rerun "), Ok(("", Command::Rerun)));
+    }
+
     #[test]
     fn cmd_quit_test() {
         let parse = cmd_quit;
@@ -313,6 +601,11 @@ mod tests {
         use InputRegister::*;
 
         assert_eq!(parse("load path"), Ok(("", LoadProgram("path"))));
+        assert_eq!(parse("load"), Ok(("", PickProgram)));
+        assert_eq!(parse("load-overlay path"), Ok(("", LoadOverlay("path"))));
+        assert_eq!(parse(" LOAD \t"), Ok(("", PickProgram)));
+        assert!(parse("loadx").is_err());
+        assert_eq!(parse(" dump ram.hex"), Ok(("", DumpMemory("ram.hex"))));
         assert_eq!(parse("fD = 0xFE"), Ok(("", SetInputReg(Fd, 0xFE))));
         assert_eq!(parse("set IRG = 0b10101101"), Ok(("", SetIrg(0b10101101))));
         assert_eq!(parse("set TEMP = 1.234"), Ok(("", SetTemp(1.234))));
@@ -320,14 +613,23 @@ mod tests {
         assert_eq!(parse("set I2 = 8.765"), Ok(("", SetI2(8.765))));
         assert_eq!(parse("set J1\t"), Ok(("", SetJ1(true))));
         assert_eq!(parse("set J2"), Ok(("", SetJ2(true))));
-        assert_eq!(parse("unset J1"), Ok(("", SetJ1(false))));
-        assert_eq!(parse("unset J2"), Ok(("", SetJ2(false))));
+        assert_eq!(parse("unset J1"), Ok(("", Unset(BoolSetting::J1))));
+        assert_eq!(parse("unset J2"), Ok(("", Unset(BoolSetting::J2))));
         assert_eq!(parse("set UIO1"), Ok(("", SetUio1(true))));
         assert_eq!(parse("\tset UIO2"), Ok(("", SetUio2(true))));
         assert_eq!(parse("set UIO3"), Ok(("", SetUio3(true))));
-        assert_eq!(parse("unset UIO1"), Ok(("", SetUio1(false))));
-        assert_eq!(parse("unset UIO2 "), Ok(("", SetUio2(false))));
-        assert_eq!(parse("unset UIO3"), Ok(("", SetUio3(false))));
+        assert_eq!(parse("unset UIO1"), Ok(("", Unset(BoolSetting::Uio1))));
+        assert_eq!(parse("unset UIO2 "), Ok(("", Unset(BoolSetting::Uio2))));
+        assert_eq!(parse("unset UIO3"), Ok(("", Unset(BoolSetting::Uio3))));
+        assert_eq!(parse("set DI1 = 7"), Ok(("", SetIrg(7))));
+        assert_eq!(
+            parse("set STEP = asm"),
+            Ok(("", SetStepMode(StepMode::Assembly)))
+        );
+        assert_eq!(
+            parse("reg R1 = 42"),
+            Ok(("", SetRegister(RegisterNumber::R1, 42)))
+        );
         assert_eq!(parse(" show memory"), Ok(("", Show(Part::Memory))));
         assert_eq!(parse("quit"), Ok(("", Quit)));
     }
@@ -10,11 +10,14 @@ use tui::{
 
 use crate::{
     helpers,
-    tui::{input::InputWidget, MachineWidget, NotificationWidget, ProgramHelpSidebar, Tui},
+    tui::{
+        input::InputWidget, FilePickerWidget, MachineWidget, NotificationWidget,
+        ProgramHelpSidebar, Tui,
+    },
 };
 
 pub const MINIMUM_ALLOWED_WIDTH: u16 = 76;
-pub const MINIMUM_ALLOWED_HEIGHT: u16 = 28;
+pub const MINIMUM_ALLOWED_HEIGHT: u16 = 29;
 const RIGHT_SIDEBAR_WIDTH: u16 = 35;
 const INPUT_AREA_HEIGHT: u16 = 2;
 
@@ -114,6 +117,9 @@ impl StatefulWidget for MainView {
         if !state.notification_state.is_empty() {
             // If there is a notification show that instead of the default view
             NotificationWidget.render(main_machine_area, buf, &mut state.notification_state);
+        } else if let Some(ref mut picker) = state.file_picker {
+            // The file picker also replaces the default view while it is open
+            FilePickerWidget.render(main_machine_area, buf, picker);
         } else {
             // Render the rest of the main view, registers and the shown part.
             MachineWidget.render(main_machine_area, buf, &mut state.machine);
@@ -146,11 +152,13 @@ impl StatefulWidget for MainView {
 /// │ R6 00000000                           │Clock                         Enter
 /// │ R7 00000000                           │Toggle autorun               CTRL+A
 /// │                                       │Toggle asm step              CTRL+W
+/// │                                       │Step over                    CTRL+O
 /// │                                       │Reset                        CTRL+R
 /// │                                       │Edge interrupt               CTRL+E
 /// │                                       │Continue                     CTRL+L
+/// │                                       │Cycle view                   CTRL+V
 /// │                                       │━╸Commands╺━━━━━━━━━━━━━━━━━━━━━━━━
-/// │                                       │load PATH          Load asm program
+/// │                                       │load [PATH]        Load asm program
 /// │                                   ... │set …             Change a settings
 /// │                                       │unset …        Unset a bool setting
 /// │───────────────────────────────────────│show …       Select part to display
@@ -213,3 +221,403 @@ pub enum TextSlice<'a> {
     /// Text with associated color.
     Colored(&'a str, Color),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        args::InteractiveArgs,
+        tui::{Part, ProgramDisplayState},
+    };
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use emulator_2a_lib::{
+        compiler::Translator,
+        machine::{RegisterNumber, State},
+        parser::AsmParser,
+    };
+
+    fn render_with_size(tui: &mut Tui, width: u16, height: u16) -> String {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        Interface.render(area, &mut buf, tui);
+        buf.content
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect()
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent {
+            code,
+            modifiers: KeyModifiers::empty(),
+        }
+    }
+
+    fn enter(tui: &mut Tui, cmd: &str) {
+        tui.notification_state.clear();
+        tui.input_field.paste(cmd);
+        tui.handle_key_event(key(KeyCode::Enter));
+    }
+
+    #[test]
+    fn interface_renders_at_any_size() {
+        let args = InteractiveArgs::default();
+        let mut tui = Tui::new(&args).expect("Failed to create TUI");
+        for part in &[Part::RegisterBlock, Part::Memory, Part::Interrupts] {
+            tui.machine.show(*part);
+            for width in 0..=MINIMUM_ALLOWED_WIDTH + 20 {
+                for height in 0..=MINIMUM_ALLOWED_HEIGHT + 20 {
+                    render_with_size(&mut tui, width, height);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_terminals_show_registers_instead_of_memory() {
+        let args = InteractiveArgs::default();
+        let mut tui = Tui::new(&args).expect("Failed to create TUI");
+        tui.machine.show(Part::Memory);
+        let small = render_with_size(&mut tui, MINIMUM_ALLOWED_WIDTH, MINIMUM_ALLOWED_HEIGHT);
+        assert!(small.contains("Registers:"));
+        assert!(!small.contains("Memory:"));
+        assert!(!small.contains("too small"));
+        let large = render_with_size(
+            &mut tui,
+            MINIMUM_ALLOWED_WIDTH + 40,
+            MINIMUM_ALLOWED_HEIGHT + 10,
+        );
+        assert!(large.contains("Memory:"));
+    }
+
+    #[test]
+    fn interrupt_panel_shows_pending_key_interrupts() {
+        let args = InteractiveArgs::default();
+        let mut tui = Tui::new(&args).expect("Failed to create TUI");
+        tui.machine.show(Part::Interrupts);
+        let width = MINIMUM_ALLOWED_WIDTH + 40;
+        let height = MINIMUM_ALLOWED_HEIGHT + 10;
+        let screen = render_with_size(&mut tui, width, height);
+        assert!(screen.contains("Key     -        -        -"));
+        // Enable key edge interrupts in the MICR
+        tui.machine.raw_mut().bus_mut().write(0xF9, 0b0000_0001);
+        tui.machine.trigger_key_interrupt();
+        let screen = render_with_size(&mut tui, width, height);
+        assert!(screen.contains("MICR 00000001"));
+        assert!(screen.contains("Key     edge     yes"));
+    }
+
+    #[test]
+    fn views_are_cycled_without_touching_the_input_field() {
+        let ctrl_v = KeyEvent {
+            modifiers: KeyModifiers::CONTROL,
+            ..key(KeyCode::Char('v'))
+        };
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.input_field.paste("show");
+        assert_eq!(tui.machine.part, Part::RegisterBlock);
+        for part in &[Part::Memory, Part::Interrupts, Part::RegisterBlock] {
+            tui.handle_key_event(ctrl_v);
+            assert_eq!(tui.machine.part, *part);
+        }
+        assert_eq!(tui.input_field.current().iter().collect::<String>(), "show");
+        let screen = render_with_size(
+            &mut tui,
+            MINIMUM_ALLOWED_WIDTH + 40,
+            MINIMUM_ALLOWED_HEIGHT + 10,
+        );
+        assert!(screen.contains("Cycle view"));
+    }
+
+    #[test]
+    fn bare_load_opens_the_file_picker() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        enter(&mut tui, "load");
+        assert!(tui.file_picker.is_some());
+        let width = MINIMUM_ALLOWED_WIDTH + 40;
+        let height = MINIMUM_ALLOWED_HEIGHT + 10;
+        let screen = render_with_size(&mut tui, width, height);
+        assert!(screen.contains("Load program from ./"));
+        assert!(screen.contains("> ../"));
+        // Neither clocks the machine nor reaches the input field
+        let program_counter = *tui.machine.registers().get(RegisterNumber::R3);
+        tui.handle_key_event(key(KeyCode::Down));
+        tui.handle_key_event(key(KeyCode::Char('x')));
+        assert!(tui.input_field.is_empty());
+        assert_eq!(
+            *tui.machine.registers().get(RegisterNumber::R3),
+            program_counter
+        );
+        tui.handle_key_event(key(KeyCode::Esc));
+        assert!(tui.file_picker.is_none());
+        let screen = render_with_size(&mut tui, width, height);
+        assert!(!screen.contains("Load program from"));
+    }
+
+    #[test]
+    fn successful_loads_are_notified() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        let path = "../testing/programs/02-stacksize-32.asm";
+        tui.load_program(path).expect("Failed to load program");
+        let program = crate::helpers::read_asm_file(path).expect("Failed to read program");
+        let bytes = Translator::compile(&program).unwrap().bytes().count();
+        assert_eq!(
+            tui.notification_state.current,
+            Some(format!(
+                "Loaded 02-stacksize-32.asm — {} bytes, stacksize 32",
+                bytes
+            ))
+        );
+        let screen = render_with_size(
+            &mut tui,
+            MINIMUM_ALLOWED_WIDTH + 40,
+            MINIMUM_ALLOWED_HEIGHT + 10,
+        );
+        assert!(screen.contains("Loaded 02-stacksize-32.asm"));
+        // The next key press only clears the notification
+        tui.handle_key_event(key(KeyCode::Char('x')));
+        assert!(tui.notification_state.is_empty());
+        assert!(tui.input_field.is_empty());
+    }
+
+    #[test]
+    fn halting_is_notified() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.load_program("../testing/programs/23-just-halt.asm")
+            .expect("Failed to load program");
+        enter(&mut tui, "next 20");
+        assert_eq!(tui.machine.state(), State::Stopped);
+        assert_eq!(
+            tui.notification_state.current,
+            Some("Machine stopped".to_string())
+        );
+    }
+
+    #[test]
+    fn memory_dumps_can_be_read_back() {
+        use emulator_2a_lib::export;
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.load_program("../testing/programs/02-stacksize-32.asm")
+            .expect("Failed to load program");
+        let memory = tui.machine.bus().memory().to_vec();
+        let dir = std::env::temp_dir();
+        let hex_path = dir.join("2a-emulator-dump-test.hex");
+        let bin_path = dir.join("2a-emulator-dump-test.bin");
+        for path in &[&hex_path, &bin_path] {
+            enter(&mut tui, &format!("dump {}", path.display()));
+            assert_eq!(
+                tui.notification_state.current,
+                Some(format!("Dumped 240 bytes of memory to {}", path.display()))
+            );
+        }
+        let hex = std::fs::read_to_string(&hex_path).expect("Failed to read dump");
+        assert_eq!(export::parse_intel_hex(&hex), Ok(memory.clone()));
+        assert_eq!(
+            std::fs::read(&bin_path).expect("Failed to read dump"),
+            memory
+        );
+        std::fs::remove_file(hex_path).unwrap();
+        std::fs::remove_file(bin_path).unwrap();
+
+        tui.dump_memory(&dir.join("does-not-exist").join("dump.hex"));
+        let notification = tui.notification_state.current.unwrap();
+        assert!(notification.starts_with("Failed to dump memory"));
+    }
+
+    #[test]
+    fn overlays_keep_the_loaded_program_and_registers() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.load_program("../testing/programs/02-stacksize-32.asm")
+            .expect("Failed to load program");
+        let program = tui.machine.bus().memory()[..0x10].to_vec();
+        let dir = std::env::temp_dir();
+        let data_path = dir.join("2a-emulator-overlay-data.asm");
+        let code_path = dir.join("2a-emulator-overlay-code.asm");
+        std::fs::write(&data_path, "#! mrasm\n.ORG 0x80\nDATA:\n.DB 1, 2, 3\n").unwrap();
+        std::fs::write(&code_path, "#! mrasm\n    NOP\n").unwrap();
+        enter(&mut tui, "reg R1 = 0x42");
+        enter(&mut tui, &format!("load-overlay {}", data_path.display()));
+        assert_eq!(
+            tui.notification_state.current,
+            Some("Overlaid 2a-emulator-overlay-data.asm — 3 bytes".to_string())
+        );
+        assert_eq!(*tui.machine.registers().get(RegisterNumber::R1), 0x42);
+        assert_eq!(&tui.machine.bus().memory()[..0x10], &program[..]);
+        assert_eq!(&tui.machine.bus().memory()[0x80..0x83], &[1, 2, 3]);
+        assert_eq!(tui.program_display_state.label_address("data"), Some(0x80));
+        let last_line = tui.program_display_state.lines.last().unwrap();
+        assert_eq!(last_line.0, 0x80..0x83);
+        assert_eq!(last_line.1.trim(), ".DB 1, 2, 3");
+        // Overwriting the current instruction is allowed, but warned about
+        enter(&mut tui, &format!("load-overlay {}", code_path.display()));
+        assert_eq!(
+            tui.notification_state.current,
+            Some(
+                "Overlaid 2a-emulator-overlay-code.asm — 1 bytes\n\
+                 The overlay overwrites the instruction at PC 0x00"
+                    .to_string()
+            )
+        );
+        let (_, first_instruction) = tui
+            .program_display_state
+            .lines
+            .iter()
+            .find(|(range, _)| !range.is_empty())
+            .unwrap();
+        assert_eq!(first_instruction.trim(), "NOP");
+        std::fs::remove_file(data_path).unwrap();
+        std::fs::remove_file(code_path).unwrap();
+    }
+
+    #[test]
+    fn breakpoints_are_toggled_by_label_and_address() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.load_program("../testing/programs/21-simple-counter.asm")
+            .expect("Failed to load program");
+        let label = tui
+            .program_display_state
+            .label_address("loop")
+            .expect("Missing label");
+        enter(&mut tui, "break LOOP");
+        enter(&mut tui, "break 0x01");
+        assert_eq!(
+            tui.machine.breakpoints().iter().collect::<Vec<_>>(),
+            vec![&label, &0x01]
+        );
+        enter(&mut tui, "break loop");
+        assert!(!tui.machine.breakpoints().contains(&label));
+        enter(&mut tui, "break NOWHERE");
+        assert_eq!(
+            tui.notification_state.current,
+            Some("Cannot set breakpoint, unknown label 'NOWHERE'".into())
+        );
+        assert_eq!(tui.machine.breakpoints().len(), 1);
+    }
+
+    #[test]
+    fn breakpoints_survive_reloads() {
+        let path = std::env::temp_dir().join("2a-emulator-reload-test.asm");
+        std::fs::write(
+            &path,
+            "#! mrasm\n    CLR R0\nLOOP:\n    INC R0\n    JR LOOP\n",
+        )
+        .unwrap();
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        tui.load_program(&path).expect("Failed to load program");
+        for command in &["break LOOP", "break 0x00", "break 0x20", "break 0x03"] {
+            enter(&mut tui, command);
+        }
+        assert_eq!(
+            tui.machine.breakpoints().iter().collect::<Vec<_>>(),
+            vec![&0x00, &0x01, &0x03, &0x20]
+        );
+        // Insert an instruction in front of the loop
+        std::fs::write(
+            &path,
+            "#! mrasm\n    CLR R0\n    CLR R1\nLOOP:\n    INC R0\n    JR LOOP\n",
+        )
+        .unwrap();
+        let result = tui.load_program(&path);
+        std::fs::remove_file(&path).ok();
+        result.expect("Failed to reload program");
+        assert_eq!(
+            tui.machine.breakpoints().iter().collect::<Vec<_>>(),
+            vec![&0x00, &0x02, &0x03, &0x20]
+        );
+        let notification = tui.notification_state.current.clone().unwrap();
+        assert!(notification.ends_with("\nBreakpoint at 0x20 is outside of the program"));
+        // Breakpoints keep their label across multiple reloads
+        std::fs::write(&path, "#! mrasm\nLOOP:\n    JR LOOP\n").unwrap();
+        let first = tui.load_program(&path);
+        std::fs::write(&path, "#! mrasm\nEND:\n    JR END\n").unwrap();
+        let second = tui.load_program(&path);
+        std::fs::remove_file(&path).ok();
+        first.expect("Failed to reload program");
+        second.expect("Failed to reload program");
+        let notification = tui.notification_state.current.clone().unwrap();
+        assert!(notification.contains("\nRemoved breakpoint at unknown label 'LOOP'"));
+        assert_eq!(
+            tui.machine.breakpoints().iter().collect::<Vec<_>>(),
+            vec![&0x03, &0x20]
+        );
+    }
+
+    #[test]
+    fn rerun_reloads_the_program_without_reading_the_file() {
+        let mut tui = Tui::new(&InteractiveArgs::default()).expect("Failed to create TUI");
+        enter(&mut tui, "rerun");
+        assert_eq!(
+            tui.notification_state.current,
+            Some("There is no program to rerun".into())
+        );
+        let path = std::env::temp_dir().join("2a-emulator-rerun-test.asm");
+        std::fs::write(
+            &path,
+            "#! mrasm\nLOOP:\n    INC R0\n    ST (LOOP), R0\n    JR LOOP\n",
+        )
+        .unwrap();
+        let result = tui.load_program(&path);
+        std::fs::remove_file(&path).ok();
+        result.expect("Failed to load program");
+        let memory = *tui.machine.bus().memory();
+        for _ in 0..30 {
+            tui.trigger_key_clock();
+        }
+        assert_ne!(*tui.machine.registers().get(RegisterNumber::R0), 0);
+        assert_ne!(*tui.machine.bus().memory(), memory);
+        enter(&mut tui, "rerun");
+        assert!(tui.notification_state.is_empty());
+        assert_eq!(*tui.machine.bus().memory(), memory);
+        assert_eq!(*tui.machine.registers().get(RegisterNumber::R0), 0);
+        assert_eq!(*tui.machine.registers().get(RegisterNumber::R3), 0);
+        assert_eq!(tui.machine.cycles_into_instruction(), 0);
+        // Ctrl+N does the same
+        tui.trigger_key_clock();
+        tui.handle_key_event(KeyEvent {
+            modifiers: KeyModifiers::CONTROL,
+            ..key(KeyCode::Char('n'))
+        });
+        assert_eq!(*tui.machine.registers().get(RegisterNumber::R3), 0);
+        assert_eq!(tui.machine.cycles_into_instruction(), 0);
+    }
+
+    #[test]
+    fn clicks_select_memory_cells_and_toggle_breakpoints() {
+        let args = InteractiveArgs {
+            mouse: true,
+            ..Default::default()
+        };
+        let mut tui = Tui::new(&args).expect("Failed to create TUI");
+        let program = AsmParser::parse("#! mrasm\n    CLR R0\nLOOP:\n    INC R0\n    JR LOOP\n")
+            .expect("Parsing failed");
+        tui.program_display_state =
+            ProgramDisplayState::from_bytecode(&Translator::compile(&program).unwrap());
+        tui.machine.show(Part::Memory);
+        let width = MINIMUM_ALLOWED_WIDTH + 40;
+        let screen = render_with_size(&mut tui, width, MINIMUM_ALLOWED_HEIGHT + 10);
+        let position = |text: &str| {
+            let byte_idx = screen.find(text).expect("Text not rendered");
+            let idx = screen[..byte_idx].chars().count() as u16;
+            (idx % width, idx / width)
+        };
+        // Select cell 0x12
+        let (x, y) = position("Memory:");
+        tui.handle_click(x + 3 + 2 * 3, y + 2 + 1);
+        assert_eq!(tui.machine.selected_memory_cell, Some(0x12));
+        // Clicking the space between cells does nothing
+        tui.handle_click(x + 3 + 2 * 3 + 2, y + 2);
+        assert_eq!(tui.machine.selected_memory_cell, Some(0x12));
+        // Toggle a breakpoint at `INC R0`
+        let (x, y) = position("INC R0");
+        tui.handle_click(x, y);
+        assert!(tui.machine.breakpoints().contains(&0x01));
+        // Labels have no address
+        let (x, y) = position("LOOP:");
+        tui.handle_click(x, y);
+        assert_eq!(tui.machine.breakpoints().len(), 1);
+        render_with_size(&mut tui, width, MINIMUM_ALLOWED_HEIGHT + 10);
+        let (x, y) = position("INC R0");
+        tui.handle_click(x, y);
+        assert!(tui.machine.breakpoints().is_empty());
+    }
+}
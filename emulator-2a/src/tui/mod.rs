@@ -1,27 +1,34 @@
 //! Everything necessary to run the Terminal User Interface.
 use crossterm::{
-    event::{KeyCode, KeyEvent, KeyModifiers as Mod},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers as Mod,
+        MouseButton, MouseEvent,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use emulator_2a_lib::{
-    compiler::Translator,
-    machine::{State, StepMode},
+    compiler::{ByteCode, Translator},
+    export,
+    machine::{RegisterNumber, State, StateChange, StepMode},
 };
 use log::{trace, warn};
 use scopeguard::defer;
 use tui::{backend::CrosstermBackend, Terminal};
 
 use std::{
+    fs,
     io::{Stdout, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     thread,
-    time::{Duration, Instant},
+    time::Instant,
 };
 
 mod board_info_sidebar;
 pub mod display;
 pub mod events;
+mod file_picker;
+pub mod frame_budget;
 pub mod input;
 pub mod interface;
 mod notification;
@@ -32,11 +39,13 @@ mod supervisor_wrapper;
 use crate::{
     args::InteractiveArgs,
     error::Error,
-    helpers::{self, dur_sub},
+    helpers::{self, dur_sub, FrequencyMeter},
 };
 pub use board_info_sidebar::BoardInfoSidebarWidget;
 use events::Events;
-use input::{Command, InputRegister, InputState};
+pub use file_picker::{FilePickerState, FilePickerWidget, Selection};
+pub use frame_budget::FrameBudget;
+use input::{BoolSetting, BreakTarget, ClearTarget, Command, InputRegister, InputState};
 use interface::Interface;
 pub use notification::{NotificationState, NotificationWidget};
 pub use program_help_sidebar::{KeybindingHelpState, ProgramDisplayState, ProgramHelpSidebar};
@@ -45,10 +54,6 @@ pub use supervisor_wrapper::{MachineState, MachineWidget, Part};
 pub type Backend = CrosstermBackend<Stdout>;
 type AbortEmulation = bool;
 
-const FRAMES_PER_SECOND: u64 = 24;
-const CYCLES_PER_SECOND: u64 = 7_372_800;
-const DURATION_BETWEEN_FRAMES: Duration = Duration::from_micros(1_000_000 / FRAMES_PER_SECOND);
-
 /// The Terminal User Interface (TUI)
 pub struct Tui {
     /// State for the [`MachineWidget`].
@@ -66,29 +71,44 @@ pub struct Tui {
     /// State for the
     /// [`ProgramDisplayWidget`](program_help_sidebar::KeybindingHelpWidget).
     program_display_state: ProgramDisplayState,
-    /// Measured frequency derived in the main loop
-    measured_freq: f32,
+    /// Measures the frequency of the machine in the main loop.
+    frequency_meter: FrequencyMeter,
+    /// Frame rate and number of cycles to emulate per frame.
+    frame_budget: FrameBudget,
+    /// Did the last auto run frame fail to emulate its cycle budget in time?
+    falling_behind: bool,
+    /// Was the terminal resized since the last frame?
+    terminal_resized: bool,
+    /// Is mouse capture enabled?
+    mouse_enabled: bool,
     /// State for the notification area.
     notification_state: NotificationState,
+    /// State for the [`FilePickerWidget`], if it is open.
+    file_picker: Option<FilePickerState>,
 }
 
 impl Tui {
     /// Creates a new Tui and shows it.
     pub fn new(args: &InteractiveArgs) -> Result<Self, Error> {
+        let config = args.init.machine_config()?;
         let (machine, program_display_state) = if let Some(path) = args.program.as_ref() {
             let program = helpers::read_asm_file(&path)?;
-            let bytecode = Translator::compile(&program);
+            let bytecode = Translator::compile(&program)?;
             (
-                MachineState::new_with_program(&args.init, path, bytecode.clone()),
+                MachineState::new_with_program(config, path, bytecode.clone()),
                 ProgramDisplayState::from_bytecode(&bytecode),
             )
         } else {
-            (MachineState::new(&args.init), ProgramDisplayState::empty())
+            (MachineState::new(config), ProgramDisplayState::empty())
         };
         let events = Events::new();
         let input_field = InputState::new();
         let keybinding_state = KeybindingHelpState::init();
-        let measured_freq = 0.0;
+        let mut frame_budget = args.fps.map(FrameBudget::new).unwrap_or_default();
+        if let Some(cycles_per_frame) = args.cycles_per_frame {
+            frame_budget = frame_budget.with_cycles_per_frame(cycles_per_frame);
+        }
+        let frequency_meter = FrequencyMeter::new(frame_budget.frames_per_second() as usize);
         let notification_state = NotificationState::empty();
         Ok(Tui {
             machine,
@@ -96,8 +116,13 @@ impl Tui {
             input_field,
             keybinding_state,
             program_display_state,
-            measured_freq,
+            frequency_meter,
+            frame_budget,
+            falling_behind: false,
             notification_state,
+            file_picker: None,
+            terminal_resized: false,
+            mouse_enabled: args.mouse,
         })
     }
     /// Create a new TUI from the given command line arguments
@@ -111,11 +136,14 @@ impl Tui {
         defer! {
             disable_raw_mode().map_err(Error::crossterm_exit).ok();
             let mut stdout = ::std::io::stdout();
-            execute!(stdout, LeaveAlternateScreen).ok();
+            execute!(stdout, DisableMouseCapture, LeaveAlternateScreen).ok();
         }
         // Initialize backend.
         let mut stdout = ::std::io::stdout();
         execute!(stdout, EnterAlternateScreen).map_err(Error::crossterm_init)?;
+        if self.mouse_enabled {
+            execute!(stdout, EnableMouseCapture).map_err(Error::crossterm_init)?;
+        }
         enable_raw_mode().map_err(Error::crossterm_init)?;
         let crossterm_backend: Backend = CrosstermBackend::new(stdout);
         let mut backend = Terminal::new(crossterm_backend).map_err(Error::tui_init)?;
@@ -136,27 +164,44 @@ impl Tui {
                 // Quit
                 break;
             }
+            // Redraw everything after a resize, the old content is garbage
+            if self.terminal_resized {
+                self.terminal_resized = false;
+                backend.autoresize()?;
+                backend.clear()?;
+            }
             // Next draw of the machine
             backend.draw(|mut f| {
                 let area = f.size();
                 f.render_stateful_widget(Interface, area, &mut self);
             })?;
             // Wait or calculate, depending on auto_run_mode
+            let duration_between_frames = self.frame_budget.duration_between_frames();
             if self.machine.auto_run_mode {
                 // Do some calculations between frames
-                while last_draw.elapsed() < DURATION_BETWEEN_FRAMES
-                    && executed_cycles < CYCLES_PER_SECOND / FRAMES_PER_SECOND
+                while last_draw.elapsed() < duration_between_frames
+                    && executed_cycles < self.frame_budget.cycles_per_frame()
                 {
                     // Let the machine do some work
-                    self.machine.trigger_key_clock();
+                    self.trigger_key_clock();
                     executed_cycles += 1;
+                    if self.machine.is_at_breakpoint() {
+                        self.machine.auto_run_mode = false;
+                        break;
+                    }
+                }
+                self.falling_behind = self
+                    .frame_budget
+                    .is_falling_behind(executed_cycles, last_draw.elapsed());
+                thread::sleep(dur_sub(duration_between_frames, last_draw.elapsed()));
+            } else {
+                self.falling_behind = false;
+                if last_draw.elapsed() < duration_between_frames {
+                    thread::sleep(duration_between_frames - last_draw.elapsed());
                 }
-                thread::sleep(dur_sub(DURATION_BETWEEN_FRAMES, last_draw.elapsed()));
-            } else if last_draw.elapsed() < DURATION_BETWEEN_FRAMES {
-                thread::sleep(DURATION_BETWEEN_FRAMES - last_draw.elapsed());
             }
-            self.measured_freq =
-                1e6 * executed_cycles as f32 / last_draw.elapsed().as_micros() as f32;
+            self.frequency_meter
+                .record(executed_cycles, last_draw.elapsed());
         }
         backend.clear()?;
         backend.show_cursor()?;
@@ -166,72 +211,168 @@ impl Tui {
     pub const fn machine(&self) -> &MachineState {
         &self.machine
     }
+    /// Get the current [`FrameBudget`].
+    pub const fn frame_budget(&self) -> &FrameBudget {
+        &self.frame_budget
+    }
+    /// Change the frame rate, keeping the targeted clock frequency.
+    pub fn set_frames_per_second(&mut self, frames_per_second: u64) {
+        self.frame_budget = FrameBudget::new(frames_per_second);
+        self.frequency_meter = FrequencyMeter::new(self.frame_budget.frames_per_second() as usize);
+    }
     /// Handle one single event in the queue.
     /// Returns whether to abort emulation or not.
     fn handle_event(&mut self) -> AbortEmulation {
-        if let Some(event) = self.events.next_key() {
-            // If some notification exist, clear that
-            if !self.notification_state.is_empty() {
-                self.notification_state.clear();
-                return false;
+        match self.events.next() {
+            Some(Event::Key(event)) => self.handle_key_event(event),
+            Some(Event::Resize(width, height)) => {
+                trace!("Terminal resized to {}x{}", width, height);
+                self.terminal_resized = true;
+                false
             }
-            use KeyCode::*;
-            trace!("{:?}", event);
-            if event.modifiers == Mod::CONTROL {
-                match event.code {
-                    Char('c') => true,
-                    Char('a') => {
-                        self.machine.toggle_auto_run_mode();
-                        false
-                    }
-                    Char('w') => {
-                        self.machine.toggle_step_mode();
-                        false
-                    }
-                    Char('e') => {
-                        self.machine.trigger_key_interrupt();
-                        self.keybinding_state.int_pressed();
-                        false
-                    }
-                    Char('r') => {
-                        self.machine.cpu_reset();
-                        self.keybinding_state.reset_pressed();
-                        false
-                    }
-                    Char('l') => {
-                        self.machine.trigger_key_continue();
-                        self.keybinding_state.continue_pressed();
-                        false
-                    }
-                    _ => {
-                        warn!("TUI cannot handle event {:?}", event);
-                        false
-                    }
+            Some(Event::Mouse(MouseEvent::Down(MouseButton::Left, x, y, _))) => {
+                self.handle_click(x, y);
+                false
+            }
+            _ => false,
+        }
+    }
+    /// Handle a left click at the given screen position.
+    ///
+    /// Clicking a program line toggles a breakpoint at its first address,
+    /// clicking a memory cell selects it.
+    fn handle_click(&mut self, x: u16, y: u16) {
+        trace!("Click at {}x{}", x, y);
+        if let Some(addr) = self.program_display_state.address_at(x, y) {
+            self.machine.toggle_breakpoint(addr);
+        } else if let Some(addr) = self.machine.memory_cell_at(x, y) {
+            self.machine.selected_memory_cell = Some(addr);
+        }
+    }
+    /// Handle a single key event.
+    /// Returns whether to abort emulation or not.
+    fn handle_key_event(&mut self, event: KeyEvent) -> AbortEmulation {
+        // If some notification exist, clear that
+        if !self.notification_state.is_empty() {
+            self.notification_state.clear();
+            return false;
+        }
+        if self.file_picker.is_some() {
+            return self.handle_file_picker_key_event(event);
+        }
+        use KeyCode::*;
+        trace!("{:?}", event);
+        if event.modifiers == Mod::CONTROL {
+            match event.code {
+                Char('c') => true,
+                Char('a') => {
+                    self.machine.toggle_auto_run_mode();
+                    false
                 }
-            } else {
-                match event.code {
-                    Enter => {
-                        if self.input_field.is_empty() {
-                            self.machine.trigger_key_clock();
-                            self.keybinding_state.clk_pressed();
-                            false
-                        } else {
-                            self.handle_input()
-                        }
-                    }
-                    Home | End | Tab | BackTab | Backspace | Left | Right | Up | Down | Delete
-                    | Char(_) => {
-                        self.input_field.handle(event);
+                Char('w') => {
+                    self.machine.toggle_step_mode();
+                    false
+                }
+                Char('o') => {
+                    self.machine.step_over();
+                    self.keybinding_state.step_over_pressed();
+                    false
+                }
+                Char('e') => {
+                    self.machine.trigger_key_interrupt();
+                    self.keybinding_state.int_pressed();
+                    false
+                }
+                Char('r') => {
+                    self.machine.cpu_reset();
+                    self.keybinding_state.reset_pressed();
+                    false
+                }
+                Char('l') => {
+                    self.machine.trigger_key_continue();
+                    self.keybinding_state.continue_pressed();
+                    false
+                }
+                Char('v') => {
+                    self.machine.cycle_part();
+                    self.keybinding_state.cycle_view_pressed();
+                    false
+                }
+                Char('k') => {
+                    self.clear_all();
+                    false
+                }
+                Char('n') => {
+                    self.rerun();
+                    false
+                }
+                _ => {
+                    warn!("TUI cannot handle event {:?}", event);
+                    false
+                }
+            }
+        } else {
+            match event.code {
+                Enter => {
+                    if self.input_field.is_empty() {
+                        self.trigger_key_clock();
+                        self.keybinding_state.clk_pressed();
                         false
+                    } else {
+                        self.handle_input()
                     }
-                    _ => {
-                        warn!("TUI cannot handle event {:?}", event);
-                        false
+                }
+                Home | End | Tab | BackTab | Backspace | Left | Right | Up | Down | Delete
+                | Char(_) => {
+                    self.input_field.handle(event);
+                    false
+                }
+                _ => {
+                    warn!("TUI cannot handle event {:?}", event);
+                    false
+                }
+            }
+        }
+    }
+    /// Handle a single key event while the file picker is open.
+    /// Returns whether to abort emulation or not.
+    fn handle_file_picker_key_event(&mut self, event: KeyEvent) -> AbortEmulation {
+        use KeyCode::*;
+        trace!("{:?}", event);
+        let picker = match self.file_picker.as_mut() {
+            Some(picker) => picker,
+            None => return false,
+        };
+        match (event.modifiers, event.code) {
+            (Mod::CONTROL, Char('c')) => return true,
+            (_, Esc) => self.file_picker = None,
+            (_, Up) => picker.previous(),
+            (_, Down) => picker.next(),
+            (_, Enter) => match picker.selected() {
+                Selection::Directory(directory) => self.open_file_picker(&directory),
+                Selection::File(path) => {
+                    self.file_picker = None;
+                    if let Err(e) = self.load_program(path) {
+                        self.warn_about_failed_load(e)
                     }
                 }
+            },
+            _ => {}
+        }
+        false
+    }
+    /// Show the entries of `directory` in the file picker.
+    ///
+    /// If the directory cannot be read, a notification is shown instead
+    /// and the file picker stays as it is.
+    fn open_file_picker(&mut self, directory: &str) {
+        match FilePickerState::open(directory) {
+            Ok(picker) => self.file_picker = Some(picker),
+            Err(e) => {
+                warn!("Failed to open directory {:?}: {}", directory, e);
+                let warning = format!("Failed to open directory '{}':\n\n{}", directory, e);
+                self.notification_state.current = Some(warning);
             }
-        } else {
-            false
         }
     }
     /// Handle the input field after an 'Enter'.
@@ -251,6 +392,18 @@ impl Tui {
                         Err(e) => self.warn_about_failed_load(e),
                     }
                 }
+                Command::LoadOverlay(path) => {
+                    let path = path.to_owned();
+                    match self.load_overlay(path) {
+                        Ok(()) => {}
+                        Err(e) => self.warn_about_failed_load(e),
+                    }
+                }
+                Command::PickProgram => self.open_file_picker(""),
+                Command::DumpMemory(path) => {
+                    let path = PathBuf::from(path);
+                    self.dump_memory(&path)
+                }
                 Command::SetInputReg(InputRegister::Fc, val) => self.machine.set_input_fc(val),
                 Command::SetInputReg(InputRegister::Fd, val) => self.machine.set_input_fd(val),
                 Command::SetInputReg(InputRegister::Fe, val) => self.machine.set_input_fe(val),
@@ -264,12 +417,55 @@ impl Tui {
                 Command::SetUio1(val) => self.machine.set_universal_input_output1(val),
                 Command::SetUio2(val) => self.machine.set_universal_input_output2(val),
                 Command::SetUio3(val) => self.machine.set_universal_input_output3(val),
+                Command::Unset(BoolSetting::J1) => self.machine.set_jumper1(false),
+                Command::Unset(BoolSetting::J2) => self.machine.set_jumper2(false),
+                Command::Unset(BoolSetting::Uio1) => {
+                    self.machine.set_universal_input_output1(false)
+                }
+                Command::Unset(BoolSetting::Uio2) => {
+                    self.machine.set_universal_input_output2(false)
+                }
+                Command::Unset(BoolSetting::Uio3) => {
+                    self.machine.set_universal_input_output3(false)
+                }
+                Command::SetStepMode(mode) => self.machine.set_step_mode(mode),
+                Command::SetRegister(register, val) => {
+                    self.machine.raw_mut().set_register(register, val)
+                }
                 Command::Show(part) => self.machine.show(part),
+                Command::ToggleBreakpoint(BreakTarget::Address(addr)) => {
+                    self.machine.toggle_breakpoint(addr);
+                }
+                Command::ToggleBreakpoint(BreakTarget::Label(label)) => {
+                    match self.program_display_state.label_address(label) {
+                        Some(addr) => {
+                            self.machine.toggle_breakpoint_at_label(label, addr);
+                        }
+                        None => {
+                            warn!("Unknown label {:?}", label);
+                            let warning =
+                                format!("Cannot set breakpoint, unknown label '{}'", label);
+                            self.notification_state.current = Some(warning);
+                        }
+                    }
+                }
+                Command::Clear(ClearTarget::Breakpoints) => self.clear_breakpoints(),
+                Command::Clear(ClearTarget::All) => self.clear_all(),
+                Command::Rerun => self.rerun(),
+                Command::Fps(fps) => self.set_frames_per_second(fps as u64),
                 Command::Next(cycles) => {
                     for _ in 0..cycles {
-                        self.machine.trigger_key_clock()
+                        self.trigger_key_clock()
                     }
                 }
+                Command::NextInstructions(instructions) => {
+                    let step_mode = self.machine.step_mode();
+                    self.machine.set_step_mode(StepMode::Assembly);
+                    for _ in 0..instructions {
+                        self.trigger_key_clock()
+                    }
+                    self.machine.set_step_mode(step_mode);
+                }
                 Command::Quit => return true,
             }
         } else {
@@ -297,13 +493,135 @@ impl Tui {
     pub fn load_program<P: Into<PathBuf>>(&mut self, path: P) -> Result<(), Error> {
         let path = path.into();
         let program = helpers::read_asm_file(&path)?;
-        let bytecode = Translator::compile(&program);
+        let bytecode = Translator::compile(&program)?;
         // Update the program display state
         self.program_display_state = ProgramDisplayState::from_bytecode(&bytecode);
         // Load the program into the machine
-        self.machine.load_program(path, bytecode);
+        let warnings = self.machine.load_program(path.clone(), bytecode.clone());
+        self.notify_about_successful_load(&path, &bytecode, &warnings);
+        Ok(())
+    }
+    /// Write the program at `path` into memory, without resetting the machine.
+    ///
+    /// Warns, if the overlay overwrites the instruction the program counter points to.
+    pub fn load_overlay<P: Into<PathBuf>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.into();
+        let program = helpers::read_asm_file(&path)?;
+        let bytecode = Translator::compile(&program)?;
+        let pc = *self.machine.registers().get(RegisterNumber::R3);
+        let pc_length = self
+            .machine
+            .peek_instruction_at(pc)
+            .map_or(1, |(_, length)| length);
+        let pc_region = pc as usize..pc as usize + pc_length as usize;
+        let overlaps_pc = bytecode.placed_lines().any(|(start, _, bytes)| {
+            let start = start as usize;
+            start < pc_region.end && pc_region.start < start + bytes.len()
+        });
+        self.program_display_state.overlay(&bytecode);
+        self.machine.load_overlay(&bytecode);
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        let bytes: usize = bytecode
+            .placed_lines()
+            .map(|(_, _, bytes)| bytes.len())
+            .sum();
+        let mut message = format!("Overlaid {} — {} bytes", name.to_string_lossy(), bytes);
+        trace!("{}", message);
+        if overlaps_pc {
+            let warning = format!("The overlay overwrites the instruction at PC 0x{:02X}", pc);
+            warn!("{}", warning);
+            message.push('\n');
+            message.push_str(&warning);
+        }
+        self.notification_state.current = Some(message);
         Ok(())
     }
+    /// Notify about the loaded program, appending the given warnings.
+    fn notify_about_successful_load(
+        &mut self,
+        path: &Path,
+        bytecode: &ByteCode,
+        warnings: &[String],
+    ) {
+        let name = path.file_name().unwrap_or(path.as_os_str());
+        let mut message = format!(
+            "Loaded {} — {} bytes, stacksize {}",
+            name.to_string_lossy(),
+            self.machine.loaded_program_len(),
+            bytecode.stacksize
+        );
+        trace!("{}", message);
+        for warning in warnings {
+            warn!("{}", warning);
+            message.push('\n');
+            message.push_str(warning);
+        }
+        self.notification_state.current = Some(message);
+    }
+    /// Write the main memory to `path` and notify about the outcome.
+    ///
+    /// Files ending in `.hex` are written as Intel HEX, everything else
+    /// as raw binary.
+    fn dump_memory(&mut self, path: &Path) {
+        let memory = self.machine.bus().memory();
+        let is_hex = matches!(path.extension(), Some(ext) if ext.eq_ignore_ascii_case("hex"));
+        let contents = if is_hex {
+            export::to_intel_hex(memory).into_bytes()
+        } else {
+            memory.to_vec()
+        };
+        let message = match fs::write(path, contents) {
+            Ok(()) => format!(
+                "Dumped {} bytes of memory to {}",
+                memory.len(),
+                path.display()
+            ),
+            Err(e) => {
+                warn!("Failed to dump memory to {}: {}", path.display(), e);
+                format!("Failed to dump memory to {}:\n\n{}", path.display(), e)
+            }
+        };
+        self.notification_state.current = Some(message);
+    }
+    /// Remove all breakpoints and notify about the number of removed ones.
+    fn clear_breakpoints(&mut self) {
+        let message = match self.machine.clear_breakpoints() {
+            0 => String::from("There are no breakpoints to remove"),
+            1 => String::from("Removed 1 breakpoint"),
+            count => format!("Removed {} breakpoints", count),
+        };
+        self.notification_state.current = Some(message);
+    }
+    /// Remove everything that can be cleared.
+    ///
+    /// Breakpoints are the only thing that can be cleared for now.
+    fn clear_all(&mut self) {
+        self.clear_breakpoints()
+    }
+    /// Reset the machine and load the current program again, without reading the file.
+    fn rerun(&mut self) {
+        if !self.machine.reset_and_reload() {
+            let warning = String::from("There is no program to rerun");
+            self.notification_state.current = Some(warning);
+        }
+    }
+    /// Trigger the clock and notify, if the machine halted.
+    fn trigger_key_clock(&mut self) {
+        if let Some(change) = self.machine.trigger_key_clock() {
+            self.notify_about_state_change(change);
+        }
+    }
+    /// Notify about the machine halting.
+    fn notify_about_state_change(&mut self, change: StateChange) {
+        let message = match (change.to, change.cause) {
+            (State::Stopped, _) => "Machine stopped".to_string(),
+            (State::ErrorStopped, Some(cause)) => format!("Machine stopped by error, {}", cause),
+            (State::ErrorStopped, None) => "Machine stopped by error".to_string(),
+            (State::Running, _) => return,
+        };
+        trace!("{}", message);
+        self.notification_state.current = Some(message);
+    }
     fn warn_about_failed_load(&mut self, error: Error) {
         warn!("Failed to run program: {}", error);
         let warning = format!("Failed to load program:\n\n{}", error);
@@ -5,11 +5,16 @@ use super::{SpacedStr, HEADER_HEIGHT};
 use crate::helpers;
 
 const COMMAND_HELP_DEFAULT: &[(&str, &str)] = &[
-    ("load PATH", "Load asm program"),
+    ("load [PATH]", "Load asm program"),
+    ("dump PATH", "Save memory to file"),
     ("set …", "Change a settings"),
     ("unset …", "Unset a bool setting"),
+    ("reg …", "Change a CPU register"),
     ("show …", "Select part to display"),
+    ("break ADDR|LABEL", "Toggle breakpoint"),
     ("next <N>", "Run N cycles"),
+    ("next-asm <N>", "Run N instructions"),
+    ("fps N", "Frames per second"),
     ("quit", "Exit the program"),
 ];
 const COMMAND_HELP_SET: &[(&str, &str)] = &[
@@ -17,7 +22,7 @@ const COMMAND_HELP_SET: &[(&str, &str)] = &[
     ("FD = x", "Input reg FD"),
     ("FE = x", "Input reg FE"),
     ("FF = x", "Input reg FF"),
-    ("IRG = x", "MR2DA2 input reg"),
+    ("IRG/DI1 = x", "MR2DA2 input reg"),
     ("TEMP = x.x", "MR2DA2 Temp voltage"),
     ("I1 = x.x", "MR2DA2 analog input 1"),
     ("I2 = x.x", "MR2DA2 analog input 2"),
@@ -26,6 +31,7 @@ const COMMAND_HELP_SET: &[(&str, &str)] = &[
     ("UIO1", "MR2DA2 universal IO1"),
     ("UIO2", "MR2DA2 universal IO2"),
     ("UIO3", "MR2DA2 universal IO3"),
+    ("STEP = asm|real", "Clock step mode"),
 ];
 const COMMAND_HELP_UNSET: &[(&str, &str)] = &[
     ("J1", "MR2DA2 jumper 1"),
@@ -34,11 +40,26 @@ const COMMAND_HELP_UNSET: &[(&str, &str)] = &[
     ("UIO2", "MR2DA2 universal IO2"),
     ("UIO3", "MR2DA2 universal IO3"),
 ];
+const COMMAND_HELP_REG: &[(&str, &str)] = &[
+    ("R0/R1/R2 = x", "General purpose reg"),
+    ("FR = x", "Flag register"),
+    ("SP = x", "Stackpointer"),
+    ("R6/R7 = x", "Internal registers"),
+];
 const COMMAND_HELP_SHOW: &[(&str, &str)] = &[
     ("memory", "Show the main memory"),
     ("register", "Show the registers"),
+    ("interrupts", "Show the interrupts"),
+];
+const COMMAND_HELP_LOAD: &[(&str, &str)] = &[
+    ("PATH", "Path to the program"),
+    ("<none>", "Browse for a program"),
+];
+const COMMAND_HELP_LOAD_OVERLAY: &[(&str, &str)] = &[("PATH", "Program to add to memory")];
+const COMMAND_HELP_CLEAR: &[(&str, &str)] = &[
+    ("breakpoints", "Remove all breakpoints"),
+    ("all", "Remove everything"),
 ];
-const COMMAND_HELP_LOAD: &[(&str, &str)] = &[("PATH", "Path to the program")];
 const COMMAND_HELP_NEXT: &[(&str, &str)] = &[("<N>", "Optional number of cycles")];
 
 /// Help widget that shows input completions.
@@ -47,7 +68,7 @@ const COMMAND_HELP_NEXT: &[(&str, &str)] = &[("<N>", "Optional number of cycles"
 ///
 /// ```text
 /// ━╸Commands╺━━━━━━━━━━━━━━━━━━━━━━━━
-/// load PATH         Load asm program
+/// load [PATH]       Load asm program
 /// set …            Change a settings
 /// unset …       Unset a bool setting
 /// show …      Select part to display
@@ -62,12 +83,18 @@ impl<'a> CommandHelpWidget<'a> {
         let input = input.to_lowercase();
         let line_count = if input.starts_with("load ") {
             COMMAND_HELP_LOAD.len()
+        } else if input.starts_with("load-overlay ") {
+            COMMAND_HELP_LOAD_OVERLAY.len()
         } else if input.starts_with("set ") {
             COMMAND_HELP_SET.len()
         } else if input.starts_with("unset ") {
             COMMAND_HELP_UNSET.len()
+        } else if input.starts_with("reg ") {
+            COMMAND_HELP_REG.len()
         } else if input.starts_with("show ") {
             COMMAND_HELP_SHOW.len()
+        } else if input.starts_with("clear ") {
+            COMMAND_HELP_CLEAR.len()
         } else if input.starts_with("next ") {
             COMMAND_HELP_NEXT.len()
         } else {
@@ -98,12 +125,18 @@ impl<'a> Widget for CommandHelpWidget<'a> {
         let help_array = if input.starts_with("load ") {
             // TODO: Complete paths
             COMMAND_HELP_LOAD
+        } else if input.starts_with("load-overlay ") {
+            COMMAND_HELP_LOAD_OVERLAY
         } else if input.starts_with("set ") {
             COMMAND_HELP_SET
         } else if input.starts_with("unset ") {
             COMMAND_HELP_UNSET
+        } else if input.starts_with("reg ") {
+            COMMAND_HELP_REG
         } else if input.starts_with("show ") {
             COMMAND_HELP_SHOW
+        } else if input.starts_with("clear ") {
+            COMMAND_HELP_CLEAR
         } else if input.starts_with("next ") {
             COMMAND_HELP_NEXT
         } else {
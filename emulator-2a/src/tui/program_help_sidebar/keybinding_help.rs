@@ -10,14 +10,16 @@ use std::time::{Duration, Instant};
 use super::{SpacedStr, HEADER_HEIGHT};
 use crate::helpers;
 
-const WIDGET_HEIGHT: u16 = 6 + HEADER_HEIGHT;
+const WIDGET_HEIGHT: u16 = 8 + HEADER_HEIGHT;
 const HIGHLIGHT_DURATION: Duration = Duration::from_millis(500);
 const BIND_CLK: (&str, &str) = ("Clock", "Enter");
 const BIND_TOGGLE_AUTORUN: (&str, &str) = ("Toggle autorun", "CTRL+A");
 const BIND_TOGGLE_ASM_STEP: (&str, &str) = ("Toggle asm step", "CTRL+W");
+const BIND_STEP_OVER: (&str, &str) = ("Step over", "CTRL+O");
 const BIND_RESET: (&str, &str) = ("Reset", "CTRL+R");
 const BIND_EDGE_INT: (&str, &str) = ("Edge interrupt", "CTRL+E");
 const BIND_CONTINUE: (&str, &str) = ("Continue", "CTRL+L");
+const BIND_CYCLE_VIEW: (&str, &str) = ("Cycle view", "CTRL+V");
 
 /// Help Widget containing key binding information.
 ///
@@ -28,9 +30,11 @@ const BIND_CONTINUE: (&str, &str) = ("Continue", "CTRL+L");
 /// Clock                         Enter
 /// Toggle autorun               CTRL+A
 /// Toggle asm step              CTRL+W
+/// Step over                    CTRL+O
 /// Reset                        CTRL+R
 /// Edge interrupt               CTRL+E
 /// Continue                     CTRL+L
+/// Cycle view                   CTRL+V
 /// ```
 pub struct KeybindingHelpWidget;
 
@@ -61,6 +65,13 @@ impl KeybindingHelpWidget {
         }
         spaced.render(area, buf)
     }
+    fn render_step_over(area: Rect, buf: &mut Buffer, state: &mut KeybindingHelpState) {
+        let mut spaced = SpacedStr::from(BIND_STEP_OVER.0, BIND_STEP_OVER.1);
+        if is_within_highlight_dur(&mut state.last_step_over_press) {
+            spaced = spaced.left_style(&helpers::BOLD);
+        }
+        spaced.render(area, buf)
+    }
     fn render_reset(area: Rect, buf: &mut Buffer, state: &mut KeybindingHelpState) {
         let mut spaced = SpacedStr::from(BIND_RESET.0, BIND_RESET.1);
         if is_within_highlight_dur(&mut state.last_reset_press) {
@@ -90,6 +101,13 @@ impl KeybindingHelpWidget {
         }
         spaced.render(area, buf)
     }
+    fn render_cycle_view(area: Rect, buf: &mut Buffer, state: &mut KeybindingHelpState) {
+        let mut spaced = SpacedStr::from(BIND_CYCLE_VIEW.0, BIND_CYCLE_VIEW.1);
+        if is_within_highlight_dur(&mut state.last_cycle_view_press) {
+            spaced = spaced.left_style(&helpers::BOLD);
+        }
+        spaced.render(area, buf)
+    }
 }
 
 impl StatefulWidget for KeybindingHelpWidget {
@@ -110,6 +128,9 @@ impl StatefulWidget for KeybindingHelpWidget {
         KeybindingHelpWidget::render_toggle_asm_step(area, buf, state);
         area.y += 1;
         area.height -= 1;
+        KeybindingHelpWidget::render_step_over(area, buf, state);
+        area.y += 1;
+        area.height -= 1;
         KeybindingHelpWidget::render_reset(area, buf, state);
         area.y += 1;
         area.height -= 1;
@@ -117,6 +138,9 @@ impl StatefulWidget for KeybindingHelpWidget {
         area.y += 1;
         area.height -= 1;
         KeybindingHelpWidget::render_continue(area, buf, state);
+        area.y += 1;
+        area.height -= 1;
+        KeybindingHelpWidget::render_cycle_view(area, buf, state);
     }
 }
 
@@ -128,9 +152,11 @@ impl StatefulWidget for KeybindingHelpWidget {
 /// render process.
 pub struct KeybindingHelpState {
     last_clk_press: Option<Instant>,
+    last_step_over_press: Option<Instant>,
     last_reset_press: Option<Instant>,
     last_edge_int_press: Option<Instant>,
     last_continue_press: Option<Instant>,
+    last_cycle_view_press: Option<Instant>,
     is_autorun_on: bool,
     is_asm_step_on: bool,
     is_edge_int_possible: bool,
@@ -142,9 +168,11 @@ impl KeybindingHelpState {
     pub const fn init() -> Self {
         KeybindingHelpState {
             last_clk_press: None,
+            last_step_over_press: None,
             last_reset_press: None,
             last_edge_int_press: None,
             last_continue_press: None,
+            last_cycle_view_press: None,
             is_autorun_on: false,
             is_asm_step_on: false,
             is_edge_int_possible: false,
@@ -154,6 +182,9 @@ impl KeybindingHelpState {
     pub fn clk_pressed(&mut self) {
         self.last_clk_press = Some(Instant::now());
     }
+    pub fn step_over_pressed(&mut self) {
+        self.last_step_over_press = Some(Instant::now());
+    }
     pub fn reset_pressed(&mut self) {
         self.last_reset_press = Some(Instant::now());
     }
@@ -163,6 +194,9 @@ impl KeybindingHelpState {
     pub fn continue_pressed(&mut self) {
         self.last_continue_press = Some(Instant::now());
     }
+    pub fn cycle_view_pressed(&mut self) {
+        self.last_cycle_view_press = Some(Instant::now());
+    }
     pub fn set_continue_possible(&mut self, possible: bool) {
         self.is_continue_possible = possible;
     }
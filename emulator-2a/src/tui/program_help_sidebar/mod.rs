@@ -29,7 +29,7 @@ pub const HEADER_HEIGHT: u16 = 1;
 ///
 /// ```text
 /// ━╸Info╺━━━━━━━━━━━━━━━━━━━━━━━━━━━━
-/// Program:     11-simple-addition.asm
+/// Program:         counter.asm (12 B)
 /// Frequency:                  7.41MHz
 /// Measured Frequency:          0.00Hz
 /// State:                      Running
@@ -47,11 +47,13 @@ pub const HEADER_HEIGHT: u16 = 1;
 /// Clock                         Enter
 /// Toggle autorun               CTRL+A
 /// Toggle asm step              CTRL+W
+/// Step over                    CTRL+O
 /// Reset                        CTRL+R
 /// Edge interrupt               CTRL+E
 /// Continue                     CTRL+L
+/// Cycle view                   CTRL+V
 /// ━╸Commands╺━━━━━━━━━━━━━━━━━━━━━━━━
-/// load PATH          Load asm program
+/// load [PATH]        Load asm program
 /// set …             Change a settings
 /// unset …        Unset a bool setting
 /// show …       Select part to display
@@ -103,7 +105,8 @@ impl StatefulWidget for ProgramHelpSidebar {
         area.height -= info_height;
         // The rest of the area can be used for the program display
         let program_display_area = area;
-        ProgramDisplayWidget(*state.machine().registers().get(RegisterNumber::R3)).render(
+        let pc = *state.machine().registers().get(RegisterNumber::R3);
+        ProgramDisplayWidget(pc, state.machine.breakpoints()).render(
             program_display_area,
             buf,
             &mut state.program_display_state,
@@ -1,8 +1,14 @@
 //! Everythin related to drawing the [`ProgramDisplayWidget`].
-use emulator_2a_lib::{compiler::ByteCode, parser::Line};
+use emulator_2a_lib::{
+    compiler::ByteCode,
+    parser::{Label, Line},
+};
 use tui::{buffer::Buffer, layout::Rect, style::Style, widgets::StatefulWidget};
 
-use std::ops::Range;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
 
 use crate::helpers;
 
@@ -10,7 +16,8 @@ use crate::helpers;
 const MAX_LINES_OF_CONTEXT: usize = 3;
 
 /// This Widget can render the current program.
-/// The first argument is the PC (program counter) value.
+/// The first argument is the PC (program counter) value,
+/// the second one contains the addresses of all breakpoints.
 ///
 /// # Example
 ///
@@ -21,13 +28,13 @@ const MAX_LINES_OF_CONTEXT: usize = 3;
 ///  LOOP:
 ///      LD R0, (0xFC)
 ///      LD R1, (0xFD)
-///      ADD R0, R1
+///  ●   ADD R0, R1
 /// >    ST (0xFF), R0
 ///      JR LOOP
 /// ```
-pub struct ProgramDisplayWidget(pub u8);
+pub struct ProgramDisplayWidget<'a>(pub u8, pub &'a BTreeSet<u8>);
 
-impl StatefulWidget for ProgramDisplayWidget {
+impl StatefulWidget for ProgramDisplayWidget<'_> {
     type State = ProgramDisplayState;
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let header = super::make_header("Program", area.width);
@@ -37,6 +44,7 @@ impl StatefulWidget for ProgramDisplayWidget {
             height: area.height - 1,
             ..area
         };
+        state.area = Some(area);
         let area_height = area.height as usize;
         let current_line_idx = state.index_for_address(self.0).unwrap_or_default();
         let lines_of_context = MAX_LINES_OF_CONTEXT.min(area_height / 2);
@@ -60,7 +68,7 @@ impl StatefulWidget for ProgramDisplayWidget {
             .min(state.lines.len().saturating_sub(area_height));
         // Iterate over lines from `current_top_line_idx` and stop after
         // we have enough to fill the area height.
-        for (idx, (_range, line)) in state
+        for (idx, (range, line)) in state
             .lines
             .iter()
             .enumerate()
@@ -84,6 +92,15 @@ impl StatefulWidget for ProgramDisplayWidget {
                     Style::default()
                 },
             );
+            if !range.is_empty() && self.1.contains(&(range.start as u8)) {
+                // Draw the marker for breakpoints
+                buf.set_string(
+                    area.left() + 1,
+                    area.top() + offset,
+                    "●",
+                    *helpers::RED_BOLD,
+                );
+            }
         }
     }
 }
@@ -92,9 +109,13 @@ impl StatefulWidget for ProgramDisplayWidget {
 pub struct ProgramDisplayState {
     /// The lines of the program with corresponding ranges of
     /// program counter values.
-    pub lines: Vec<(Range<u8>, String)>,
+    pub lines: Vec<(Range<usize>, String)>,
     /// The index of the topmost line currently displayed.
     pub current_top_line_idx: usize,
+    /// Addresses of the program's labels.
+    labels: BTreeMap<Label, u8>,
+    /// Area the lines were last drawn to, if they were drawn at all.
+    area: Option<Rect>,
 }
 
 impl ProgramDisplayState {
@@ -103,11 +124,13 @@ impl ProgramDisplayState {
         ProgramDisplayState {
             lines: vec![],
             current_top_line_idx: 0,
+            labels: BTreeMap::new(),
+            area: None,
         }
     }
     /// Create the state from reading [`ByteCode`] input.
     pub fn from_bytecode(bytecode: &ByteCode) -> Self {
-        let mut byte_counter: u8 = 0;
+        let mut byte_counter = 0;
         let lines = bytecode
             .lines
             .iter()
@@ -115,7 +138,7 @@ impl ProgramDisplayState {
             .map(|(line, bytes)| {
                 let string = line.to_string();
                 let from = byte_counter;
-                let to = byte_counter + bytes.len() as u8;
+                let to = byte_counter + bytes.len();
                 byte_counter = to;
                 (from..to, string)
             })
@@ -123,7 +146,56 @@ impl ProgramDisplayState {
         ProgramDisplayState {
             lines,
             current_top_line_idx: 0,
+            labels: bytecode.labels.clone(),
+            area: None,
+        }
+    }
+    /// Add the lines of the overlay given by `bytecode`.
+    ///
+    /// Lines whose bytes are overwritten by the overlay are replaced, the
+    /// labels of the overlay are added to the known ones.
+    pub fn overlay(&mut self, bytecode: &ByteCode) {
+        for (start, line, bytes) in bytecode.placed_lines() {
+            let start = start as usize;
+            let end = start + bytes.len();
+            self.lines
+                .retain(|(range, _)| range.is_empty() || range.end <= start || range.start >= end);
+            let idx = self
+                .lines
+                .iter()
+                .position(|(range, _)| range.start >= end)
+                .unwrap_or(self.lines.len());
+            self.lines.insert(idx, (start..end, line.to_string()));
         }
+        self.labels.extend(
+            bytecode
+                .labels
+                .iter()
+                .map(|(label, addr)| (label.clone(), *addr)),
+        );
+    }
+    /// Get the address of the program line drawn at the given screen position.
+    ///
+    /// Returns `None`, if no line containing bytes is drawn at that position.
+    pub fn address_at(&self, x: u16, y: u16) -> Option<u8> {
+        let area = self.area?;
+        if x < area.left() || x >= area.right() || y < area.top() || y >= area.bottom() {
+            return None;
+        }
+        let idx = self.current_top_line_idx + (y - area.top()) as usize;
+        self.lines
+            .get(idx)
+            .filter(|(range, _)| !range.is_empty())
+            .map(|(range, _)| range.start as u8)
+    }
+    /// Get the address of the given label of the displayed program.
+    ///
+    /// Labels are compared case insensitively.
+    pub fn label_address(&self, label: &str) -> Option<u8> {
+        self.labels
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(label))
+            .map(|(_, addr)| *addr)
     }
     /// Get the program line that is contained at `addr` in memory.
     ///
@@ -136,10 +208,37 @@ impl ProgramDisplayState {
             .enumerate()
             // Only keep entries where the range contains our address
             // This _should_ leave us with at most a single element
-            .filter(|(_, (range, _))| range.contains(&addr))
+            .filter(|(_, (range, _))| range.contains(&(addr as usize)))
             // Discard everything but the index
             .map(|(idx, _)| idx)
             // Return the first index
             .next()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use emulator_2a_lib::{compiler::Translator, parser::AsmParser};
+
+    use super::*;
+
+    fn bytecode(program: &str) -> ByteCode {
+        let asm = AsmParser::parse(program).expect("Parsing failed");
+        Translator::compile(&asm).expect("Compilation failed")
+    }
+
+    #[test]
+    fn overlays_replace_the_lines_at_the_end_of_memory() {
+        let mut state = ProgramDisplayState::from_bytecode(&bytecode("#! mrasm\n    NOP\n"));
+        state.overlay(&bytecode("#! mrasm\n.ORG 0xFE\n.DB 1\n"));
+        state.overlay(&bytecode("#! mrasm\n.ORG 0xFE\n.DB 2\n"));
+        let placed: Vec<_> = state
+            .lines
+            .iter()
+            .filter(|(range, _)| range.contains(&0xFE))
+            .map(|(_, line)| line.trim())
+            .collect();
+        assert_eq!(placed, vec![".DB 2"]);
+        assert_eq!(state.index_for_address(0xFE), Some(state.lines.len() - 1));
+    }
+}
@@ -1,17 +1,18 @@
 //! Everything related to drawing the [`ProgramInfoWidget`].
-use emulator_2a_lib::machine::State;
+use emulator_2a_lib::{disassembler, machine::State};
 use tui::{buffer::Buffer, layout::Rect, widgets::Widget};
 
-use std::{borrow::Cow, path::PathBuf};
+use std::path::PathBuf;
 
 use super::{SpacedStr, HEADER_HEIGHT};
 use crate::{helpers, tui::Tui};
 
-const WIDGET_HEIGHT: u16 = 4 + HEADER_HEIGHT;
+const WIDGET_HEIGHT: u16 = 5 + HEADER_HEIGHT;
 const INFO_PROGRAM: (&str, &str) = ("Program:", "");
 const INFO_FREQ: (&str, &str) = ("Frequency:", "");
 const INFO_FREQ_MEASURED: (&str, &str) = ("Measured Frequency:", "");
 const INFO_STATE: (&str, &str) = ("State:", "");
+const INFO_INSTRUCTION: (&str, &str) = ("Instruction:", "");
 
 /// Widget for additional information about the
 /// current execution.
@@ -20,30 +21,60 @@ const INFO_STATE: (&str, &str) = ("State:", "");
 ///
 /// ```text
 /// ━╸Info╺━━━━━━━━━━━━━━━━━━━━━━━━━━━━
-/// Program:     11-simple-addition.asm
+/// Program:         counter.asm (12 B)
 /// Frequency:                  7.41MHz
 /// Measured Frequency:          0.00Hz
 /// State:                      Running
+/// Instruction:          INC R0 (done)
 /// ```
+///
+/// The size of the loaded program is given in bytes.
+/// The instruction is the one contained in the instruction register. Only the
+/// mnemonic is shown, if the instruction consists of more than one byte.
+/// While the instruction is executed, the number of clock cycles spent on it
+/// is shown, i.e. `INC R0 (cycle 2)`. Once it is `done`, the next clock
+/// starts the next instruction.
+///
+/// If the host cannot keep up with the frequency, the measured
+/// frequency is highlighted and prefixed with `max`.
 pub struct ProgramInfoWidget<'a> {
     program: Option<&'a PathBuf>,
+    program_len: usize,
     freq: f32,
     freq_measured: f32,
+    falling_behind: bool,
     state: State,
+    instruction: String,
+    instruction_done: bool,
+    cycles_into_instruction: usize,
 }
 
 impl<'a> ProgramInfoWidget<'a> {
     /// Read all necessary information from the given [`Tui`].
     pub fn from(tui: &'a Tui) -> Self {
         let program = tui.machine.program_path();
-        let freq = super::super::CYCLES_PER_SECOND as f32;
-        let freq_measured = tui.measured_freq;
+        let program_len = tui.machine.loaded_program_len();
+        let freq = tui.frame_budget().target_frequency() as f32;
+        let freq_measured = tui.frequency_meter.hz();
+        let falling_behind = tui.falling_behind;
         let state = tui.machine.state();
+        let register = tui.machine.instruction_register();
+        let instruction = match disassembler::decode(&[register.get_raw()], 0) {
+            Some((instruction, _)) => instruction.to_string(),
+            None => register.mnemonic().to_owned(),
+        };
+        let instruction_done = tui.machine.is_instruction_done();
+        let cycles_into_instruction = tui.machine.cycles_into_instruction();
         ProgramInfoWidget {
             program,
+            program_len,
             freq,
             freq_measured,
+            falling_behind,
             state,
+            instruction,
+            instruction_done,
+            cycles_into_instruction,
         }
     }
     /// Get the height necessary for drawing this widget.
@@ -55,8 +86,8 @@ impl<'a> ProgramInfoWidget<'a> {
             .program
             .as_ref()
             .and_then(|path| path.file_name())
-            .map(|file_name| file_name.to_string_lossy())
-            .unwrap_or(Cow::Borrowed(""));
+            .map(|file_name| format!("{} ({} B)", file_name.to_string_lossy(), self.program_len))
+            .unwrap_or_default();
         let spaced = SpacedStr::from(INFO_PROGRAM.0, &name).right_style(&helpers::BOLD);
         spaced.render(area, buf)
     }
@@ -67,21 +98,38 @@ impl<'a> ProgramInfoWidget<'a> {
     }
     fn render_freq_measured(&self, area: Rect, buf: &mut Buffer) {
         let freq_measured = helpers::format_number(self.freq_measured);
-        let spaced = SpacedStr::from(INFO_FREQ_MEASURED.0, &freq_measured);
-        spaced.render(area, buf)
+        if self.falling_behind {
+            let freq_measured = format!("max {}", freq_measured);
+            SpacedStr::from(INFO_FREQ_MEASURED.0, &freq_measured)
+                .right_style(&helpers::YELLOW_BOLD)
+                .render(area, buf)
+        } else {
+            SpacedStr::from(INFO_FREQ_MEASURED.0, &freq_measured).render(area, buf)
+        }
     }
     fn render_state(&self, area: Rect, buf: &mut Buffer) {
+        let state = self.state.to_string();
+        let spaced = SpacedStr::from(INFO_STATE.0, &state);
         let spaced = match self.state {
-            State::Running => SpacedStr::from(INFO_STATE.0, "Running"),
-            State::Stopped => {
-                SpacedStr::from(INFO_STATE.0, "Stopped").right_style(&helpers::YELLOW_BOLD)
-            }
-            State::ErrorStopped => {
-                SpacedStr::from(INFO_STATE.0, "Stopped by Error").right_style(&helpers::RED_BOLD)
-            }
+            State::Running => spaced,
+            State::Stopped => spaced.right_style(&helpers::YELLOW_BOLD),
+            State::ErrorStopped => spaced.right_style(&helpers::RED_BOLD),
         };
         spaced.render(area, buf)
     }
+    fn render_instruction(&self, area: Rect, buf: &mut Buffer) {
+        let instruction = if self.instruction_done {
+            format!("{} (done)", self.instruction)
+        } else {
+            format!(
+                "{} (cycle {})",
+                self.instruction, self.cycles_into_instruction
+            )
+        };
+        SpacedStr::from(INFO_INSTRUCTION.0, &instruction)
+            .right_style(&helpers::BOLD)
+            .render(area, buf)
+    }
 }
 
 impl<'a> Widget for ProgramInfoWidget<'a> {
@@ -101,5 +149,8 @@ impl<'a> Widget for ProgramInfoWidget<'a> {
         area.y += 1;
         area.height -= 1;
         self.render_state(area, buf);
+        area.y += 1;
+        area.height -= 1;
+        self.render_instruction(area, buf);
     }
 }
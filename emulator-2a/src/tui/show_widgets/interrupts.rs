@@ -0,0 +1,122 @@
+//! Everything related to drawing the [`InterruptsWidget`].
+use emulator_2a_lib::machine::{Bus, MICR, MISR};
+use tui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+
+use crate::helpers;
+
+/// A widget for displaying the interrupt sources.
+///
+/// The first parameter is the bus, the second one the
+/// interrupt enable flag of the CPU.
+/// If no interrupt source is enabled in the MICR, the whole panel is dimmed.
+///
+/// # Example
+///
+/// ```text
+/// Interrupts:  IE on   MICR 00000001  MISR 00010001
+/// Source  Enabled  Pending  Request
+/// Key     edge     yes      yes
+/// Timer   -        -        -
+/// UART    -        -        -
+/// Bus     -        -        -
+/// ```
+pub struct InterruptsWidget<'a>(pub &'a Bus, pub bool);
+
+impl Widget for InterruptsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let micr = *self.0.micr();
+        let misr = *self.0.misr();
+        let interrupt_enabled = self.1;
+        let dimmed = micr.is_empty();
+        let style_for = |active: bool| {
+            if dimmed {
+                *helpers::DIMMED
+            } else if active {
+                *helpers::BOLD
+            } else {
+                Style::default()
+            }
+        };
+        let header = format!(
+            "Interrupts:  IE {:<3}  MICR {:08b}  MISR {:08b}",
+            if interrupt_enabled { "on" } else { "off" },
+            micr.bits(),
+            misr.bits()
+        );
+        buf.set_stringn(
+            area.left(),
+            area.top(),
+            header,
+            area.width as usize,
+            *helpers::DIMMED,
+        );
+        if area.height < 2 {
+            return;
+        }
+        buf.set_stringn(
+            area.left(),
+            area.top() + 1,
+            "Source  Enabled  Pending  Request",
+            area.width as usize,
+            *helpers::DIMMED,
+        );
+        // Sources are (name, edge enable, level enable, pending, request active)
+        let sources = [
+            (
+                "Key",
+                MICR::KEY_EDGE_INTERRUPT_ENABLE,
+                MICR::empty(),
+                MISR::KEY_INTERRUPT_PENDING,
+                MISR::KEY_INTERRUPT_REQUEST_ACTIVE,
+            ),
+            (
+                "Timer",
+                MICR::TIMER_EDGE_INTERRUPT_ENABLE,
+                MICR::empty(),
+                MISR::TIMER_INTERRUPT_PENDING,
+                MISR::TIMER_INTERRUPT_REQUEST_ACTIVE,
+            ),
+            (
+                "UART",
+                MICR::UART_EDGE_INTERRUPT_ENABLE,
+                MICR::UART_LEVEL_INTERRUPT_ENABLE,
+                MISR::UART_INTERUPT_PENDING,
+                MISR::UART_INTERRUPT_REQUEST_ACTIVE,
+            ),
+            (
+                "Bus",
+                MICR::BUS_EDGE_INTERRUPT_ENABLE,
+                MICR::BUS_LEVEL_INTERRUPT_ENABLE,
+                MISR::BUS_INTERRUPT_PENDING,
+                MISR::BUS_INTERRUPT_REQUEST_ACTIVE,
+            ),
+        ];
+        let rows = sources.iter().take(area.height as usize - 2);
+        for (index, (name, edge, level, pending, request)) in rows.enumerate() {
+            // Key and timer have no level interrupts, `intersects` is false for them
+            let edge = micr.intersects(*edge);
+            let level = micr.intersects(*level);
+            let enabled = match (edge, level) {
+                (true, true) => "both",
+                (true, false) => "edge",
+                (false, true) => "level",
+                (false, false) => "-",
+            };
+            let yes_no = |flag| if misr.contains(flag) { "yes" } else { "-" };
+            let row = format!(
+                "{:<8}{:<9}{:<9}{}",
+                name,
+                enabled,
+                yes_no(*pending),
+                yes_no(*request)
+            );
+            buf.set_stringn(
+                area.left(),
+                area.top() + 2 + index as u16,
+                row,
+                area.width as usize,
+                style_for(edge || level || misr.intersects(*pending | *request)),
+            );
+        }
+    }
+}
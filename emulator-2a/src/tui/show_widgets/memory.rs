@@ -8,7 +8,8 @@ const MINIMUM_ALLOWED_HEIGHT_FOR_MEMORY_DISPLAY: u16 = 17;
 
 /// A widget for displaying the memory.
 ///
-/// The first parameter is a reference to the memory.
+/// The first parameter is a reference to the memory,
+/// the second one the address of the selected cell, which is highlighted.
 ///
 /// # Example
 ///
@@ -31,14 +32,37 @@ const MINIMUM_ALLOWED_HEIGHT_FOR_MEMORY_DISPLAY: u16 = 17;
 /// D_ 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
 /// E_ 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
 /// ```
-pub struct MemoryWidget<'a>(pub &'a [u8; 0xF0]);
+pub struct MemoryWidget<'a>(pub &'a [u8; 0xF0], pub Option<u8>);
+
+impl MemoryWidget<'_> {
+    /// Does the memory fit into the given area?
+    ///
+    /// The area includes the line for the title.
+    pub fn fits(area: Rect) -> bool {
+        area.width >= MINIMUM_ALLOWED_WIDTH_FOR_MEMORY_DISPLAY
+            && area.height > MINIMUM_ALLOWED_HEIGHT_FOR_MEMORY_DISPLAY
+    }
+    /// Get the address of the cell drawn at the given screen position.
+    ///
+    /// `area` is the area the widget was rendered to, including the title.
+    pub fn cell_at(area: Rect, x: u16, y: u16) -> Option<u8> {
+        // Skip the title and the top row of annotations
+        let column = x.checked_sub(area.left() + 3)?;
+        let row = y.checked_sub(area.top() + 2)?;
+        // Cells are two characters wide, separated by a space
+        if column % 3 == 2 || column / 3 >= 0x10 || row >= 0xF {
+            return None;
+        }
+        Some((row * 0x10 + column / 3) as u8)
+    }
+}
 
 impl Widget for MemoryWidget<'_> {
     fn render(self, mut area: Rect, buf: &mut Buffer) {
         // Display title
         buf.set_string(area.left(), area.top(), "Memory:", *helpers::DIMMED);
         area.y += 1;
-        area.height -= 1;
+        area.height = area.height.saturating_sub(1);
         // Make sure, that we have enough space!
         if area.width < MINIMUM_ALLOWED_WIDTH_FOR_MEMORY_DISPLAY {
             buf.set_string(
@@ -71,8 +95,10 @@ impl Widget for MemoryWidget<'_> {
             area.height -= 1;
             // Iterate over the memory
             for (index, content) in self.0.iter().enumerate() {
-                // Draw non-empty cells bold
-                let style = if *content == 0 {
+                // Draw non-empty cells bold, highlight the selected one
+                let style = if self.1 == Some(index as u8) {
+                    *helpers::YELLOW_BOLD
+                } else if *content == 0 {
                     Style::default()
                 } else {
                     *helpers::BOLD
@@ -1,5 +1,7 @@
+mod interrupts;
 mod memory;
 mod register_block;
 
+pub use interrupts::InterruptsWidget;
 pub use memory::MemoryWidget;
 pub use register_block::RegisterBlockWidget;
@@ -1,6 +1,7 @@
 use emulator_2a_lib::{
     compiler::ByteCode,
-    machine::{Machine, StepMode},
+    machine::{Machine, MachineConfig, StateChange, StepMode},
+    parser::Label,
 };
 use tui::{
     buffer::Buffer,
@@ -10,16 +11,17 @@ use tui::{
 };
 
 use std::{
+    collections::BTreeMap,
+    mem,
     ops::{Deref, DerefMut},
     path::PathBuf,
 };
 
 use crate::{
-    args::InitialMachineConfiguration,
     helpers,
     tui::{
         display::Display,
-        show_widgets::{MemoryWidget, RegisterBlockWidget},
+        show_widgets::{InterruptsWidget, MemoryWidget, RegisterBlockWidget},
         BoardInfoSidebarWidget,
     },
 };
@@ -69,8 +71,16 @@ pub struct MachineState {
     pub draw_counter: usize,
     /// Is the auto run mode active?
     pub auto_run_mode: bool,
+    /// The memory cell selected by the user, if any.
+    pub selected_memory_cell: Option<u8>,
+    /// Area the memory was last drawn to, if it was drawn at all.
+    memory_area: Option<Rect>,
     /// Currenly active program.
     program: Option<PathBuf>,
+    /// Labels of the breakpoints that were set by label, keyed by their address.
+    breakpoint_labels: BTreeMap<u8, Label>,
+    /// Number of clock cycles emulated since the last instruction was completed.
+    cycles_into_instruction: usize,
 }
 
 /// Displayable parts.
@@ -81,20 +91,25 @@ pub struct MachineState {
 pub enum Part {
     RegisterBlock,
     Memory,
+    Interrupts,
 }
 
 impl MachineState {
     /// Create a new MachineState.
     ///
-    /// The given [`InitialMachineConfiguration`] is used to configure the underlying
+    /// The given [`MachineConfig`] is used to configure the underlying
     /// [`Machine`]. Initially the additional displayed part is the [`Part::RegisterBlock`].
-    pub fn new(conf: &InitialMachineConfiguration) -> Self {
+    pub fn new(conf: MachineConfig) -> Self {
         MachineState {
             part: Part::RegisterBlock,
-            machine: Machine::new(conf.clone().into()),
+            machine: Machine::new(conf),
             draw_counter: 0,
             auto_run_mode: false,
+            selected_memory_cell: None,
+            memory_area: None,
             program: None,
+            breakpoint_labels: BTreeMap::new(),
+            cycles_into_instruction: 0,
         }
     }
     /// Create a new MachineState with a program.
@@ -103,22 +118,67 @@ impl MachineState {
     /// before the configuration is applied. This allows the configuration to apply
     /// input register values.
     pub fn new_with_program<P: Into<PathBuf>>(
-        conf: &InitialMachineConfiguration,
+        conf: MachineConfig,
         path: P,
         program: ByteCode,
     ) -> Self {
         MachineState {
             part: Part::RegisterBlock,
-            machine: Machine::new_with_program(conf.clone().into(), program),
+            machine: Machine::new_with_program(conf, program),
             draw_counter: 0,
             auto_run_mode: false,
+            selected_memory_cell: None,
+            memory_area: None,
             program: Some(path.into()),
+            breakpoint_labels: BTreeMap::new(),
+            cycles_into_instruction: 0,
         }
     }
+    /// Get the address of the memory cell drawn at the given screen position.
+    ///
+    /// Returns `None`, if the memory is not displayed or no cell is at that position.
+    pub fn memory_cell_at(&self, x: u16, y: u16) -> Option<u8> {
+        self.memory_area
+            .and_then(|area| MemoryWidget::cell_at(area, x, y))
+    }
+    /// Trigger the clock, counting the cycles into the current instruction.
+    ///
+    /// See [`Machine::trigger_key_clock`].
+    pub fn trigger_key_clock(&mut self) -> Option<StateChange> {
+        if self.machine.is_instruction_done() {
+            self.cycles_into_instruction = 0;
+        }
+        self.cycles_into_instruction += 1;
+        self.machine.trigger_key_clock()
+    }
+    /// Reset the machine and load the current program again.
+    ///
+    /// See [`Machine::reset_and_reload`].
+    pub fn reset_and_reload(&mut self) -> bool {
+        self.cycles_into_instruction = 0;
+        self.machine.reset_and_reload()
+    }
+    /// Get the number of clock cycles emulated since the last instruction was completed.
+    ///
+    /// Once the current instruction is completed, this includes the completing cycle.
+    /// Cycles spent waiting for the memory are counted, too.
+    pub const fn cycles_into_instruction(&self) -> usize {
+        self.cycles_into_instruction
+    }
     /// Select another part for display.
     pub fn show(&mut self, part: Part) {
         self.part = part;
     }
+    /// Display the next part, wrapping around after the last one.
+    ///
+    /// The order is [`Part::RegisterBlock`], [`Part::Memory`], [`Part::Interrupts`].
+    pub fn cycle_part(&mut self) {
+        self.part = match self.part {
+            Part::RegisterBlock => Part::Memory,
+            Part::Memory => Part::Interrupts,
+            Part::Interrupts => Part::RegisterBlock,
+        };
+    }
 
     pub fn toggle_auto_run_mode(&mut self) {
         self.auto_run_mode = !self.auto_run_mode
@@ -132,9 +192,74 @@ impl MachineState {
         self.machine.set_step_mode(new_mode);
     }
 
-    pub fn load_program(&mut self, path: PathBuf, bytecode: ByteCode) {
+    /// Add the breakpoint at `addr` or remove it, if it exists.
+    ///
+    /// Unlike [`Machine::toggle_breakpoint`], this also forgets the label the
+    /// breakpoint was set by. Returns whether the breakpoint exists afterwards.
+    pub fn toggle_breakpoint(&mut self, addr: u8) -> bool {
+        self.breakpoint_labels.remove(&addr);
+        self.machine.toggle_breakpoint(addr)
+    }
+
+    /// Add the breakpoint at `label`, which is located at `addr`, or remove it, if it exists.
+    ///
+    /// The label is remembered to relocate the breakpoint when a program is loaded.
+    /// Returns whether the breakpoint exists afterwards.
+    pub fn toggle_breakpoint_at_label(&mut self, label: &str, addr: u8) -> bool {
+        let exists = self.toggle_breakpoint(addr);
+        if exists {
+            self.breakpoint_labels.insert(addr, label.to_owned());
+        }
+        exists
+    }
+
+    /// Remove all breakpoints, including the labels they were set by.
+    ///
+    /// Returns the number of removed breakpoints.
+    pub fn clear_breakpoints(&mut self) -> usize {
+        let count = self.machine.breakpoints().len();
+        self.breakpoint_labels.clear();
+        self.machine.clear_breakpoints();
+        count
+    }
+
+    /// Load the given program, keeping the breakpoints where possible.
+    ///
+    /// Breakpoints set by label are moved to the label's address in the new program,
+    /// breakpoints set by address stay where they are. Returns a warning for every
+    /// breakpoint that was dropped because its label is gone, or that is now located
+    /// outside of the program.
+    pub fn load_program(&mut self, path: PathBuf, bytecode: ByteCode) -> Vec<String> {
+        let program_length = bytecode.bytes().count();
+        let labels = mem::take(&mut self.breakpoint_labels);
+        let breakpoints = self.machine.breakpoints().clone();
+        self.machine.clear_breakpoints();
+        let mut warnings = vec![];
+        for addr in breakpoints {
+            match labels.get(&addr) {
+                Some(label) => match bytecode.label_address(label) {
+                    Some(new_addr) => {
+                        self.machine.add_breakpoint(new_addr);
+                        self.breakpoint_labels.insert(new_addr, label.clone());
+                    }
+                    None => {
+                        warnings.push(format!("Removed breakpoint at unknown label '{}'", label))
+                    }
+                },
+                None => {
+                    if addr as usize >= program_length {
+                        warnings.push(format!(
+                            "Breakpoint at 0x{:02X} is outside of the program",
+                            addr
+                        ));
+                    }
+                    self.machine.add_breakpoint(addr);
+                }
+            }
+        }
         self.machine.load(bytecode);
         self.program = Some(path);
+        warnings
     }
 
     pub fn program_path(&self) -> Option<&PathBuf> {
@@ -146,8 +271,8 @@ impl MachineWidget {
     /// Renders the [`OutputRegisterWidget`] correctly.
     fn render_output_registers(&self, area: Rect, buf: &mut Buffer, state: &mut MachineState) {
         // Fetch output register values
-        let out_fe = state.machine.bus().output_fe();
-        let out_ff = state.machine.bus().output_ff();
+        let out_fe = state.machine.output_fe();
+        let out_ff = state.machine.output_ff();
         // Calculate area
         let inner_area = Rect {
             width: OUTPUT_REGISTER_WIDGET_WIDTH,
@@ -292,12 +417,19 @@ impl StatefulWidget for MachineWidget {
         };
         // Render the additional part
 
+        // Fall back to the registers, if the memory does not fit
+        state.memory_area = None;
         match state.part {
-            Part::Memory => {
+            Part::Memory if MemoryWidget::fits(show_area) => {
                 let memory = state.machine.bus().memory();
-                MemoryWidget(memory).render(show_area, buf)
+                MemoryWidget(memory, state.selected_memory_cell).render(show_area, buf);
+                state.memory_area = Some(show_area);
             }
-            Part::RegisterBlock => {
+            Part::Interrupts => {
+                let interrupt_enabled = state.machine.registers().interrupt_enable_flag();
+                InterruptsWidget(state.machine.bus(), interrupt_enabled).render(show_area, buf)
+            }
+            Part::Memory | Part::RegisterBlock => {
                 let registers = state.machine.registers();
                 RegisterBlockWidget(registers).render(show_area, buf)
             }
@@ -335,3 +467,70 @@ impl DerefMut for MachineState {
         &mut self.machine
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use emulator_2a_lib::{compiler::Translator, runner::RunnerConfigBuilder};
+
+    use std::fs::read_to_string;
+
+    use super::*;
+    use crate::args::InitialMachineConfigurationBuilder;
+
+    #[test]
+    fn interactive_machine_agrees_with_library_runner() {
+        let path = "../testing/programs/21-simple-counter.asm";
+        let conf = InitialMachineConfigurationBuilder::default()
+            .fc(0x42)
+            .build()
+            .unwrap()
+            .machine_config()
+            .expect("Failed to create MachineConfig");
+        let cycles = 10_000;
+        // Interactive machine, as used by the TUI
+        let asm = helpers::read_asm_file(path).expect("Failed to parse program");
+        let mut state =
+            MachineState::new_with_program(conf.clone(), path, Translator::compile(&asm).unwrap());
+        for _ in 0..cycles {
+            state.trigger_key_clock();
+        }
+        // Library runner, as used by the `run` subcommand
+        let program = read_to_string(path).expect("Failed to read program");
+        let config = RunnerConfigBuilder::default()
+            .with_machine_config(conf)
+            .with_max_cycles(cycles)
+            .with_program(&program)
+            .build()
+            .expect("Failed to create RunnerConfig");
+        let results = config.run().expect("Failed to parse program");
+        assert_eq!(results.emulated_cycles, cycles);
+        assert_eq!(state.machine, results.machine);
+    }
+
+    #[test]
+    fn cycles_into_instruction_match_the_instruction_length() {
+        let path = "../testing/programs/21-simple-counter.asm";
+        let asm = helpers::read_asm_file(path).expect("Failed to parse program");
+        let mut state = MachineState::new_with_program(
+            MachineConfig::default(),
+            path,
+            Translator::compile(&asm).unwrap(),
+        );
+        for _ in 0..10 {
+            // Move to the start of the next instruction
+            while !state.is_instruction_done() || state.is_waiting_for_memory() {
+                state.trigger_key_clock();
+            }
+            let mut reference = state.machine.clone();
+            let expected = reference.step_over();
+            assert!(expected > 1);
+            state.trigger_key_clock();
+            while !state.is_instruction_done() {
+                assert!(state.cycles_into_instruction() < expected);
+                state.trigger_key_clock();
+            }
+            assert_eq!(state.cycles_into_instruction(), expected);
+            assert_eq!(state.machine, reference);
+        }
+    }
+}
@@ -33,7 +33,7 @@ LOOP:
     LD R0, 42
     LD R0, (0)
     ST (0xFF), R0
-    LDSP 0x40
+    LDSP 0xEF
     PUSH R0
     POP R0
     PUSHF
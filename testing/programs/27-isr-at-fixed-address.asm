@@ -0,0 +1,20 @@
+#! mrasm
+
+; The interrupt service routine is placed at a fixed address using a second .ORG
+
+    .ORG 0
+    JR MAIN
+    JR ISR
+
+MAIN:
+    LDSP 0xEF
+    BITS (0xF9), 1
+    EI
+LOOP:
+    JR LOOP
+
+    .ORG 0x20
+ISR:
+    MOV R0, 42
+    ST (0xFF), R0
+    STOP